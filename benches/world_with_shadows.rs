@@ -1,6 +1,8 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 
-use std::{f64::consts::PI, time::Duration};
+use std::time::Duration;
+
+use raytracerchallenge::scalar::consts::PI;
 
 use raytracerchallenge::{
     camera::Camera,
@@ -78,8 +80,8 @@ fn setup_world() -> World {
     let light = PointLight::new(Point::new(-10, 10, -10), WHITE);
     let light2 = PointLight::new(Point::new(10, 5, -10), Color::new(0.2, 0.2, 0.2));
 
-    world.add_light(light);
-    world.add_light(light2);
+    world.add_light(Box::new(light));
+    world.add_light(Box::new(light2));
 
     world
 }
@@ -97,7 +99,7 @@ fn setup_camera() -> Camera {
 }
 
 fn world_bench(world: World, camera: Camera) {
-    let _canvas = camera.render(&world, 0).unwrap();
+    let _canvas = camera.render(&world).unwrap();
 }
 
 fn criterion_benchmark(c: &mut Criterion) {
@@ -109,7 +111,7 @@ fn criterion_benchmark(c: &mut Criterion) {
     //group.measurement_time(Duration::from_secs(22));
 
     group.bench_function("world", |b| {
-        b.iter(|| world_bench(black_box(setup_world()), black_box(camera.clone())))
+        b.iter(|| world_bench(black_box(setup_world()), black_box(camera)))
     });
 
     group.finish();
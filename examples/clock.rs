@@ -1,8 +1,10 @@
+use std::fs::File;
 use std::io::Write;
-use std::{f64::consts::PI, fs::File};
+
+use raytracerchallenge::scalar::consts::PI;
 
 use raytracerchallenge::{
-    canvas::Canvas, color::Color, matrix::Mat4, ppm::write_to_ppm, tuple::Point,
+    canvas::Canvas, color::Color, matrix::Mat4, ppm::write_to_ppm, scalar::Scalar, tuple::Point,
 };
 
 #[mutants::skip]
@@ -15,7 +17,7 @@ fn main() {
     }
 
     for (i, point) in points.iter_mut().enumerate() {
-        let rotation: f64 = i as f64 * PI / 6.;
+        let rotation: Scalar = i as Scalar * PI / 6.;
         println!("rotation: {}", rotation);
         let rot_matrix = Mat4::new_rotation_z(rotation);
         *point = rot_matrix * *point;
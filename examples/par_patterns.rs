@@ -1,6 +1,8 @@
+use std::fs::File;
 use std::io::Write;
 use std::time::Instant;
-use std::{f64::consts::PI, fs::File};
+
+use raytracerchallenge::scalar::consts::PI;
 
 use raytracerchallenge::color::BLACK;
 use raytracerchallenge::material::{ColorType, Shininess};
@@ -96,8 +98,8 @@ fn main() {
     let light = PointLight::new(Point::new(-10, 10, -10), WHITE);
     let light2 = PointLight::new(Point::new(10, 5, -10), Color::new(0.2, 0.2, 0.2));
 
-    world.add_light(light);
-    world.add_light(light2);
+    world.add_light(Box::new(light));
+    world.add_light(Box::new(light2));
 
     let mut camera = Camera::new(1920, 1080, PI / 3.0);
 
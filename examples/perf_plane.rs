@@ -1,4 +1,4 @@
-use std::f64::consts::PI;
+use raytracerchallenge::scalar::consts::PI;
 
 use raytracerchallenge::material::{ColorType, Shininess};
 use raytracerchallenge::shapes::plane::Plane;
@@ -79,8 +79,8 @@ fn main() {
     let light = PointLight::new(Point::new(-10, 10, -10), WHITE);
     let light2 = PointLight::new(Point::new(10, 5, -10), Color::new(0.2, 0.2, 0.2));
 
-    world.add_light(light);
-    world.add_light(light2);
+    world.add_light(Box::new(light));
+    world.add_light(Box::new(light2));
 
     let mut camera = Camera::new(1920, 1080, PI / 3.0);
 
@@ -91,5 +91,5 @@ fn main() {
     ));
 
     let world_ref = &world;
-    let _canvas = camera.render(world_ref, 0).unwrap();
+    let _canvas = camera.render(world_ref).unwrap();
 }
@@ -1,4 +1,4 @@
-use std::f64::consts::PI;
+use raytracerchallenge::scalar::consts::PI;
 
 use raytracerchallenge::{
     camera::Camera,
@@ -77,8 +77,8 @@ fn main() {
     let light = PointLight::new(Point::new(-10, 10, -10), WHITE);
     let light2 = PointLight::new(Point::new(10, 5, -10), Color::new(0.2, 0.2, 0.2));
 
-    world.add_light(light);
-    world.add_light(light2);
+    world.add_light(Box::new(light));
+    world.add_light(Box::new(light2));
 
     let mut camera = Camera::new(1000, 500, PI / 3.0);
 
@@ -90,7 +90,7 @@ fn main() {
 
     //let start_time = Instant::now();
 
-    let _canvas = camera.render(&world, 0).unwrap();
+    let _canvas = camera.render(&world).unwrap();
 
     //let end_time = start_time.elapsed().as_millis();
 
@@ -21,8 +21,8 @@ struct Environment {
 #[allow(dead_code)]
 #[mutants::skip]
 fn tick(environment: &Environment, projectile: &mut Projectile) {
-    projectile.position = projectile.position + projectile.velocity;
-    projectile.velocity = projectile.velocity + environment.gravity + environment.wind;
+    projectile.position += projectile.velocity;
+    projectile.velocity += environment.gravity + environment.wind;
 }
 
 #[mutants::skip]
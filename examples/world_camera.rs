@@ -1,6 +1,8 @@
+use std::fs::File;
 use std::io::Write;
 use std::time::Instant;
-use std::{f64::consts::PI, fs::File};
+
+use raytracerchallenge::scalar::consts::PI;
 
 use raytracerchallenge::color::BLACK;
 use raytracerchallenge::material::{ColorType, Shininess};
@@ -74,22 +76,19 @@ fn main() {
     left.material_mut().specular = 0.3;
     left.material_mut().shininess = 200 as Shininess;
 
-    let mut world = World::default();
-
-    world.add_objects(&mut vec![
-        Box::new(floor),
-        Box::new(left_wall),
-        Box::new(right_wall),
-        Box::new(middle),
-        Box::new(right),
-        Box::new(left),
-    ]);
-
-    let light = PointLight::new(Point::new(-10, 10, -10), WHITE);
-    let light2 = PointLight::new(Point::new(10, 5, -10), Color::new(0.2, 0.2, 0.2));
-
-    world.add_light(light);
-    world.add_light(light2);
+    let world = World::builder()
+        .object(floor)
+        .object(left_wall)
+        .object(right_wall)
+        .object(middle)
+        .object(right)
+        .object(left)
+        .light(PointLight::new(Point::new(-10, 10, -10), WHITE))
+        .light(PointLight::new(
+            Point::new(10, 5, -10),
+            Color::new(0.2, 0.2, 0.2),
+        ))
+        .build();
 
     let mut camera = Camera::new(1920, 1080, PI / 3.0);
 
@@ -101,7 +100,7 @@ fn main() {
 
     let start_time = Instant::now();
     let world_ref = &world;
-    let canvas = camera.render(world_ref, 0).unwrap();
+    let canvas = camera.render(world_ref).unwrap();
 
     let end_time = start_time.elapsed().as_millis();
 
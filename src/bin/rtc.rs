@@ -0,0 +1,446 @@
+//! Renders scene files (YAML or JSON) without writing any Rust, see ```rtc help```.
+//!
+//! A scene file holds a ```camera``` and a ```world```, in the format of the "serde" feature. ```scenes/``` has examples.
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use raytracerchallenge::scalar::consts::PI;
+
+use raytracerchallenge::{
+    bmp::write_bmp_with,
+    camera::Camera,
+    canvas::Canvas,
+    hdr::write_hdr,
+    ppm::write_ppm_with,
+    quantize::{BitDepth, Dither, Gamma, Quantization},
+    terminal::{terminal_preview, TerminalColors},
+    validation::Diagnostic,
+    world::World,
+};
+use serde::Deserialize;
+
+const USAGE: &str = "usage:
+    rtc render <scene> [-o <image>] [--size <width>x<height>] [--threads <n>] [--samples <n>] [--depth <n>] [--gamma <curve>] [--dither <kind>] [--bits <8|16>] [--preview <columns>]
+        Renders the scene to a .ppm, .bmp, .hdr (or, with the png feature, a .png) image, by default named after the scene.
+        .hdr images keep colors brighter than white, e.g. for tone mapping them later.
+        --size, --samples and --depth override the camera of the scene.
+        --threads renders in parallel, 0 uses all cores (needs the rayon feature).
+        --gamma is srgb (the default), linear or a number like 2.2, applied when writing 8 bit images.
+        --dither is none (the default), ordered or noise, against banding in smooth gradients of 8 bit images.
+        --bits 16 writes .ppm and .png images with 16 bits per component.
+        --preview prints the image this many characters wide to the terminal, in color unless NO_COLOR is set.
+    rtc validate <scene>
+        Checks that the scene can be read, and for mistakes like objects scaled by 0 or lights inside of objects.
+        Scenes with errors are not rendered.
+    rtc info <scene>
+        Prints what the scene contains.
+    rtc help
+        Prints this text.
+
+Scenes are read as JSON if their name ends in .json, and as YAML otherwise.
+Image textures, cube maps and custom patterns cannot be described in scene files, so rtc validate rejects them as unreadable.";
+
+#[derive(Deserialize)]
+/// The contents of a scene file
+struct SceneFile {
+    camera: Camera,
+    world: World,
+}
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Render(PathBuf, RenderOptions),
+    Validate(PathBuf),
+    Info(PathBuf),
+    Help,
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct RenderOptions {
+    output: Option<PathBuf>,
+    size: Option<(usize, usize)>,
+    threads: Option<usize>,
+    samples: Option<usize>,
+    depth: Option<usize>,
+    gamma: Option<Gamma>,
+    dither: Option<Dither>,
+    bits: Option<BitDepth>,
+    preview: Option<usize>,
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Command, String> {
+    let mut args = args.into_iter();
+    let command = args.next().ok_or("no command given")?;
+    if command == "help" || command == "--help" || command == "-h" {
+        return Ok(Command::Help);
+    }
+    let scene = PathBuf::from(args.next().ok_or("no scene file given")?);
+    match command.as_str() {
+        "render" => {
+            let mut options = RenderOptions::default();
+            while let Some(flag) = args.next() {
+                let value = args.next().ok_or_else(|| format!("{flag} needs a value"))?;
+                match flag.as_str() {
+                    "-o" | "--output" => options.output = Some(PathBuf::from(value)),
+                    "--size" => options.size = Some(parse_size(&value)?),
+                    "--threads" => options.threads = Some(parse_number(&flag, &value)?),
+                    "--samples" => options.samples = Some(parse_number(&flag, &value)?),
+                    "--depth" => options.depth = Some(parse_number(&flag, &value)?),
+                    "--gamma" => options.gamma = Some(parse_gamma(&value)?),
+                    "--dither" => options.dither = Some(parse_dither(&value)?),
+                    "--bits" => options.bits = Some(parse_depth(&value)?),
+                    "--preview" => options.preview = Some(parse_number(&flag, &value)?),
+                    _ => return Err(format!("unknown option {flag}")),
+                }
+            }
+            Ok(Command::Render(scene, options))
+        }
+        "validate" | "info" if args.next().is_some() => {
+            Err(format!("{command} takes nothing but the scene file"))
+        }
+        "validate" => Ok(Command::Validate(scene)),
+        "info" => Ok(Command::Info(scene)),
+        _ => Err(format!("unknown command {command}")),
+    }
+}
+
+fn parse_number(flag: &str, value: &str) -> Result<usize, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{flag} needs a whole number, not {value}"))
+}
+
+fn parse_size(value: &str) -> Result<(usize, usize), String> {
+    let error = || format!("--size needs a size like 1920x1080, not {value}");
+    let (width, height) = value.split_once('x').ok_or_else(error)?;
+    match (width.parse(), height.parse()) {
+        (Ok(width), Ok(height)) if width > 0 && height > 0 => Ok((width, height)),
+        _ => Err(error()),
+    }
+}
+
+fn parse_gamma(value: &str) -> Result<Gamma, String> {
+    match value {
+        "srgb" => Ok(Gamma::Srgb),
+        "linear" => Ok(Gamma::Linear),
+        _ => match value.parse() {
+            Ok(gamma) if gamma > 0.0 => Ok(Gamma::Power(gamma)),
+            _ => Err(format!(
+                "--gamma needs srgb, linear or a positive number, not {value}"
+            )),
+        },
+    }
+}
+
+fn parse_dither(value: &str) -> Result<Dither, String> {
+    match value {
+        "none" => Ok(Dither::None),
+        "ordered" => Ok(Dither::Ordered),
+        "noise" => Ok(Dither::GradientNoise),
+        _ => Err(format!(
+            "--dither needs none, ordered or noise, not {value}"
+        )),
+    }
+}
+
+fn parse_depth(value: &str) -> Result<BitDepth, String> {
+    match value {
+        "8" => Ok(BitDepth::Eight),
+        "16" => Ok(BitDepth::Sixteen),
+        _ => Err(format!("--bits needs 8 or 16, not {value}")),
+    }
+}
+
+fn read_scene(path: &Path) -> Result<SceneFile, String> {
+    let contents =
+        fs::read_to_string(path).map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+    let scene = if path.extension().is_some_and(|e| e == "json") {
+        serde_json::from_str(&contents).map_err(|e| e.to_string())
+    } else {
+        serde_yaml::from_str(&contents).map_err(|e| e.to_string())
+    };
+    scene.map_err(|e| format!("invalid scene {}: {e}", path.display()))
+}
+
+/// Prints the problems of the scene, failing if any of them are errors. Returns the number of warnings.
+fn check_scene(path: &Path, camera: &Camera, world: &World) -> Result<usize, String> {
+    let diagnostics: Vec<Diagnostic> = camera
+        .validate()
+        .into_iter()
+        .chain(world.validate())
+        .collect();
+    for diagnostic in &diagnostics {
+        eprintln!("{diagnostic}");
+    }
+    match diagnostics.iter().filter(|d| d.is_error()).count() {
+        0 => Ok(diagnostics.len()),
+        errors => Err(format!(
+            "invalid scene {}: {errors} error(s)",
+            path.display()
+        )),
+    }
+}
+
+fn render(camera: &Camera, world: &World) -> Result<Canvas, String> {
+    #[cfg(feature = "rayon")]
+    if camera.settings.threads.is_some() {
+        return camera.par_render(world).map_err(|e| format!("{e:?}"));
+    }
+    #[cfg(not(feature = "rayon"))]
+    if camera.settings.threads.is_some_and(|threads| threads != 1) {
+        return Err("rendering with several threads needs the rayon feature".to_string());
+    }
+    camera.render(world).map_err(|e| format!("{e:?}"))
+}
+
+#[derive(Debug, PartialEq)]
+enum ImageFormat {
+    Ppm,
+    Bmp,
+    Hdr,
+    #[cfg(feature = "png")]
+    Png,
+}
+
+fn image_format(path: &Path) -> Result<ImageFormat, String> {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("ppm") => Ok(ImageFormat::Ppm),
+        Some("bmp") => Ok(ImageFormat::Bmp),
+        Some("hdr") => Ok(ImageFormat::Hdr),
+        #[cfg(feature = "png")]
+        Some("png") => Ok(ImageFormat::Png),
+        _ => Err(format!(
+            "cannot write {}: only .ppm{}, .bmp and .hdr images are supported",
+            path.display(),
+            if cfg!(feature = "png") { ", .png" } else { "" }
+        )),
+    }
+}
+
+fn write_image(
+    canvas: Canvas,
+    path: &Path,
+    format: ImageFormat,
+    quantization: Quantization,
+) -> Result<(), String> {
+    let error = |e: std::io::Error| format!("cannot write {}: {e}", path.display());
+    let mut writer = BufWriter::new(File::create(path).map_err(error)?);
+    match format {
+        ImageFormat::Ppm => write_ppm_with(&canvas, &mut writer, quantization).map_err(error)?,
+        ImageFormat::Bmp => write_bmp_with(&canvas, &mut writer, quantization).map_err(error)?,
+        ImageFormat::Hdr => write_hdr(&canvas, &mut writer).map_err(error)?,
+        #[cfg(feature = "png")]
+        ImageFormat::Png => {
+            raytracerchallenge::png::write_png_with(&canvas, &mut writer, quantization)
+                .map_err(|e| format!("cannot write {}: {e}", path.display()))?
+        }
+    }
+    writer.flush().map_err(error)
+}
+
+fn run(command: Command) -> Result<(), String> {
+    match command {
+        Command::Help => println!("{USAGE}"),
+        Command::Validate(path) => {
+            let scene = read_scene(&path)?;
+            match check_scene(&path, &scene.camera, &scene.world)? {
+                0 => println!("{} is valid", path.display()),
+                warnings => println!("{} is valid, with {warnings} warning(s)", path.display()),
+            }
+        }
+        Command::Info(path) => {
+            let SceneFile { camera, world } = read_scene(&path)?;
+            println!(
+                "camera: {} x {} pixels, {:.1}° field of view, {} samples per pixel, recursion limit {}, {:?} integrator",
+                camera.hsize,
+                camera.vsize,
+                camera.field_of_view * 180.0 / PI,
+                camera.settings.samples_per_pixel,
+                camera.settings.recursion_limit,
+                camera.integrator
+            );
+            println!(
+                "world: {} objects, {} lights",
+                world.objects().len(),
+                world.lights().len()
+            );
+            if world.skybox().is_some() {
+                println!("  with a skybox");
+            }
+            if let Some(samples) = world.light_samples() {
+                println!("  {samples} lights sampled per point");
+            }
+            if let Some(ambient_occlusion) = world.ambient_occlusion() {
+                println!("  {ambient_occlusion:?}");
+            }
+            if let Some(global_illumination) = world.global_illumination() {
+                println!("  {global_illumination:?}");
+            }
+            if let Some(fog) = world.fog() {
+                println!("  {fog:?}");
+            }
+        }
+        Command::Render(path, options) => {
+            let SceneFile { mut camera, world } = read_scene(&path)?;
+            if let Some((width, height)) = options.size {
+                camera.set_size(width, height);
+            }
+            if let Some(samples) = options.samples {
+                camera.settings.samples_per_pixel = samples;
+            }
+            if let Some(depth) = options.depth {
+                camera.settings.recursion_limit = depth;
+            }
+            if let Some(threads) = options.threads {
+                // 0 means all cores, which is what rayon's pools do by default
+                camera.settings.threads = Some(threads);
+            }
+            let output = options.output.unwrap_or_else(|| {
+                path.with_extension(if cfg!(feature = "png") { "png" } else { "ppm" })
+            });
+            // before rendering, which may take long
+            let format = image_format(&output)?;
+            check_scene(&path, &camera, &world)?;
+
+            let start_time = Instant::now();
+            let canvas = render(&camera, &world)?;
+            println!(
+                "Rendered {} objects at {} x {} pixels in {} milliseconds.",
+                world.objects().len(),
+                camera.hsize,
+                camera.vsize,
+                start_time.elapsed().as_millis()
+            );
+            if let Some(columns) = options.preview {
+                let colors = if std::env::var_os("NO_COLOR").is_some() {
+                    TerminalColors::Ascii
+                } else {
+                    TerminalColors::TrueColor
+                };
+                print!("{}", terminal_preview(&canvas, columns, colors));
+            }
+            let quantization = Quantization::new(options.gamma.unwrap_or_default())
+                .with_dither(options.dither.unwrap_or_default())
+                .with_depth(options.bits.unwrap_or_default());
+            write_image(canvas, &output, format, quantization)?;
+            println!("Written to {}", output.display());
+        }
+    }
+    Ok(())
+}
+
+#[mutants::skip]
+fn main() {
+    if let Err(error) = parse_args(std::env::args().skip(1)).and_then(run) {
+        eprintln!("{error}\n\n{USAGE}");
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod rtc_tests {
+    use std::path::PathBuf;
+
+    use raytracerchallenge::{
+        quantize::{BitDepth, Dither, Gamma},
+        world::World,
+    };
+
+    use crate::{image_format, parse_args, read_scene, Command, ImageFormat, RenderOptions};
+
+    fn args(args: &str) -> Result<Command, String> {
+        parse_args(args.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn render_options() {
+        assert_eq!(
+            args("render scene.yaml -o out.png --size 1920x1080 --threads 8 --samples 4 --depth 5 --gamma 2.2 --dither noise --bits 16 --preview 80"),
+            Ok(Command::Render(
+                PathBuf::from("scene.yaml"),
+                RenderOptions {
+                    output: Some(PathBuf::from("out.png")),
+                    size: Some((1920, 1080)),
+                    threads: Some(8),
+                    samples: Some(4),
+                    depth: Some(5),
+                    gamma: Some(Gamma::Power(2.2)),
+                    dither: Some(Dither::GradientNoise),
+                    bits: Some(BitDepth::Sixteen),
+                    preview: Some(80),
+                }
+            ))
+        );
+        assert_eq!(
+            args("render scene.yaml"),
+            Ok(Command::Render(
+                PathBuf::from("scene.yaml"),
+                RenderOptions::default()
+            ))
+        );
+    }
+
+    #[test]
+    fn invalid_args() {
+        assert!(args("").is_err());
+        assert!(args("render").is_err());
+        assert!(args("draw scene.yaml").is_err());
+        assert!(args("render scene.yaml --size 1920").is_err());
+        assert!(args("render scene.yaml --size 0x10").is_err());
+        assert!(args("render scene.yaml --samples many").is_err());
+        assert!(args("render scene.yaml --depth").is_err());
+        assert!(args("render scene.yaml --gamma 0").is_err());
+        assert!(args("render scene.yaml --gamma rec709").is_err());
+        assert!(args("render scene.yaml --dither floyd").is_err());
+        assert!(args("render scene.yaml --bits 32").is_err());
+        assert!(args("info scene.yaml extra").is_err());
+        assert_eq!(
+            args("validate scene.yaml"),
+            Ok(Command::Validate(PathBuf::from("scene.yaml")))
+        );
+    }
+
+    #[test]
+    fn image_formats() {
+        assert_eq!(
+            image_format(&PathBuf::from("out.ppm")),
+            Ok(ImageFormat::Ppm)
+        );
+        assert_eq!(
+            image_format(&PathBuf::from("out.bmp")),
+            Ok(ImageFormat::Bmp)
+        );
+        assert_eq!(
+            image_format(&PathBuf::from("out.hdr")),
+            Ok(ImageFormat::Hdr)
+        );
+        assert!(image_format(&PathBuf::from("out.jpg")).is_err());
+        assert!(image_format(&PathBuf::from("out")).is_err());
+    }
+
+    #[test]
+    fn example_scenes_are_valid() {
+        for entry in std::fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/scenes")).unwrap() {
+            let path = entry.unwrap().path();
+            let scene = read_scene(&path).unwrap_or_else(|e| panic!("{e}"));
+            assert_eq!(scene.camera.validate(), vec![], "{}", path.display());
+            assert_eq!(scene.world.validate(), vec![], "{}", path.display());
+        }
+    }
+
+    #[test]
+    fn yaml_round_trip() {
+        let scene = read_scene(&PathBuf::from(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/scenes/spheres.yaml"
+        )))
+        .unwrap();
+        let yaml = serde_yaml::to_string(&scene.world).unwrap();
+        let world: World = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(serde_yaml::to_string(&world).unwrap(), yaml);
+    }
+}
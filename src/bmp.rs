@@ -0,0 +1,140 @@
+//! BMP file format logic. Unlike PNG, BMP needs no dependencies, so it is always available.
+use std::io::{self, Write};
+
+use crate::{canvas::Canvas, quantize::Quantization};
+
+/// Size of the file header, which is followed by the info header
+const FILE_HEADER_SIZE: u32 = 14;
+/// Size of the BITMAPINFOHEADER used for opaque images
+const INFO_HEADER_SIZE: u32 = 40;
+/// Size of the BITMAPV4HEADER used for images with alpha, which are not understood without its channel masks
+const V4_HEADER_SIZE: u32 = 108;
+/// Uncompressed pixels
+const BI_RGB: u32 = 0;
+/// Uncompressed pixels laid out by the channel masks of the header
+const BI_BITFIELDS: u32 = 3;
+/// 2835 pixels per meter, i.e. 72 DPI
+const PIXELS_PER_METER: u32 = 2835;
+
+/// Writes the canvas as an uncompressed BMP image: 24 bits per pixel, or 32 with alpha for canvases with transparent pixels (see [`Canvas::alpha_at`]).
+/// Colors are converted from linear to sRGB, see [`crate::color::Color::to_rgb8`].
+pub fn write_bmp<W: Write>(canvas: &Canvas, writer: W) -> io::Result<()> {
+    write_bmp_with(canvas, writer, Quantization::default())
+}
+
+/// Writes the canvas as a BMP image like [`write_bmp`], converting colors to 8 bits as given by ```quantization```
+pub fn write_bmp_with<W: Write>(
+    canvas: &Canvas,
+    mut writer: W,
+    quantization: Quantization,
+) -> io::Result<()> {
+    let opaque = canvas.is_opaque();
+    let (bytes_per_pixel, info_header_size) = if opaque {
+        (3, INFO_HEADER_SIZE)
+    } else {
+        (4, V4_HEADER_SIZE)
+    };
+    let width = canvas.width();
+    let height = canvas.height();
+    // rows are padded to a multiple of 4 bytes
+    let row_size = (width * bytes_per_pixel).div_ceil(4) * 4;
+    let image_size = u32::try_from(row_size * height)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "image too large for BMP"))?;
+    let offset = FILE_HEADER_SIZE + info_header_size;
+
+    let mut header = Vec::with_capacity(offset as usize);
+    header.extend_from_slice(b"BM");
+    header.extend_from_slice(&(offset + image_size).to_le_bytes());
+    header.extend_from_slice(&[0; 4]);
+    header.extend_from_slice(&offset.to_le_bytes());
+
+    header.extend_from_slice(&info_header_size.to_le_bytes());
+    header.extend_from_slice(&(width as i32).to_le_bytes());
+    header.extend_from_slice(&(height as i32).to_le_bytes());
+    header.extend_from_slice(&1u16.to_le_bytes());
+    header.extend_from_slice(&(bytes_per_pixel as u16 * 8).to_le_bytes());
+    header.extend_from_slice(&if opaque { BI_RGB } else { BI_BITFIELDS }.to_le_bytes());
+    header.extend_from_slice(&image_size.to_le_bytes());
+    header.extend_from_slice(&PIXELS_PER_METER.to_le_bytes());
+    header.extend_from_slice(&PIXELS_PER_METER.to_le_bytes());
+    // no palette
+    header.extend_from_slice(&[0; 8]);
+    if !opaque {
+        // red, green, blue and alpha masks of the BGRA pixels
+        for mask in [0x00ff0000u32, 0x0000ff00, 0x000000ff, 0xff000000] {
+            header.extend_from_slice(&mask.to_le_bytes());
+        }
+        header.extend_from_slice(b"BGRs");
+        // endpoints and gamma, unused for sRGB
+        header.extend_from_slice(&[0; 48]);
+    }
+    writer.write_all(&header)?;
+
+    let mut row = vec![0; row_size];
+    // rows are stored bottom to top
+    for y in (0..height).rev() {
+        for x in 0..width {
+            // like PNG, BMP colors are not premultiplied
+            let (color, alpha) = canvas.straight_at(x, y);
+            let [red, green, blue] = quantization.quantize(color, x, y);
+            let pixel = &mut row[x * bytes_per_pixel..(x + 1) * bytes_per_pixel];
+            if opaque {
+                pixel.copy_from_slice(&[blue, green, red]);
+            } else {
+                pixel.copy_from_slice(&[blue, green, red, (alpha * 255.0).round() as u8]);
+            }
+        }
+        writer.write_all(&row)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod bmp_tests {
+    use crate::{
+        bmp::write_bmp,
+        canvas::Canvas,
+        color::{Color, WHITE},
+    };
+
+    fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn opaque() {
+        let mut canvas = Canvas::new(2, 2);
+        canvas.write_pixel(0, 0, Color::new(1, 0, 0)).unwrap();
+        canvas.write_pixel(1, 1, WHITE).unwrap();
+        let mut bytes = Vec::new();
+        write_bmp(&canvas, &mut bytes).unwrap();
+
+        assert_eq!(&bytes[0..2], b"BM");
+        // 2 rows of 6 bytes, padded to 8
+        assert_eq!(bytes.len(), 14 + 40 + 16);
+        assert_eq!(u32_at(&bytes, 2), bytes.len() as u32);
+        assert_eq!(u32_at(&bytes, 10), 54);
+        assert_eq!(u32_at(&bytes, 18), 2);
+        assert_eq!(u32_at(&bytes, 22), 2);
+        assert_eq!(bytes[28], 24);
+        // the bottom row comes first, in BGR order
+        assert_eq!(&bytes[54..62], &[0, 0, 0, 255, 255, 255, 0, 0]);
+        assert_eq!(&bytes[62..70], &[0, 0, 255, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn transparent() {
+        let mut canvas = Canvas::new(1, 1);
+        canvas.write_pixel(0, 0, Color::new(0.5, 0.0, 0.0)).unwrap();
+        canvas.write_alpha(0, 0, 0.5).unwrap();
+        let mut bytes = Vec::new();
+        write_bmp(&canvas, &mut bytes).unwrap();
+
+        assert_eq!(bytes.len(), 14 + 108 + 4);
+        assert_eq!(bytes[28], 32);
+        assert_eq!(u32_at(&bytes, 30), 3);
+        assert_eq!(u32_at(&bytes, 66), 0xff000000);
+        // not premultiplied
+        assert_eq!(&bytes[122..126], &[0, 0, 255, 128]);
+    }
+}
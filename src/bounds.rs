@@ -0,0 +1,184 @@
+//! Axis aligned bounding boxes, to skip objects a ray cannot hit.
+//! Custom shapes join the culling and the bounding volume hierarchy of the world by returning their box from [`crate::shapes::shape::Shape::local_bounds`].
+use crate::{epsilon::EPSILON, matrix::Mat4, ray::Ray, scalar::Scalar, tuple::Point};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+/// A box aligned to the axes, containing everything between ```min``` and ```max```
+pub struct BoundingBox {
+    /// The corner with the smallest coordinates
+    pub min: Point,
+    /// The corner with the largest coordinates
+    pub max: Point,
+}
+
+impl BoundingBox {
+    /// The box between the two corners
+    pub fn new(min: Point, max: Point) -> Self {
+        Self { min, max }
+    }
+
+    /// A box containing nothing, which grows by including points or other boxes
+    pub fn empty() -> Self {
+        Self {
+            min: Point::new(Scalar::INFINITY, Scalar::INFINITY, Scalar::INFINITY),
+            max: Point::new(-Scalar::INFINITY, -Scalar::INFINITY, -Scalar::INFINITY),
+        }
+    }
+
+    /// The smallest box containing all the points
+    pub fn of_points(points: &[Point]) -> Self {
+        points
+            .iter()
+            .fold(Self::empty(), |bounds, p| bounds.including(*p))
+    }
+
+    /// This box grown to contain ```p```
+    pub fn including(self, p: Point) -> Self {
+        Self {
+            min: Point::new(
+                self.min.x.min(p.x),
+                self.min.y.min(p.y),
+                self.min.z.min(p.z),
+            ),
+            max: Point::new(
+                self.max.x.max(p.x),
+                self.max.y.max(p.y),
+                self.max.z.max(p.z),
+            ),
+        }
+    }
+
+    /// The smallest box containing both boxes
+    pub fn merge(self, other: BoundingBox) -> Self {
+        self.including(other.min).including(other.max)
+    }
+
+    /// The center of the box
+    pub fn centroid(&self) -> Point {
+        Point::new(
+            (self.min.x + self.max.x) / 2.0,
+            (self.min.y + self.max.y) / 2.0,
+            (self.min.z + self.max.z) / 2.0,
+        )
+    }
+
+    /// The box containing this one after transforming it with ```matrix```, e.g. from object to world space.
+    /// It may be bigger than necessary, as the transformed box is usually not aligned to the axes anymore.
+    pub fn transform(&self, matrix: Mat4) -> Self {
+        let corners = [
+            Point::new(self.min.x, self.min.y, self.min.z),
+            Point::new(self.min.x, self.min.y, self.max.z),
+            Point::new(self.min.x, self.max.y, self.min.z),
+            Point::new(self.min.x, self.max.y, self.max.z),
+            Point::new(self.max.x, self.min.y, self.min.z),
+            Point::new(self.max.x, self.min.y, self.max.z),
+            Point::new(self.max.x, self.max.y, self.min.z),
+            Point::new(self.max.x, self.max.y, self.max.z),
+        ];
+        corners.iter().fold(Self::empty(), |bounds, corner| {
+            bounds.including(matrix * *corner)
+        })
+    }
+
+    /// Whether ```p``` is inside the box or on its surface
+    pub fn contains_point(&self, p: Point) -> bool {
+        (self.min.x..=self.max.x).contains(&p.x)
+            && (self.min.y..=self.max.y).contains(&p.y)
+            && (self.min.z..=self.max.z).contains(&p.z)
+    }
+
+    /// Whether the ray passes through the box at all (in either direction along the ray)
+    pub fn intersects(&self, ray: &Ray) -> bool {
+        let (x_min, x_max) = slab(ray.origin.x, ray.direction.x, self.min.x, self.max.x);
+        let (y_min, y_max) = slab(ray.origin.y, ray.direction.y, self.min.y, self.max.y);
+        let (z_min, z_max) = slab(ray.origin.z, ray.direction.z, self.min.z, self.max.z);
+
+        let t_min = x_min.max(y_min).max(z_min);
+        let t_max = x_max.min(y_max).min(z_max);
+        t_min <= t_max
+    }
+}
+
+/// Entry and exit distance of a ray through the slab between min and max on one axis
+fn slab(origin: Scalar, direction: Scalar, min: Scalar, max: Scalar) -> (Scalar, Scalar) {
+    let t_min_numerator = min - EPSILON - origin;
+    let t_max_numerator = max + EPSILON - origin;
+
+    let (t_min, t_max) = if direction.abs() >= EPSILON {
+        (t_min_numerator / direction, t_max_numerator / direction)
+    } else {
+        (
+            t_min_numerator * Scalar::INFINITY,
+            t_max_numerator * Scalar::INFINITY,
+        )
+    };
+
+    if t_min > t_max {
+        (t_max, t_min)
+    } else {
+        (t_min, t_max)
+    }
+}
+
+#[cfg(test)]
+mod bounding_box_tests {
+    use crate::{
+        bounds::BoundingBox,
+        matrix::Mat4,
+        ray::Ray,
+        scalar::{consts::PI, Scalar},
+        tuple::{Point, Vector},
+    };
+
+    #[test]
+    fn of_points() {
+        let b = BoundingBox::of_points(&[
+            Point::new(-1, 2, 0),
+            Point::new(3, -2, 1),
+            Point::new(0, 0, -4),
+        ]);
+        assert_eq!(b.min, Point::new(-1, -2, -4));
+        assert_eq!(b.max, Point::new(3, 2, 1));
+        assert_eq!(b.centroid(), Point::new(1.0, 0.0, -1.5));
+    }
+
+    #[test]
+    fn merge() {
+        let a = BoundingBox::new(Point::new(0, 0, 0), Point::new(1, 1, 1));
+        let b = BoundingBox::new(Point::new(-1, 0.5, 0), Point::new(0, 3, 0.5));
+        let m = a.merge(b);
+        assert_eq!(m.min, Point::new(-1, 0, 0));
+        assert_eq!(m.max, Point::new(1, 3, 1));
+        assert_eq!(BoundingBox::empty().merge(a), a);
+    }
+
+    #[test]
+    fn transform() {
+        let b = BoundingBox::new(Point::new(-1, -1, -1), Point::new(1, 1, 1));
+        let moved = b.transform(Mat4::new_translation(1, 2, 3) * Mat4::new_scaling(2, 1, 1));
+        assert_eq!(moved.min, Point::new(-1, 1, 2));
+        assert_eq!(moved.max, Point::new(3, 3, 4));
+        // rotating a cube grows its axis aligned box
+        let rotated = b.transform(Mat4::new_rotation_y(PI / 4.0));
+        assert!((rotated.max.x - Scalar::sqrt(2.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn contains_point() {
+        let b = BoundingBox::new(Point::new(-1, 0, -1), Point::new(1, 2, 1));
+        assert!(b.contains_point(Point::new(0.5, 1.0, 0.0)));
+        assert!(b.contains_point(Point::new(1, 2, -1)));
+        assert!(!b.contains_point(Point::new(0.0, -0.5, 0.0)));
+        assert!(!b.contains_point(Point::new(0, 1, 3)));
+        assert!(!BoundingBox::empty().contains_point(Point::new(0, 0, 0)));
+    }
+
+    #[test]
+    fn intersects() {
+        let b = BoundingBox::new(Point::new(-1, -1, -1), Point::new(1, 1, 1));
+        assert!(b.intersects(&Ray::new(Point::new(0, 0, -5), Vector::new(0, 0, 1))));
+        assert!(b.intersects(&Ray::new(Point::new(0.5, 0.5, 0.0), Vector::new(1, 0, 0))));
+        assert!(!b.intersects(&Ray::new(Point::new(2, 0, -5), Vector::new(0, 0, 1))));
+        assert!(!b.intersects(&Ray::new(Point::new(0, 0, -5), Vector::new(0, 1, 0))));
+    }
+}
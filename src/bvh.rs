@@ -0,0 +1,237 @@
+use crate::{
+    bounds::BoundingBox, intersection::Intersection, ray::Ray, shapes::shape::Shape, stats,
+};
+
+/// Objects per leaf, below which nodes are not split any further
+const MAX_LEAF_OBJECTS: usize = 2;
+
+#[derive(Debug)]
+enum Node {
+    Leaf {
+        bounds: BoundingBox,
+        objects: Vec<usize>,
+    },
+    Branch {
+        bounds: BoundingBox,
+        left: usize,
+        right: usize,
+    },
+}
+
+impl Node {
+    fn bounds(&self) -> &BoundingBox {
+        match self {
+            Node::Leaf { bounds, .. } | Node::Branch { bounds, .. } => bounds,
+        }
+    }
+}
+
+/// A bounding volume hierarchy over the objects of a world: a tree of boxes, each containing the objects of the boxes below it.
+/// Rays only test the objects in the boxes they pass through, instead of every object.
+///
+/// Objects without bounds (like planes) are tested by every ray.
+#[derive(Debug, Default)]
+pub(crate) struct Bvh {
+    nodes: Vec<Node>,
+    unbounded: Vec<usize>,
+}
+
+impl Bvh {
+    /// Builds the hierarchy over ```objects```, which the indices of its leaves refer to
+    pub(crate) fn build(objects: &[Box<dyn Shape>]) -> Self {
+        let mut bvh = Self::default();
+        let mut bounded = Vec::new();
+        for (index, object) in objects.iter().enumerate() {
+            match object.bounds() {
+                Some(bounds) => bounded.push((index, bounds)),
+                None => bvh.unbounded.push(index),
+            }
+        }
+        if !bounded.is_empty() {
+            bvh.build_node(&mut bounded);
+        }
+        bvh
+    }
+
+    /// Adds the node for ```objects``` (and the nodes below it), returning its index
+    fn build_node(&mut self, objects: &mut [(usize, BoundingBox)]) -> usize {
+        let bounds = objects
+            .iter()
+            .fold(BoundingBox::empty(), |bounds, (_, b)| bounds.merge(*b));
+        if objects.len() <= MAX_LEAF_OBJECTS {
+            self.nodes.push(Node::Leaf {
+                bounds,
+                objects: objects.iter().map(|(index, _)| *index).collect(),
+            });
+            return self.nodes.len() - 1;
+        }
+
+        // split at the median along the axis the centers are spread out the most
+        let centroids = objects
+            .iter()
+            .fold(BoundingBox::empty(), |c, (_, b)| c.including(b.centroid()));
+        let extent = centroids.max - centroids.min;
+        let axis = |b: &BoundingBox| {
+            let c = b.centroid();
+            if extent.x >= extent.y && extent.x >= extent.z {
+                c.x
+            } else if extent.y >= extent.z {
+                c.y
+            } else {
+                c.z
+            }
+        };
+        objects.sort_by(|(_, a), (_, b)| axis(a).total_cmp(&axis(b)));
+        let (left_objects, right_objects) = objects.split_at_mut(objects.len() / 2);
+
+        // reserve the slot, so the root ends up at index 0
+        let index = self.nodes.len();
+        self.nodes.push(Node::Leaf {
+            bounds,
+            objects: Vec::new(),
+        });
+        let left = self.build_node(left_objects);
+        let right = self.build_node(right_objects);
+        self.nodes[index] = Node::Branch {
+            bounds,
+            left,
+            right,
+        };
+        index
+    }
+
+    /// Intersects the ray with all objects whose boxes it passes through. The intersections are not sorted.
+    pub(crate) fn intersect<'a>(
+        &self,
+        objects: &'a [Box<dyn Shape>],
+        r: &Ray,
+        intersections: &mut Vec<Intersection<'a>>,
+    ) {
+        self.any(objects, r, |object| {
+            object.intersect(r, intersections);
+            false
+        });
+    }
+
+    /// Calls ```f``` with every object whose boxes the ray passes through, until it returns true. Returns whether it did.
+    pub(crate) fn any<'a>(
+        &self,
+        objects: &'a [Box<dyn Shape>],
+        r: &Ray,
+        mut f: impl FnMut(&'a dyn Shape) -> bool,
+    ) -> bool {
+        for &index in &self.unbounded {
+            stats::record(|stats| stats.intersection_tests += 1);
+            if f(objects[index].as_ref()) {
+                return true;
+            }
+        }
+        if self.nodes.is_empty() {
+            return false;
+        }
+
+        let mut stack = vec![0];
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            stats::record(|stats| stats.bounds_tests += 1);
+            if !node.bounds().intersects(r) {
+                continue;
+            }
+            match node {
+                Node::Leaf {
+                    objects: indices, ..
+                } => {
+                    for &index in indices {
+                        stats::record(|stats| stats.intersection_tests += 1);
+                        if f(objects[index].as_ref()) {
+                            return true;
+                        }
+                    }
+                }
+                Node::Branch { left, right, .. } => {
+                    stack.push(*right);
+                    stack.push(*left);
+                }
+            }
+        }
+        false
+    }
+
+    #[cfg(test)]
+    pub(crate) fn depth(&self) -> usize {
+        fn depth_of(bvh: &Bvh, node: usize) -> usize {
+            match &bvh.nodes[node] {
+                Node::Leaf { .. } => 1,
+                Node::Branch { left, right, .. } => {
+                    1 + depth_of(bvh, *left).max(depth_of(bvh, *right))
+                }
+            }
+        }
+        if self.nodes.is_empty() {
+            0
+        } else {
+            depth_of(self, 0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod bvh_tests {
+    use crate::{
+        bvh::Bvh,
+        matrix::Mat4,
+        ray::Ray,
+        scalar::Scalar,
+        shapes::{plane::Plane, shape::Shape, sphere::Sphere},
+        stats,
+        tuple::{Point, Vector},
+    };
+
+    /// Unit spheres in a row along the x axis, 3 apart
+    fn row_of_spheres(n: usize) -> Vec<Box<dyn Shape>> {
+        (0..n)
+            .map(|i| {
+                let mut s = Sphere::default();
+                s.set_transformation_matrix(Mat4::new_translation(3.0 * i as Scalar, 0.0, 0.0));
+                Box::new(s) as Box<dyn Shape>
+            })
+            .collect()
+    }
+
+    #[test]
+    fn balanced() {
+        let objects = row_of_spheres(16);
+        let bvh = Bvh::build(&objects);
+        assert_eq!(bvh.depth(), 4);
+        assert!(bvh.unbounded.is_empty());
+        assert_eq!(Bvh::build(&[]).depth(), 0);
+    }
+
+    #[test]
+    fn only_tests_objects_along_the_ray() {
+        let objects = row_of_spheres(16);
+        let bvh = Bvh::build(&objects);
+        let r = Ray::new(Point::new(9, 0, -5), Vector::new(0, 0, 1));
+        let mut xs = Vec::new();
+        let (_, stats) = stats::collect(|| bvh.intersect(&objects, &r, &mut xs));
+        assert_eq!(xs.len(), 2);
+        assert!(std::ptr::eq(
+            xs[0].object as *const dyn Shape as *const u8,
+            objects[3].as_ref() as *const dyn Shape as *const u8
+        ));
+        assert!(stats.intersection_tests <= 2);
+    }
+
+    #[test]
+    fn unbounded_objects_are_always_tested() {
+        let mut objects = row_of_spheres(3);
+        objects.push(Box::new(Plane::default()));
+        let bvh = Bvh::build(&objects);
+        assert_eq!(bvh.unbounded, vec![3]);
+        let r = Ray::new(Point::new(-5, 1, 0), Vector::new(0, -1, 0));
+        let mut xs = Vec::new();
+        bvh.intersect(&objects, &r, &mut xs);
+        assert_eq!(xs.len(), 1);
+        assert_eq!(xs[0].t, 1.0);
+    }
+}
@@ -1,15 +1,39 @@
 /// You use a camera to render the scene from a certain viewpoint to a [`Canvas`]
 use crate::{
-    canvas::{Canvas, CanvasError},
+    canvas::{Canvas, CanvasError, DepthBuffer},
+    color::{Color, BLACK, WHITE},
+    deep::{DeepImage, DeepSample},
+    distributed::Tile,
+    intersection::Intersection,
+    lens::DepthOfField,
     matrix::{Mat4, IDENTITY_MATRIX_4},
     ray::Ray,
+    render_pass::{ObjectIdBuffer, RenderLayers, RenderPass, RenderPasses},
+    sampling::{self, Rng, Sampler, SamplingStrategy},
+    scalar::Scalar,
+    settings::{self, RenderSettings},
+    stats::{self, RenderStats},
     tuple::{Point, Vector},
-    world::World,
+    validation::{self, Diagnostic},
+    world::{AmbientOcclusion, World},
 };
 
 #[cfg(feature = "rayon")]
 use rayon::prelude::*;
 
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// How a camera computes the light arriving along its rays
+pub enum Integrator {
+    /// The model of the book: light sources, a constant ambient term, and perfect reflection and refraction
+    #[default]
+    Whitted,
+    /// Monte Carlo path tracing: light also bounces diffusely between objects and emissive objects light their surroundings (global illumination).
+    /// Each ray follows a single random path, so the image is noisy unless [`RenderSettings::samples_per_pixel`] is raised considerably.
+    /// The number of bounces is limited by [`RenderSettings::recursion_limit`].
+    PathTracing,
+}
+
 #[derive(Copy, Clone, Debug)]
 /// A camera that can render the scene from it's viewpoint.
 pub struct Camera {
@@ -18,21 +42,48 @@ pub struct Camera {
     /// horizontal size of the image
     pub vsize: usize,
     /// field of view in unit circle degrees (90° would be PI/2)
-    pub field_of_view: f64,
+    pub field_of_view: Scalar,
     transform: Mat4,
     inverted_transform: Mat4,
     /// the size of an individual pixel (both height and width, pixels are assumed to be square)
-    pub pixel_size: f64,
-    half_width: f64,
-    half_height: f64,
+    pub pixel_size: Scalar,
+    half_width: Scalar,
+    half_height: Scalar,
+    /// The exposure value (EV100) used to turn the light arriving at the camera into pixel colors, like the exposure settings of a real camera.
+    /// Use this together with physical light units (e.g. [`crate::light::PointLight::with_power`]). None (the default) leaves colors untouched.
+    pub exposure_value: Option<Scalar>,
+    /// Brightens (positive) or darkens (negative) the image by this many stops, on top of [`Self::exposure_value`]. 0 by default.
+    pub exposure_compensation: Scalar,
+    /// Multipliers for the red, green and blue channels of the image, e.g. to neutralize the tint of colored lights. [`WHITE`] (the default) leaves colors untouched.
+    pub white_balance: Color,
+    /// How the rays of a pixel are spread, see [`SamplingStrategy`]
+    pub sampling: SamplingStrategy,
+    /// A lens blurring objects that are not at its focal distance. None (the default) is a pinhole camera keeping everything sharp.
+    /// The blur is only smooth with enough [`RenderSettings::samples_per_pixel`], as each ray passes through a single point of the aperture.
+    pub depth_of_field: Option<DepthOfField>,
+    /// The brightest a single ray may be before it is averaged into its pixel, None (the default) for no limit.
+    /// Brighter samples are scaled down keeping their hue, which suppresses fireflies: single blown-out pixels
+    /// caused by rare paths that reflect or refract a light into the camera. This trades them for a slightly darker image.
+    pub max_sample_radiance: Option<Scalar>,
+    /// The seed all randomness of a render derives from: the sample positions within pixels, the paths of [`Integrator::PathTracing`]
+    /// and the sampling of lights, ambient occlusion and global illumination. Renders of the same world with the same seed are bit-identical,
+    /// except for parallel renders with global illumination, see [`crate::world::GlobalIllumination`].
+    pub seed: u64,
+    /// The auxiliary images [`Self::render_layers`] renders in addition to the final image. None by default.
+    pub passes: RenderPasses,
+    /// Samples per pixel, recursion limit, threads and more, see [`RenderSettings`]
+    pub settings: RenderSettings,
+    /// How the light along camera rays is computed, [`Integrator::Whitted`] by default.
+    /// Only [`Self::render`] and its variants use it, auxiliary images like [`Self::render_layers`] always use the Whitted model.
+    pub integrator: Integrator,
 }
 
 impl Camera {
     /// Creates a new camera with a horizontal pixel count of ```hsize``` and a vertial pixel count of ```vsize```.
     /// The field of view is given as parts of the unit circle: 360° would be 2*PI, 90° PI/2.
-    pub fn new(hsize: usize, vsize: usize, field_of_view: f64) -> Self {
+    pub fn new(hsize: usize, vsize: usize, field_of_view: Scalar) -> Self {
         let half_view = (field_of_view / 2.0).tan();
-        let aspect = hsize as f64 / vsize as f64;
+        let aspect = hsize as Scalar / vsize as Scalar;
 
         let (half_width, half_height) = if aspect >= 1.0 {
             (half_view, half_view / aspect)
@@ -40,7 +91,7 @@ impl Camera {
             (half_view * aspect, half_view)
         };
 
-        let pixel_size = (half_width * 2.0) / hsize as f64;
+        let pixel_size = (half_width * 2.0) / hsize as Scalar;
 
         Self {
             hsize,
@@ -51,6 +102,16 @@ impl Camera {
             pixel_size,
             half_width,
             half_height,
+            exposure_value: None,
+            exposure_compensation: 0.0,
+            white_balance: WHITE,
+            sampling: SamplingStrategy::default(),
+            depth_of_field: None,
+            max_sample_radiance: None,
+            seed: 0,
+            passes: RenderPasses::NONE,
+            settings: RenderSettings::default(),
+            integrator: Integrator::default(),
         }
     }
 
@@ -65,20 +126,216 @@ impl Camera {
         self.inverted_transform = transform.inverse();
     }
 
-    fn ray_for_pixel(&self, px: usize, py: usize) -> Ray {
-        let x_offset = (px as f64 + 0.5) * self.pixel_size;
-        let y_offset = (py as f64 + 0.5) * self.pixel_size;
+    /// Checks the camera for settings that render nothing or garbage, like an image without pixels. Nothing found if empty.
+    /// See also [`World::validate`].
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        if self.hsize == 0 || self.vsize == 0 {
+            diagnostics.push(Diagnostic::error(format!(
+                "camera: the image has no pixels ({} x {})",
+                self.hsize, self.vsize
+            )));
+        }
+        if !(self.field_of_view > 0.0 && self.field_of_view < crate::scalar::consts::PI) {
+            diagnostics.push(Diagnostic::error(format!(
+                "camera: the field of view is {}, it must be between 0 and PI (180°)",
+                self.field_of_view
+            )));
+        }
+        validation::check_transform("camera", self.transform, &mut diagnostics);
+        if self.settings.samples_per_pixel == 0 {
+            diagnostics.push(Diagnostic::error(
+                "camera: 0 samples per pixel, so every pixel is black",
+            ));
+        }
+        validation::check_shadow_bias("camera", self.settings.shadow_bias, &mut diagnostics);
+        diagnostics
+    }
+
+    /// Changes the size of the image in pixels, keeping the field of view and all other settings
+    pub fn set_size(&mut self, hsize: usize, vsize: usize) {
+        let resized = Camera::new(hsize, vsize, self.field_of_view);
+        self.hsize = hsize;
+        self.vsize = vsize;
+        self.pixel_size = resized.pixel_size;
+        self.half_width = resized.half_width;
+        self.half_height = resized.half_height;
+    }
+
+    /// The ray through the center of a pixel, e.g. for finding the object shown there with [`World::cast_ray`]
+    pub fn ray_for_pixel(&self, px: usize, py: usize) -> Ray {
+        self.ray_through_pixel(px, py, (0.5, 0.5), (0.0, 0.0))
+    }
+
+    /// A ray through a position within a pixel, (0, 0) being its top left and (1, 1) its bottom right corner.
+    /// With [`Self::depth_of_field`], it starts at ```lens```, a point within the aperture of radius 1, and passes through the focal plane where the pinhole ray would.
+    fn ray_through_pixel(
+        &self,
+        px: usize,
+        py: usize,
+        (dx, dy): (Scalar, Scalar),
+        (lens_x, lens_y): (Scalar, Scalar),
+    ) -> Ray {
+        let x_offset = (px as Scalar + dx) * self.pixel_size;
+        let y_offset = (py as Scalar + dy) * self.pixel_size;
 
         let world_x = self.half_width - x_offset;
         let world_y = self.half_height - y_offset;
 
-        let pixel = self.inverted_transform * Point::new(world_x, world_y, -1.);
-        let origin = self.inverted_transform * Point::new(0, 0, 0);
+        let (pixel, origin) = match self.depth_of_field {
+            Some(dof) => (
+                Point::new(
+                    world_x * dof.focal_distance,
+                    world_y * dof.focal_distance,
+                    -dof.focal_distance,
+                ),
+                Point::new(
+                    lens_x * dof.aperture_radius,
+                    lens_y * dof.aperture_radius,
+                    0.0,
+                ),
+            ),
+            None => (Point::new(world_x, world_y, -1.), Point::new(0, 0, 0)),
+        };
+        let pixel = self.inverted_transform * pixel;
+        let origin = self.inverted_transform * origin;
         let direction = (pixel - origin).normalized();
 
         Ray::new(origin, direction)
     }
 
+    /// The color of a pixel and its alpha, averaged over [`RenderSettings::samples_per_pixel`] rays. The color is exposed.
+    fn pixel_color<'a>(
+        &self,
+        world: &'a World,
+        px: usize,
+        py: usize,
+        intersections: &mut Vec<Intersection<'a>>,
+    ) -> (Color, Scalar) {
+        self.in_render(|| self.sampled_pixel_color(world, px, py, intersections))
+    }
+
+    /// Runs ```f``` with the seed and settings of this camera applying to everything shaded, see [`Self::seed`] and [`Self::settings`]
+    fn in_render<T>(&self, f: impl FnOnce() -> T) -> T {
+        sampling::with_seed(self.seed, || settings::with_settings(&self.settings, f))
+    }
+
+    /// Same as [`Self::pixel_color`], with the seed of the render already set
+    fn sampled_pixel_color<'a>(
+        &self,
+        world: &'a World,
+        px: usize,
+        py: usize,
+        intersections: &mut Vec<Intersection<'a>>,
+    ) -> (Color, Scalar) {
+        // offset from the seed of the sample positions, so both get different random numbers
+        let mut rng = Rng::new(self.pixel_seed(px, py).wrapping_add(1));
+        if self.settings.samples_per_pixel <= 1 && self.depth_of_field.is_none() {
+            let ray = self.ray_for_pixel(px, py);
+            let (color, alpha) = self.trace(world, &ray, intersections, &mut rng);
+            return (self.expose(self.clamp_sample(color)), alpha);
+        }
+
+        let samples = self.pixel_samples(px, py);
+        let (color, alpha) =
+            samples
+                .iter()
+                .fold((BLACK, 0.0), |(color, alpha), &(offset, lens)| {
+                    let ray = self.ray_through_pixel(px, py, offset, lens);
+                    let (sample, sample_alpha) = self.trace(world, &ray, intersections, &mut rng);
+                    (color + self.clamp_sample(sample), alpha + sample_alpha)
+                });
+        let scale = 1.0 / samples.len() as Scalar;
+        (self.expose(color * scale), alpha * scale)
+    }
+
+    /// The color of a single camera ray and its alpha, which is 0 for rays missing everything if the background is transparent.
+    fn trace<'a>(
+        &self,
+        world: &'a World,
+        ray: &Ray,
+        intersections: &mut Vec<Intersection<'a>>,
+        rng: &mut Rng,
+    ) -> (Color, Scalar) {
+        stats::record(|stats| stats.camera_rays += 1);
+        match self.integrator {
+            Integrator::Whitted if self.settings.transparent_background => {
+                world.color_with_alpha_at(ray, intersections, self.settings.recursion_limit)
+            }
+            Integrator::Whitted => (
+                world.color_at(ray, intersections, self.settings.recursion_limit),
+                1.0,
+            ),
+            Integrator::PathTracing => {
+                if self.settings.transparent_background
+                    && world.first_hit(ray, intersections).is_none()
+                {
+                    return (BLACK, 0.0);
+                }
+                let color = world.path_traced_color_at(
+                    ray,
+                    intersections,
+                    rng,
+                    self.settings.recursion_limit,
+                );
+                (color, 1.0)
+            }
+        }
+    }
+
+    /// Scales ```sample``` down to [`Self::max_sample_radiance`] if any of its channels exceeds it.
+    fn clamp_sample(&self, sample: Color) -> Color {
+        let brightest = sample.red.max(sample.green).max(sample.blue);
+        match self.max_sample_radiance {
+            Some(max) if brightest > max => sample * (max / brightest),
+            _ => sample,
+        }
+    }
+
+    /// The positions within a pixel its rays go through, see [`RenderSettings::samples_per_pixel`]
+    fn pixel_offsets(&self, px: usize, py: usize) -> Vec<(Scalar, Scalar)> {
+        if self.settings.samples_per_pixel <= 1 {
+            return vec![(0.5, 0.5)];
+        }
+        Sampler::new(self.sampling, self.pixel_seed(px, py))
+            .unit_square(self.settings.samples_per_pixel)
+    }
+
+    /// The positions within a pixel its rays go through, each with the point within the aperture it starts at, see [`Self::depth_of_field`].
+    /// Without depth of field, all rays start at the center of the lens.
+    fn pixel_samples(&self, px: usize, py: usize) -> Vec<((Scalar, Scalar), (Scalar, Scalar))> {
+        let offsets = self.pixel_offsets(px, py);
+        let Some(dof) = self.depth_of_field else {
+            return offsets
+                .into_iter()
+                .map(|offset| (offset, (0.0, 0.0)))
+                .collect();
+        };
+        // offset from the seeds of the sample positions and the traced paths, so all get different random numbers
+        let lens = Sampler::new(self.sampling, self.pixel_seed(px, py).wrapping_add(2))
+            .unit_square(offsets.len())
+            .into_iter()
+            .map(|sample| dof.aperture.sample(sample));
+        offsets.into_iter().zip(lens).collect()
+    }
+
+    /// A seed for the random numbers of a pixel.
+    /// Seeding per pixel makes the result independent of the order pixels are rendered in.
+    fn pixel_seed(&self, px: usize, py: usize) -> u64 {
+        let pixel_index = (py * self.hsize + px) as u64;
+        Rng::new(self.seed ^ pixel_index.wrapping_mul(0x9E37_79B9_7F4A_7C15)).next_u64()
+    }
+
+    /// Scales the light arriving at the camera according to its exposure and white balance, see [`Self::exposure_value`].
+    fn expose(&self, color: Color) -> Color {
+        let exposed = match self.exposure_value {
+            // the maximum luminance that does not overexpose a sensor at ISO 100
+            Some(ev) => color * (1.0 / (1.2 * Scalar::powf(2.0, ev))),
+            None => color,
+        };
+        exposed * self.exposure_compensation.exp2() * self.white_balance
+    }
+
     /// This function is a simple way to position and rotate the camera.
     ///
     /// You provide ```from``` as a start point (where the camera is positioned), a ```to``` point where the camera looks at and an ```up``` vector which should approximately point into the direction that is up.
@@ -100,57 +357,577 @@ impl Camera {
     }
 
     /// renders the given world using this camera.
-    pub fn render(&self, world: &World, recursion_limit: usize) -> Result<Canvas, CanvasError> {
+    pub fn render(&self, world: &World) -> Result<Canvas, CanvasError> {
         let mut image = Canvas::new(self.hsize, self.vsize);
+        self.render_into(world, &mut image)?;
+        Ok(image)
+    }
 
-        let mut intersections = Vec::new();
+    /// Renders the given world like [`Self::render`], additionally counting the rays and intersection tests it takes, see [`RenderStats`].
+    /// Statistics are only collected here, plain renders do not pay for the bookkeeping beyond a check per counter.
+    pub fn render_with_stats(&self, world: &World) -> Result<(Canvas, RenderStats), CanvasError> {
+        let (image, stats) = stats::collect(|| self.render(world));
+        Ok((image?, stats))
+    }
 
+    /// Renders the given world like [`Self::render`], additionally returning the distance from the camera to the first object hit for every pixel.
+    /// The depth is taken along the ray through the center of the pixel, even with several [`RenderSettings::samples_per_pixel`].
+    pub fn render_with_depth(&self, world: &World) -> Result<(Canvas, DepthBuffer), CanvasError> {
+        world.clear_irradiance_cache();
+        let mut image = Canvas::new(self.hsize, self.vsize);
+        let mut depth = DepthBuffer::new(self.hsize, self.vsize);
+        let mut intersections = Vec::new();
         for y in 0..self.vsize {
             for x in 0..self.hsize {
-                let ray = self.ray_for_pixel(x, y);
-                let color = world.color_at(&ray, &mut intersections, recursion_limit);
+                let (color, _) = self.pixel_color(world, x, y, &mut intersections);
                 image.write_pixel(x, y, color)?;
+                let ray = self.ray_for_pixel(x, y);
+                let distance = self.in_render(|| world.depth_at(&ray, &mut intersections));
+                depth.write_depth(x, y, distance)?;
             }
         }
+        Ok((image, depth))
+    }
+
+    /// Renders the given world like [`Self::render`], additionally rendering every pass selected in [`Self::passes`].
+    pub fn render_layers(&self, world: &World) -> Result<RenderLayers, CanvasError> {
+        world.clear_irradiance_cache();
+        let passes: Vec<RenderPass> = self.passes.iter().collect();
+        let mut beauty = Canvas::new(self.hsize, self.vsize);
+        let mut images = vec![Canvas::new(self.hsize, self.vsize); passes.len()];
+        let mut intersections = Vec::new();
+        self.in_render(|| -> Result<(), CanvasError> {
+            for y in 0..self.vsize {
+                for x in 0..self.hsize {
+                    let samples = self.pixel_samples(x, y);
+                    let mut color = BLACK;
+                    let mut pass_colors = vec![BLACK; passes.len()];
+                    for &(offset, lens) in &samples {
+                        let ray = self.ray_through_pixel(x, y, offset, lens);
+                        let layers = world.layers_at(
+                            &ray,
+                            &mut intersections,
+                            self.settings.recursion_limit,
+                        );
+                        color += self.clamp_sample(layers.color);
+                        for (pass_color, pass) in pass_colors.iter_mut().zip(&passes) {
+                            *pass_color += pass.color(&layers);
+                        }
+                    }
 
+                    let scale = 1.0 / samples.len() as Scalar;
+                    beauty.write_pixel(x, y, self.expose(color * scale))?;
+                    for ((image, pass), pass_color) in
+                        images.iter_mut().zip(&passes).zip(pass_colors)
+                    {
+                        let pass_color = if pass.is_light() {
+                            self.expose(pass_color * scale)
+                        } else {
+                            pass_color * scale
+                        };
+                        image.write_pixel(x, y, pass_color)?;
+                    }
+                }
+            }
+            Ok(())
+        })?;
+        Ok(RenderLayers::new(
+            beauty,
+            passes.into_iter().zip(images).collect(),
+        ))
+    }
+
+    /// Renders only the ambient occlusion of the world, see [`AmbientOcclusion`]: white where surfaces are not occluded at all, darker in corners and crevices.
+    /// Materials and lights are ignored, which makes this useful to check the shapes and scale of a scene. Pixels that do not hit anything are black.
+    pub fn render_ambient_occlusion(
+        &self,
+        world: &World,
+        ambient_occlusion: AmbientOcclusion,
+    ) -> Result<Canvas, CanvasError> {
+        let mut image = Canvas::new(self.hsize, self.vsize);
+        let mut intersections = Vec::new();
+        self.in_render(|| -> Result<(), CanvasError> {
+            for y in 0..self.vsize {
+                for x in 0..self.hsize {
+                    let samples = self.pixel_samples(x, y);
+                    let (mut unoccluded, mut hits) = (0.0, 0);
+                    for &(offset, lens) in &samples {
+                        let ray = self.ray_through_pixel(x, y, offset, lens);
+                        if let Some(occlusion) =
+                            world.occlusion_at(&ray, &mut intersections, &ambient_occlusion)
+                        {
+                            unoccluded += occlusion;
+                            hits += 1;
+                        }
+                    }
+                    let scale = 1.0 / samples.len() as Scalar;
+                    image.write_pixel(x, y, WHITE * (unoccluded * scale))?;
+                    if self.settings.transparent_background {
+                        image.write_alpha(x, y, hits as Scalar * scale)?;
+                    }
+                }
+            }
+            Ok(())
+        })?;
         Ok(image)
     }
 
-    /// Same as ```render()```, but uses all available system threads to parallelize.
+    /// Renders every surface seen through each pixel with its depth and opacity instead of a single color, see [`DeepImage`].
+    /// With several [`RenderSettings::samples_per_pixel`], each ray contributes its own samples, weighted by its share of the pixel. Colors are exposed.
+    /// The skybox is left out, pixels where rays miss everything are empty.
+    pub fn render_deep(&self, world: &World) -> Result<DeepImage, CanvasError> {
+        world.clear_irradiance_cache();
+        let mut image = DeepImage::new(self.hsize, self.vsize);
+        let mut intersections = Vec::new();
+        self.in_render(|| -> Result<(), CanvasError> {
+            for y in 0..self.vsize {
+                for x in 0..self.hsize {
+                    let samples = self.pixel_samples(x, y);
+                    let coverage = 1.0 / samples.len() as Scalar;
+                    for &(offset, lens) in &samples {
+                        let ray = self.ray_through_pixel(x, y, offset, lens);
+                        stats::record(|stats| stats.camera_rays += 1);
+                        for sample in world.deep_samples_at(
+                            &ray,
+                            &mut intersections,
+                            self.settings.recursion_limit,
+                        ) {
+                            image.add_sample(
+                                x,
+                                y,
+                                DeepSample {
+                                    color: self.expose(sample.color) * coverage,
+                                    alpha: sample.alpha * coverage,
+                                    ..sample
+                                },
+                            )?;
+                        }
+                    }
+                }
+            }
+            Ok(())
+        })?;
+        Ok(image)
+    }
+
+    /// Renders which object is visible in every pixel, see [`ObjectIdBuffer`]. Objects are identified by their [`crate::shapes::shape::ObjectId`].
+    pub fn render_object_ids(&self, world: &World) -> Result<ObjectIdBuffer, CanvasError> {
+        let mut ids = ObjectIdBuffer::new(self.hsize, self.vsize);
+        let mut intersections = Vec::new();
+        for y in 0..self.vsize {
+            for x in 0..self.hsize {
+                let samples = self.pixel_samples(x, y);
+                let coverage = 1.0 / samples.len() as Scalar;
+                for (offset, lens) in samples {
+                    let ray = self.ray_through_pixel(x, y, offset, lens);
+                    let id = self.in_render(|| {
+                        world
+                            .first_hit(&ray, &mut intersections)
+                            .and_then(|hit| hit.object.id())
+                    });
+                    if let Some(id) = id {
+                        ids.add_coverage(x, y, id, coverage)?;
+                    }
+                }
+            }
+        }
+        Ok(ids)
+    }
+
+    /// Renders the given world into an existing canvas, which needs to be the size of the camera image.
+    /// Reusing the canvas avoids allocating a new one for every frame.
+    pub fn render_into(&self, world: &World, canvas: &mut Canvas) -> Result<(), CanvasError> {
+        world.clear_irradiance_cache();
+        self.render_pixels_into(world, canvas)
+    }
+
+    /// Same as [`Self::render_into`], but keeps the indirect light cached by earlier renders, see [`render_all`]
+    fn render_pixels_into(&self, world: &World, canvas: &mut Canvas) -> Result<(), CanvasError> {
+        if canvas.width() != self.hsize || canvas.height() != self.vsize {
+            return Err(CanvasError::InvalidSize);
+        }
+        let mut intersections = Vec::new();
+        for y in 0..self.vsize {
+            for x in 0..self.hsize {
+                let (color, alpha) = self.pixel_color(world, x, y, &mut intersections);
+                canvas.write_pixel(x, y, color)?;
+                if self.settings.transparent_background {
+                    canvas.write_alpha(x, y, alpha)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Renders only a part of the image, e.g. on one of several machines, see [`crate::distributed`].
+    /// The pixels are the same as those at the position of the tile in a full render.
+    pub fn render_tile(&self, world: &World, tile: Tile) -> Result<Canvas, CanvasError> {
+        if tile.x + tile.width > self.hsize || tile.y + tile.height > self.vsize {
+            return Err(CanvasError::InvalidSize);
+        }
+        world.clear_irradiance_cache();
+        let mut image = Canvas::new(tile.width, tile.height);
+        let mut intersections = Vec::new();
+        for y in 0..tile.height {
+            for x in 0..tile.width {
+                let (color, alpha) =
+                    self.pixel_color(world, tile.x + x, tile.y + y, &mut intersections);
+                image.write_pixel(x, y, color)?;
+                if self.settings.transparent_background {
+                    image.write_alpha(x, y, alpha)?;
+                }
+            }
+        }
+        Ok(image)
+    }
+
+    /// Renders the given world into a row-major buffer of ```hsize * vsize``` colors.
+    pub fn render_into_colors(
+        &self,
+        world: &World,
+        buffer: &mut [Color],
+    ) -> Result<(), CanvasError> {
+        self.render_into_pixels(world, buffer, 1, |pixel, color| pixel[0] = color)
+    }
+
+    /// Renders the given world into a row-major buffer of 8 bit sRGB pixels (3 bytes each, ```hsize * vsize * 3``` in total),
+    /// as expected by most window and video libraries. See [`Color::to_rgb8`].
+    pub fn render_into_rgb8(&self, world: &World, buffer: &mut [u8]) -> Result<(), CanvasError> {
+        self.render_into_pixels(world, buffer, 3, |pixel, color| {
+            pixel.copy_from_slice(&color.to_rgb8())
+        })
+    }
+
+    /// Renders into a row-major buffer with ```stride``` elements per pixel, written by ```write```
+    fn render_into_pixels<T>(
+        &self,
+        world: &World,
+        buffer: &mut [T],
+        stride: usize,
+        write: impl Fn(&mut [T], Color),
+    ) -> Result<(), CanvasError> {
+        if buffer.len() != self.hsize * self.vsize * stride {
+            return Err(CanvasError::InvalidSize);
+        }
+        world.clear_irradiance_cache();
+        let mut intersections = Vec::new();
+        for (i, pixel) in buffer.chunks_exact_mut(stride).enumerate() {
+            let (x, y) = (i % self.hsize, i / self.hsize);
+            write(pixel, self.pixel_color(world, x, y, &mut intersections).0);
+        }
+        Ok(())
+    }
+
+    /// Same as ```render()```, but renders rows in parallel on all available system threads, or as many as [`RenderSettings::threads`] asks for.
     #[cfg(feature = "rayon")]
-    pub fn par_render(&self, world: &World, recursion_limit: usize) -> Result<Canvas, CanvasError> {
-        let mut rows = Vec::with_capacity(self.vsize);
-        (0..(self.vsize))
-            .into_par_iter()
-            .map(|y| self.render_row(world, y, recursion_limit))
-            .collect_into_vec(&mut rows);
+    pub fn par_render(&self, world: &World) -> Result<Canvas, CanvasError> {
+        world.clear_irradiance_cache();
         let mut canvas = Canvas::new(self.hsize, self.vsize);
-        for (row, rowv) in rows.iter().enumerate() {
-            for (col, color) in rowv.iter().enumerate() {
-                canvas.write_pixel(col, row, *color)?;
+        self.par_render_rows(&mut canvas, |y, row, alpha| {
+            self.render_row_into(world, y, row, alpha)
+        });
+        Ok(canvas)
+    }
+
+    /// Same as [`Self::render_with_stats`], but in parallel like [`Self::par_render`].
+    #[cfg(feature = "rayon")]
+    pub fn par_render_with_stats(
+        &self,
+        world: &World,
+    ) -> Result<(Canvas, RenderStats), CanvasError> {
+        world.clear_irradiance_cache();
+        let mut canvas = Canvas::new(self.hsize, self.vsize);
+        let row_stats = self.par_render_rows(&mut canvas, |y, row, alpha| {
+            stats::collect(|| self.render_row_into(world, y, row, alpha)).1
+        });
+        let mut stats = RenderStats::default();
+        for row_stats in &row_stats {
+            stats.merge(row_stats);
+        }
+        Ok((canvas, stats))
+    }
+
+    /// Renders the rows of ```canvas``` in parallel with ```render_row```, which gets the alpha of the row if [`RenderSettings::transparent_background`] is set.
+    /// Returns what ```render_row``` returned for each row.
+    #[cfg(feature = "rayon")]
+    fn par_render_rows<T: Send>(
+        &self,
+        canvas: &mut Canvas,
+        render_row: impl Fn(usize, &mut [Color], Option<&mut [Scalar]>) -> T + Sync + Send,
+    ) -> Vec<T> {
+        let mut results = Vec::with_capacity(self.vsize);
+        self.on_threads(|| {
+            if self.settings.transparent_background {
+                canvas
+                    .par_rows_with_alpha_mut()
+                    .enumerate()
+                    .map(|(y, (row, alpha))| render_row(y, row, Some(alpha)))
+                    .collect_into_vec(&mut results);
+            } else {
+                canvas
+                    .par_rows_mut()
+                    .enumerate()
+                    .map(|(y, row)| render_row(y, row, None))
+                    .collect_into_vec(&mut results);
             }
+        });
+        results
+    }
+
+    /// Runs ```f``` on as many threads as [`RenderSettings::threads`] asks for
+    #[cfg(feature = "rayon")]
+    fn on_threads(&self, f: impl FnOnce() + Send) {
+        let pool = self.settings.threads.and_then(|threads| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .ok()
+        });
+        match pool {
+            Some(pool) => pool.install(f),
+            // the global pool of rayon, with one thread per core
+            None => f(),
         }
-        Ok(canvas)
     }
 
+    /// Renders a single row of the image into ```row```, and its alpha into ```alpha``` if given
     #[cfg(feature = "rayon")]
-    fn render_row(
+    fn render_row_into(
         &self,
         world: &World,
         y: usize,
-        recursion_limit: usize,
-    ) -> Vec<crate::color::Color> {
+        row: &mut [Color],
+        mut alpha: Option<&mut [Scalar]>,
+    ) {
+        let mut intersections = Vec::new();
+        for (x, pixel) in row.iter_mut().enumerate() {
+            let (color, pixel_alpha) = self.pixel_color(world, x, y, &mut intersections);
+            *pixel = color;
+            if let Some(alpha) = alpha.as_deref_mut() {
+                alpha[x] = pixel_alpha;
+            }
+        }
+    }
+
+    /// The colors and alphas of a single row of the image
+    #[cfg(feature = "preview")]
+    pub(crate) fn render_row(&self, world: &World, y: usize) -> Vec<(Color, Scalar)> {
         let mut vec = Vec::with_capacity(self.hsize);
         let mut intersections = Vec::new();
         for x in 0..self.hsize {
-            let ray = self.ray_for_pixel(x, y);
-            let color = world.color_at(&ray, &mut intersections, recursion_limit);
-            vec.push(color);
+            vec.push(self.pixel_color(world, x, y, &mut intersections));
         }
         vec
     }
 }
 
+/// Renders the world from several cameras, e.g. different angles of the same scene, returning one image per camera.
+///
+/// The views share the preprocessing of the world: indirect light cached for [`crate::world::GlobalIllumination`] by one view is reused by the others,
+/// so with global illumination the images differ slightly from rendering each camera on its own. With the "rayon" feature, the views are rendered in parallel.
+pub fn render_all(world: &World, cameras: &[Camera]) -> Result<Vec<Canvas>, CanvasError> {
+    world.clear_irradiance_cache();
+    let render = |camera: &Camera| {
+        let mut canvas = Canvas::new(camera.hsize, camera.vsize);
+        camera.render_pixels_into(world, &mut canvas)?;
+        Ok(canvas)
+    };
+    #[cfg(feature = "rayon")]
+    return cameras.par_iter().map(render).collect();
+    #[cfg(not(feature = "rayon"))]
+    cameras.iter().map(render).collect()
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+/// Two cameras side by side, rendering the left and right eye images of a stereoscopic picture from one [`Camera`].
+pub struct StereoRig {
+    /// The distance between the two eyes, in world units
+    pub interocular_distance: Scalar,
+    /// The distance in front of the camera at which the eyes converge. Objects at this distance appear on the screen plane.
+    /// Use [`Scalar::INFINITY`] for parallel eyes.
+    pub convergence_distance: Scalar,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// How the two images of a [`StereoRig`] are combined into one canvas
+pub enum StereoLayout {
+    /// Left eye image on the left, right eye image on the right, for parallel viewing or VR headsets
+    SideBySide,
+    /// Red-cyan anaglyph: the red channel of the left eye image, green and blue of the right eye image
+    Anaglyph,
+}
+
+impl StereoLayout {
+    /// Combines the ```left``` and ```right``` eye images, which need to be of the same size.
+    pub fn compose(&self, left: &Canvas, right: &Canvas) -> Result<Canvas, CanvasError> {
+        let (width, height) = (left.width(), left.height());
+        if right.width() != width || right.height() != height {
+            return Err(CanvasError::InvalidSize);
+        }
+        match self {
+            StereoLayout::SideBySide => {
+                let mut canvas = Canvas::new(width * 2, height);
+                for y in 0..height {
+                    for x in 0..width {
+                        canvas.write_pixel(x, y, left.pixel_at(x, y)?)?;
+                        canvas.write_pixel(x + width, y, right.pixel_at(x, y)?)?;
+                    }
+                }
+                Ok(canvas)
+            }
+            StereoLayout::Anaglyph => {
+                let mut canvas = Canvas::new(width, height);
+                for y in 0..height {
+                    for x in 0..width {
+                        let (l, r) = (left.pixel_at(x, y)?, right.pixel_at(x, y)?);
+                        canvas.write_pixel(x, y, Color::new(l.red, r.green, r.blue))?;
+                    }
+                }
+                Ok(canvas)
+            }
+        }
+    }
+}
+
+impl StereoRig {
+    /// A rig with the given distance between the eyes, converging at ```convergence_distance``` in front of the camera
+    pub fn new(interocular_distance: Scalar, convergence_distance: Scalar) -> Self {
+        Self {
+            interocular_distance,
+            convergence_distance,
+        }
+    }
+
+    /// The left and right eye cameras for ```camera```. They share its settings and are moved sideways by half the interocular distance each, turned inwards to look at the convergence point.
+    pub fn eyes(&self, camera: &Camera) -> (Camera, Camera) {
+        let eye = |side: Scalar| {
+            // in camera space, +x points to the left
+            let x = side * self.interocular_distance / 2.0;
+            let target = if self.convergence_distance.is_finite() {
+                Point::new(0.0, 0.0, -self.convergence_distance)
+            } else {
+                Point::new(x, 0.0, -1.0)
+            };
+            let view =
+                Camera::view_transform(Point::new(x, 0.0, 0.0), target, Vector::new(0, 1, 0));
+            let mut eye = *camera;
+            eye.set_transform(view * camera.transform());
+            eye
+        };
+        (eye(1.0), eye(-1.0))
+    }
+
+    /// Renders the left and right eye images of ```world``` as seen by ```camera```
+    pub fn render(&self, camera: &Camera, world: &World) -> Result<(Canvas, Canvas), CanvasError> {
+        let (left, right) = self.eyes(camera);
+        Ok((left.render(world)?, right.render(world)?))
+    }
+
+    /// Renders both eye images and combines them into one canvas according to ```layout```
+    pub fn render_composed(
+        &self,
+        camera: &Camera,
+        world: &World,
+        layout: StereoLayout,
+    ) -> Result<Canvas, CanvasError> {
+        let (left, right) = self.render(camera, world)?;
+        layout.compose(&left, &right)
+    }
+}
+
+#[cfg(test)]
+mod stereo_tests {
+    use crate::{
+        camera::{Camera, StereoLayout, StereoRig},
+        canvas::{Canvas, CanvasError},
+        color::Color,
+        matrix::Mat4,
+        scalar::{consts::PI, Scalar},
+        tuple::{Point, Vector},
+        world::World,
+    };
+
+    #[test]
+    fn eyes_converge() {
+        let mut camera = Camera::new(11, 11, PI / 2.);
+        camera.set_transform(Mat4::new_translation(0, 0, -5));
+        let (left, right) = StereoRig::new(0.5, 4.0).eyes(&camera);
+        let l = left.ray_for_pixel(5, 5);
+        let r = right.ray_for_pixel(5, 5);
+        assert_eq!(l.origin, Point::new(0.25, 0.0, 5.0));
+        assert_eq!(r.origin, Point::new(-0.25, 0.0, 5.0));
+        // both look at the convergence point
+        let convergence = Point::new(0, 0, 1);
+        assert_eq!(
+            l.position((convergence - l.origin).magnitude()),
+            convergence
+        );
+        assert_eq!(
+            r.position((convergence - r.origin).magnitude()),
+            convergence
+        );
+        assert_eq!(left.hsize, camera.hsize);
+    }
+
+    #[test]
+    fn parallel_eyes() {
+        let camera = Camera::new(11, 11, PI / 2.);
+        let (left, right) = StereoRig::new(0.5, Scalar::INFINITY).eyes(&camera);
+        assert_eq!(left.ray_for_pixel(5, 5).direction, Vector::new(0, 0, -1));
+        assert_eq!(right.ray_for_pixel(5, 5).direction, Vector::new(0, 0, -1));
+    }
+
+    #[test]
+    fn compose() {
+        let left = Canvas::new_with_color(2, 1, Color::new(0.1, 0.2, 0.3));
+        let right = Canvas::new_with_color(2, 1, Color::new(0.4, 0.5, 0.6));
+
+        let side_by_side = StereoLayout::SideBySide.compose(&left, &right).unwrap();
+        assert_eq!(side_by_side.width(), 4);
+        assert_eq!(
+            side_by_side.pixel_at(1, 0).unwrap(),
+            Color::new(0.1, 0.2, 0.3)
+        );
+        assert_eq!(
+            side_by_side.pixel_at(2, 0).unwrap(),
+            Color::new(0.4, 0.5, 0.6)
+        );
+
+        let anaglyph = StereoLayout::Anaglyph.compose(&left, &right).unwrap();
+        assert_eq!(anaglyph.width(), 2);
+        assert_eq!(anaglyph.pixel_at(0, 0).unwrap(), Color::new(0.1, 0.5, 0.6));
+
+        assert_eq!(
+            StereoLayout::Anaglyph
+                .compose(&left, &Canvas::new(1, 1))
+                .unwrap_err(),
+            CanvasError::InvalidSize
+        );
+    }
+
+    #[test]
+    fn render() {
+        let w = World::test_world();
+        let mut camera = Camera::new(11, 11, PI / 2.);
+        camera.set_transform(Camera::view_transform(
+            Point::new(0, 0, -5),
+            Point::new(0, 0, 0),
+            Vector::new(0, 1, 0),
+        ));
+        let rig = StereoRig::new(1.0, 5.0);
+        let (left, right) = rig.render(&camera, &w).unwrap();
+        // the sphere sits at the convergence point, but is lit differently from each eye
+        assert_ne!(left.pixel_at(5, 5).unwrap(), right.pixel_at(5, 5).unwrap());
+        let composed = rig
+            .render_composed(&camera, &w, StereoLayout::SideBySide)
+            .unwrap();
+        assert_eq!(
+            composed.pixel_at(16, 5).unwrap(),
+            right.pixel_at(5, 5).unwrap()
+        );
+    }
+}
+
 #[cfg(test)]
 mod view_transformation_tests {
     use crate::{
@@ -206,15 +983,22 @@ mod view_transformation_tests {
 
 #[cfg(test)]
 mod camera_tests {
-    use std::f64::consts::PI;
-
     use crate::{
-        camera::Camera,
-        color::Color,
+        camera::{Camera, Integrator},
+        canvas::{Canvas, CanvasError},
+        color::{Color, BLACK, WHITE},
         epsilon::EpsilonEqual,
+        lens::{Aperture, DepthOfField},
         matrix::{Mat4, IDENTITY_MATRIX_4},
+        render_pass::RenderPass,
+        scalar::{consts::PI, Scalar},
+        settings::DEFAULT_RECURSION_LIMIT,
+        shapes::{
+            plane::Plane,
+            shape::{ObjectId, Shape},
+        },
         tuple::{Point, Vector},
-        world::World,
+        world::{AmbientOcclusion, GlobalIllumination, World},
     };
 
     #[test]
@@ -225,6 +1009,7 @@ mod camera_tests {
         assert_eq!(c.field_of_view, PI / 2.);
         assert_eq!(c.transform, IDENTITY_MATRIX_4);
         assert_eq!(c.inverted_transform, IDENTITY_MATRIX_4);
+        assert_eq!(c.settings.recursion_limit, DEFAULT_RECURSION_LIMIT);
     }
 
     #[test]
@@ -239,6 +1024,27 @@ mod camera_tests {
         assert!(c.pixel_size.e_equals(0.01));
     }
 
+    #[test]
+    fn validate() {
+        assert!(Camera::new(10, 10, PI / 2.).validate().is_empty());
+        let mut c = Camera::new(0, 10, PI);
+        c.settings.samples_per_pixel = 0;
+        c.set_transform(Mat4::new_scaling(0.0, 0.0, 0.0));
+        let diagnostics = c.validate();
+        assert_eq!(diagnostics.len(), 4);
+        assert!(diagnostics.iter().all(|d| d.is_error()));
+    }
+
+    #[test]
+    fn set_size() {
+        let mut c = Camera::new(10, 10, PI / 2.);
+        c.settings.samples_per_pixel = 4;
+        c.set_size(125, 200);
+        assert_eq!((c.hsize, c.vsize), (125, 200));
+        assert!(c.pixel_size.e_equals(0.01));
+        assert_eq!(c.settings.samples_per_pixel, 4);
+    }
+
     #[test]
     fn ray_through_center() {
         let c = Camera::new(201, 101, PI / 2.);
@@ -262,7 +1068,7 @@ mod camera_tests {
         assert_eq!(r.origin, Point::new(0, 2, -5));
         assert_eq!(
             r.direction,
-            Vector::new(2.0_f64.sqrt() / 2.0, 0.0, -(2.0_f64.sqrt() / 2.0))
+            Vector::new(Scalar::sqrt(2.0) / 2.0, 0.0, -(Scalar::sqrt(2.0) / 2.0))
         )
     }
 
@@ -274,22 +1080,510 @@ mod camera_tests {
         let to = Point::new(0, 0, 0);
         let up = Vector::new(0, 1, 0);
         c.set_transform(Camera::view_transform(from, to, up));
-        let image = c.render(&w, 0).unwrap();
+        let image = c.render(&w).unwrap();
         assert_eq!(
             image.pixel_at(5, 5).unwrap(),
             Color::new(0.38066, 0.47583, 0.2855)
         );
     }
+
+    #[test]
+    fn render_with_stats() {
+        let mut w = World::test_world();
+        let mut c = Camera::new(11, 11, PI / 2.);
+        c.set_transform(Camera::view_transform(
+            Point::new(0, 0, -5),
+            Point::new(0, 0, 0),
+            Vector::new(0, 1, 0),
+        ));
+        let (image, stats) = c.render_with_stats(&w).unwrap();
+        assert_eq!(
+            image.pixel_at(5, 5).unwrap(),
+            c.render(&w).unwrap().pixel_at(5, 5).unwrap()
+        );
+        assert_eq!(stats.camera_rays, 121);
+        assert!(stats.shadow_rays > 0);
+        assert_eq!(stats.reflection_rays, 0);
+        // both spheres share a single box, only rays passing through it test them
+        assert_eq!(stats.bounds_tests, stats.total_rays());
+        assert!(stats.intersection_tests < 2 * stats.total_rays());
+        assert_eq!(stats.max_depth, 1);
+
+        let mut floor = Plane::default();
+        floor.material_mut().reflective = 0.5;
+        floor.set_transformation_matrix(Mat4::new_translation(0, -1, 0));
+        w.add_object(Box::new(floor));
+        let (_, stats) = c.render_with_stats(&w).unwrap();
+        assert!(stats.reflection_rays > 0);
+        // the floor reflects the spheres, which are not reflective themselves
+        assert_eq!(stats.max_depth, 2);
+    }
+
+    #[test]
+    fn recursion_limit() {
+        let mut w = World::test_world();
+        let mut floor = Plane::default();
+        floor.material_mut().reflective = 0.5;
+        floor.set_transformation_matrix(Mat4::new_translation(0, -1, 0));
+        w.add_object(Box::new(floor));
+        let mut c = Camera::new(11, 11, PI / 2.);
+        c.set_transform(Camera::view_transform(
+            Point::new(0, 0, -5),
+            Point::new(0, 0, 0),
+            Vector::new(0, 1, 0),
+        ));
+        // the floor in front of the sphere reflects it
+        let reflecting = c.render(&w).unwrap().pixel_at(5, 7).unwrap();
+        c.settings.recursion_limit = 0;
+        let plain = c.render(&w).unwrap().pixel_at(5, 7).unwrap();
+        assert!(reflecting.green > plain.green);
+    }
+
+    #[test]
+    fn anti_aliasing() {
+        let w = World::test_world();
+        let mut c = Camera::new(11, 11, PI / 2.);
+        c.set_transform(Camera::view_transform(
+            Point::new(0, 0, -5),
+            Point::new(0, 0, 0),
+            Vector::new(0, 1, 0),
+        ));
+        let single = c.render(&w).unwrap();
+        c.settings.samples_per_pixel = 9;
+        let sampled = c.render(&w).unwrap();
+        // pixels off the sphere stay black, pixels on its edge blend it with the background
+        assert_eq!(sampled.pixel_at(2, 5).unwrap(), BLACK);
+        let edge = sampled.pixel_at(4, 5).unwrap();
+        assert!(edge.red > 0.0 && edge.red < single.pixel_at(4, 5).unwrap().red);
+        // reproducible with the same seed, but not with another one
+        assert_eq!(c.render(&w).unwrap().pixel_at(4, 5).unwrap(), edge);
+        c.seed = 1;
+        assert_ne!(c.render(&w).unwrap().pixel_at(4, 5).unwrap(), edge);
+    }
+
+    #[test]
+    fn render_with_depth() {
+        let w = World::test_world();
+        let mut c = Camera::new(11, 11, PI / 2.);
+        c.set_transform(Camera::view_transform(
+            Point::new(0, 0, -5),
+            Point::new(0, 0, 0),
+            Vector::new(0, 1, 0),
+        ));
+        let (image, depth) = c.render_with_depth(&w).unwrap();
+        assert_eq!(
+            image.pixel_at(5, 5).unwrap(),
+            Color::new(0.38066, 0.47583, 0.2855)
+        );
+        assert!(depth.depth_at(5, 5).unwrap().e_equals(4.0));
+        assert!(depth.depth_at(4, 5).unwrap() > 4.0);
+        assert_eq!(depth.depth_at(0, 0).unwrap(), Scalar::INFINITY);
+    }
+
+    #[test]
+    fn render_layers() {
+        let w = World::test_world();
+        let mut c = Camera::new(11, 11, PI / 2.);
+        c.set_transform(Camera::view_transform(
+            Point::new(0, 0, -5),
+            Point::new(0, 0, 0),
+            Vector::new(0, 1, 0),
+        ));
+        c.passes = [RenderPass::Normal, RenderPass::Albedo, RenderPass::Ambient]
+            .into_iter()
+            .collect();
+        c.exposure_compensation = 1.0;
+        let layers = c.render_layers(&w).unwrap();
+        assert_eq!(
+            layers.beauty.pixel_at(5, 5).unwrap(),
+            c.render(&w).unwrap().pixel_at(5, 5).unwrap()
+        );
+        assert_eq!(layers.passes().len(), 3);
+        assert!(layers.get(RenderPass::Direct).is_none());
+        assert_eq!(
+            layers
+                .get(RenderPass::Normal)
+                .unwrap()
+                .pixel_at(5, 5)
+                .unwrap(),
+            Color::new(0, 0, -1)
+        );
+        // only light is exposed
+        assert_eq!(
+            layers
+                .get(RenderPass::Albedo)
+                .unwrap()
+                .pixel_at(5, 5)
+                .unwrap(),
+            Color::new(0.8, 1.0, 0.6)
+        );
+        assert_eq!(
+            layers
+                .get(RenderPass::Ambient)
+                .unwrap()
+                .pixel_at(5, 5)
+                .unwrap(),
+            Color::new(0.16, 0.2, 0.12)
+        );
+        assert_eq!(
+            layers
+                .get(RenderPass::Normal)
+                .unwrap()
+                .pixel_at(0, 0)
+                .unwrap(),
+            BLACK
+        );
+    }
+
+    #[test]
+    fn render_ambient_occlusion() {
+        let w = World::test_world();
+        let mut c = Camera::new(11, 11, PI / 2.);
+        c.set_transform(Camera::view_transform(
+            Point::new(0, 0, -5),
+            Point::new(0, 0, 0),
+            Vector::new(0, 1, 0),
+        ));
+        let image = c
+            .render_ambient_occlusion(&w, AmbientOcclusion::new(16, 10.0))
+            .unwrap();
+        assert_eq!(image.pixel_at(5, 5).unwrap(), WHITE);
+        assert_eq!(image.pixel_at(0, 0).unwrap(), BLACK);
+        assert!(image.is_opaque());
+    }
+
+    #[test]
+    fn render_object_ids() {
+        let mut w = World::test_world();
+        w.objects_mut()[0].set_id(Some(ObjectId(7)));
+        let mut c = Camera::new(11, 11, PI / 2.);
+        c.set_transform(Camera::view_transform(
+            Point::new(0, 0, -5),
+            Point::new(0, 0, 0),
+            Vector::new(0, 1, 0),
+        ));
+        let ids = c.render_object_ids(&w).unwrap();
+        assert_eq!(ids.id_at(5, 5), Ok(Some(ObjectId(7))));
+        assert_eq!(ids.coverage_at(5, 5).unwrap(), &[(ObjectId(7), 1.0)]);
+        assert_eq!(ids.id_at(0, 0), Ok(None));
+
+        // the edge of the sphere is partially covered
+        c.settings.samples_per_pixel = 16;
+        let ids = c.render_object_ids(&w).unwrap();
+        let (id, coverage) = ids.coverage_at(4, 5).unwrap()[0];
+        assert_eq!(id, ObjectId(7));
+        assert!(coverage > 0.0 && coverage < 1.0);
+    }
+
+    #[test]
+    fn transparent_background() {
+        let w = World::test_world();
+        let mut c = Camera::new(11, 11, PI / 2.);
+        c.set_transform(Camera::view_transform(
+            Point::new(0, 0, -5),
+            Point::new(0, 0, 0),
+            Vector::new(0, 1, 0),
+        ));
+        let image = c.render(&w).unwrap();
+        assert!(image.is_opaque());
+
+        c.settings.transparent_background = true;
+        c.settings.samples_per_pixel = 16;
+        let image = c.render(&w).unwrap();
+        assert!(!image.is_opaque());
+        assert_eq!(image.alpha_at(5, 5), Ok(1.0));
+        assert_eq!(image.alpha_at(0, 0), Ok(0.0));
+        assert_eq!(image.pixel_at(0, 0).unwrap(), BLACK);
+        let edge = image.alpha_at(4, 5).unwrap();
+        assert!(edge > 0.0 && edge < 1.0);
+    }
+
+    #[test]
+    fn max_distance() {
+        let w = World::test_world();
+        let mut c = Camera::new(11, 11, PI / 2.);
+        c.set_transform(Camera::view_transform(
+            Point::new(0, 0, -5),
+            Point::new(0, 0, 0),
+            Vector::new(0, 1, 0),
+        ));
+        assert_ne!(c.render(&w).unwrap().pixel_at(5, 5).unwrap(), BLACK);
+
+        // the sphere is 4 units away
+        c.settings.max_distance = 3.0;
+        assert_eq!(c.render(&w).unwrap().pixel_at(5, 5).unwrap(), BLACK);
+        c.settings.max_distance = 4.5;
+        assert_ne!(c.render(&w).unwrap().pixel_at(5, 5).unwrap(), BLACK);
+    }
+
+    #[test]
+    fn path_tracing() {
+        let w = World::test_world();
+        let mut c = Camera::new(11, 11, PI / 2.);
+        c.set_transform(Camera::view_transform(
+            Point::new(0, 0, -5),
+            Point::new(0, 0, 0),
+            Vector::new(0, 1, 0),
+        ));
+        let whitted = c.render(&w).unwrap().pixel_at(5, 5).unwrap();
+        c.integrator = Integrator::PathTracing;
+        c.settings.samples_per_pixel = 4;
+        let traced = c.render(&w).unwrap();
+        let center = traced.pixel_at(5, 5).unwrap();
+        // no ambient light, and nothing for the light to bounce off
+        assert!(center.green > 0.0 && center.green < whitted.green);
+        assert_eq!(traced.pixel_at(0, 0).unwrap(), BLACK);
+        assert_eq!(c.render(&w).unwrap().pixel_at(5, 5).unwrap(), center);
+
+        c.settings.transparent_background = true;
+        let traced = c.render(&w).unwrap();
+        assert_eq!(traced.alpha_at(0, 0), Ok(0.0));
+        assert_eq!(traced.pixel_at(5, 5).unwrap(), center);
+    }
+
+    #[test]
+    fn depth_of_field() {
+        let w = World::test_world();
+        let mut c = Camera::new(11, 11, PI / 2.);
+        c.set_transform(Camera::view_transform(
+            Point::new(0, 0, -5),
+            Point::new(0, 0, 0),
+            Vector::new(0, 1, 0),
+        ));
+        let pinhole = c.render(&w).unwrap();
+
+        // rays through the lens meet again in the focal plane
+        c.depth_of_field = Some(DepthOfField::new(0.5, 4.0));
+        let r = c.ray_through_pixel(5, 5, (0.5, 0.5), (1.0, 0.0));
+        assert_eq!(r.origin, Point::new(-0.5, 0, -5));
+        assert_eq!(r.position(4.0 / r.direction.z), Point::new(0, 0, -1));
+
+        // the sphere's surface is in focus, and blurs into its surroundings when focusing far behind it
+        c.settings.samples_per_pixel = 16;
+        let blurred = c.render(&w).unwrap();
+        assert!(
+            (blurred.pixel_at(5, 5).unwrap().green - pinhole.pixel_at(5, 5).unwrap().green).abs()
+                < 0.05
+        );
+        c.depth_of_field = Some(DepthOfField::new(2.0, 20.0));
+        let edge = c.render(&w).unwrap().pixel_at(3, 5).unwrap();
+        assert_eq!(pinhole.pixel_at(3, 5).unwrap(), BLACK);
+        assert!(edge.green > 0.0);
+
+        // a pinhole aperture changes nothing
+        c.depth_of_field = Some(
+            DepthOfField::new(0.0, 4.0).with_aperture(Aperture::Polygon {
+                blades: 6,
+                rotation: 0.0,
+            }),
+        );
+        c.settings.samples_per_pixel = 1;
+        assert_eq!(
+            c.render(&w).unwrap().pixel_at(5, 5).unwrap(),
+            pinhole.pixel_at(5, 5).unwrap()
+        );
+    }
+
+    #[test]
+    fn render_deep() {
+        let mut w = World::test_world();
+        w.objects_mut()[0].material_mut().transparency = 0.5;
+        let mut c = Camera::new(11, 11, PI / 2.);
+        c.set_transform(Camera::view_transform(
+            Point::new(0, 0, -5),
+            Point::new(0, 0, 0),
+            Vector::new(0, 1, 0),
+        ));
+        let deep = c.render_deep(&w).unwrap();
+        // the front of the transparent outer sphere, then the opaque inner one hiding everything behind it
+        let samples = deep.samples_at(5, 5).unwrap();
+        assert_eq!(samples.len(), 2);
+        assert!(samples[0].depth.e_equals(4.0) && samples[0].alpha.e_equals(0.5));
+        assert!(samples[1].depth.e_equals(4.5) && samples[1].alpha.e_equals(1.0));
+        assert!(deep.samples_at(0, 0).unwrap().is_empty());
+
+        let flat = deep.flatten();
+        assert_eq!(flat.alpha_at(5, 5), Ok(1.0));
+        assert_eq!(flat.alpha_at(0, 0), Ok(0.0));
+    }
+
+    #[test]
+    fn render_all() {
+        let mut w = World::test_world();
+        w.set_global_illumination(Some(GlobalIllumination::new(4, 0.5)));
+        let front = Camera::view_transform(
+            Point::new(0, 0, -5),
+            Point::new(0, 0, 0),
+            Vector::new(0, 1, 0),
+        );
+        let side = Camera::view_transform(
+            Point::new(5, 0, 0),
+            Point::new(0, 0, 0),
+            Vector::new(0, 1, 0),
+        );
+        let mut cameras = [Camera::new(11, 11, PI / 2.), Camera::new(5, 3, PI / 2.)];
+        cameras[0].set_transform(front);
+        cameras[1].set_transform(side);
+
+        let images = super::render_all(&w, &cameras).unwrap();
+        assert_eq!(images.len(), 2);
+        assert_eq!((images[1].width(), images[1].height()), (5, 3));
+        assert_eq!(
+            images[0].pixel_at(0, 0).unwrap(),
+            cameras[0].render(&w).unwrap().pixel_at(0, 0).unwrap()
+        );
+        // the right side of the sphere faces away from the light
+        assert_eq!(
+            images[1].pixel_at(2, 1).unwrap(),
+            cameras[1].render(&w).unwrap().pixel_at(2, 1).unwrap()
+        );
+        assert_ne!(
+            images[1].pixel_at(2, 1).unwrap(),
+            images[0].pixel_at(5, 5).unwrap()
+        );
+        assert!(super::render_all(&w, &[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn max_sample_radiance() {
+        let w = World::test_world();
+        let mut c = Camera::new(11, 11, PI / 2.);
+        c.set_transform(Camera::view_transform(
+            Point::new(0, 0, -5),
+            Point::new(0, 0, 0),
+            Vector::new(0, 1, 0),
+        ));
+        let unclamped = c.render(&w).unwrap().pixel_at(5, 5).unwrap();
+        c.max_sample_radiance = Some(0.3);
+        let clamped = c.render(&w).unwrap().pixel_at(5, 5).unwrap();
+        // scaled down to the limit, keeping the hue
+        assert_eq!(clamped, unclamped * (0.3 / unclamped.green));
+        c.max_sample_radiance = Some(1.0);
+        assert_eq!(c.render(&w).unwrap().pixel_at(5, 5).unwrap(), unclamped);
+
+        c.settings.samples_per_pixel = 4;
+        c.max_sample_radiance = Some(0.3);
+        let image = c.render(&w).unwrap();
+        for y in 0..11 {
+            for x in 0..11 {
+                let pixel = image.pixel_at(x, y).unwrap();
+                assert!(pixel.red.max(pixel.green).max(pixel.blue) <= 0.3 + 1e-9);
+            }
+        }
+    }
+
+    /// The exact channel values of all pixels, to compare renders bit for bit
+    // the bits are u32 with the "f32" feature
+    #[allow(clippy::useless_conversion)]
+    fn channel_bits(canvas: &Canvas) -> Vec<[u64; 3]> {
+        let mut bits = Vec::new();
+        for y in 0..canvas.height() {
+            for x in 0..canvas.width() {
+                let c = canvas.pixel_at(x, y).unwrap();
+                let exact = |channel: Scalar| u64::from(channel.to_bits());
+                bits.push([exact(c.red), exact(c.green), exact(c.blue)]);
+            }
+        }
+        bits
+    }
+
+    #[test]
+    fn seed_reproduces_renders() {
+        let mut w = World::test_world();
+        let mut floor = Plane::default();
+        floor.set_transformation_matrix(Mat4::new_translation(0, -1, 0));
+        w.add_object(Box::new(floor));
+        w.set_ambient_occlusion(Some(AmbientOcclusion::new(4, 2.0)));
+        w.set_global_illumination(Some(GlobalIllumination::new(4, 0.5)));
+        let mut c = Camera::new(11, 11, PI / 2.);
+        c.set_transform(Camera::view_transform(
+            Point::new(0, 0, -5),
+            Point::new(0, 0, 0),
+            Vector::new(0, 1, 0),
+        ));
+        c.settings.samples_per_pixel = 2;
+        c.seed = 3;
+        let image = channel_bits(&c.render(&w).unwrap());
+        assert_eq!(channel_bits(&c.render(&w).unwrap()), image);
+        let layers = c.render_layers(&w).unwrap();
+        assert_eq!(channel_bits(&layers.beauty), image);
+
+        // the seed also changes the noise of the world's sampling, not only the sample positions
+        c.settings.samples_per_pixel = 1;
+        let single = channel_bits(&c.render(&w).unwrap());
+        c.seed = 4;
+        assert_ne!(channel_bits(&c.render(&w).unwrap()), single);
+    }
+
+    #[test]
+    fn render_into() {
+        let w = World::test_world();
+        let mut c = Camera::new(11, 11, PI / 2.);
+        c.set_transform(Camera::view_transform(
+            Point::new(0, 0, -5),
+            Point::new(0, 0, 0),
+            Vector::new(0, 1, 0),
+        ));
+        let expected = Color::new(0.38066, 0.47583, 0.2855);
+
+        let mut canvas = Canvas::new(11, 11);
+        c.render_into(&w, &mut canvas).unwrap();
+        assert_eq!(canvas.pixel_at(5, 5).unwrap(), expected);
+        assert_eq!(
+            c.render_into(&w, &mut Canvas::new(10, 11)),
+            Err(CanvasError::InvalidSize)
+        );
+
+        let mut colors = vec![BLACK; 11 * 11];
+        c.render_into_colors(&w, &mut colors).unwrap();
+        assert_eq!(colors[5 * 11 + 5], expected);
+        assert_eq!(colors, canvas.as_slice());
+
+        let mut bytes = vec![0; 11 * 11 * 3];
+        c.render_into_rgb8(&w, &mut bytes).unwrap();
+        let i = (5 * 11 + 5) * 3;
+        assert_eq!(bytes[i..i + 3], expected.to_rgb8());
+        assert_eq!(
+            c.render_into_rgb8(&w, &mut bytes[1..]),
+            Err(CanvasError::InvalidSize)
+        );
+    }
+
+    #[test]
+    fn exposure_value() {
+        let mut c = Camera::new(11, 11, PI / 2.);
+        let color = Color::new(1.2, 2.4, 0.0);
+        assert_eq!(c.expose(color), color);
+        c.exposure_value = Some(1.0);
+        assert_eq!(c.expose(color), Color::new(0.5, 1.0, 0.0));
+    }
+
+    #[test]
+    fn exposure_compensation_and_white_balance() {
+        let mut c = Camera::new(11, 11, PI / 2.);
+        let color = Color::new(0.2, 0.4, 0.8);
+        c.exposure_compensation = 1.0;
+        assert_eq!(c.expose(color), Color::new(0.4, 0.8, 1.6));
+        c.exposure_compensation = -1.0;
+        c.white_balance = Color::new(2.0, 1.0, 0.5);
+        assert_eq!(c.expose(color), Color::new(0.2, 0.2, 0.2));
+        c.exposure_value = Some(1.0);
+        c.exposure_compensation = 0.0;
+        c.white_balance = WHITE;
+        assert_eq!(
+            c.expose(Color::new(1.2, 2.4, 0.0)),
+            Color::new(0.5, 1.0, 0.0)
+        );
+    }
 }
 
 #[cfg(test)]
 #[cfg(feature = "rayon")]
 mod par_tests {
-    use std::f64::consts::PI;
-
     use crate::{
         camera::Camera,
         color::Color,
+        scalar::consts::PI,
         tuple::{Point, Vector},
         world::World,
     };
@@ -0,0 +1,223 @@
+//! Smooth camera movements for fly-through sequences
+use crate::{
+    camera::Camera,
+    matrix::Mat4,
+    scalar::Scalar,
+    tuple::{Point, Vector},
+};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+/// How the camera speeds up and slows down between two waypoints of a [`CameraPath`]
+pub enum Easing {
+    /// Constant speed
+    #[default]
+    Linear,
+    /// Starts slowly and speeds up
+    EaseIn,
+    /// Starts fast and slows down
+    EaseOut,
+    /// Starts and ends slowly (smoothstep), so the camera comes to a halt at every waypoint
+    EaseInOut,
+}
+
+impl Easing {
+    /// Maps the progress ```t``` (between 0 and 1) to the eased progress
+    pub fn apply(&self, t: Scalar) -> Scalar {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2.0 - t),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+/// A point the camera passes, together with where it looks at that moment
+pub struct Waypoint {
+    /// Where the camera is
+    pub position: Point,
+    /// The point the camera looks at
+    pub target: Point,
+}
+
+impl Waypoint {
+    /// A waypoint at ```position```, looking at ```target```
+    pub fn new(position: Point, target: Point) -> Self {
+        Self { position, target }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// A camera path through a list of [`Waypoint`]s. Positions and targets are interpolated between consecutive waypoints.
+pub struct CameraPath {
+    /// The waypoints in the order they are passed
+    pub waypoints: Vec<Waypoint>,
+    /// The approximate up direction of the camera, see [`Camera::view_transform`]
+    pub up: Vector,
+    /// The easing applied to every segment between two waypoints
+    pub easing: Easing,
+}
+
+impl CameraPath {
+    /// An empty path with the given up direction and easing
+    pub fn new(up: Vector, easing: Easing) -> Self {
+        Self {
+            waypoints: Vec::new(),
+            up,
+            easing,
+        }
+    }
+
+    /// Appends a waypoint at ```position```, looking at ```target```
+    pub fn add_waypoint(&mut self, position: Point, target: Point) {
+        self.waypoints.push(Waypoint::new(position, target));
+    }
+
+    /// The view transformation at ```t``` (0 at the first waypoint, 1 at the last one), each segment taking the same time.
+    /// Returns None if the path has no waypoints.
+    pub fn view_transform_at(&self, t: Scalar) -> Option<Mat4> {
+        let (first, rest) = self.waypoints.split_first()?;
+        if rest.is_empty() {
+            return Some(Camera::view_transform(
+                first.position,
+                first.target,
+                self.up,
+            ));
+        }
+
+        let segments = rest.len();
+        let scaled = t.clamp(0.0, 1.0) * segments as Scalar;
+        let segment = (scaled.floor() as usize).min(segments - 1);
+        let local = self.easing.apply(scaled - segment as Scalar);
+
+        let from = self.waypoints[segment];
+        let to = self.waypoints[segment + 1];
+        let position = from.position.lerp(to.position, local);
+        let target = from.target.lerp(to.target, local);
+        Some(Camera::view_transform(position, target, self.up))
+    }
+
+    /// A copy of ```camera``` positioned on the path at ```t```, see [`Self::view_transform_at`]
+    pub fn camera_at(&self, camera: &Camera, t: Scalar) -> Option<Camera> {
+        let mut camera = *camera;
+        camera.set_transform(self.view_transform_at(t)?);
+        Some(camera)
+    }
+
+    /// ```count``` cameras evenly spread along the whole path, the first at the first and the last at the last waypoint.
+    /// Returns an empty list if the path has no waypoints.
+    pub fn frames(&self, camera: &Camera, count: usize) -> Vec<Camera> {
+        (0..count)
+            .filter_map(|frame| {
+                let t = if count > 1 {
+                    frame as Scalar / (count - 1) as Scalar
+                } else {
+                    0.0
+                };
+                self.camera_at(camera, t)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod camera_path_tests {
+    use crate::{
+        camera::Camera,
+        camera_path::{CameraPath, Easing},
+        scalar::consts::PI,
+        tuple::{Point, Vector},
+    };
+
+    fn test_path(easing: Easing) -> CameraPath {
+        let mut path = CameraPath::new(Vector::new(0, 1, 0), easing);
+        path.add_waypoint(Point::new(0, 0, -10), Point::new(0, 0, 0));
+        path.add_waypoint(Point::new(0, 0, -6), Point::new(0, 0, 0));
+        path.add_waypoint(Point::new(4, 0, -6), Point::new(4, 0, 0));
+        path
+    }
+
+    #[test]
+    fn easing() {
+        for easing in [
+            Easing::Linear,
+            Easing::EaseIn,
+            Easing::EaseOut,
+            Easing::EaseInOut,
+        ] {
+            assert_eq!(easing.apply(0.0), 0.0);
+            assert_eq!(easing.apply(1.0), 1.0);
+            assert_eq!(easing.apply(2.0), 1.0);
+        }
+        assert_eq!(Easing::Linear.apply(0.25), 0.25);
+        assert_eq!(Easing::EaseIn.apply(0.5), 0.25);
+        assert_eq!(Easing::EaseOut.apply(0.5), 0.75);
+        assert_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+        assert!(Easing::EaseInOut.apply(0.1) < 0.1);
+    }
+
+    #[test]
+    fn waypoints_are_reached() {
+        let path = test_path(Easing::EaseInOut);
+        let up = Vector::new(0, 1, 0);
+        assert_eq!(
+            path.view_transform_at(0.0).unwrap(),
+            Camera::view_transform(Point::new(0, 0, -10), Point::new(0, 0, 0), up)
+        );
+        assert_eq!(
+            path.view_transform_at(0.5).unwrap(),
+            Camera::view_transform(Point::new(0, 0, -6), Point::new(0, 0, 0), up)
+        );
+        assert_eq!(
+            path.view_transform_at(1.0).unwrap(),
+            Camera::view_transform(Point::new(4, 0, -6), Point::new(4, 0, 0), up)
+        );
+    }
+
+    #[test]
+    fn interpolates() {
+        let path = test_path(Easing::Linear);
+        assert_eq!(
+            path.view_transform_at(0.25).unwrap(),
+            Camera::view_transform(
+                Point::new(0, 0, -8),
+                Point::new(0, 0, 0),
+                Vector::new(0, 1, 0)
+            )
+        );
+        let eased = test_path(Easing::EaseIn);
+        assert_eq!(
+            eased.view_transform_at(0.25).unwrap(),
+            Camera::view_transform(
+                Point::new(0, 0, -9),
+                Point::new(0, 0, 0),
+                Vector::new(0, 1, 0)
+            )
+        );
+    }
+
+    #[test]
+    fn frames() {
+        let camera = Camera::new(10, 10, PI / 2.);
+        let path = test_path(Easing::Linear);
+        let frames = path.frames(&camera, 5);
+        assert_eq!(frames.len(), 5);
+        assert_eq!(frames[0].transform(), path.view_transform_at(0.0).unwrap());
+        assert_eq!(frames[1].transform(), path.view_transform_at(0.25).unwrap());
+        assert_eq!(frames[4].transform(), path.view_transform_at(1.0).unwrap());
+        assert_eq!(frames[2].hsize, 10);
+    }
+
+    #[test]
+    fn empty_and_single() {
+        let camera = Camera::new(10, 10, PI / 2.);
+        let mut path = CameraPath::new(Vector::new(0, 1, 0), Easing::Linear);
+        assert!(path.camera_at(&camera, 0.5).is_none());
+        assert!(path.frames(&camera, 3).is_empty());
+        path.add_waypoint(Point::new(1, 2, 3), Point::new(0, 0, 0));
+        assert_eq!(path.frames(&camera, 3).len(), 3);
+        assert_eq!(path.view_transform_at(0.7), path.view_transform_at(0.0));
+    }
+}
@@ -1,4 +1,7 @@
-use crate::color::Color;
+use crate::{color::Color, quantize::Quantization, scalar::Scalar};
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
 
 const BASE_COLOR: Color = Color {
     red: 0.,
@@ -11,33 +14,79 @@ const BASE_COLOR: Color = Color {
 pub enum CanvasError {
     /// The provided coordinates are not inside the dimensions of the canvas.
     InvalidCoordinates,
+    /// The provided canvas or buffer does not have the expected size.
+    InvalidSize,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Operators compressing colors brighter than 1.0 into the displayable range, instead of just clipping them.
+pub enum ToneMapping {
+    /// c / (1 + c) per component. Simple, but desaturates and flattens bright areas.
+    Reinhard,
+    /// An approximation of the ACES filmic curve, with more contrast and a softer roll-off into white
+    AcesApprox,
+}
+
+impl ToneMapping {
+    /// Maps a single (linear) color component
+    pub fn map(&self, c: Scalar) -> Scalar {
+        let c = c.max(0.0);
+        match self {
+            ToneMapping::Reinhard => c / (1.0 + c),
+            ToneMapping::AcesApprox => {
+                ((c * (2.51 * c + 0.03)) / (c * (2.43 * c + 0.59) + 0.14)).clamp(0.0, 1.0)
+            }
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// How the pixels of another canvas are combined with those of a canvas, see [`Canvas::composite`].
+/// As canvas colors are premultiplied with their alpha, no mode needs to multiply them again.
+pub enum BlendMode {
+    /// Replaces color and alpha, see [`Canvas::blit`]
+    Replace,
+    /// Puts the other canvas in front, letting this one show through where it is transparent
+    Over,
+    /// Adds the colors, e.g. for light passes rendered separately
+    Add,
+    /// Multiplies the colors, e.g. for ambient occlusion over the beauty pass. Keeps the alpha of this canvas.
+    Multiply,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// How pixels are sampled when resizing a canvas, see [`Canvas::resized`]
+pub enum Resampling {
+    /// Takes the closest pixel, keeping hard pixel edges when scaling up
+    Nearest,
+    /// Interpolates between the four closest pixels. Averages pairs of pixels when scaling down by half.
+    Bilinear,
 }
 
 #[derive(Clone, Debug)]
 /// The canvas this renderer draws it results on.
+///
+/// Besides colors, every pixel has an alpha value between 0 (transparent) and 1 (opaque, the default).
+/// Colors of partially transparent pixels are premultiplied with their alpha.
 pub struct Canvas {
-    canvas: Vec<Vec<Color>>,
+    /// Row by row, top to bottom
+    pixels: Vec<Color>,
+    /// Only allocated once a pixel is not opaque
+    alpha: Option<Vec<Scalar>>,
     width: usize,
     height: usize,
 }
 
 impl Canvas {
-    ///
+    /// A new canvas, every pixel black
     pub fn new(width: usize, height: usize) -> Self {
         Canvas::new_with_color(width, height, BASE_COLOR)
     }
     /// A new canvas, every pixel filled with the provided [`Color`]
     pub fn new_with_color(width: usize, height: usize, color: Color) -> Self {
-        let mut vec = Vec::with_capacity(height);
-        for _i in 0..height {
-            let mut inner_vec: Vec<Color> = Vec::with_capacity(width);
-            for _j in 0..width {
-                inner_vec.push(color)
-            }
-            vec.push(inner_vec);
-        }
         Canvas {
-            canvas: vec,
+            pixels: vec![color; width * height],
+            alpha: None,
             height,
             width,
         }
@@ -49,7 +98,7 @@ impl Canvas {
         if !self.check_coordinates(x, y) {
             return Err(CanvasError::InvalidCoordinates);
         }
-        Ok(self.canvas[y][x])
+        Ok(self.pixels[y * self.width + x])
     }
 
     /// Sets the [`Color`] of the pixel at the provided coordinates.
@@ -58,15 +107,183 @@ impl Canvas {
         if !self.check_coordinates(x, y) {
             return Err(CanvasError::InvalidCoordinates);
         }
-        self.canvas[y][x] = color;
+        self.pixels[y * self.width + x] = color;
         Ok(())
     }
 
+    /// Returns the alpha of the pixel at the provided coordinates, 0 being transparent and 1 opaque.
+    /// Returns a [`CanvasError::InvalidCoordinates`] if the provided coordinates are not inside of the canvas dimensions.
+    pub fn alpha_at(&self, x: usize, y: usize) -> Result<Scalar, CanvasError> {
+        if !self.check_coordinates(x, y) {
+            return Err(CanvasError::InvalidCoordinates);
+        }
+        Ok(self
+            .alpha
+            .as_ref()
+            .map_or(1.0, |alpha| alpha[y * self.width + x]))
+    }
+
+    /// Sets the alpha of the pixel at the provided coordinates, 0 being transparent and 1 opaque.
+    /// Returns a [`CanvasError::InvalidCoordinates`] if the provided coordinates are not inside of the canvas dimensions.
+    pub fn write_alpha(&mut self, x: usize, y: usize, alpha: Scalar) -> Result<(), CanvasError> {
+        if !self.check_coordinates(x, y) {
+            return Err(CanvasError::InvalidCoordinates);
+        }
+        if self.alpha.is_none() && alpha == 1.0 {
+            return Ok(());
+        }
+        let width = self.width;
+        let height = self.height;
+        self.alpha.get_or_insert_with(|| vec![1.0; width * height])[y * width + x] = alpha;
+        Ok(())
+    }
+
+    /// The color (not premultiplied) and alpha (clamped to 0 to 1) of the pixel, as image files store them.
+    /// Panics if the coordinates are not inside of the canvas.
+    pub(crate) fn straight_at(&self, x: usize, y: usize) -> (Color, Scalar) {
+        let color = self
+            .pixel_at(x, y)
+            .expect("Canvas WIDTH and HEIGHT volation.");
+        let alpha = self
+            .alpha_at(x, y)
+            .expect("Canvas WIDTH and HEIGHT volation.")
+            .clamp(0.0, 1.0);
+        if alpha > 0.0 && alpha < 1.0 {
+            (color * (1.0 / alpha), alpha)
+        } else {
+            (color, alpha)
+        }
+    }
+
+    /// Whether every pixel is fully opaque
+    pub fn is_opaque(&self) -> bool {
+        self.alpha.iter().flatten().all(|a| *a >= 1.0)
+    }
+
     /// Checks if the coordinates provided are valid (inside of the canvas's dimensions)
     pub const fn check_coordinates(&self, x: usize, y: usize) -> bool {
         !(x >= self.width || y >= self.height)
     }
 
+    /// Compresses the colors of all pixels into the range between 0 and 1 using the given operator.
+    /// Before that, colors are multiplied by 2^```exposure``` (in stops), 0 leaving them as they are.
+    /// Apply this after rendering and before writing the canvas to a file.
+    pub fn tone_map(&mut self, operator: ToneMapping, exposure: Scalar) {
+        let factor = exposure.exp2();
+        for pixel in self.pixels.iter_mut() {
+            let c = *pixel * factor;
+            *pixel = Color::new(
+                operator.map(c.red),
+                operator.map(c.green),
+                operator.map(c.blue),
+            );
+        }
+    }
+
+    /// Brightens (positive) or darkens (negative) all pixels by ```exposure``` stops and multiplies their channels with ```white_balance```,
+    /// the same adjustment as [`crate::camera::Camera::exposure_compensation`] and [`crate::camera::Camera::white_balance`], but after rendering.
+    pub fn adjust_exposure(&mut self, exposure: Scalar, white_balance: Color) {
+        let factor = exposure.exp2();
+        for pixel in self.pixels.iter_mut() {
+            *pixel = *pixel * factor * white_balance;
+        }
+    }
+
+    /// Copies ```other``` into this canvas with its top left corner at (```x```, ```y```), including its alpha.
+    /// Pixels falling outside of this canvas are left out.
+    pub fn blit(&mut self, other: &Canvas, x: usize, y: usize) {
+        self.composite(other, x, y, BlendMode::Replace);
+    }
+
+    /// Combines ```other``` with this canvas using ```mode```, with its top left corner at (```x```, ```y```).
+    /// Pixels falling outside of this canvas are left out.
+    pub fn composite(&mut self, other: &Canvas, x: usize, y: usize, mode: BlendMode) {
+        let width = other.width.min(self.width.saturating_sub(x));
+        let height = other.height.min(self.height.saturating_sub(y));
+        for other_y in 0..height {
+            for other_x in 0..width {
+                let source = other.pixels[other_y * other.width + other_x];
+                let source_alpha = other
+                    .alpha
+                    .as_ref()
+                    .map_or(1.0, |alpha| alpha[other_y * other.width + other_x]);
+                let index = (y + other_y) * self.width + x + other_x;
+                let destination = self.pixels[index];
+                let destination_alpha = self.alpha.as_ref().map_or(1.0, |alpha| alpha[index]);
+
+                let (color, alpha) = match mode {
+                    BlendMode::Replace => (source, source_alpha),
+                    BlendMode::Over => (
+                        source + destination * (1.0 - source_alpha),
+                        source_alpha + destination_alpha * (1.0 - source_alpha),
+                    ),
+                    BlendMode::Add => (
+                        source + destination,
+                        (source_alpha + destination_alpha).min(1.0),
+                    ),
+                    BlendMode::Multiply => (source * destination, destination_alpha),
+                };
+                self.pixels[index] = color;
+                self.write_alpha(x + other_x, y + other_y, alpha)
+                    .expect("Canvas WIDTH and HEIGHT volation.");
+            }
+        }
+    }
+
+    /// A copy of this canvas scaled to ```width``` x ```height```, e.g. to upscale a small preview for display,
+    /// or to downsample a larger render for antialiasing. Alpha is resampled as well.
+    pub fn resized(&self, width: usize, height: usize, resampling: Resampling) -> Canvas {
+        let mut resized = Canvas::new(width, height);
+        if self.width == 0 || self.height == 0 {
+            return resized;
+        }
+        let scale_x = self.width as Scalar / width as Scalar;
+        let scale_y = self.height as Scalar / height as Scalar;
+        for y in 0..height {
+            for x in 0..width {
+                // pixel centers are mapped onto each other, so the image does not shift
+                let source_x = (x as Scalar + 0.5) * scale_x;
+                let source_y = (y as Scalar + 0.5) * scale_y;
+                let (color, alpha) = match resampling {
+                    Resampling::Nearest => self.sample(source_x as usize, source_y as usize),
+                    Resampling::Bilinear => self.sample_bilinear(source_x - 0.5, source_y - 0.5),
+                };
+                resized.pixels[y * width + x] = color;
+                resized
+                    .write_alpha(x, y, alpha)
+                    .expect("Canvas WIDTH and HEIGHT volation.");
+            }
+        }
+        resized
+    }
+
+    /// Color and alpha of the pixel, with coordinates clamped to the canvas
+    fn sample(&self, x: usize, y: usize) -> (Color, Scalar) {
+        let index = y.min(self.height - 1) * self.width + x.min(self.width - 1);
+        (
+            self.pixels[index],
+            self.alpha.as_ref().map_or(1.0, |alpha| alpha[index]),
+        )
+    }
+
+    /// Color and alpha interpolated between the four pixels around (```x```, ```y```), in pixel units
+    fn sample_bilinear(&self, x: Scalar, y: Scalar) -> (Color, Scalar) {
+        let x = x.max(0.0);
+        let y = y.max(0.0);
+        let (left, top) = (x.floor() as usize, y.floor() as usize);
+        let (fx, fy) = (x.fract(), y.fract());
+        let lerp = |(a, a_alpha): (Color, Scalar), (b, b_alpha): (Color, Scalar), t: Scalar| {
+            (a.lerp(b, t), a_alpha * (1.0 - t) + b_alpha * t)
+        };
+        let upper = lerp(self.sample(left, top), self.sample(left + 1, top), fx);
+        let lower = lerp(
+            self.sample(left, top + 1),
+            self.sample(left + 1, top + 1),
+            fx,
+        );
+        lerp(upper, lower, fy)
+    }
+
     #[mutants::skip]
     /// Vertical size of the canvas
     pub const fn height(&self) -> usize {
@@ -79,18 +296,328 @@ impl Canvas {
         self.width
     }
 
+    /// The colors of all pixels, row by row from the top, e.g. for handing them to other libraries at once.
+    /// The pixel at (x, y) is at index ```y * width + x```.
+    pub fn as_slice(&self) -> &[Color] {
+        &self.pixels
+    }
+
+    /// The colors of all pixels for changing them at once, see [`Self::as_slice`]
+    pub fn as_mut_slice(&mut self) -> &mut [Color] {
+        &mut self.pixels
+    }
+
+    /// The rows of the canvas, from the top
+    pub fn rows(&self) -> impl Iterator<Item = &[Color]> + '_ {
+        // an empty canvas of width 0 has no pixels, so the chunk size does not matter for it
+        self.pixels.chunks(self.width.max(1))
+    }
+
+    /// The pixels as 8 bit RGB, row by row from the top (```width * height * 3``` bytes), converted as given by ```quantization```.
+    /// ```Quantization::default()``` gives sRGB like [`Color::to_rgb8`]. Alpha is left out.
+    pub fn to_rgb8(&self, quantization: Quantization) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.pixels.len() * 3);
+        for (i, color) in self.pixels.iter().enumerate() {
+            let (x, y) = (i % self.width, i / self.width);
+            bytes.extend_from_slice(&quantization.quantize(*color, x, y));
+        }
+        bytes
+    }
+
+    /// The pixels as 8 bit RGBA like [`Self::to_rgb8`] (```width * height * 4``` bytes), as expected by window surfaces, GPU textures and the ```image``` crate.
+    /// Colors are not premultiplied with alpha.
+    pub fn to_rgba8(&self, quantization: Quantization) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.pixels.len() * 4);
+        for i in 0..self.pixels.len() {
+            let (x, y) = (i % self.width, i / self.width);
+            let (color, alpha) = self.straight_at(x, y);
+            bytes.extend_from_slice(&quantization.quantize(color, x, y));
+            bytes.push((alpha * 255.0).round() as u8);
+        }
+        bytes
+    }
+
+    /// The rows of the canvas for changing them, from the top. Rows are disjoint, so they can be handed to different threads.
+    pub fn rows_mut(&mut self) -> impl Iterator<Item = &mut [Color]> + '_ {
+        self.pixels.chunks_mut(self.width.max(1))
+    }
+
+    /// The rows of the canvas for changing them in parallel, e.g. by a renderer or filter, see [`Self::rows_mut`]
+    #[cfg(feature = "rayon")]
+    pub fn par_rows_mut(&mut self) -> impl IndexedParallelIterator<Item = &mut [Color]> + '_ {
+        self.pixels.par_chunks_mut(self.width.max(1))
+    }
+
+    /// The rows of the canvas together with their alpha, like [`Self::par_rows_mut`]
+    #[cfg(feature = "rayon")]
+    pub(crate) fn par_rows_with_alpha_mut(
+        &mut self,
+    ) -> impl IndexedParallelIterator<Item = (&mut [Color], &mut [Scalar])> + '_ {
+        let width = self.width.max(1);
+        let size = self.pixels.len();
+        let alpha = self.alpha.get_or_insert_with(|| vec![1.0; size]);
+        self.pixels
+            .par_chunks_mut(width)
+            .zip(alpha.par_chunks_mut(width))
+    }
+
+    /// Compares this canvas with ```other``` (e.g. a reference render), channel by channel including alpha.
+    /// Channels differing by at most ```epsilon``` count as equal.
+    /// Returns a [`CanvasError::InvalidSize`] if the canvases differ in size.
+    pub fn diff(&self, other: &Canvas, epsilon: Scalar) -> Result<DiffReport, CanvasError> {
+        if self.width != other.width || self.height != other.height {
+            return Err(CanvasError::InvalidSize);
+        }
+        let mut errors = Vec::with_capacity(self.pixels.len());
+        let mut sum = 0.0;
+        for i in 0..self.pixels.len() {
+            let (a, b) = (self.pixels[i], other.pixels[i]);
+            let alpha = |canvas: &Canvas| canvas.alpha.as_ref().map_or(1.0, |alpha| alpha[i]);
+            let channels = [
+                (a.red - b.red).abs(),
+                (a.green - b.green).abs(),
+                (a.blue - b.blue).abs(),
+                (alpha(self) - alpha(other)).abs(),
+            ];
+            sum += channels.iter().sum::<Scalar>();
+            errors.push(channels.into_iter().fold(0.0, Scalar::max));
+        }
+        Ok(DiffReport {
+            max_error: errors.iter().copied().fold(0.0, Scalar::max),
+            mean_error: if errors.is_empty() {
+                0.0
+            } else {
+                sum / (errors.len() * 4) as Scalar
+            },
+            differing_pixels: errors.iter().filter(|error| **error > epsilon).count(),
+            epsilon,
+            errors,
+            width: self.width,
+            height: self.height,
+        })
+    }
+
+    /// A borrowed view of the ```width``` x ```height``` region with its top left corner at (```x```, ```y```), e.g. a tile of a render.
+    /// Returns a [`CanvasError::InvalidCoordinates`] if the region is not inside of the canvas.
+    pub fn view(
+        &self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) -> Result<CanvasView<'_>, CanvasError> {
+        let fits = |start: usize, size: usize, limit: usize| {
+            start.checked_add(size).is_some_and(|end| end <= limit)
+        };
+        if !fits(x, width, self.width) || !fits(y, height, self.height) {
+            return Err(CanvasError::InvalidCoordinates);
+        }
+        Ok(CanvasView {
+            canvas: self,
+            x,
+            y,
+            width,
+            height,
+        })
+    }
+
+    /// A copy of the ```width``` x ```height``` region with its top left corner at (```x```, ```y```), including alpha.
+    /// Returns a [`CanvasError::InvalidCoordinates`] if the region is not inside of the canvas.
+    pub fn crop(
+        &self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) -> Result<Canvas, CanvasError> {
+        Ok(self.view(x, y, width, height)?.to_canvas())
+    }
+
     #[mutants::skip]
-    /// Returns the backing [`Vec`] of this canvas.
-    pub fn get_canvas(&self) -> &Vec<Vec<Color>> {
-        &self.canvas
+    #[deprecated(note = "copies all pixels, use as_slice or rows instead")]
+    /// Returns the pixels of this canvas as a vector of rows.
+    pub fn get_canvas(&self) -> Vec<Vec<Color>> {
+        self.rows().map(<[Color]>::to_vec).collect()
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+/// A rectangular region of a canvas, borrowed instead of copied, see [`Canvas::view`]. Coordinates are relative to the region.
+pub struct CanvasView<'a> {
+    canvas: &'a Canvas,
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+
+impl<'a> CanvasView<'a> {
+    /// Returns the [`Color`] of the pixel at the provided coordinates.
+    /// Returns a [`CanvasError::InvalidCoordinates`] if the provided coordinates are not inside of the view.
+    pub fn pixel_at(&self, x: usize, y: usize) -> Result<Color, CanvasError> {
+        if x >= self.width || y >= self.height {
+            return Err(CanvasError::InvalidCoordinates);
+        }
+        self.canvas.pixel_at(self.x + x, self.y + y)
+    }
+
+    /// Returns the alpha of the pixel at the provided coordinates, see [`Canvas::alpha_at`].
+    /// Returns a [`CanvasError::InvalidCoordinates`] if the provided coordinates are not inside of the view.
+    pub fn alpha_at(&self, x: usize, y: usize) -> Result<Scalar, CanvasError> {
+        if x >= self.width || y >= self.height {
+            return Err(CanvasError::InvalidCoordinates);
+        }
+        self.canvas.alpha_at(self.x + x, self.y + y)
+    }
+
+    /// Horizontal size of the view
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Vertical size of the view
+    pub const fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The rows of the view, from the top
+    pub fn rows(&self) -> impl Iterator<Item = &'a [Color]> + '_ {
+        let canvas: &'a Canvas = self.canvas;
+        (self.y..self.y + self.height).map(move |y| {
+            let start = y * canvas.width + self.x;
+            &canvas.pixels[start..start + self.width]
+        })
+    }
+
+    /// A copy of the region as a canvas of its own, including alpha
+    pub fn to_canvas(&self) -> Canvas {
+        let mut canvas = Canvas::new(self.width, self.height);
+        for (target, row) in canvas.rows_mut().zip(self.rows()) {
+            target.copy_from_slice(row);
+        }
+        if !self.canvas.is_opaque() {
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    let alpha = self
+                        .alpha_at(x, y)
+                        .expect("Canvas WIDTH and HEIGHT volation.");
+                    canvas
+                        .write_alpha(x, y, alpha)
+                        .expect("Canvas WIDTH and HEIGHT volation.");
+                }
+            }
+        }
+        canvas
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// How much two canvases differ, see [`Canvas::diff`]. For image regression tests against reference renders.
+pub struct DiffReport {
+    /// The largest difference of a single channel
+    pub max_error: Scalar,
+    /// The average difference over all channels of all pixels
+    pub mean_error: Scalar,
+    /// The number of pixels with a channel differing by more than epsilon
+    pub differing_pixels: usize,
+    epsilon: Scalar,
+    /// The largest channel difference per pixel
+    errors: Vec<Scalar>,
+    width: usize,
+    height: usize,
+}
+
+impl DiffReport {
+    /// Whether no pixel differs by more than epsilon
+    pub fn is_match(&self) -> bool {
+        self.differing_pixels == 0
+    }
+
+    /// An image of where the canvases differ: black where they match, red for differing pixels, the brighter the larger the difference
+    pub fn heatmap(&self) -> Canvas {
+        let mut heatmap = Canvas::new(self.width, self.height);
+        for (pixel, error) in heatmap.pixels.iter_mut().zip(&self.errors) {
+            if *error > self.epsilon {
+                *pixel = Color::new(error / self.max_error, 0.0, 0.0);
+            }
+        }
+        heatmap
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// The distance from the camera to the first object hit, per pixel. Pixels that do not hit anything are infinitely far away.
+pub struct DepthBuffer {
+    depths: Vec<Scalar>,
+    width: usize,
+    height: usize,
+}
+
+impl DepthBuffer {
+    /// A new depth buffer, every pixel infinitely far away
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            depths: vec![Scalar::INFINITY; width * height],
+            width,
+            height,
+        }
+    }
+
+    /// Returns the depth of the pixel at the provided coordinates.
+    /// Returns a [`CanvasError::InvalidCoordinates`] if the provided coordinates are not inside of the buffer dimensions.
+    pub fn depth_at(&self, x: usize, y: usize) -> Result<Scalar, CanvasError> {
+        if x >= self.width || y >= self.height {
+            return Err(CanvasError::InvalidCoordinates);
+        }
+        Ok(self.depths[y * self.width + x])
+    }
+
+    /// Sets the depth of the pixel at the provided coordinates.
+    /// Returns a [`CanvasError::InvalidCoordinates`] if the provided coordinates are not inside of the buffer dimensions.
+    pub fn write_depth(&mut self, x: usize, y: usize, depth: Scalar) -> Result<(), CanvasError> {
+        if x >= self.width || y >= self.height {
+            return Err(CanvasError::InvalidCoordinates);
+        }
+        self.depths[y * self.width + x] = depth;
+        Ok(())
+    }
+
+    /// Horizontal size of the buffer
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Vertical size of the buffer
+    pub const fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The depths of all pixels, row by row
+    pub fn depths(&self) -> &[Scalar] {
+        &self.depths
+    }
+
+    /// A grayscale image of the depths for viewing: white at ```near``` and closer, black at ```far``` and beyond.
+    pub fn to_canvas(&self, near: Scalar, far: Scalar) -> Canvas {
+        let mut canvas = Canvas::new(self.width, self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let depth = self.depths[y * self.width + x];
+                let value = (1.0 - (depth - near) / (far - near)).clamp(0.0, 1.0);
+                canvas.pixels[y * self.width + x] = Color::new(value, value, value);
+            }
+        }
+        canvas
     }
 }
 
 #[cfg(test)]
 mod canvas_tests {
     use crate::{
-        canvas::{Canvas, CanvasError},
-        color::Color,
+        canvas::{BlendMode, Canvas, CanvasError, Resampling, ToneMapping},
+        color::{Color, BLACK, WHITE},
+        quantize::{Gamma, Quantization},
+        scalar::Scalar,
     };
 
     const RED: Color = Color {
@@ -164,4 +691,313 @@ mod canvas_tests {
         assert_eq!(canvas.width(), 10);
         assert_eq!(canvas.height(), 20);
     }
+
+    #[test]
+    fn slices_and_rows() {
+        let mut canvas = Canvas::new(3, 2);
+        canvas.write_pixel(2, 0, RED).unwrap();
+        assert_eq!(canvas.as_slice().len(), 6);
+        assert_eq!(canvas.as_slice()[2], RED);
+
+        canvas.as_mut_slice()[4] = RED;
+        assert_eq!(canvas.pixel_at(1, 1), Ok(RED));
+
+        let rows: Vec<&[Color]> = canvas.rows().collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1], &[BLACK, RED, BLACK]);
+        #[allow(deprecated)]
+        let copied = canvas.get_canvas();
+        assert_eq!(copied, rows);
+        assert_eq!(Canvas::new(0, 0).rows().count(), 0);
+    }
+
+    #[test]
+    fn rows_mut() {
+        let mut canvas = Canvas::new(2, 3);
+        for (y, row) in canvas.rows_mut().enumerate() {
+            row[1] = Color::new(y as Scalar, 0.0, 0.0);
+        }
+        assert_eq!(canvas.pixel_at(1, 2), Ok(Color::new(2, 0, 0)));
+        assert_eq!(canvas.pixel_at(0, 2), Ok(BLACK));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn par_rows_mut() {
+        use rayon::prelude::*;
+
+        let mut canvas = Canvas::new(2, 3);
+        canvas
+            .par_rows_mut()
+            .enumerate()
+            .for_each(|(y, row)| row[0] = Color::new(0.0, y as Scalar, 0.0));
+        assert_eq!(canvas.pixel_at(0, 1), Ok(Color::new(0, 1, 0)));
+        assert!(canvas.is_opaque());
+
+        canvas
+            .par_rows_with_alpha_mut()
+            .for_each(|(_, alpha)| alpha[1] = 0.5);
+        assert_eq!(canvas.alpha_at(1, 2), Ok(0.5));
+        assert_eq!(canvas.alpha_at(0, 2), Ok(1.0));
+    }
+
+    #[test]
+    fn rgb8_and_rgba8() {
+        let mut c = Canvas::new(2, 1);
+        c.write_pixel(0, 0, RED * 0.5).unwrap();
+        c.write_alpha(0, 0, 0.5).unwrap();
+        c.write_pixel(1, 0, Color::new(2.0, 0.5, 0.0)).unwrap();
+        let quantization = Quantization::new(Gamma::Linear);
+        assert_eq!(c.to_rgb8(quantization), vec![128, 0, 0, 255, 128, 0]);
+        assert_eq!(
+            c.to_rgba8(quantization),
+            vec![255, 0, 0, 128, 255, 128, 0, 255]
+        );
+        assert_eq!(
+            c.to_rgb8(Quantization::default())[3..],
+            Color::new(2.0, 0.5, 0.0).to_rgb8()
+        );
+    }
+
+    #[test]
+    fn alpha() {
+        let mut c = Canvas::new(2, 2);
+        assert!(c.is_opaque());
+        assert_eq!(c.alpha_at(1, 1), Ok(1.0));
+        c.write_alpha(1, 0, 1.0).unwrap();
+        assert!(c.is_opaque());
+        c.write_alpha(1, 0, 0.25).unwrap();
+        assert!(!c.is_opaque());
+        assert_eq!(c.alpha_at(1, 0), Ok(0.25));
+        assert_eq!(c.alpha_at(0, 1), Ok(1.0));
+        assert_eq!(c.alpha_at(2, 0), Err(CanvasError::InvalidCoordinates));
+        assert_eq!(
+            c.write_alpha(0, 2, 0.0),
+            Err(CanvasError::InvalidCoordinates)
+        );
+    }
+
+    #[test]
+    fn blit() {
+        let mut c = Canvas::new(3, 3);
+        let mut tile = Canvas::new_with_color(2, 2, RED);
+        tile.write_alpha(1, 1, 0.5).unwrap();
+        c.blit(&tile, 2, 1);
+        assert_eq!(c.pixel_at(2, 1), Ok(RED));
+        assert_eq!(c.pixel_at(2, 2), Ok(RED));
+        assert_eq!(c.pixel_at(1, 1), Ok(BLACK));
+        assert_eq!(c.alpha_at(2, 2), Ok(1.0));
+
+        c.blit(&tile, 1, 1);
+        assert_eq!(c.alpha_at(2, 2), Ok(0.5));
+        assert_eq!(c.pixel_at(0, 0), Ok(BLACK));
+        // completely outside
+        c.blit(&tile, 3, 0);
+    }
+
+    #[test]
+    fn composite_over() {
+        let mut c = Canvas::new_with_color(2, 1, WHITE);
+        c.write_alpha(1, 0, 0.5).unwrap();
+        let mut watermark = Canvas::new_with_color(2, 1, Color::new(0.5, 0.0, 0.0));
+        watermark.write_alpha(0, 0, 0.5).unwrap();
+        watermark.write_alpha(1, 0, 0.5).unwrap();
+        c.composite(&watermark, 0, 0, BlendMode::Over);
+        assert_eq!(c.pixel_at(0, 0), Ok(Color::new(1.0, 0.5, 0.5)));
+        assert_eq!(c.alpha_at(0, 0), Ok(1.0));
+        assert_eq!(c.pixel_at(1, 0), Ok(Color::new(1.0, 0.5, 0.5)));
+        assert_eq!(c.alpha_at(1, 0), Ok(0.75));
+    }
+
+    #[test]
+    fn composite_add_and_multiply() {
+        let mut c = Canvas::new_with_color(1, 1, Color::new(0.5, 0.25, 1.0));
+        c.composite(
+            &Canvas::new_with_color(1, 1, Color::new(0.5, 0.5, 0.5)),
+            0,
+            0,
+            BlendMode::Add,
+        );
+        assert_eq!(c.pixel_at(0, 0), Ok(Color::new(1.0, 0.75, 1.5)));
+        assert_eq!(c.alpha_at(0, 0), Ok(1.0));
+
+        let mut occlusion = Canvas::new_with_color(1, 1, Color::new(0.5, 0.5, 0.5));
+        occlusion.write_alpha(0, 0, 0.0).unwrap();
+        c.composite(&occlusion, 0, 0, BlendMode::Multiply);
+        assert_eq!(c.pixel_at(0, 0), Ok(Color::new(0.5, 0.375, 0.75)));
+        assert_eq!(c.alpha_at(0, 0), Ok(1.0));
+    }
+
+    #[test]
+    fn resize_nearest() {
+        let mut c = Canvas::new(2, 1);
+        c.write_pixel(1, 0, RED).unwrap();
+        c.write_alpha(1, 0, 0.5).unwrap();
+        let up = c.resized(4, 2, Resampling::Nearest);
+        assert_eq!((up.width(), up.height()), (4, 2));
+        assert_eq!(up.pixel_at(1, 1), Ok(BLACK));
+        assert_eq!(up.pixel_at(2, 0), Ok(RED));
+        assert_eq!(up.alpha_at(3, 1), Ok(0.5));
+        assert_eq!(up.alpha_at(0, 0), Ok(1.0));
+    }
+
+    #[test]
+    fn resize_bilinear() {
+        let mut c = Canvas::new(4, 2);
+        c.write_pixel(1, 0, WHITE).unwrap();
+        c.write_pixel(2, 1, RED).unwrap();
+        let down = c.resized(2, 1, Resampling::Bilinear);
+        assert_eq!(down.pixel_at(0, 0), Ok(Color::new(0.25, 0.25, 0.25)));
+        assert_eq!(down.pixel_at(1, 0), Ok(Color::new(0.25, 0.0, 0.0)));
+        assert!(down.is_opaque());
+
+        let up = Canvas::new_with_color(1, 1, RED).resized(3, 3, Resampling::Bilinear);
+        assert!(up.as_slice().iter().all(|pixel| *pixel == RED));
+        assert_eq!(
+            Canvas::new(0, 0)
+                .resized(2, 2, Resampling::Bilinear)
+                .pixel_at(1, 1),
+            Ok(BLACK)
+        );
+    }
+
+    #[test]
+    fn diff() {
+        let reference = Canvas::new_with_color(2, 2, WHITE);
+        let mut c = reference.clone();
+        assert!(c.diff(&reference, 0.0).unwrap().is_match());
+
+        c.write_pixel(1, 0, Color::new(1.0, 0.5, 1.0)).unwrap();
+        c.write_pixel(0, 1, Color::new(1.0, 1.0, 0.999)).unwrap();
+        c.write_alpha(1, 1, 0.75).unwrap();
+        let report = c.diff(&reference, 0.01).unwrap();
+        assert!(!report.is_match());
+        assert_eq!(report.differing_pixels, 2);
+        assert_eq!(report.max_error, 0.5);
+        assert!((report.mean_error - 0.751 / 16.0).abs() < 1e-9);
+
+        let heatmap = report.heatmap();
+        assert_eq!(heatmap.pixel_at(0, 0), Ok(BLACK));
+        assert_eq!(heatmap.pixel_at(1, 0), Ok(RED));
+        assert_eq!(heatmap.pixel_at(0, 1), Ok(BLACK));
+        assert_eq!(heatmap.pixel_at(1, 1), Ok(Color::new(0.5, 0.0, 0.0)));
+
+        assert_eq!(
+            c.diff(&Canvas::new(2, 1), 0.0),
+            Err(CanvasError::InvalidSize)
+        );
+    }
+
+    #[test]
+    fn crop_and_view() {
+        let mut c = Canvas::new(4, 3);
+        c.write_pixel(2, 1, RED).unwrap();
+        c.write_alpha(3, 2, 0.5).unwrap();
+
+        let view = c.view(1, 1, 3, 2).unwrap();
+        assert_eq!((view.width(), view.height()), (3, 2));
+        assert_eq!(view.pixel_at(1, 0), Ok(RED));
+        assert_eq!(view.alpha_at(2, 1), Ok(0.5));
+        assert_eq!(view.pixel_at(3, 0), Err(CanvasError::InvalidCoordinates));
+        let rows: Vec<&[Color]> = view.rows().collect();
+        assert_eq!(rows, vec![&[BLACK, RED, BLACK][..], &[BLACK; 3][..]]);
+
+        let cropped = c.crop(1, 1, 3, 2).unwrap();
+        assert_eq!(
+            cropped.as_slice(),
+            &[BLACK, RED, BLACK, BLACK, BLACK, BLACK]
+        );
+        assert_eq!(cropped.alpha_at(2, 1), Ok(0.5));
+        assert_eq!(cropped.alpha_at(0, 0), Ok(1.0));
+        assert!(c
+            .crop(0, 0, 4, 3)
+            .unwrap()
+            .diff(&c, 0.0)
+            .unwrap()
+            .is_match());
+
+        assert_eq!(
+            c.view(2, 0, 3, 1).err(),
+            Some(CanvasError::InvalidCoordinates)
+        );
+        assert_eq!(
+            c.crop(0, 1, 1, usize::MAX).err(),
+            Some(CanvasError::InvalidCoordinates)
+        );
+        assert_eq!(c.crop(4, 3, 0, 0).unwrap().width(), 0);
+    }
+
+    #[test]
+    fn tone_map_reinhard() {
+        let mut c = Canvas::new_with_color(2, 1, Color::new(1.9, 1.0, 0.0));
+        c.write_pixel(1, 0, Color::new(3.0, -1.0, 0.5)).unwrap();
+        c.tone_map(ToneMapping::Reinhard, 0.0);
+        assert_eq!(c.pixel_at(0, 0).unwrap(), Color::new(0.65517, 0.5, 0.0));
+        assert_eq!(c.pixel_at(1, 0).unwrap(), Color::new(0.75, 0.0, 0.33333));
+    }
+
+    #[test]
+    fn tone_map_exposure() {
+        let mut c = Canvas::new_with_color(1, 1, Color::new(0.5, 1.5, 0.0));
+        c.tone_map(ToneMapping::Reinhard, 1.0);
+        assert_eq!(c.pixel_at(0, 0).unwrap(), Color::new(0.5, 0.75, 0.0));
+    }
+
+    #[test]
+    fn tone_map_aces() {
+        let mut c = Canvas::new_with_color(1, 1, Color::new(0.0, 0.18, 100.0));
+        c.tone_map(ToneMapping::AcesApprox, 0.0);
+        let color = c.pixel_at(0, 0).unwrap();
+        assert_eq!(color.red, 0.0);
+        assert!(color.green > 0.2 && color.green < 0.3);
+        assert!(color.blue > 0.99 && color.blue <= 1.0);
+    }
+
+    #[test]
+    fn adjust_exposure() {
+        let mut c = Canvas::new_with_color(1, 1, Color::new(0.2, 0.4, 0.8));
+        c.adjust_exposure(1.0, Color::new(1.0, 0.5, 0.25));
+        assert_eq!(c.pixel_at(0, 0).unwrap(), Color::new(0.4, 0.4, 0.4));
+    }
+}
+
+#[cfg(test)]
+mod depth_buffer_tests {
+    use crate::{
+        canvas::{CanvasError, DepthBuffer},
+        color::Color,
+        scalar::Scalar,
+    };
+
+    #[test]
+    fn new() {
+        let d = DepthBuffer::new(3, 2);
+        assert_eq!(d.width(), 3);
+        assert_eq!(d.height(), 2);
+        assert!(d.depths().iter().all(|depth| *depth == Scalar::INFINITY));
+    }
+
+    #[test]
+    fn write_depth() {
+        let mut d = DepthBuffer::new(3, 2);
+        d.write_depth(2, 1, 4.5).unwrap();
+        assert_eq!(d.depth_at(2, 1), Ok(4.5));
+        assert_eq!(d.depths()[5], 4.5);
+        assert_eq!(d.depth_at(3, 0), Err(CanvasError::InvalidCoordinates));
+        assert_eq!(
+            d.write_depth(0, 2, 1.0),
+            Err(CanvasError::InvalidCoordinates)
+        );
+    }
+
+    #[test]
+    fn to_canvas() {
+        let mut d = DepthBuffer::new(3, 1);
+        d.write_depth(0, 0, 1.0).unwrap();
+        d.write_depth(1, 0, 3.0).unwrap();
+        let c = d.to_canvas(2.0, 4.0);
+        assert_eq!(c.pixel_at(0, 0).unwrap(), Color::new(1, 1, 1));
+        assert_eq!(c.pixel_at(1, 0).unwrap(), Color::new(0.5, 0.5, 0.5));
+        assert_eq!(c.pixel_at(2, 0).unwrap(), Color::new(0, 0, 0));
+    }
 }
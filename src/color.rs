@@ -1,6 +1,8 @@
-use std::ops::{Add, Mul, Sub};
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Sub, SubAssign};
 
 use crate::epsilon::EpsilonEqual;
+use crate::scalar::{IntoScalar, Scalar};
 
 /// The color black
 pub const BLACK: Color = Color {
@@ -16,28 +18,185 @@ pub const WHITE: Color = Color {
     blue: 1.0,
 };
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Errors parsing a hex color string may cause
+pub enum HexColorError {
+    /// The string (without the leading "#") is neither 3 nor 6 characters long.
+    InvalidLength,
+    /// The string contains a character that is not a hexadecimal digit.
+    InvalidDigit,
+}
+
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A color, represented as it's components red, green and blue.
 /// The maximum value for a component is 1.0, though computations should be able to exceed that limit.
 /// Therefore, no error is thrown.
 pub struct Color {
     /// the red component
-    pub red: f64,
+    pub red: Scalar,
     /// the green component
-    pub green: f64,
+    pub green: Scalar,
     /// the blue component
-    pub blue: f64,
+    pub blue: Scalar,
 }
 
 impl Color {
     /// Creates a new color
-    pub fn new<T: Into<f64>>(red: T, green: T, blue: T) -> Self {
+    pub fn new<T: IntoScalar>(red: T, green: T, blue: T) -> Self {
         Self {
-            red: red.into(),
-            green: green.into(),
-            blue: blue.into(),
+            red: red.into_scalar(),
+            green: green.into_scalar(),
+            blue: blue.into_scalar(),
         }
     }
+
+    /// Creates a color from 8 bit components, as given by image editors and design tools.
+    /// Such colors are in sRGB, so they are converted to linear components, see [`Color::to_linear`].
+    pub fn from_rgb8(red: u8, green: u8, blue: u8) -> Self {
+        Self::new(
+            red as Scalar / 255.0,
+            green as Scalar / 255.0,
+            blue as Scalar / 255.0,
+        )
+        .to_linear()
+    }
+
+    /// The 8 bit sRGB components of this color, the inverse of [`Color::from_rgb8`]. Components outside of 0 to 1 are clamped.
+    pub fn to_rgb8(&self) -> [u8; 3] {
+        let srgb = self.to_srgb();
+        let convert = |c: Scalar| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+        [convert(srgb.red), convert(srgb.green), convert(srgb.blue)]
+    }
+
+    /// Linear interpolation: this color for ```t``` = 0, ```other``` for ```t``` = 1.
+    /// Interpolates linear light, so gradients between sRGB colors may look darker in the middle than in image editors.
+    pub fn lerp(&self, other: Color, t: Scalar) -> Color {
+        *self * (1.0 - t) + other * t
+    }
+
+    /// Parses a hex color like "#ffaa00" or the short form "#fa0" (the "#" is optional). Like [`Color::from_rgb8`], the color is converted from sRGB to linear.
+    pub fn from_hex(hex: &str) -> Result<Self, HexColorError> {
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(HexColorError::InvalidDigit);
+        }
+        let component = |digits: &str| {
+            u8::from_str_radix(digits, 16).expect("only hexadecimal digits are left")
+        };
+        match digits.len() {
+            6 => Ok(Self::from_rgb8(
+                component(&digits[0..2]),
+                component(&digits[2..4]),
+                component(&digits[4..6]),
+            )),
+            3 => Ok(Self::from_rgb8(
+                component(&digits[0..1]) * 17,
+                component(&digits[1..2]) * 17,
+                component(&digits[2..3]) * 17,
+            )),
+            _ => Err(HexColorError::InvalidLength),
+        }
+    }
+
+    /// Converts a color with linear components (as used by all computations) to sRGB, the color space of image files and displays.
+    /// Writing linear values directly makes images look too dark.
+    pub fn to_srgb(&self) -> Self {
+        self.map(linear_to_srgb)
+    }
+
+    /// Converts a color in sRGB (e.g. read from an image file) to linear components, the inverse of [`Color::to_srgb`].
+    pub fn to_linear(&self) -> Self {
+        self.map(srgb_to_linear)
+    }
+
+    /// Creates a color from hue (in degrees, wrapping around at 360), saturation and value (both between 0 and 1).
+    /// Handy for generating palettes, e.g. by sweeping the hue.
+    pub fn from_hsv(hue: Scalar, saturation: Scalar, value: Scalar) -> Self {
+        let chroma = value * saturation;
+        Self::from_hue_chroma(hue, chroma, value - chroma)
+    }
+
+    /// Creates a color from hue (in degrees, wrapping around at 360), saturation and lightness (both between 0 and 1).
+    pub fn from_hsl(hue: Scalar, saturation: Scalar, lightness: Scalar) -> Self {
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        Self::from_hue_chroma(hue, chroma, lightness - chroma / 2.0)
+    }
+
+    /// The hue (in degrees between 0 and 360), saturation and value of this color, the inverse of [`Color::from_hsv`]
+    pub fn to_hsv(&self) -> (Scalar, Scalar, Scalar) {
+        let (hue, chroma, max, _) = self.hue_chroma();
+        let saturation = if max == 0.0 { 0.0 } else { chroma / max };
+        (hue, saturation, max)
+    }
+
+    /// The hue (in degrees between 0 and 360), saturation and lightness of this color, the inverse of [`Color::from_hsl`]
+    pub fn to_hsl(&self) -> (Scalar, Scalar, Scalar) {
+        let (hue, chroma, max, min) = self.hue_chroma();
+        let lightness = (max + min) / 2.0;
+        let saturation = if lightness == 0.0 || lightness == 1.0 {
+            0.0
+        } else {
+            chroma / (1.0 - (2.0 * lightness - 1.0).abs())
+        };
+        (hue, saturation, lightness)
+    }
+
+    /// The color of the given hue and chroma, with ```offset``` added to all components
+    fn from_hue_chroma(hue: Scalar, chroma: Scalar, offset: Scalar) -> Self {
+        let sector = hue.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+        let (red, green, blue) = match sector as u8 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        Self::new(red + offset, green + offset, blue + offset)
+    }
+
+    /// Hue, chroma, maximum and minimum component of this color
+    fn hue_chroma(&self) -> (Scalar, Scalar, Scalar, Scalar) {
+        let max = self.red.max(self.green).max(self.blue);
+        let min = self.red.min(self.green).min(self.blue);
+        let chroma = max - min;
+        let hue = if chroma == 0.0 {
+            0.0
+        } else if max == self.red {
+            60.0 * ((self.green - self.blue) / chroma).rem_euclid(6.0)
+        } else if max == self.green {
+            60.0 * ((self.blue - self.red) / chroma + 2.0)
+        } else {
+            60.0 * ((self.red - self.green) / chroma + 4.0)
+        };
+        (hue, chroma, max, min)
+    }
+
+    fn map(&self, f: impl Fn(Scalar) -> Scalar) -> Self {
+        Self {
+            red: f(self.red),
+            green: f(self.green),
+            blue: f(self.blue),
+        }
+    }
+}
+
+pub(crate) fn linear_to_srgb(c: Scalar) -> Scalar {
+    if c <= 0.0031308 {
+        12.92 * c
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn srgb_to_linear(c: Scalar) -> Scalar {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
 }
 
 impl PartialEq for Color {
@@ -60,6 +219,18 @@ impl Add for Color {
     }
 }
 
+impl AddAssign for Color {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sum for Color {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(BLACK, Add::add)
+    }
+}
+
 impl Sub for Color {
     type Output = Self;
 
@@ -72,10 +243,16 @@ impl Sub for Color {
     }
 }
 
-impl Mul<f64> for Color {
+impl SubAssign for Color {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Mul<Scalar> for Color {
     type Output = Self;
 
-    fn mul(self, rhs: f64) -> Self::Output {
+    fn mul(self, rhs: Scalar) -> Self::Output {
         Color {
             red: self.red * rhs,
             green: self.green * rhs,
@@ -84,6 +261,20 @@ impl Mul<f64> for Color {
     }
 }
 
+impl Mul<Color> for Scalar {
+    type Output = Color;
+
+    fn mul(self, rhs: Color) -> Self::Output {
+        rhs * self
+    }
+}
+
+impl MulAssign<Scalar> for Color {
+    fn mul_assign(&mut self, rhs: Scalar) {
+        *self = *self * rhs;
+    }
+}
+
 impl Mul for Color {
     type Output = Self;
 
@@ -96,9 +287,60 @@ impl Mul for Color {
     }
 }
 
+impl MulAssign for Color {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
+impl Div<Scalar> for Color {
+    type Output = Self;
+
+    fn div(self, rhs: Scalar) -> Self::Output {
+        Color {
+            red: self.red / rhs,
+            green: self.green / rhs,
+            blue: self.blue / rhs,
+        }
+    }
+}
+
+impl DivAssign<Scalar> for Color {
+    fn div_assign(&mut self, rhs: Scalar) {
+        *self = *self / rhs;
+    }
+}
+
+impl From<[Scalar; 3]> for Color {
+    fn from([red, green, blue]: [Scalar; 3]) -> Self {
+        Self { red, green, blue }
+    }
+}
+
+impl From<(Scalar, Scalar, Scalar)> for Color {
+    fn from((red, green, blue): (Scalar, Scalar, Scalar)) -> Self {
+        Self { red, green, blue }
+    }
+}
+
+impl From<Color> for [Scalar; 3] {
+    fn from(color: Color) -> Self {
+        [color.red, color.green, color.blue]
+    }
+}
+
+impl From<Color> for (Scalar, Scalar, Scalar) {
+    fn from(color: Color) -> Self {
+        (color.red, color.green, color.blue)
+    }
+}
+
 #[cfg(test)]
 mod color_tests {
-    use crate::color::Color;
+    use crate::{
+        color::{Color, HexColorError, BLACK, WHITE},
+        scalar::Scalar,
+    };
 
     #[test]
     fn instantiate() {
@@ -145,4 +387,126 @@ mod color_tests {
         let reference = Color::new(0.9, 0.2, 0.04);
         assert_eq!(c1 * c2, reference);
     }
+
+    #[test]
+    fn srgb() {
+        assert_eq!(BLACK.to_srgb(), BLACK);
+        assert_eq!(WHITE.to_srgb(), WHITE);
+        assert_eq!(
+            Color::new(0.5, 0.2, 0.001).to_srgb(),
+            Color::new(0.73536, 0.48453, 0.01292)
+        );
+        assert_eq!(
+            Color::new(0.73536, 0.48453, 0.01292).to_linear(),
+            Color::new(0.5, 0.2, 0.001)
+        );
+    }
+
+    #[test]
+    fn from_hsv() {
+        assert_eq!(Color::from_hsv(0.0, 1.0, 1.0), Color::new(1, 0, 0));
+        assert_eq!(Color::from_hsv(120.0, 1.0, 1.0), Color::new(0, 1, 0));
+        assert_eq!(Color::from_hsv(240.0, 1.0, 0.5), Color::new(0.0, 0.0, 0.5));
+        assert_eq!(Color::from_hsv(30.0, 0.5, 1.0), Color::new(1.0, 0.75, 0.5));
+        assert_eq!(Color::from_hsv(-60.0, 1.0, 1.0), Color::new(1, 0, 1));
+        assert_eq!(Color::from_hsv(420.0, 1.0, 1.0), Color::new(1, 1, 0));
+        assert_eq!(Color::from_hsv(200.0, 0.0, 0.3), Color::new(0.3, 0.3, 0.3));
+    }
+
+    #[test]
+    fn from_hsl() {
+        assert_eq!(Color::from_hsl(0.0, 1.0, 0.5), Color::new(1, 0, 0));
+        assert_eq!(Color::from_hsl(180.0, 1.0, 0.25), Color::new(0.0, 0.5, 0.5));
+        assert_eq!(Color::from_hsl(30.0, 1.0, 0.75), Color::new(1.0, 0.75, 0.5));
+        assert_eq!(Color::from_hsl(90.0, 0.7, 1.0), WHITE);
+    }
+
+    #[test]
+    fn hsv_hsl_round_trip() {
+        for color in [
+            Color::new(0.2, 0.4, 0.9),
+            Color::new(0.9, 0.1, 0.3),
+            Color::new(0.5, 0.8, 0.1),
+            Color::new(0.6, 0.6, 0.6),
+            BLACK,
+        ] {
+            let (h, s, v) = color.to_hsv();
+            assert_eq!(Color::from_hsv(h, s, v), color);
+            let (h, s, l) = color.to_hsl();
+            assert_eq!(Color::from_hsl(h, s, l), color);
+        }
+        let (h, s, v) = Color::new(1.0, 0.75, 0.5).to_hsv();
+        assert!((h - 30.0).abs() < 1e-9 && (s - 0.5).abs() < 1e-9 && v == 1.0);
+    }
+
+    #[test]
+    fn from_rgb8() {
+        assert_eq!(Color::from_rgb8(255, 0, 255), Color::new(1, 0, 1));
+        assert_eq!(
+            Color::from_rgb8(188, 0, 0).to_srgb(),
+            Color::new(188.0 / 255.0, 0.0, 0.0)
+        );
+        assert_eq!(Color::from_rgb8(188, 0, 0), Color::new(0.50289, 0.0, 0.0));
+    }
+
+    #[test]
+    fn assign_operators() {
+        let mut c = Color::new(0.5, 0.25, 1.0);
+        c += Color::new(0.5, 0.25, 1.0);
+        assert_eq!(c, Color::new(1.0, 0.5, 2.0));
+        c -= Color::new(0.5, 0.0, 0.5);
+        assert_eq!(c, Color::new(0.5, 0.5, 1.5));
+        c *= Color::new(2.0, 1.0, 0.0);
+        assert_eq!(c, Color::new(1.0, 0.5, 0.0));
+        c *= 2.0;
+        assert_eq!(c, Color::new(2.0, 1.0, 0.0));
+        c /= 4.0;
+        assert_eq!(c, Color::new(0.5, 0.25, 0.0));
+        assert_eq!(2.0 * c, c * 2.0);
+    }
+
+    #[test]
+    fn lerp() {
+        let a = Color::new(1.0, 0.0, 0.5);
+        assert_eq!(a.lerp(WHITE, 0.0), a);
+        assert_eq!(a.lerp(WHITE, 0.5), Color::new(1.0, 0.5, 0.75));
+        assert_eq!(a.lerp(WHITE, 1.0), WHITE);
+    }
+
+    #[test]
+    fn array_conversions() {
+        let c = Color::from([0.5, 0.25, 1.0]);
+        assert_eq!(c, Color::new(0.5, 0.25, 1.0));
+        assert_eq!(<[Scalar; 3]>::from(c), [0.5, 0.25, 1.0]);
+        assert_eq!(Color::from((1.0, 0.0, 0.0)), Color::new(1, 0, 0));
+        assert_eq!(<(Scalar, Scalar, Scalar)>::from(WHITE), (1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn sum_colors() {
+        let colors = [Color::new(0.5, 0.0, 0.0), WHITE, Color::new(0.0, 0.0, 1.0)];
+        assert_eq!(colors.into_iter().sum::<Color>(), Color::new(1.5, 1.0, 2.0));
+        assert_eq!(std::iter::empty().sum::<Color>(), BLACK);
+    }
+
+    #[test]
+    fn to_rgb8() {
+        assert_eq!(Color::from_rgb8(188, 0, 255).to_rgb8(), [188, 0, 255]);
+        assert_eq!(Color::new(2.0, -1.0, 0.5).to_rgb8(), [255, 0, 188]);
+    }
+
+    #[test]
+    fn from_hex() {
+        assert_eq!(
+            Color::from_hex("#ffaa00"),
+            Ok(Color::from_rgb8(255, 170, 0))
+        );
+        assert_eq!(Color::from_hex("FFAA00"), Ok(Color::from_rgb8(255, 170, 0)));
+        assert_eq!(Color::from_hex("#fa0"), Ok(Color::from_rgb8(255, 170, 0)));
+        assert_eq!(Color::from_hex("#000"), Ok(BLACK));
+        assert_eq!(Color::from_hex("#ffaa0"), Err(HexColorError::InvalidLength));
+        assert_eq!(Color::from_hex(""), Err(HexColorError::InvalidLength));
+        assert_eq!(Color::from_hex("#ffaa0g"), Err(HexColorError::InvalidDigit));
+        assert_eq!(Color::from_hex("#+fa"), Err(HexColorError::InvalidDigit));
+    }
 }
@@ -0,0 +1,187 @@
+//! Deep images, storing every surface along the rays of a pixel instead of a single resolved color, for deep compositing
+use std::fmt::Write;
+
+use crate::{
+    canvas::{Canvas, CanvasError},
+    color::{Color, BLACK},
+    scalar::Scalar,
+};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+/// A surface seen through a pixel
+pub struct DeepSample {
+    /// The distance from the camera to the surface
+    pub depth: Scalar,
+    /// The light the surface adds to the pixel, premultiplied with its alpha
+    pub color: Color,
+    /// How much of the pixel the surface covers and how much it hides of what is behind it, between 0 and 1
+    pub alpha: Scalar,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// Per pixel lists of [`DeepSample`]s, sorted front to back, see [`crate::camera::Camera::render_deep`].
+/// Pixels where rays miss everything have no samples.
+pub struct DeepImage {
+    width: usize,
+    height: usize,
+    samples: Vec<Vec<DeepSample>>,
+}
+
+impl DeepImage {
+    /// An image of the given size without any samples
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            samples: vec![Vec::new(); width * height],
+        }
+    }
+
+    /// The width in pixels
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// The height in pixels
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    fn index(&self, x: usize, y: usize) -> Result<usize, CanvasError> {
+        if x >= self.width || y >= self.height {
+            return Err(CanvasError::InvalidCoordinates);
+        }
+        Ok(y * self.width + x)
+    }
+
+    /// The samples of a pixel, nearest first
+    pub fn samples_at(&self, x: usize, y: usize) -> Result<&[DeepSample], CanvasError> {
+        let index = self.index(x, y)?;
+        Ok(&self.samples[index])
+    }
+
+    /// Adds a sample to a pixel, keeping its samples sorted by depth
+    pub fn add_sample(
+        &mut self,
+        x: usize,
+        y: usize,
+        sample: DeepSample,
+    ) -> Result<(), CanvasError> {
+        let index = self.index(x, y)?;
+        let samples = &mut self.samples[index];
+        let position = samples.partition_point(|s| s.depth <= sample.depth);
+        samples.insert(position, sample);
+        Ok(())
+    }
+
+    /// Composites the samples of every pixel front to back ("over"), giving a regular image with alpha.
+    pub fn flatten(&self) -> Canvas {
+        let mut canvas = Canvas::new(self.width, self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let (color, alpha) = self.samples[y * self.width + x].iter().fold(
+                    (BLACK, 0.0),
+                    |(color, alpha), sample| {
+                        let visible = 1.0 - alpha;
+                        (
+                            color + sample.color * visible,
+                            alpha + sample.alpha * visible,
+                        )
+                    },
+                );
+                canvas
+                    .write_pixel(x, y, color)
+                    .expect("Canvas WIDTH and HEIGHT volation.");
+                canvas
+                    .write_alpha(x, y, alpha)
+                    .expect("Canvas WIDTH and HEIGHT volation.");
+            }
+        }
+        canvas
+    }
+
+    /// A plain text export for deep compositing tools: a ```DEEP``` header line and the size, then one line per pixel (left to right, top to bottom)
+    /// with the number of samples followed by depth, red, green, blue and alpha of each sample.
+    pub fn to_text(&self) -> String {
+        let mut text = format!("DEEP\n{} {}\n", self.width, self.height);
+        for samples in &self.samples {
+            write!(text, "{}", samples.len()).expect("writing to a String cannot fail");
+            for s in samples {
+                write!(
+                    text,
+                    " {} {} {} {} {}",
+                    s.depth, s.color.red, s.color.green, s.color.blue, s.alpha
+                )
+                .expect("writing to a String cannot fail");
+            }
+            text.push('\n');
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod deep_image_tests {
+    use crate::{
+        canvas::CanvasError,
+        color::{Color, BLACK},
+        deep::{DeepImage, DeepSample},
+        scalar::Scalar,
+    };
+
+    fn sample(depth: Scalar, color: Color, alpha: Scalar) -> DeepSample {
+        DeepSample {
+            depth,
+            color,
+            alpha,
+        }
+    }
+
+    #[test]
+    fn samples_are_sorted() {
+        let mut image = DeepImage::new(2, 1);
+        image
+            .add_sample(1, 0, sample(5.0, Color::new(0, 0, 1), 1.0))
+            .unwrap();
+        image
+            .add_sample(1, 0, sample(2.0, Color::new(0.5, 0.0, 0.0), 0.5))
+            .unwrap();
+        let depths: Vec<Scalar> = image
+            .samples_at(1, 0)
+            .unwrap()
+            .iter()
+            .map(|s| s.depth)
+            .collect();
+        assert_eq!(depths, vec![2.0, 5.0]);
+        assert!(image.samples_at(0, 0).unwrap().is_empty());
+        assert_eq!(
+            image.add_sample(2, 0, sample(1.0, BLACK, 1.0)),
+            Err(CanvasError::InvalidCoordinates)
+        );
+    }
+
+    #[test]
+    fn flatten() {
+        let mut image = DeepImage::new(2, 1);
+        image
+            .add_sample(0, 0, sample(5.0, Color::new(0, 0, 1), 1.0))
+            .unwrap();
+        image
+            .add_sample(0, 0, sample(2.0, Color::new(0.5, 0.0, 0.0), 0.5))
+            .unwrap();
+        let canvas = image.flatten();
+        assert_eq!(canvas.pixel_at(0, 0).unwrap(), Color::new(0.5, 0.0, 0.5));
+        assert_eq!(canvas.alpha_at(0, 0), Ok(1.0));
+        assert_eq!(canvas.pixel_at(1, 0).unwrap(), BLACK);
+        assert_eq!(canvas.alpha_at(1, 0), Ok(0.0));
+    }
+
+    #[test]
+    fn to_text() {
+        let mut image = DeepImage::new(2, 1);
+        image
+            .add_sample(1, 0, sample(2.5, Color::new(0.5, 0.0, 1.0), 0.5))
+            .unwrap();
+        assert_eq!(image.to_text(), "DEEP\n2 1\n0\n1 2.5 0.5 0 1 0.5\n");
+    }
+}
@@ -0,0 +1,356 @@
+//! Farming out a render to several machines: the frame is split into [`Tile`]s, which workers claim and render, and the finished tiles are merged again.
+//!
+//! The transport is a shared directory (e.g. a network share): [`write_jobs`] puts one job file per tile into it,
+//! every worker calls [`run_jobs`] until no jobs are left, and [`collect_results`] merges the result files into the final image.
+//! Scenes contain closures and other objects that cannot be serialized, so every worker builds the world and camera itself, running the same program.
+//! As the random numbers of a pixel only depend on its position and [`Camera::seed`], the merged image is identical to rendering it on a single machine.
+use std::{
+    fmt::Write as _,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    camera::Camera,
+    canvas::{Canvas, CanvasError},
+    color::Color,
+    scalar::Scalar,
+    world::World,
+};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// A rectangular part of the frame, in pixels
+pub struct Tile {
+    /// The column of the left edge
+    pub x: usize,
+    /// The row of the top edge
+    pub y: usize,
+    /// The width in pixels
+    pub width: usize,
+    /// The height in pixels
+    pub height: usize,
+}
+
+#[derive(Debug)]
+/// Errors of distributed rendering
+pub enum DistributedError {
+    /// Reading or writing a job or result file failed
+    Io(io::Error),
+    /// A job or result file is not valid
+    InvalidData,
+    /// A tile does not fit the frame or its image
+    Canvas(CanvasError),
+    /// Not all tiles have been rendered yet. Contains the number of missing tiles.
+    Incomplete(usize),
+}
+
+impl From<io::Error> for DistributedError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<CanvasError> for DistributedError {
+    fn from(error: CanvasError) -> Self {
+        Self::Canvas(error)
+    }
+}
+
+/// Splits a frame of ```width``` x ```height``` pixels into tiles of at most ```tile_size``` x ```tile_size``` pixels, row by row.
+/// Tiles at the right and bottom edges are smaller if the frame size is not a multiple of ```tile_size```.
+pub fn tiles(width: usize, height: usize, tile_size: usize) -> Vec<Tile> {
+    let tile_size = tile_size.max(1);
+    let mut tiles = Vec::new();
+    for y in (0..height).step_by(tile_size) {
+        for x in (0..width).step_by(tile_size) {
+            tiles.push(Tile {
+                x,
+                y,
+                width: tile_size.min(width - x),
+                height: tile_size.min(height - y),
+            });
+        }
+    }
+    tiles
+}
+
+/// Copies a rendered tile (see [`Camera::render_tile`]) into its place in the frame
+pub fn merge_tile(frame: &mut Canvas, tile: Tile, image: &Canvas) -> Result<(), CanvasError> {
+    if image.width() != tile.width || image.height() != tile.height {
+        return Err(CanvasError::InvalidSize);
+    }
+    for y in 0..tile.height {
+        for x in 0..tile.width {
+            frame.write_pixel(tile.x + x, tile.y + y, image.pixel_at(x, y)?)?;
+            let alpha = image.alpha_at(x, y)?;
+            if alpha < 1.0 {
+                frame.write_alpha(tile.x + x, tile.y + y, alpha)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn encode_header(tile: Tile) -> String {
+    format!(
+        "TILE {} {} {} {}\n",
+        tile.x, tile.y, tile.width, tile.height
+    )
+}
+
+/// Serializes a rendered tile: its header line and one line per pixel (row by row) with red, green, blue and alpha.
+/// Values are written with full precision, so decoding gives back the exact image.
+pub fn encode_tile(tile: Tile, image: &Canvas) -> Result<String, CanvasError> {
+    let mut text = encode_header(tile);
+    for y in 0..image.height() {
+        for x in 0..image.width() {
+            let color = image.pixel_at(x, y)?;
+            let alpha = image.alpha_at(x, y)?;
+            writeln!(
+                text,
+                "{} {} {} {}",
+                color.red, color.green, color.blue, alpha
+            )
+            .expect("writing to a String cannot fail");
+        }
+    }
+    Ok(text)
+}
+
+fn decode_header<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Tile, DistributedError> {
+    if tokens.next() != Some("TILE") {
+        return Err(DistributedError::InvalidData);
+    }
+    let mut number = || -> Result<usize, DistributedError> {
+        tokens
+            .next()
+            .and_then(|token| token.parse().ok())
+            .ok_or(DistributedError::InvalidData)
+    };
+    Ok(Tile {
+        x: number()?,
+        y: number()?,
+        width: number()?,
+        height: number()?,
+    })
+}
+
+/// Reads a tile serialized by [`encode_tile`]
+pub fn decode_tile(text: &str) -> Result<(Tile, Canvas), DistributedError> {
+    let mut tokens = text.split_whitespace();
+    let tile = decode_header(&mut tokens)?;
+    let mut image = Canvas::new(tile.width, tile.height);
+    for y in 0..tile.height {
+        for x in 0..tile.width {
+            let mut value = || -> Result<Scalar, DistributedError> {
+                tokens
+                    .next()
+                    .and_then(|token| token.parse().ok())
+                    .ok_or(DistributedError::InvalidData)
+            };
+            let color = Color::new(value()?, value()?, value()?);
+            let alpha = value()?;
+            image.write_pixel(x, y, color)?;
+            if alpha < 1.0 {
+                image.write_alpha(x, y, alpha)?;
+            }
+        }
+    }
+    if tokens.next().is_some() {
+        return Err(DistributedError::InvalidData);
+    }
+    Ok((tile, image))
+}
+
+fn job_path(dir: &Path, index: usize) -> PathBuf {
+    dir.join(format!("tile-{index}.job"))
+}
+
+fn result_path(dir: &Path, index: usize) -> PathBuf {
+    dir.join(format!("tile-{index}.result"))
+}
+
+/// Writes one job file per tile into ```dir```, which must be reachable by all workers
+pub fn write_jobs(dir: &Path, tiles: &[Tile]) -> Result<(), DistributedError> {
+    fs::create_dir_all(dir)?;
+    for (index, tile) in tiles.iter().enumerate() {
+        fs::write(job_path(dir, index), encode_header(*tile))?;
+    }
+    Ok(())
+}
+
+/// Claims and renders jobs from ```dir``` until none are left, returning how many tiles this worker rendered.
+/// Jobs are claimed by renaming their file, so several workers can share a directory without rendering a tile twice.
+pub fn run_jobs(dir: &Path, camera: &Camera, world: &World) -> Result<usize, DistributedError> {
+    let claimed = dir.join(format!("claimed-{}", std::process::id()));
+    let mut rendered = 0;
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some(index) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix("tile-")?.strip_suffix(".job"))
+            .and_then(|index| index.parse::<usize>().ok())
+        else {
+            continue;
+        };
+        // another worker was faster
+        if fs::rename(&path, &claimed).is_err() {
+            continue;
+        }
+        let job = fs::read_to_string(&claimed)?;
+        let tile = decode_header(&mut job.split_whitespace())?;
+        let image = camera.render_tile(world, tile)?;
+        // written under another name first, so a result is never read half-written
+        let partial = claimed.with_extension("partial");
+        fs::write(&partial, encode_tile(tile, &image)?)?;
+        fs::rename(&partial, result_path(dir, index))?;
+        fs::remove_file(&claimed)?;
+        rendered += 1;
+    }
+    Ok(rendered)
+}
+
+/// Merges the results of all ```tiles``` (as passed to [`write_jobs`]) in ```dir``` into a frame of ```width``` x ```height``` pixels.
+/// Returns [`DistributedError::Incomplete`] if some tiles have not been rendered yet.
+pub fn collect_results(
+    dir: &Path,
+    tiles: &[Tile],
+    width: usize,
+    height: usize,
+) -> Result<Canvas, DistributedError> {
+    let missing = (0..tiles.len())
+        .filter(|index| !result_path(dir, *index).exists())
+        .count();
+    if missing > 0 {
+        return Err(DistributedError::Incomplete(missing));
+    }
+    let mut frame = Canvas::new(width, height);
+    for (index, expected) in tiles.iter().enumerate() {
+        let (tile, image) = decode_tile(&fs::read_to_string(result_path(dir, index))?)?;
+        if tile != *expected {
+            return Err(DistributedError::InvalidData);
+        }
+        merge_tile(&mut frame, tile, &image)?;
+    }
+    Ok(frame)
+}
+
+#[cfg(test)]
+mod distributed_tests {
+    use std::fs;
+
+    use crate::{
+        camera::Camera,
+        canvas::{Canvas, CanvasError},
+        color::Color,
+        distributed::{
+            collect_results, decode_tile, encode_tile, merge_tile, run_jobs, tiles, write_jobs,
+            DistributedError, Tile,
+        },
+        scalar::{consts::PI, Scalar},
+        tuple::{Point, Vector},
+        world::World,
+    };
+
+    fn camera() -> Camera {
+        let mut c = Camera::new(11, 7, PI / 2.);
+        c.set_transform(Camera::view_transform(
+            Point::new(0, 0, -5),
+            Point::new(0, 0, 0),
+            Vector::new(0, 1, 0),
+        ));
+        c.settings.samples_per_pixel = 4;
+        c
+    }
+
+    fn assert_same_image(a: &Canvas, b: &Canvas) {
+        for y in 0..a.height() {
+            for x in 0..a.width() {
+                assert_eq!(a.pixel_at(x, y), b.pixel_at(x, y));
+                assert_eq!(a.alpha_at(x, y), b.alpha_at(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn tiles_cover_frame() {
+        let t = tiles(11, 7, 4);
+        assert_eq!(t.len(), 6);
+        assert_eq!(
+            t[5],
+            Tile {
+                x: 8,
+                y: 4,
+                width: 3,
+                height: 3
+            }
+        );
+        let area: usize = t.iter().map(|tile| tile.width * tile.height).sum();
+        assert_eq!(area, 77);
+    }
+
+    #[test]
+    fn merged_tiles_equal_full_render() {
+        let w = World::test_world();
+        let c = camera();
+        let mut frame = Canvas::new(11, 7);
+        for tile in tiles(11, 7, 4) {
+            merge_tile(&mut frame, tile, &c.render_tile(&w, tile).unwrap()).unwrap();
+        }
+        assert_same_image(&frame, &c.render(&w).unwrap());
+        assert_eq!(
+            merge_tile(&mut frame, tiles(11, 7, 4)[0], &Canvas::new(3, 3)),
+            Err(CanvasError::InvalidSize)
+        );
+    }
+
+    #[test]
+    fn encode_decode() {
+        let tile = Tile {
+            x: 2,
+            y: 1,
+            width: 2,
+            height: 1,
+        };
+        let mut image = Canvas::new(2, 1);
+        image
+            .write_pixel(0, 0, Color::new(0.1, 1.0 / 3.0, 2.5))
+            .unwrap();
+        image.write_alpha(1, 0, 0.25).unwrap();
+        let (decoded_tile, decoded) = decode_tile(&encode_tile(tile, &image).unwrap()).unwrap();
+        assert_eq!(decoded_tile, tile);
+        assert_eq!(
+            decoded.pixel_at(0, 0).unwrap().green.to_bits(),
+            Scalar::to_bits(1.0 / 3.0)
+        );
+        assert_same_image(&decoded, &image);
+        assert!(matches!(
+            decode_tile("TILE 0 0 1 1\n0 0 0"),
+            Err(DistributedError::InvalidData)
+        ));
+        assert!(matches!(
+            decode_tile("P3 1 1"),
+            Err(DistributedError::InvalidData)
+        ));
+    }
+
+    #[test]
+    fn file_farm() {
+        let dir = std::env::temp_dir().join(format!("raytracer-farm-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let w = World::test_world();
+        let c = camera();
+        let t = tiles(11, 7, 4);
+        write_jobs(&dir, &t).unwrap();
+        assert!(matches!(
+            collect_results(&dir, &t, 11, 7),
+            Err(DistributedError::Incomplete(6))
+        ));
+        assert_eq!(run_jobs(&dir, &c, &w).unwrap(), 6);
+        assert_eq!(run_jobs(&dir, &c, &w).unwrap(), 0);
+        let frame = collect_results(&dir, &t, 11, 7).unwrap();
+        assert_same_image(&frame, &c.render(&w).unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
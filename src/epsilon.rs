@@ -1,4 +1,6 @@
-pub const EPSILON: f64 = 0.0001;
+use crate::scalar::Scalar;
+
+pub const EPSILON: Scalar = 0.0001;
 
 /// Represents epsilon equality.
 pub trait EpsilonEqual {
@@ -6,7 +8,7 @@ pub trait EpsilonEqual {
     fn e_equals(self, other: Self) -> bool;
 }
 
-impl EpsilonEqual for f64 {
+impl EpsilonEqual for Scalar {
     fn e_equals(self, other: Self) -> bool {
         (self - other).abs() < EPSILON
     }
@@ -22,17 +24,18 @@ impl EpsilonEqual for i32 {
 #[cfg(test)]
 mod equal_tests {
     use crate::epsilon::EpsilonEqual;
+    use crate::scalar::Scalar;
 
     #[test]
     fn test_epsilon_equal() {
-        assert!(1.0f64.e_equals(1.00000000001f64));
-        assert!(!1.0f64.e_equals(1.1f64));
+        assert!(Scalar::e_equals(1.0, 1.00000000001));
+        assert!(!Scalar::e_equals(1.0, 1.1));
     }
 
     #[test]
     fn test_epsilon_equal_nan() {
-        assert!(!f64::NAN.e_equals(f64::NAN));
-        assert!(!f64::NAN.e_equals(1.1f64));
+        assert!(!Scalar::NAN.e_equals(Scalar::NAN));
+        assert!(!Scalar::NAN.e_equals(1.1));
     }
 
     #[test]
@@ -0,0 +1,95 @@
+//! Conversions between [`Point`], [`Vector`], [`Mat4`] and the vectors and matrices of [glam](https://docs.rs/glam),
+//! in single (```Vec3```, ```Mat4```) and double (```DVec3```, ```DMat4```) precision.
+//! Converting to a precision lower than [`crate::scalar::Scalar`] rounds.
+use crate::{
+    matrix::Mat4,
+    scalar::Scalar,
+    tuple::{Point, Vector},
+};
+
+macro_rules! impl_glam_conversions {
+    ($float:ty, $vec3:ty, $mat4:ty) => {
+        impl From<Point> for $vec3 {
+            fn from(p: Point) -> Self {
+                Self::new(p.x as $float, p.y as $float, p.z as $float)
+            }
+        }
+
+        impl From<$vec3> for Point {
+            fn from(v: $vec3) -> Self {
+                Point::new(v.x as Scalar, v.y as Scalar, v.z as Scalar)
+            }
+        }
+
+        impl From<Vector> for $vec3 {
+            fn from(v: Vector) -> Self {
+                Self::new(v.x as $float, v.y as $float, v.z as $float)
+            }
+        }
+
+        impl From<$vec3> for Vector {
+            fn from(v: $vec3) -> Self {
+                Vector::new(v.x as Scalar, v.y as Scalar, v.z as Scalar)
+            }
+        }
+
+        // glam matrices are stored by columns, ours by rows
+        impl From<Mat4> for $mat4 {
+            fn from(m: Mat4) -> Self {
+                Self::from_cols_array_2d(
+                    &m.transpose()
+                        .to_array()
+                        .map(|column| column.map(|value| value as $float)),
+                )
+            }
+        }
+
+        impl From<$mat4> for Mat4 {
+            fn from(m: $mat4) -> Self {
+                Mat4::new(
+                    m.to_cols_array_2d()
+                        .map(|column| column.map(|value| value as Scalar)),
+                )
+                .transpose()
+            }
+        }
+    };
+}
+
+impl_glam_conversions!(f32, glam::Vec3, glam::Mat4);
+impl_glam_conversions!(f64, glam::DVec3, glam::DMat4);
+
+#[cfg(test)]
+mod glam_interop_tests {
+    use crate::{
+        matrix::Mat4,
+        transform::Transform,
+        tuple::{Point, Vector},
+    };
+
+    #[test]
+    fn tuples() {
+        let p = Point::new(1.0, -2.0, 0.5);
+        assert_eq!(glam::DVec3::from(p), glam::DVec3::new(1.0, -2.0, 0.5));
+        assert_eq!(Point::from(glam::Vec3::new(1.0, -2.0, 0.5)), p);
+        let v = Vector::new(0, 3, -4);
+        assert_eq!(glam::Vec3::from(v), glam::Vec3::new(0.0, 3.0, -4.0));
+        assert_eq!(Vector::from(glam::DVec3::new(0.0, 3.0, -4.0)), v);
+    }
+
+    #[test]
+    fn matrices() {
+        let m = Mat4::default()
+            .scaled(2, 3, 4)
+            .rotated_y(0.5)
+            .translated(1, 2, 3);
+        let converted = glam::DMat4::from(m);
+        let p = Point::new(1, -1, 2);
+        assert_eq!(
+            Point::from(converted.transform_point3(glam::DVec3::from(p))),
+            m * p
+        );
+        assert_eq!(Mat4::from(converted), m);
+        assert_eq!(Mat4::from(glam::Mat4::from(m)), m);
+    }
+}
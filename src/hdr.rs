@@ -0,0 +1,194 @@
+//! Radiance HDR (.hdr) file format logic. Unlike PPM, PNG and BMP, HDR images keep colors brighter than 1.0,
+//! so renders can be tone mapped and graded later without losing highlights.
+use std::io::{self, Write};
+
+use crate::{canvas::Canvas, color::Color, scalar::Scalar};
+
+/// Scanlines of this width can be run length encoded, others are written flat
+const RLE_WIDTHS: std::ops::RangeInclusive<usize> = 8..=0x7fff;
+/// The longest run of equal bytes of a run length encoded component
+const MAX_RUN: usize = 127;
+/// The longest sequence of bytes written as they are
+const MAX_LITERALS: usize = 128;
+
+/// Writes the canvas as a Radiance HDR image. Colors are written as they are (linear, not clamped to 1.0), only negative components become 0.
+/// Canvas alpha is not written.
+pub fn write_hdr<W: Write>(canvas: &Canvas, mut writer: W) -> io::Result<()> {
+    let width = canvas.width();
+    let height = canvas.height();
+    write!(
+        writer,
+        "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {height} +X {width}\n"
+    )?;
+
+    let mut pixels = Vec::with_capacity(width);
+    let mut encoded = Vec::new();
+    for y in 0..height {
+        pixels.clear();
+        for x in 0..width {
+            let color = canvas
+                .pixel_at(x, y)
+                .expect("Canvas WIDTH and HEIGHT volation.");
+            pixels.push(to_rgbe(color));
+        }
+        encoded.clear();
+        if RLE_WIDTHS.contains(&width) {
+            encoded.extend_from_slice(&[2, 2, (width >> 8) as u8, (width & 0xff) as u8]);
+            // each component is encoded separately, as exponents and dark channels repeat a lot
+            for component in 0..4 {
+                let values: Vec<u8> = pixels.iter().map(|pixel| pixel[component]).collect();
+                run_length_encode(&values, &mut encoded);
+            }
+        } else {
+            encoded.extend(pixels.iter().flatten());
+        }
+        writer.write_all(&encoded)?;
+    }
+    Ok(())
+}
+
+/// The color as a shared exponent and three 8 bit mantissas
+pub(crate) fn to_rgbe(color: Color) -> [u8; 4] {
+    let red = color.red.max(0.0);
+    let green = color.green.max(0.0);
+    let blue = color.blue.max(0.0);
+    let brightest = red.max(green).max(blue);
+    if !brightest.is_finite() || brightest < 1e-32 {
+        return [0; 4];
+    }
+    // brightest = mantissa * 2^exponent, with mantissa in [0.5, 1)
+    let mut exponent = brightest.log2().floor() as i32 + 1;
+    if brightest / Scalar::powi(2.0, exponent) >= 1.0 {
+        exponent += 1;
+    }
+    let exponent = exponent.clamp(-128, 127);
+    let scale = 256.0 / Scalar::powi(2.0, exponent);
+    let mantissa = |c: Scalar| (c * scale).min(255.0) as u8;
+    [
+        mantissa(red),
+        mantissa(green),
+        mantissa(blue),
+        (exponent + 128) as u8,
+    ]
+}
+
+/// Writes runs of at least 3 equal bytes as the run length (plus 128) and the byte, everything else as the number of literal bytes followed by them
+fn run_length_encode(values: &[u8], encoded: &mut Vec<u8>) {
+    let mut i = 0;
+    while i < values.len() {
+        let run = values[i..]
+            .iter()
+            .take(MAX_RUN)
+            .take_while(|&&value| value == values[i])
+            .count();
+        if run >= 3 {
+            encoded.extend_from_slice(&[128 + run as u8, values[i]]);
+            i += run;
+            continue;
+        }
+        let start = i;
+        while i < values.len() && i - start < MAX_LITERALS {
+            let run_ahead =
+                i + 2 < values.len() && values[i] == values[i + 1] && values[i] == values[i + 2];
+            if run_ahead {
+                break;
+            }
+            i += 1;
+        }
+        encoded.push((i - start) as u8);
+        encoded.extend_from_slice(&values[start..i]);
+    }
+}
+
+#[cfg(test)]
+mod hdr_tests {
+    use crate::{
+        canvas::Canvas,
+        color::{Color, BLACK},
+        hdr::{to_rgbe, write_hdr},
+    };
+
+    /// Decodes the pixels of a HDR image written by [`write_hdr`]
+    fn decode(bytes: &[u8]) -> (usize, usize, Vec<[u8; 4]>) {
+        let header_end = bytes.windows(2).position(|w| w == b"\n\n").unwrap() + 2;
+        let size_end = header_end
+            + bytes[header_end..]
+                .iter()
+                .position(|&b| b == b'\n')
+                .unwrap();
+        let size = std::str::from_utf8(&bytes[header_end..size_end]).unwrap();
+        let parts: Vec<&str> = size.split_whitespace().collect();
+        let (height, width): (usize, usize) =
+            (parts[1].parse().unwrap(), parts[3].parse().unwrap());
+
+        let mut data = bytes[size_end + 1..].iter().copied();
+        let mut pixels = Vec::new();
+        for _ in 0..height {
+            if !(8..=0x7fff).contains(&width) {
+                for _ in 0..width {
+                    let mut pixel = [0; 4];
+                    pixel.iter_mut().for_each(|c| *c = data.next().unwrap());
+                    pixels.push(pixel);
+                }
+                continue;
+            }
+            let marker: Vec<u8> = data.by_ref().take(4).collect();
+            assert_eq!(marker, vec![2, 2, (width >> 8) as u8, width as u8]);
+            let mut row = vec![[0; 4]; width];
+            for component in 0..4 {
+                let mut x = 0;
+                while x < width {
+                    let count = data.next().unwrap() as usize;
+                    if count > 128 {
+                        let value = data.next().unwrap();
+                        for pixel in &mut row[x..x + count - 128] {
+                            pixel[component] = value;
+                        }
+                        x += count - 128;
+                    } else {
+                        for pixel in &mut row[x..x + count] {
+                            pixel[component] = data.next().unwrap();
+                        }
+                        x += count;
+                    }
+                }
+            }
+            pixels.extend(row);
+        }
+        assert_eq!(data.next(), None);
+        (width, height, pixels)
+    }
+
+    #[test]
+    fn rgbe() {
+        assert_eq!(to_rgbe(Color::new(1.0, 0.5, 0.25)), [128, 64, 32, 129]);
+        assert_eq!(to_rgbe(Color::new(4.0, 0.0, 2.0)), [128, 0, 64, 131]);
+        assert_eq!(to_rgbe(Color::new(0.75, -1.0, 0.0)), [192, 0, 0, 128]);
+        assert_eq!(to_rgbe(BLACK), [0; 4]);
+    }
+
+    #[test]
+    fn keeps_bright_colors() {
+        for width in [3, 20] {
+            let mut canvas = Canvas::new(width, 2);
+            canvas.write_pixel(1, 0, Color::new(10, 20, 40)).unwrap();
+            canvas.write_pixel(2, 1, Color::new(0.5, 0.5, 0.5)).unwrap();
+            let mut bytes = Vec::new();
+            write_hdr(&canvas, &mut bytes).unwrap();
+            assert!(bytes.starts_with(b"#?RADIANCE\n"));
+
+            let (read_width, read_height, pixels) = decode(&bytes);
+            assert_eq!((read_width, read_height), (width, 2));
+            assert_eq!(pixels.len(), width * 2);
+            for y in 0..2 {
+                for x in 0..width {
+                    assert_eq!(
+                        pixels[y * width + x],
+                        to_rgbe(canvas.pixel_at(x, y).unwrap())
+                    );
+                }
+            }
+            assert_eq!(pixels[1], [40, 80, 160, 134]);
+        }
+    }
+}
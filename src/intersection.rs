@@ -1,19 +1,34 @@
 use crate::{
-    epsilon::EPSILON,
+    material::Material,
     ray::Ray,
+    scalar::{IntoScalar, Scalar},
+    settings,
     shapes::shape::Shape,
     tuple::{Point, Vector},
 };
 
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Intersection<'a> {
-    pub t: f64,
+    pub t: Scalar,
     pub object: &'a dyn Shape,
+    /// Which face of a shape consisting of several faces was hit, if any
+    pub face_hit: Option<FaceHit>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+/// The face of a multi-face shape (like a triangle mesh) an intersection occurred on, and where on that face.
+pub struct FaceHit {
+    /// Index of the face within its shape
+    pub face: usize,
+    /// First barycentric coordinate of the hit on the face
+    pub u: Scalar,
+    /// Second barycentric coordinate of the hit on the face
+    pub v: Scalar,
 }
 
 #[derive(Debug, PartialEq)]
 pub struct PreparedComputations<'a> {
-    pub t: f64,
+    pub t: Scalar,
     pub object: &'a dyn Shape,
     pub point: Point,
     pub over_point: Point,
@@ -23,16 +38,37 @@ pub struct PreparedComputations<'a> {
     pub inside: bool,
     pub reflectv: Vector,
     /// refraction ingoing angle
-    pub n1: f64,
+    pub n1: Scalar,
     /// refraction outgoing angle
-    pub n2: f64,
+    pub n2: Scalar,
+}
+
+impl<'a> PreparedComputations<'a> {
+    /// The material of the side of the object that was hit, see [`Shape::material_facing`]
+    pub fn material(&self) -> &'a Material {
+        self.object.material_facing(self.inside)
+    }
 }
 
 impl<'a> Intersection<'a> {
-    pub fn new<T: Into<f64>>(t: T, object: &'a dyn Shape) -> Intersection<'a> {
+    pub fn new<T: IntoScalar>(t: T, object: &'a dyn Shape) -> Intersection<'a> {
+        Self {
+            t: t.into_scalar(),
+            object,
+            face_hit: None,
+        }
+    }
+
+    /// An intersection with one face of a multi-face shape
+    pub fn new_with_face<T: IntoScalar>(
+        t: T,
+        object: &'a dyn Shape,
+        face_hit: FaceHit,
+    ) -> Intersection<'a> {
         Self {
-            t: t.into(),
+            t: t.into_scalar(),
             object,
+            face_hit: Some(face_hit),
         }
     }
 
@@ -40,9 +76,9 @@ impl<'a> Intersection<'a> {
         &'a self,
         r: &Ray,
         intersections: &Vec<Intersection>,
-    ) -> PreparedComputations {
+    ) -> PreparedComputations<'a> {
         let point = r.position(self.t);
-        let normal = self.object.normal_at(point);
+        let normal = self.object.normal_at_hit(point, self);
 
         let eyev = -r.direction;
 
@@ -52,8 +88,18 @@ impl<'a> Intersection<'a> {
             (false, normal)
         };
 
-        let over_point = point + normal * EPSILON;
-        let under_point = point - normal * EPSILON;
+        let bias = self
+            .object
+            .shadow_bias()
+            .unwrap_or_else(settings::shadow_bias);
+        let over_point = point + normal * bias;
+        let under_point = point - normal * bias;
+
+        // bumps only change the shading, over and under point stay on the actual surface
+        let normal = match &self.object.material_facing(inside).bump_map {
+            Some(bump_map) => bump_map.perturb(self.object, point, normal),
+            None => normal,
+        };
 
         let reflectv = r.direction.reflect(normal);
 
@@ -75,7 +121,7 @@ impl<'a> Intersection<'a> {
     }
 
     /// Computes the ingress and egress refraction values for this intersection
-    fn compute_n1_n2(&'a self, intersections: &Vec<Intersection<'a>>) -> (f64, f64) {
+    fn compute_n1_n2(&'a self, intersections: &Vec<Intersection<'a>>) -> (Scalar, Scalar) {
         let mut containers: Vec<&dyn Shape> = Vec::new();
 
         let mut n1 = 0.0;
@@ -118,31 +164,6 @@ impl<'a> PartialOrd for Intersection<'a> {
     }
 }
 
-/// Computes the first (from the viewpoint of the origin of a ray) hit of the ray out of several intersections.
-/// Use this to determine the object a camera actually sees.
-///
-/// This function consumes the contents of the "intersections" vector.
-/// You can, however, re-use it later, which reduces the number of vector allocations for intersections from O(n) to O(1).
-pub fn consuming_hit<'a>(intersections: &mut Vec<Intersection<'a>>) -> Option<Intersection<'a>> {
-    let mut lowest_non_neg_opt: Option<Intersection> = None;
-
-    while let Some(intersection) = intersections.pop() {
-        if intersection.t < 0.0 {
-            continue;
-        }
-        match &mut lowest_non_neg_opt {
-            None => lowest_non_neg_opt = Some(intersection),
-            Some(lowest_non_neg) => {
-                if intersection.t < lowest_non_neg.t {
-                    lowest_non_neg_opt = Some(intersection)
-                }
-            }
-        }
-    }
-
-    lowest_non_neg_opt
-}
-
 /// Computes the first (from the viewpoint of the origin of a ray) hit of the ray out of several intersections.
 /// Use this to determine the object a camera actually sees.
 ///
@@ -175,6 +196,7 @@ mod intersection_tests {
         intersection::Intersection,
         matrix::Mat4,
         ray::Ray,
+        scalar::Scalar,
         shapes::{plane::Plane, shape::Shape, sphere::Sphere},
         tuple::{Point, Vector},
     };
@@ -194,7 +216,7 @@ mod intersection_tests {
         let so = &s as &dyn Shape;
         let i1 = Intersection::new(1, so);
         let i2 = Intersection::new(2, so);
-        let xs = vec![i1, i2];
+        let xs = [i1, i2];
         assert_eq!(xs.len(), 2);
         assert!(xs[0].t.e_equals(1.));
         assert!(xs[1].t.e_equals(2.));
@@ -220,7 +242,7 @@ mod intersection_tests {
         let shape = &sphere as &dyn Shape;
         let i = Intersection::new(4.0, shape);
         let comps = i.prepare_computations(&r, &vec![i]);
-        assert_eq!(comps.inside, false);
+        assert!(!comps.inside);
     }
     #[test]
     fn test_precompute_inside() {
@@ -231,7 +253,7 @@ mod intersection_tests {
         let comps = i.prepare_computations(&r, &vec![i]);
         assert_eq!(comps.point, Point::new(0, 0, 1));
         assert_eq!(comps.eyev, Vector::new(0, 0, -1));
-        assert_eq!(comps.inside, true);
+        assert!(comps.inside);
         assert_eq!(comps.normalv, Vector::new(0, 0, -1));
     }
 
@@ -246,18 +268,29 @@ mod intersection_tests {
         assert!(comps.point.z > comps.over_point.z);
     }
 
+    #[test]
+    fn shadow_bias_of_object() {
+        let r = Ray::new(Point::new(0, 0, -5), Vector::new(0, 0, 1));
+        let mut shape = Sphere::default();
+        shape.set_shadow_bias(Some(0.5));
+        let i = Intersection::new(4, &shape);
+        let comps = i.prepare_computations(&r, &vec![i]);
+        assert!(comps.over_point.z.e_equals(-1.5));
+        assert!(comps.under_point.z.e_equals(-0.5));
+    }
+
     #[test]
     fn test_prepare_computations_reflection_vec() {
         let shape = Plane::default();
         let r = Ray::new(
             Point::new(0, 1, -1),
-            Vector::const_new(0.0, -(2.0_f64.sqrt()), 2.0_f64.sqrt()),
+            Vector::const_new(0.0, -(Scalar::sqrt(2.0)), Scalar::sqrt(2.0)),
         );
-        let i = Intersection::new(2.0_f64.sqrt(), &shape);
+        let i = Intersection::new(Scalar::sqrt(2.0), &shape);
         let comps = i.prepare_computations(&r, &vec![i]);
         assert_eq!(
             comps.reflectv,
-            Vector::new(0.0, 2.0_f64.sqrt(), 2.0_f64.sqrt())
+            Vector::new(0.0, Scalar::sqrt(2.0), Scalar::sqrt(2.0))
         );
     }
 
@@ -278,24 +311,12 @@ mod intersection_tests {
         let r = Ray::new(Point::new(0, 0, -4), Vector::new(0., 0., 0.25));
 
         let intersections = vec![
-            Intersection { t: 2.0, object: &a },
-            Intersection {
-                t: 2.75,
-                object: &b,
-            },
-            Intersection {
-                t: 3.25,
-                object: &c,
-            },
-            Intersection {
-                t: 4.75,
-                object: &b,
-            },
-            Intersection {
-                t: 5.25,
-                object: &c,
-            },
-            Intersection { t: 6.0, object: &a },
+            Intersection::new(2.0, &a),
+            Intersection::new(2.75, &b),
+            Intersection::new(3.25, &c),
+            Intersection::new(4.75, &b),
+            Intersection::new(5.25, &c),
+            Intersection::new(6.0, &a),
         ];
 
         param_test_n1_n2(0, &r, 1.0, 1.5, &intersections);
@@ -309,8 +330,8 @@ mod intersection_tests {
     fn param_test_n1_n2(
         index: usize,
         r: &Ray,
-        n1: f64,
-        n2: f64,
+        n1: Scalar,
+        n2: Scalar,
         intersections: &Vec<Intersection>,
     ) {
         let comps = intersections[index].prepare_computations(r, intersections);
@@ -338,62 +359,6 @@ mod intersection_tests {
     }
 }
 
-#[cfg(test)]
-mod hit_tests {
-    use crate::{
-        intersection::consuming_hit,
-        shapes::{shape::Shape, sphere::Sphere},
-    };
-
-    use super::Intersection;
-
-    #[test]
-    fn positive_t() {
-        let s = Sphere::default();
-        let so = &s as &dyn Shape;
-        let i1 = Intersection::new(1, so);
-        let i2 = Intersection::new(2, so);
-        let mut xs = vec![i1, i2];
-        let i = consuming_hit(&mut xs).unwrap();
-        assert_eq!(i, i1);
-    }
-
-    #[test]
-    fn some_negative_t() {
-        let s = Sphere::default();
-        let so = &s as &dyn Shape;
-        let i1 = Intersection::new(-1, so);
-        let i2 = Intersection::new(1, so);
-        let mut xs = vec![i1, i2];
-        let i = consuming_hit(&mut xs).unwrap();
-        assert_eq!(i, i2);
-    }
-
-    #[test]
-    fn negative_t() {
-        let s = Sphere::default();
-        let so = &s as &dyn Shape;
-        let i1 = Intersection::new(-2, so);
-        let i2 = Intersection::new(-1, so);
-        let mut xs = vec![i1, i2];
-        let i = consuming_hit(&mut xs);
-        assert!(i.is_none());
-    }
-
-    #[test]
-    fn lowest_nonnegative() {
-        let s = Sphere::default();
-        let so = &s as &dyn Shape;
-        let i1 = Intersection::new(5, so);
-        let i2 = Intersection::new(7, so);
-        let i3 = Intersection::new(-3, so);
-        let i4 = Intersection::new(2, so);
-        let mut xs = vec![i1, i2, i3, i4];
-        let i = consuming_hit(&mut xs).unwrap();
-        assert_eq!(i, i4);
-    }
-}
-
 #[cfg(test)]
 mod non_consuming_hit_tests {
     use crate::{
@@ -0,0 +1,156 @@
+use std::{collections::HashMap, sync::RwLock};
+
+use crate::{
+    color::{Color, BLACK},
+    scalar::Scalar,
+    tuple::{Point, Vector},
+};
+
+/// Cached values are only reused on surfaces facing (almost) the same way
+const MIN_NORMAL_AGREEMENT: Scalar = 0.9;
+
+#[derive(Copy, Clone, Debug)]
+struct IrradianceSample {
+    point: Point,
+    normal: Vector,
+    irradiance: Color,
+}
+
+/// Stores the indirect light arriving at points, so that nearby points facing the same way can reuse it instead of sampling it again.
+///
+/// Samples are sorted into a grid of cells the size of the reuse distance, so lookups only need to check the neighbouring cells.
+/// The cache can be shared between threads.
+#[derive(Debug, Default)]
+pub(crate) struct IrradianceCache {
+    cells: RwLock<HashMap<(i64, i64, i64), Vec<IrradianceSample>>>,
+}
+
+impl IrradianceCache {
+    fn cell(point: Point, spacing: Scalar) -> (i64, i64, i64) {
+        (
+            (point.x / spacing).floor() as i64,
+            (point.y / spacing).floor() as i64,
+            (point.z / spacing).floor() as i64,
+        )
+    }
+
+    /// The irradiance at ```point``` interpolated from cached samples closer than ```spacing```, None if there are none.
+    pub(crate) fn lookup(&self, point: Point, normal: Vector, spacing: Scalar) -> Option<Color> {
+        let cells = self.cells.read().expect("irradiance cache poisoned");
+        let (x, y, z) = Self::cell(point, spacing);
+
+        let mut sum = BLACK;
+        let mut total_weight = 0.0;
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    let Some(samples) = cells.get(&(x + dx, y + dy, z + dz)) else {
+                        continue;
+                    };
+                    for sample in samples {
+                        let distance = (sample.point - point).magnitude();
+                        if distance >= spacing || sample.normal.dot(normal) < MIN_NORMAL_AGREEMENT {
+                            continue;
+                        }
+                        let weight = 1.0 - distance / spacing;
+                        sum += sample.irradiance * weight;
+                        total_weight += weight;
+                    }
+                }
+            }
+        }
+
+        (total_weight > 0.0).then(|| sum * (1.0 / total_weight))
+    }
+
+    /// Caches the ```irradiance``` at ```point```
+    pub(crate) fn insert(&self, point: Point, normal: Vector, spacing: Scalar, irradiance: Color) {
+        let mut cells = self.cells.write().expect("irradiance cache poisoned");
+        cells
+            .entry(Self::cell(point, spacing))
+            .or_default()
+            .push(IrradianceSample {
+                point,
+                normal,
+                irradiance,
+            });
+    }
+
+    /// Removes all samples, e.g. because the scene changed
+    pub(crate) fn clear(&self) {
+        self.cells
+            .write()
+            .expect("irradiance cache poisoned")
+            .clear();
+    }
+
+    #[cfg(test)]
+    /// The number of cached samples
+    pub(crate) fn len(&self) -> usize {
+        self.cells
+            .read()
+            .expect("irradiance cache poisoned")
+            .values()
+            .map(Vec::len)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod irradiance_cache_tests {
+    use crate::{
+        color::Color,
+        irradiance_cache::IrradianceCache,
+        tuple::{Point, Vector},
+    };
+
+    #[test]
+    fn lookup() {
+        let cache = IrradianceCache::default();
+        let up = Vector::new(0, 1, 0);
+        assert_eq!(cache.lookup(Point::new(0, 0, 0), up, 1.0), None);
+
+        cache.insert(Point::new(0, 0, 0), up, 1.0, Color::new(1, 0, 0));
+        cache.insert(Point::new(1.0, 0.0, 0.0), up, 1.0, Color::new(0, 1, 0));
+        assert_eq!(cache.len(), 2);
+
+        // closer samples weigh more
+        assert_eq!(
+            cache.lookup(Point::new(0.1, 0.0, 0.0), up, 1.0),
+            Some(Color::new(0.9, 0.1, 0.0))
+        );
+        // in between, both samples are weighted equally
+        assert_eq!(
+            cache.lookup(Point::new(0.5, 0.0, 0.0), up, 1.0),
+            Some(Color::new(0.5, 0.5, 0.0))
+        );
+        // too far away
+        assert_eq!(cache.lookup(Point::new(0.5, 2.0, 0.0), up, 1.0), None);
+        // facing another way
+        assert_eq!(
+            cache.lookup(Point::new(0.1, 0.0, 0.0), Vector::new(1, 0, 0), 1.0),
+            None
+        );
+    }
+
+    #[test]
+    fn across_cells() {
+        let cache = IrradianceCache::default();
+        let up = Vector::new(0, 1, 0);
+        cache.insert(Point::new(-0.1, 0.0, 0.0), up, 1.0, Color::new(1, 1, 1));
+        assert!(cache.lookup(Point::new(0.1, 0.0, 0.0), up, 1.0).is_some());
+    }
+
+    #[test]
+    fn clear() {
+        let cache = IrradianceCache::default();
+        cache.insert(
+            Point::new(0, 0, 0),
+            Vector::new(0, 1, 0),
+            1.0,
+            Color::new(1, 1, 1),
+        );
+        cache.clear();
+        assert_eq!(cache.len(), 0);
+    }
+}
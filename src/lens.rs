@@ -0,0 +1,150 @@
+//! Depth of field: a camera lens with an aperture, instead of a pinhole that keeps everything in focus
+use crate::{
+    sampling::concentric_disk,
+    scalar::{consts::PI, Scalar},
+};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// The shape of the opening of a lens. Out-of-focus highlights (bokeh) take on this shape.
+pub enum Aperture {
+    /// A round opening, giving round bokeh
+    #[default]
+    Disk,
+    /// A regular polygon formed by the blades of the aperture, like in real cameras: 6 blades give hexagonal bokeh.
+    /// ```rotation``` turns the polygon (in radians), with a corner pointing right at 0. Fewer than 3 blades give a disk.
+    Polygon {
+        /// The number of blades, i.e. corners of the polygon
+        blades: usize,
+        /// The rotation of the polygon in radians
+        rotation: Scalar,
+    },
+}
+
+impl Aperture {
+    /// Maps a position in the unit square (e.g. from a [`crate::sampling::Sampler`]) to a point within the aperture of radius 1.
+    /// Evenly spread positions stay evenly spread over the aperture.
+    pub fn sample(&self, (u, v): (Scalar, Scalar)) -> (Scalar, Scalar) {
+        match *self {
+            Aperture::Polygon { blades, rotation } if blades >= 3 => {
+                // pick one of the triangles between the center and two neighbouring corners, then a point within it
+                let scaled = u * blades as Scalar;
+                let triangle = (scaled.floor() as usize).min(blades - 1);
+                let distance = (scaled - triangle as Scalar).sqrt();
+                let corner = |i: usize| {
+                    let angle = rotation + 2.0 * PI * i as Scalar / blades as Scalar;
+                    (angle.cos(), angle.sin())
+                };
+                let (a, b) = (corner(triangle), corner(triangle + 1));
+                (
+                    distance * ((1.0 - v) * a.0 + v * b.0),
+                    distance * ((1.0 - v) * a.1 + v * b.1),
+                )
+            }
+            _ => concentric_disk((u, v)),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// A thin lens, see [`crate::camera::Camera::depth_of_field`]: objects at the focal distance are sharp, objects nearer or farther away get blurrier the bigger the aperture.
+pub struct DepthOfField {
+    /// The radius of the aperture in world units. 0 is a pinhole camera, keeping everything sharp.
+    pub aperture_radius: Scalar,
+    /// The distance in front of the camera that is in focus
+    pub focal_distance: Scalar,
+    /// The shape of the aperture, [`Aperture::Disk`] by default
+    pub aperture: Aperture,
+}
+
+impl DepthOfField {
+    /// A round aperture with the given radius, focused at ```focal_distance```
+    pub fn new(aperture_radius: Scalar, focal_distance: Scalar) -> Self {
+        Self {
+            aperture_radius,
+            focal_distance,
+            aperture: Aperture::default(),
+        }
+    }
+
+    /// The same lens with another aperture shape
+    pub fn with_aperture(mut self, aperture: Aperture) -> Self {
+        self.aperture = aperture;
+        self
+    }
+}
+
+#[cfg(test)]
+mod aperture_tests {
+    use crate::{
+        lens::Aperture,
+        sampling::Rng,
+        scalar::{consts::PI, Scalar},
+    };
+
+    /// How far below the end of a sector corners are sampled, f32 cannot hold smaller steps
+    const NUDGE: Scalar = if cfg!(feature = "f32") { 1e-6 } else { 1e-12 };
+    /// How close samples get to the corners
+    const CORNER_TOLERANCE: Scalar = if cfg!(feature = "f32") { 1e-4 } else { 1e-5 };
+
+    fn samples(aperture: Aperture) -> Vec<(Scalar, Scalar)> {
+        let mut rng = Rng::new(1);
+        (0..1000)
+            .map(|_| aperture.sample((rng.next_scalar(), rng.next_scalar())))
+            .collect()
+    }
+
+    #[test]
+    fn disk() {
+        for (x, y) in samples(Aperture::Disk) {
+            assert!(x.hypot(y) <= 1.0);
+        }
+        assert_eq!(Aperture::Disk.sample((0.5, 0.5)), (0.0, 0.0));
+    }
+
+    #[test]
+    fn hexagon() {
+        let hexagon = Aperture::Polygon {
+            blades: 6,
+            rotation: 0.0,
+        };
+        // every point lies within all edges, which are cos(30°) from the center
+        let apothem = (PI / 6.0).cos();
+        for (x, y) in samples(hexagon) {
+            for edge in 0..6 {
+                let angle = PI / 6.0 + edge as Scalar * PI / 3.0;
+                assert!(x * angle.cos() + y * angle.sin() <= apothem + 1e-9);
+            }
+        }
+        // corners are reached, unlike with a disk of the apothem's radius
+        let (x, y) = hexagon.sample((1.0 / 6.0 - NUDGE, 0.0));
+        assert!((x - 1.0).abs() < CORNER_TOLERANCE && y.abs() < CORNER_TOLERANCE);
+    }
+
+    #[test]
+    fn rotation() {
+        let rotated = Aperture::Polygon {
+            blades: 4,
+            rotation: PI / 4.0,
+        };
+        let (x, y) = rotated.sample((0.25 - NUDGE, 0.0));
+        assert!(
+            (x - (PI / 4.0).cos()).abs() < CORNER_TOLERANCE
+                && (y - (PI / 4.0).sin()).abs() < CORNER_TOLERANCE
+        );
+    }
+
+    #[test]
+    fn too_few_blades_give_disk() {
+        let sample = (0.3, 0.7);
+        assert_eq!(
+            Aperture::Polygon {
+                blades: 2,
+                rotation: 1.0
+            }
+            .sample(sample),
+            Aperture::Disk.sample(sample)
+        );
+    }
+}
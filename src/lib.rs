@@ -9,32 +9,81 @@
 //! ## rayon
 //! You can activate the "rayon" feature to enable cpu-paralellism.
 //! It will utilize all cores and split the workload at rendering each row seperately.
+//! ## preview
+//! Opens a window showing renders row by row as they finish, see [`preview::PreviewWindow`]. Closing it or pressing Escape aborts the render.
+//! ## serde
+//! Implements ```Serialize``` and ```Deserialize``` for [`world::World`], [`camera::Camera`] and everything in them, to save scenes to and load them from files.
+//! Shapes, lights and patterns the crate does not know about (e.g. custom patterns) cannot be serialized.
+//! Neither can texture and cube maps ([`pattern::PatternKind::Texture`] and [`pattern::PatternKind::Cube`]), as they do not remember the images or functions they were made from:
+//! serializing a material using one of them fails with an error, and scene files cannot contain them.
+//! ## cli
+//! Builds the ```rtc``` binary, which renders scene files written in YAML or JSON: ```rtc render scenes/spheres.yaml -o spheres.png --size 1920x1080```.
+//! Enables "serde". Run ```rtc help``` for all commands and options.
 //! ## shininess_as_float
 //! Per standard, the shininess value of a material is stored as an unsized integer to improve performance, as raising a float to the power of an int is significantly faster than to the power of a float
+//! ## glam / nalgebra
+//! Converts [`tuple::Point`], [`tuple::Vector`] and [`matrix::Mat4`] from and to the vectors and matrices of glam or nalgebra, to embed the raytracer in applications built on them.
+//! ## f32
+//! Computes everything in single precision, see [`scalar::Scalar`]. Halves the memory used by meshes and canvases, at the cost of more shadow acne and artifacts in large scenes.
 
+pub mod bmp;
+pub mod bounds;
+/// A bounding volume hierarchy to find the objects a ray may hit
+mod bvh;
 /// A camera, used to render the world from a certain view.
 pub mod camera;
+pub mod camera_path;
 /// A canvas to render the world to.
 pub mod canvas;
 
 /// The color of a point or a pixel on a canvas
 pub mod color;
+pub mod deep;
+pub mod distributed;
 mod epsilon;
+#[cfg(feature = "glam")]
+mod glam_interop;
+pub mod hdr;
 /// An intersection occurs when a ray hits an object
 mod intersection;
+/// Caches indirect light for global illumination
+mod irradiance_cache;
+pub mod lens;
 /// A light source in the scene
 pub mod light;
 /// Every object in the scene has a material
 pub mod material;
 /// The nxn matrices used for computations
 pub mod matrix;
+#[cfg(feature = "nalgebra")]
+mod nalgebra_interop;
 pub mod pattern;
+#[cfg(feature = "png")]
+pub mod png;
 /// PPM file format logic
 pub mod ppm;
+#[cfg(feature = "preview")]
+pub mod preview;
+pub mod quantize;
 /// What gives a raytracer it's name
 pub mod ray;
+pub mod render_pass;
+/// Pseudo random numbers and sample patterns for stochastic sampling
+pub mod sampling;
+pub mod scalar;
+pub mod scene;
+#[cfg(feature = "serde")]
+mod serialization;
+pub mod settings;
 /// All shapes reside here
 pub mod shapes;
+pub mod skybox;
+pub mod stats;
+pub mod terminal;
+/// Chainable transformations of shapes and patterns
+pub mod transform;
 /// Vectors and Points in 3d euclidean space
 pub mod tuple;
+pub mod uv;
+pub mod validation;
 pub mod world;
@@ -1,13 +1,125 @@
-use crate::{color::Color, tuple::Point};
+use crate::{
+    color::{Color, BLACK},
+    intersection::Intersection,
+    matrix::Mat4,
+    scalar::{consts::PI, Scalar},
+    shapes::shape::ObjectId,
+    tuple::{Point, Vector},
+    world::World,
+};
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+use std::{any::Any, collections::HashSet, fmt::Debug};
 
+#[cfg(feature = "rayon")]
+/// Trait dependencies for Light - differ depending on rayon being active
+pub trait LightBound: Any + Debug + Send + Sync {}
+
+#[cfg(not(feature = "rayon"))]
+/// Trait dependencies for Light - differ depending on rayon being active
+pub trait LightBound: Any + Debug {}
+
+/// Which objects a light illuminates, identified by their [`ObjectId`].
+///
+/// Objects the light is not linked to only receive its ambient part, as if they were in its shadow.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LightLinking {
+    /// The light illuminates every object
+    #[default]
+    All,
+    /// The light only illuminates objects with one of these ids
+    Only(HashSet<ObjectId>),
+    /// The light illuminates every object except for the ones with these ids
+    AllExcept(HashSet<ObjectId>),
+}
+
+impl LightLinking {
+    /// Whether an object with the given id is illuminated. Objects without id are only excluded by [`Self::Only`].
+    pub fn illuminates(&self, id: Option<ObjectId>) -> bool {
+        match self {
+            LightLinking::All => true,
+            LightLinking::Only(ids) => id.is_some_and(|id| ids.contains(&id)),
+            LightLinking::AllExcept(ids) => id.is_none_or(|id| !ids.contains(&id)),
+        }
+    }
+}
+
+/// This trait encapsulates the shared behaviour of all light sources in the world.
+///
+/// If you want to add your own light, implement this trait for it.
+/// Shading and shadow tests only ever talk to lights through this trait.
+pub trait Light: LightBound {
+    /// The color and strength of this light.
+    fn intensity(&self) -> Color;
+    /// The (normalized) direction from ```point``` towards the light, i.e. where the light is sampled from.
+    fn direction_from(&self, point: Point) -> Vector;
+    /// The distance between ```point``` and the light. Shadow rays ignore objects further away than this.
+    fn distance_from(&self, point: Point) -> Scalar;
+    /// The light arriving at ```point```, not taking shadows into account.
+    /// Override this if the light is not equally strong everywhere.
+    fn intensity_at(&self, _point: Point) -> Color {
+        self.intensity()
+    }
+    /// How much of this light reaches ```point```, as a color: WHITE if nothing is in the way, BLACK if the point is completely in shadow.
+    /// By default, casts a ray towards the light and lets every object in between tint or block it, see [`World::transmission_along`].
+    fn transmission<'a>(
+        &self,
+        world: &'a World,
+        point: Point,
+        intersections: &mut Vec<Intersection<'a>>,
+    ) -> Color {
+        world.transmission_along(
+            point,
+            self.direction_from(point),
+            self.distance_from(point),
+            intersections,
+        )
+    }
+    /// Which objects this light illuminates.
+    fn linking(&self) -> &LightLinking;
+    /// Links this light to a set of objects, see [`LightLinking`].
+    fn set_linking(&mut self, linking: LightLinking);
+    /// Whether this light contributes diffuse lighting.
+    fn diffuse(&self) -> bool;
+    /// Enables or disables the diffuse contribution of this light.
+    fn set_diffuse(&mut self, diffuse: bool);
+    /// Whether this light causes specular highlights.
+    /// Disable this e.g. for fill lights, so they do not add a second highlight.
+    fn specular(&self) -> bool;
+    /// Enables or disables the specular contribution of this light.
+    fn set_specular(&mut self, specular: bool);
+    /// Moves and turns the light by ```matrix```, e.g. when placing a group of objects and lights, see [`World::merge_transformed`].
+    fn transform(&mut self, matrix: Mat4);
+    /// Compares this light to any other one.
+    ///
+    /// Needed to implement PartialEq for all lights.
+    fn eq(&self, other: &dyn Any) -> bool;
+    /// Converts this to any, used to implement PartialEq.
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl PartialEq for dyn Light {
+    fn eq(&self, other: &dyn Light) -> bool {
+        self.eq(other.as_any())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A simple, omni-directional point light.
 pub struct PointLight {
     /// Position of this light in the world
     pub position: Point,
     /// The color and strength of this light. Use a more dimmed color for less intensity.
     pub intensity: Color,
+    /// Which objects this light illuminates
+    pub linking: LightLinking,
+    /// Whether this light contributes diffuse lighting
+    pub diffuse: bool,
+    /// Whether this light causes specular highlights
+    pub specular: bool,
+    /// Whether the light gets weaker with the square of the distance, like real lights do
+    pub inverse_square: bool,
 }
 
 impl PointLight {
@@ -16,13 +128,357 @@ impl PointLight {
         Self {
             position,
             intensity,
+            linking: LightLinking::All,
+            diffuse: true,
+            specular: true,
+            inverse_square: false,
+        }
+    }
+
+    /// Instantiates a new PointLight with the given ```color``` and a luminous ```power``` in lumens.
+    ///
+    /// Unlike lights created with [`Self::new`], its light falls off with the square of the distance. Use the exposure of the camera to bring such scenes into a displayable range.
+    pub fn with_power(position: Point, color: Color, power: Scalar) -> Self {
+        Self {
+            inverse_square: true,
+            ..Self::new(position, color * (power / (4.0 * PI)))
+        }
+    }
+}
+
+impl LightBound for PointLight {}
+
+impl Light for PointLight {
+    fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    fn direction_from(&self, point: Point) -> Vector {
+        (self.position - point).normalized()
+    }
+
+    fn distance_from(&self, point: Point) -> Scalar {
+        (self.position - point).magnitude()
+    }
+
+    fn intensity_at(&self, point: Point) -> Color {
+        attenuated(
+            self.intensity,
+            self.inverse_square,
+            self.distance_from(point),
+        )
+    }
+
+    fn linking(&self) -> &LightLinking {
+        &self.linking
+    }
+
+    fn set_linking(&mut self, linking: LightLinking) {
+        self.linking = linking;
+    }
+
+    fn diffuse(&self) -> bool {
+        self.diffuse
+    }
+
+    fn set_diffuse(&mut self, diffuse: bool) {
+        self.diffuse = diffuse;
+    }
+
+    fn specular(&self) -> bool {
+        self.specular
+    }
+
+    fn set_specular(&mut self, specular: bool) {
+        self.specular = specular;
+    }
+
+    fn transform(&mut self, matrix: Mat4) {
+        self.position = matrix * self.position;
+    }
+
+    fn eq(&self, other: &dyn Any) -> bool {
+        other.downcast_ref::<Self>() == Some(self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// A light shining from a position in a cone around its direction.
+///
+/// Inside the inner cone, the light is at full strength. Between the inner and the outer cone it fades out smoothly, outside the outer cone there is no light at all.
+pub struct SpotLight {
+    /// Position of this light in the world
+    pub position: Point,
+    /// The direction the light shines in
+    pub direction: Vector,
+    /// Angle (in radians) between the direction and the edge of the fully lit cone
+    pub inner_angle: Scalar,
+    /// Angle (in radians) between the direction and the edge of the light cone
+    pub outer_angle: Scalar,
+    /// The color and strength of this light. Use a more dimmed color for less intensity.
+    pub intensity: Color,
+    /// Which objects this light illuminates
+    pub linking: LightLinking,
+    /// Whether this light contributes diffuse lighting
+    pub diffuse: bool,
+    /// Whether this light causes specular highlights
+    pub specular: bool,
+    /// Whether the light gets weaker with the square of the distance, like real lights do
+    pub inverse_square: bool,
+}
+
+impl SpotLight {
+    /// Instantiates a new SpotLight at ```position```, shining in ```direction``` with the given cone angles (in radians).
+    pub fn new(
+        position: Point,
+        direction: Vector,
+        inner_angle: Scalar,
+        outer_angle: Scalar,
+        intensity: Color,
+    ) -> Self {
+        Self {
+            position,
+            direction,
+            inner_angle,
+            outer_angle,
+            intensity,
+            linking: LightLinking::All,
+            diffuse: true,
+            specular: true,
+            inverse_square: false,
+        }
+    }
+
+    /// How much of the light reaches ```point```, from 0 (outside the outer cone) to 1 (inside the inner cone).
+    pub fn falloff(&self, point: Point) -> Scalar {
+        let cos_angle = (point - self.position)
+            .normalized()
+            .dot(self.direction.normalized());
+        let cos_inner = self.inner_angle.cos();
+        let cos_outer = self.outer_angle.cos();
+
+        if cos_angle >= cos_inner {
+            return 1.0;
+        }
+        if cos_angle <= cos_outer {
+            return 0.0;
+        }
+
+        let t = (cos_angle - cos_outer) / (cos_inner - cos_outer);
+        t * t * (3.0 - 2.0 * t)
+    }
+
+    /// Instantiates a new SpotLight with the given ```color``` and a luminous ```power``` in lumens, see [`PointLight::with_power`].
+    /// The power is the one of a point light shining in all directions, so narrowing the cone does not make the light brighter.
+    pub fn with_power(
+        position: Point,
+        direction: Vector,
+        inner_angle: Scalar,
+        outer_angle: Scalar,
+        color: Color,
+        power: Scalar,
+    ) -> Self {
+        Self {
+            inverse_square: true,
+            ..Self::new(
+                position,
+                direction,
+                inner_angle,
+                outer_angle,
+                color * (power / (4.0 * PI)),
+            )
         }
     }
 }
 
+impl LightBound for SpotLight {}
+
+impl Light for SpotLight {
+    fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    fn direction_from(&self, point: Point) -> Vector {
+        (self.position - point).normalized()
+    }
+
+    fn distance_from(&self, point: Point) -> Scalar {
+        (self.position - point).magnitude()
+    }
+
+    fn intensity_at(&self, point: Point) -> Color {
+        attenuated(
+            self.intensity,
+            self.inverse_square,
+            self.distance_from(point),
+        ) * self.falloff(point)
+    }
+
+    /// Points outside of the cone are always in shadow, without casting a shadow ray.
+    fn transmission<'a>(
+        &self,
+        world: &'a World,
+        point: Point,
+        intersections: &mut Vec<Intersection<'a>>,
+    ) -> Color {
+        if self.falloff(point) == 0.0 {
+            return BLACK;
+        }
+        world.transmission_along(
+            point,
+            self.direction_from(point),
+            self.distance_from(point),
+            intersections,
+        )
+    }
+
+    fn linking(&self) -> &LightLinking {
+        &self.linking
+    }
+
+    fn set_linking(&mut self, linking: LightLinking) {
+        self.linking = linking;
+    }
+
+    fn diffuse(&self) -> bool {
+        self.diffuse
+    }
+
+    fn set_diffuse(&mut self, diffuse: bool) {
+        self.diffuse = diffuse;
+    }
+
+    fn specular(&self) -> bool {
+        self.specular
+    }
+
+    fn set_specular(&mut self, specular: bool) {
+        self.specular = specular;
+    }
+
+    fn transform(&mut self, matrix: Mat4) {
+        self.position = matrix * self.position;
+        self.direction = (matrix * self.direction).normalized();
+    }
+
+    fn eq(&self, other: &dyn Any) -> bool {
+        other.downcast_ref::<Self>() == Some(self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// A light infinitely far away, like the sun: all its rays are parallel and it has no position.
+pub struct DirectionalLight {
+    /// The direction the light travels in
+    pub direction: Vector,
+    /// The color and strength of this light. Use a more dimmed color for less intensity.
+    pub intensity: Color,
+    /// Which objects this light illuminates
+    pub linking: LightLinking,
+    /// Whether this light contributes diffuse lighting
+    pub diffuse: bool,
+    /// Whether this light causes specular highlights
+    pub specular: bool,
+}
+
+impl DirectionalLight {
+    /// Instantiates a new DirectionalLight shining in ```direction``` with the ```intensity``` as color.
+    pub fn new(direction: Vector, intensity: Color) -> Self {
+        Self {
+            direction,
+            intensity,
+            linking: LightLinking::All,
+            diffuse: true,
+            specular: true,
+        }
+    }
+
+    /// Instantiates a new DirectionalLight with the given ```color``` and an ```illuminance``` in lux (sunlight is about 100000 lux).
+    pub fn with_illuminance(direction: Vector, color: Color, illuminance: Scalar) -> Self {
+        Self::new(direction, color * illuminance)
+    }
+}
+
+impl LightBound for DirectionalLight {}
+
+impl Light for DirectionalLight {
+    fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    fn direction_from(&self, _point: Point) -> Vector {
+        -self.direction.normalized()
+    }
+
+    /// The light is infinitely far away, so every object along the shadow ray casts a shadow.
+    fn distance_from(&self, _point: Point) -> Scalar {
+        Scalar::INFINITY
+    }
+
+    fn linking(&self) -> &LightLinking {
+        &self.linking
+    }
+
+    fn set_linking(&mut self, linking: LightLinking) {
+        self.linking = linking;
+    }
+
+    fn diffuse(&self) -> bool {
+        self.diffuse
+    }
+
+    fn set_diffuse(&mut self, diffuse: bool) {
+        self.diffuse = diffuse;
+    }
+
+    fn specular(&self) -> bool {
+        self.specular
+    }
+
+    fn set_specular(&mut self, specular: bool) {
+        self.specular = specular;
+    }
+
+    fn transform(&mut self, matrix: Mat4) {
+        self.direction = (matrix * self.direction).normalized();
+    }
+
+    fn eq(&self, other: &dyn Any) -> bool {
+        other.downcast_ref::<Self>() == Some(self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// The intensity of a light at a given distance, falling off with the square of the distance if ```inverse_square``` is set.
+fn attenuated(intensity: Color, inverse_square: bool, distance: Scalar) -> Color {
+    if inverse_square {
+        intensity * (1.0 / (distance * distance))
+    } else {
+        intensity
+    }
+}
+
 #[cfg(test)]
-pub mod point_light_tests {
-    use crate::{color::Color, light::PointLight, tuple::Point};
+mod point_light_tests {
+    use crate::{
+        color::{Color, WHITE},
+        light::{Light, PointLight},
+        scalar::consts::PI,
+        tuple::{Point, Vector},
+    };
 
     #[test]
     fn instantiate() {
@@ -32,4 +488,184 @@ pub mod point_light_tests {
         assert_eq!(light.intensity, intensity);
         assert_eq!(light.position, position);
     }
+
+    #[test]
+    fn sampled_from_point() {
+        let light = PointLight::new(Point::new(0, 10, 0), WHITE);
+        let p = Point::new(0, 2, 0);
+        assert_eq!(light.direction_from(p), Vector::new(0, 1, 0));
+        assert_eq!(light.distance_from(p), 8.0);
+        assert_eq!(light.intensity_at(p), WHITE);
+    }
+
+    #[test]
+    fn with_power_falls_off_with_distance() {
+        let light = PointLight::with_power(Point::new(0, 0, 0), WHITE, 4.0 * PI * 100.0);
+        assert_eq!(light.intensity, Color::new(100, 100, 100));
+        assert_eq!(
+            light.intensity_at(Point::new(0, 10, 0)),
+            Color::new(1, 1, 1)
+        );
+        assert_eq!(
+            light.intensity_at(Point::new(0, 0, 20)),
+            Color::new(0.25, 0.25, 0.25)
+        );
+    }
+
+    #[test]
+    fn compare_as_trait_objects() {
+        let l1: Box<dyn Light> = Box::new(PointLight::new(Point::new(0, 10, 0), WHITE));
+        let l2: Box<dyn Light> = Box::new(PointLight::new(Point::new(0, 10, 0), WHITE));
+        let l3: Box<dyn Light> = Box::new(PointLight::new(Point::new(0, 5, 0), WHITE));
+        assert!(*l1 == *l2);
+        assert!(*l1 != *l3);
+    }
+}
+
+#[cfg(test)]
+mod spot_light_tests {
+    use crate::{
+        color::{BLACK, WHITE},
+        light::{Light, SpotLight},
+        matrix::Mat4,
+        scalar::consts::PI,
+        tuple::{Point, Vector},
+        world::World,
+    };
+
+    fn spot() -> SpotLight {
+        SpotLight::new(
+            Point::new(0, 10, 0),
+            Vector::new(0, -1, 0),
+            PI / 8.0,
+            PI / 4.0,
+            WHITE,
+        )
+    }
+
+    #[test]
+    fn transform() {
+        let mut light = spot();
+        light.transform(Mat4::new_translation(5, 0, 0) * Mat4::new_rotation_z(PI / 2.0));
+        assert_eq!(light.position, Point::new(-5, 0, 0));
+        assert_eq!(light.direction, Vector::new(1, 0, 0));
+        assert_eq!(light.falloff(Point::new(0, 0, 0)), 1.0);
+    }
+
+    #[test]
+    fn inside_inner_cone() {
+        let light = spot();
+        assert_eq!(light.falloff(Point::new(0, 0, 0)), 1.0);
+        assert_eq!(light.intensity_at(Point::new(1, 0, 0)), WHITE);
+    }
+
+    #[test]
+    fn outside_outer_cone() {
+        let light = spot();
+        assert_eq!(light.falloff(Point::new(20, 0, 0)), 0.0);
+        assert_eq!(light.intensity_at(Point::new(0, 20, 0)), BLACK);
+    }
+
+    #[test]
+    fn smooth_falloff_between_cones() {
+        let light = spot();
+        let mut previous = 1.0;
+        for x in 5..=9 {
+            let f = light.falloff(Point::new(x, 0, 0));
+            assert!(f < previous && f > 0.0);
+            previous = f;
+        }
+        assert_eq!(light.falloff(Point::new(11, 0, 0)), 0.0);
+    }
+
+    #[test]
+    fn outside_cone_is_in_shadow() {
+        let w = World::default();
+        let light = spot();
+        let mut intersections = Vec::new();
+        assert_eq!(
+            light.transmission(&w, Point::new(20, 0, 0), &mut intersections),
+            BLACK
+        );
+        assert_eq!(
+            light.transmission(&w, Point::new(0, 0, 0), &mut intersections),
+            WHITE
+        );
+    }
+}
+
+#[cfg(test)]
+mod directional_light_tests {
+    use crate::{
+        color::{BLACK, WHITE},
+        light::{DirectionalLight, Light},
+        matrix::Mat4,
+        scalar::Scalar,
+        shapes::{shape::Shape, sphere::Sphere},
+        tuple::{Point, Vector},
+        world::World,
+    };
+
+    #[test]
+    fn same_direction_everywhere() {
+        let light = DirectionalLight::new(Vector::new(0, -2, 0), WHITE);
+        assert_eq!(
+            light.direction_from(Point::new(0, 0, 0)),
+            Vector::new(0, 1, 0)
+        );
+        assert_eq!(
+            light.direction_from(Point::new(100, -50, 3)),
+            Vector::new(0, 1, 0)
+        );
+        assert_eq!(light.distance_from(Point::new(0, 0, 0)), Scalar::INFINITY);
+    }
+
+    #[test]
+    fn shadow_without_distance_cutoff() {
+        let mut w = World::default();
+        let mut s = Sphere::default();
+        s.set_transformation_matrix(Mat4::new_translation(0, 10_000, 0));
+        w.add_object(Box::new(s));
+
+        let light = DirectionalLight::new(Vector::new(0, -1, 0), WHITE);
+        let mut intersections = Vec::new();
+        assert_eq!(
+            light.transmission(&w, Point::new(0, 0, 0), &mut intersections),
+            BLACK
+        );
+        assert_eq!(
+            light.transmission(&w, Point::new(5, 0, 0), &mut intersections),
+            WHITE
+        );
+    }
+}
+
+#[cfg(test)]
+mod light_linking_tests {
+    use std::collections::HashSet;
+
+    use crate::{light::LightLinking, shapes::shape::ObjectId};
+
+    #[test]
+    fn all() {
+        let linking = LightLinking::All;
+        assert!(linking.illuminates(Some(ObjectId(1))));
+        assert!(linking.illuminates(None));
+    }
+
+    #[test]
+    fn only() {
+        let linking = LightLinking::Only(HashSet::from([ObjectId(1)]));
+        assert!(linking.illuminates(Some(ObjectId(1))));
+        assert!(!linking.illuminates(Some(ObjectId(2))));
+        assert!(!linking.illuminates(None));
+    }
+
+    #[test]
+    fn all_except() {
+        let linking = LightLinking::AllExcept(HashSet::from([ObjectId(1)]));
+        assert!(!linking.illuminates(Some(ObjectId(1))));
+        assert!(linking.illuminates(Some(ObjectId(2))));
+        assert!(linking.illuminates(None));
+    }
 }
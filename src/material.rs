@@ -1,41 +1,74 @@
 use crate::{
     color::{Color, BLACK},
     epsilon::EpsilonEqual,
-    light::PointLight,
+    light::Light,
     pattern::Pattern,
+    scalar::{consts::PI, Scalar},
     shapes::shape::Shape,
     tuple::{Point, Vector},
+    uv::{BumpMap, Decal},
 };
 
 #[cfg(feature = "shininess_as_float")]
 /// The shininess of a material. This type exists to facilitate usage of the feature "shininess_as_float" (documented at the crate root).
-pub type Shininess = f64;
+pub type Shininess = Scalar;
 
 #[cfg(not(feature = "shininess_as_float"))]
 /// The shininess of a material. This type exists to facilitate usage of the feature "shininess_as_float" (documented at the crate root).
 pub type Shininess = i32;
 
 #[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
 /// The material any object in the rendered world must have.
 /// The materials actual color at a given world position can be determined using its ```lighting()``` method which uses the phong shading model.
 pub struct Material {
     /// The Color of the material
     pub color: ColorType,
     /// Ambient factor used in the color rendering
-    pub ambient: f64,
+    pub ambient: Scalar,
     /// Diffuse factor used in the color rendering
-    pub diffuse: f64,
+    pub diffuse: Scalar,
     /// Specular factor used in the color rendering
-    pub specular: f64,
+    pub specular: Scalar,
     /// Shininess factor used in the color rendering.
     /// For performance reasons, this is an ```i32``` by default. Use the "shininess_as_float" feature to switch over to floating point.
     pub shininess: Shininess,
     /// Reflection factor
-    pub reflective: f64,
+    pub reflective: Scalar,
     /// Transparency of the material. Values considered between 0 and 1 where 0 = solid and 1 = fully transparent.
-    pub transparency: f64,
+    pub transparency: Scalar,
     /// The material's refractive index when shining light through it. Only applied if transparency != 0.
-    pub refractive_index: f64,
+    pub refractive_index: Scalar,
+    /// The shape of the specular highlight, see [`SpecularModel`]
+    pub specular_model: SpecularModel,
+    /// Images projected onto the material, like labels or logos, see [`Decal`]
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            skip_deserializing,
+            skip_serializing_if = "Vec::is_empty",
+            serialize_with = "crate::serialization::unsupported"
+        )
+    )]
+    pub decals: Vec<Decal>,
+    /// Bumps on the surface, faked by tilting the normals, see [`BumpMap`]
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            skip_deserializing,
+            skip_serializing_if = "Option::is_none",
+            serialize_with = "crate::serialization::unsupported"
+        )
+    )]
+    pub bump_map: Option<BumpMap>,
+    /// An optional transparent layer on top of the material, like lacquer or car paint, see [`ClearCoat`]
+    pub clear_coat: Option<ClearCoat>,
+    /// Light given off by the material itself. It is added regardless of any light source, so the material stays bright even in shadow, but it does not illuminate other objects.
+    pub emissive: Color,
 }
 
 #[cfg(feature = "shininess_as_float")]
@@ -55,6 +88,11 @@ impl Default for Material {
             reflective: 0.0,
             transparency: 0.0,
             refractive_index: 1.0,
+            specular_model: SpecularModel::Phong,
+            decals: Vec::new(),
+            bump_map: None,
+            clear_coat: None,
+            emissive: BLACK,
         }
     }
 }
@@ -68,6 +106,11 @@ impl<'a> PartialEq for Material<'a> {
             && epsilon_equal(self.diffuse, other.diffuse)
             && epsilon_equal(self.specular, other.specular)
             && epsilon_equal(self.shininess, other.shininess)
+            && self.decals == other.decals
+            && self.bump_map == other.bump_map
+            && self.clear_coat == other.clear_coat
+            && self.specular_model == other.specular_model
+            && self.emissive == other.emissive
     }
 }
 
@@ -79,6 +122,11 @@ impl PartialEq for Material {
             && self.diffuse.e_equals(other.diffuse)
             && self.specular.e_equals(other.specular)
             && self.shininess.e_equals(other.shininess)
+            && self.decals == other.decals
+            && self.bump_map == other.bump_map
+            && self.clear_coat == other.clear_coat
+            && self.specular_model == other.specular_model
+            && self.emissive == other.emissive
     }
 }
 
@@ -103,13 +151,13 @@ impl Material {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         color: ColorType,
-        ambient: f64,
-        diffuse: f64,
-        specular: f64,
+        ambient: Scalar,
+        diffuse: Scalar,
+        specular: Scalar,
         shininess: Shininess,
-        reflective: f64,
-        transparency: f64,
-        refractive_index: f64,
+        reflective: Scalar,
+        transparency: Scalar,
+        refractive_index: Scalar,
     ) -> Self {
         Self {
             color,
@@ -120,69 +168,92 @@ impl Material {
             reflective,
             transparency,
             refractive_index,
+            specular_model: SpecularModel::Phong,
+            decals: Vec::new(),
+            bump_map: None,
+            clear_coat: None,
+            emissive: BLACK,
         }
     }
 
-    /// Ambient = false disables the ambient factor, so that two light sources dont double the ambient factor
+    /// The color of this material at a given point (world space) of ```object```.
+    /// Decals are layered on top of the base color, in order.
+    pub(crate) fn color_at(&self, object: &dyn Shape, point: Point) -> Color {
+        let base = match &self.color {
+            ColorType::Color(color) => *color,
+            ColorType::Pattern(pattern) => pattern.apply_pattern_world_space(object, point),
+        };
+        self.decals
+            .iter()
+            .fold(base, |color, decal| decal.over(color, object, point))
+    }
+
+    /// The diffuse and specular light from ```light```. The ambient light and the emissive color are added once per hit by the world, not per light.
+    /// Transmission is the part of the light that is not blocked by objects in between, see [`crate::light::Light::transmission`].
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn lighting(
         &self,
-        light: &PointLight,
+        light: &dyn Light,
         object: &dyn Shape,
         point: Point,
         eyev: Vector,
         normalv: Vector,
-        in_shadow: bool,
-        use_ambient: bool,
+        transmission: Color,
     ) -> Color {
-        let color = match &self.color {
-            ColorType::Color(color) => *color,
-            ColorType::Pattern(pattern) => pattern.apply_pattern_world_space(object, point),
-        };
-
-        let effective_color = color * light.intensity;
-
-        let lightv = (light.position - point).normalized();
-
-        let ambient = if use_ambient {
-            effective_color * self.ambient
-        } else {
-            BLACK
-        };
-
-        if in_shadow {
-            return ambient;
+        if transmission == BLACK {
+            return BLACK;
         }
 
+        let intensity = light.intensity_at(point) * transmission;
+        let effective_color = self.color_at(object, point) * intensity;
+
+        let lightv = light.direction_from(point);
         let light_dot_normal = lightv.dot(normalv);
 
         let (diffuse, specular) = if light_dot_normal < 0.0 {
             // light is behind object
             (BLACK, BLACK)
         } else {
-            let diffuse = effective_color * self.diffuse * light_dot_normal;
-            let reflectv = -lightv.reflect(normalv);
-            let reflect_dot_eye = reflectv.dot(eyev);
-            let specular = if reflect_dot_eye <= 0.0 {
-                // light reflects away from eye
+            let diffuse = if light.diffuse() {
+                effective_color * self.diffuse * light_dot_normal
+            } else {
                 BLACK
+            };
+            let specular = if light.specular() {
+                intensity * self.specular * self.specular_factor(lightv, eyev, normalv)
             } else {
-                let factor = self.compute_specular_factor(reflect_dot_eye);
-                light.intensity * self.specular * factor
+                BLACK
             };
             (diffuse, specular)
         };
 
-        ambient + diffuse + specular
+        diffuse + specular
+    }
+
+    /// How much of the light is reflected towards the eye, according to the [`SpecularModel`]
+    fn specular_factor(&self, lightv: Vector, eyev: Vector, normalv: Vector) -> Scalar {
+        match self.specular_model {
+            SpecularModel::Phong => {
+                let reflectv = -lightv.reflect(normalv);
+                let reflect_dot_eye = reflectv.dot(eyev);
+                if reflect_dot_eye <= 0.0 {
+                    // light reflects away from eye
+                    0.0
+                } else {
+                    self.compute_specular_factor(reflect_dot_eye)
+                }
+            }
+            SpecularModel::Ggx { roughness } => ggx_factor(roughness, lightv, eyev, normalv),
+        }
     }
 
     #[cfg(not(feature = "shininess_as_float"))]
-    fn compute_specular_factor(&self, reflect_dot_eye: f64) -> f64 {
+    fn compute_specular_factor(&self, reflect_dot_eye: Scalar) -> Scalar {
         reflect_dot_eye.powi(self.shininess)
     }
 
     #[cfg(feature = "shininess_as_float")]
-    fn compute_specular_factor(&self, reflect_dot_eye: f64) -> f64 {
+    fn compute_specular_factor(&self, reflect_dot_eye: Scalar) -> Scalar {
         reflect_dot_eye.powf(self.shininess)
     }
 
@@ -196,11 +267,85 @@ impl Material {
     }
 }
 
-#[derive(Clone, PartialEq)]
-/// The different types of colorings for a material - plain colors, patterns,...
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// The model used for the specular highlight of a material
+pub enum SpecularModel {
+    /// The classic Phong highlight, its size controlled by the material's ```shininess```
+    Phong,
+    /// A microfacet highlight using the GGX (Trowbridge-Reitz) distribution, which looks more natural especially on metals.
+    /// Roughness goes from 0 (mirror-like, tiny highlight) to 1 (very rough, broad highlight), ```shininess``` is ignored.
+    Ggx {
+        /// The roughness of the surface, between 0 and 1
+        roughness: Scalar,
+    },
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// A thin transparent layer on top of a material, like lacquer on wood or the clear coat of car paint.
+/// It reflects its surroundings and adds its own highlight, more so at grazing angles, and lets the rest of the light through to the base material.
+pub struct ClearCoat {
+    /// How much the coat reflects, between 0 and 1, before taking the viewing angle into account
+    pub reflective: Scalar,
+    /// The roughness of the coat's highlight, see [`SpecularModel::Ggx`]
+    pub roughness: Scalar,
+    /// The refractive index of the coat, which determines how much stronger reflections get at grazing angles
+    pub refractive_index: Scalar,
+}
+
+impl ClearCoat {
+    /// Creates a new clear coat
+    pub fn new(reflective: Scalar, roughness: Scalar, refractive_index: Scalar) -> Self {
+        Self {
+            reflective,
+            roughness,
+            refractive_index,
+        }
+    }
+
+    /// The fraction of light the coat reflects, given the cosine between the eye and the normal (Schlick's approximation, coming from air).
+    pub fn reflectance(&self, cos_eye_normal: Scalar) -> Scalar {
+        let r0 = ((1.0 - self.refractive_index) / (1.0 + self.refractive_index)).powi(2);
+        let fresnel = r0 + (1.0 - r0) * (1.0 - cos_eye_normal.clamp(0.0, 1.0)).powi(5);
+        fresnel * self.reflective
+    }
+}
+
+/// The GGX specular term including the cosine of the light's angle: D * G / (4 * (n·v)).
+/// Fresnel is left out, the material's ```specular``` factor takes its place.
+pub(crate) fn ggx_factor(
+    roughness: Scalar,
+    lightv: Vector,
+    eyev: Vector,
+    normalv: Vector,
+) -> Scalar {
+    let n_dot_l = normalv.dot(lightv);
+    let n_dot_v = normalv.dot(eyev);
+    if n_dot_l <= 0.0 || n_dot_v <= 0.0 {
+        return 0.0;
+    }
+    let halfv = (lightv + eyev).normalized();
+    let n_dot_h = normalv.dot(halfv).max(0.0);
+
+    // a tiny minimum keeps a perfectly smooth surface from dividing by zero
+    let alpha = (roughness * roughness).max(1e-4);
+    let alpha_squared = alpha * alpha;
+    let denominator = n_dot_h * n_dot_h * (alpha_squared - 1.0) + 1.0;
+    let distribution = alpha_squared / (PI * denominator * denominator);
+
+    let k = (roughness + 1.0).powi(2) / 8.0;
+    let geometry_l = n_dot_l / (n_dot_l * (1.0 - k) + k);
+    let geometry_v = n_dot_v / (n_dot_v * (1.0 - k) + k);
 
+    distribution * geometry_l * geometry_v / (4.0 * n_dot_v)
+}
+
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 // Computation speed is more important than some bytes - colors are only stored once per object.
 #[allow(clippy::large_enum_variant)]
+/// The different types of colorings for a material - plain colors, patterns,...
 pub enum ColorType {
     /// A plain color everywhere on the object
     Color(Color),
@@ -223,14 +368,13 @@ impl Debug for ColorType {
 #[cfg(test)]
 mod material_tests {
 
-    use std::rc::Rc;
-
     use crate::{
         color::{Color, BLACK, WHITE},
         light::PointLight,
         material::{ColorType, Material},
         matrix::IDENTITY_MATRIX_4,
-        pattern::Pattern,
+        pattern::{pattern_function, Pattern},
+        scalar::Scalar,
         shapes::sphere::Sphere,
         tuple::{Point, Vector},
     };
@@ -282,60 +426,70 @@ mod material_tests {
         let m = Material::default();
         assert_eq!(m, m);
 
-        let mut m2 = Material::default();
-        m2.color = ColorType::Color(Color::new(2, 2, 2));
+        let m2 = Material {
+            color: ColorType::Color(Color::new(2, 2, 2)),
+            ..Default::default()
+        };
         assert_ne!(m, m2);
 
-        let mut m2_2 = Material::default();
-        m2_2.color = ColorType::Pattern(Pattern::new(Rc::new(|_p| WHITE), IDENTITY_MATRIX_4));
+        let m2_2 = Material {
+            color: ColorType::Pattern(Pattern::new(
+                pattern_function(|_p| WHITE),
+                IDENTITY_MATRIX_4,
+            )),
+            ..Default::default()
+        };
         assert_ne!(m, m2_2);
 
-        let mut m3 = Material::default();
-        m3.ambient = 34.2;
+        let m3 = Material {
+            ambient: 34.2,
+            ..Default::default()
+        };
         assert_ne!(m, m3);
 
-        let mut m4 = Material::default();
-        m4.diffuse = 34.2;
+        let m4 = Material {
+            diffuse: 34.2,
+            ..Default::default()
+        };
         assert_ne!(m, m4);
 
-        let mut m5 = Material::default();
-        m5.specular = 34.2;
+        let m5 = Material {
+            specular: 34.2,
+            ..Default::default()
+        };
         assert_ne!(m, m5);
 
-        let mut m6 = Material::default();
-        m6.shininess = 34;
+        let m6 = Material {
+            shininess: 34,
+            ..Default::default()
+        };
         assert_ne!(m, m6);
     }
 
     #[test]
     fn pattern() {
-        let mut m = Material::default();
-        m.color = ColorType::Pattern(Pattern::stripe(WHITE, BLACK));
-        m.ambient = 1.0;
-        m.diffuse = 0.0;
-        m.specular = 0.0;
+        let m = Material {
+            color: ColorType::Pattern(Pattern::stripe(WHITE, BLACK)),
+            ambient: 0.0,
+            diffuse: 1.0,
+            specular: 0.0,
+            ..Default::default()
+        };
         let eyev = Vector::new(0, 0, -1);
         let normalv = Vector::new(0, 0, -1);
-        let light = PointLight::new(Point::new(0, 0, -10), WHITE);
-
-        let c1 = m.lighting(
-            &light,
-            &Sphere::default(),
-            Point::new(0.9, 0, 0),
-            eyev,
-            normalv,
-            false,
-            true,
-        );
-        let c2 = m.lighting(
-            &light,
-            &Sphere::default(),
-            Point::new(1.1, 0, 0),
-            eyev,
-            normalv,
-            false,
-            true,
-        );
+        // lit head-on, so that only the pattern's color remains
+        let color_at = |x: Scalar| {
+            m.lighting(
+                &PointLight::new(Point::new(x, 0.0, -10.0), WHITE),
+                &Sphere::default(),
+                Point::new(x, 0.0, 0.0),
+                eyev,
+                normalv,
+                WHITE,
+            )
+        };
+        let c1 = color_at(0.9);
+        let c2 = color_at(1.1);
         assert_eq!(c1, WHITE);
         assert_eq!(c2, BLACK);
     }
@@ -344,13 +498,14 @@ mod material_tests {
 #[cfg(test)]
 mod lighting_tests {
     use crate::{
-        color::Color,
+        color::{Color, BLACK, WHITE},
         light::PointLight,
+        scalar::Scalar,
         shapes::sphere::Sphere,
         tuple::{Point, Vector},
     };
 
-    use super::Material;
+    use super::{Material, SpecularModel};
 
     #[test]
     fn lighting_eye_between_light_and_surface() {
@@ -358,18 +513,10 @@ mod lighting_tests {
         let position = Point::new(0, 0, 0);
 
         let eyev = Vector::new(0, 0, -1);
-        let normalv = eyev.clone();
+        let normalv = eyev;
         let light = PointLight::new(Point::new(0, 0, -10), Color::new(1, 1, 1));
-        let result = m.lighting(
-            &light,
-            &Sphere::default(),
-            position,
-            eyev,
-            normalv,
-            false,
-            true,
-        );
-        assert_eq!(result, Color::new(1.9, 1.9, 1.9));
+        let result = m.lighting(&light, &Sphere::default(), position, eyev, normalv, WHITE);
+        assert_eq!(result, Color::new(1.8, 1.8, 1.8));
     }
 
     #[test]
@@ -377,19 +524,11 @@ mod lighting_tests {
         let m = Material::default();
         let position = Point::new(0, 0, 0);
 
-        let eyev = Vector::new(0.0, 2.0_f64.sqrt() / 2., -(2.0_f64.sqrt() / 2.));
+        let eyev = Vector::new(0.0, Scalar::sqrt(2.0) / 2., -(Scalar::sqrt(2.0) / 2.));
         let normalv = Vector::new(0, 0, -1);
         let light = PointLight::new(Point::new(0, 0, -10), Color::new(1, 1, 1));
-        let result = m.lighting(
-            &light,
-            &Sphere::default(),
-            position,
-            eyev,
-            normalv,
-            false,
-            true,
-        );
-        assert_eq!(result, Color::new(1.0, 1.0, 1.0));
+        let result = m.lighting(&light, &Sphere::default(), position, eyev, normalv, WHITE);
+        assert_eq!(result, Color::new(0.9, 0.9, 0.9));
     }
 
     #[test]
@@ -400,16 +539,8 @@ mod lighting_tests {
         let eyev = Vector::new(0, 0, -1);
         let normalv = Vector::new(0, 0, -1);
         let light = PointLight::new(Point::new(0, 10, -10), Color::new(1, 1, 1));
-        let result = m.lighting(
-            &light,
-            &Sphere::default(),
-            position,
-            eyev,
-            normalv,
-            false,
-            true,
-        );
-        assert_eq!(result, Color::new(0.7364, 0.7364, 0.7364));
+        let result = m.lighting(&light, &Sphere::default(), position, eyev, normalv, WHITE);
+        assert_eq!(result, Color::new(0.6364, 0.6364, 0.6364));
     }
 
     #[test]
@@ -417,60 +548,164 @@ mod lighting_tests {
         let m = Material::default();
         let position = Point::new(0, 0, 0);
 
-        let eyev = Vector::new(0.0, -(2.0_f64.sqrt()) / 2., -(2.0_f64.sqrt() / 2.));
+        let eyev = Vector::new(0.0, -(Scalar::sqrt(2.0)) / 2., -(Scalar::sqrt(2.0) / 2.));
         let normalv = Vector::new(0, 0, -1);
         let light = PointLight::new(Point::new(0, 10, -10), Color::new(1, 1, 1));
+        let result = m.lighting(&light, &Sphere::default(), position, eyev, normalv, WHITE);
+        assert_eq!(result, Color::new(1.5364, 1.5364, 1.5364));
+    }
+
+    #[test]
+    fn lighting_light_behind_object() {
+        let m = Material::default();
+        let position = Point::new(0, 0, 0);
+
+        let eyev = Vector::new(0, 0, -1);
+        let normalv = eyev;
+        let light = PointLight::new(Point::new(0, 0, 10), Color::new(1, 1, 1));
+        let result = m.lighting(&light, &Sphere::default(), position, eyev, normalv, WHITE);
+        assert_eq!(result, BLACK);
+    }
+
+    #[test]
+    fn lighting_in_shadow() {
+        let m = Material::default();
+        let position = Point::new(0, 0, 0);
+
+        let eyev = Vector::new(0, 0, -1);
+        let normalv = Vector::new(0, 0, -1);
+        let light = PointLight::new(Point::new(0, 0, -10), Color::new(1, 1, 1));
+        let transmission = BLACK;
         let result = m.lighting(
             &light,
             &Sphere::default(),
             position,
             eyev,
             normalv,
-            false,
-            true,
+            transmission,
         );
-        assert_eq!(result, Color::new(1.6364, 1.6364, 1.6364));
+        assert_eq!(result, BLACK);
     }
 
     #[test]
-    fn lighting_light_behind_object() {
-        let m = Material::default();
-        let position = Point::new(0, 0, 0);
-
+    fn ggx_highlight() {
+        let m = Material {
+            specular_model: SpecularModel::Ggx { roughness: 0.5 },
+            ..Default::default()
+        };
         let eyev = Vector::new(0, 0, -1);
-        let normalv = eyev.clone();
-        let light = PointLight::new(Point::new(0, 0, 10), Color::new(1, 1, 1));
+        let normalv = Vector::new(0, 0, -1);
+        let light = PointLight::new(Point::new(0, 0, -10), Color::new(1, 1, 1));
         let result = m.lighting(
             &light,
             &Sphere::default(),
-            position,
+            Point::new(0, 0, 0),
             eyev,
             normalv,
-            false,
-            true,
+            WHITE,
         );
-        assert_eq!(result, Color::new(0.1, 0.1, 0.1));
+        // diffuse 0.9 + specular 0.9 * 1 / (4 * pi * 0.25²)
+        assert_eq!(result, Color::new(2.04592, 2.04592, 2.04592));
     }
 
     #[test]
-    fn lighting_in_shadow() {
+    fn ggx_roughness_spreads_highlight() {
+        let specular_at = |roughness: Scalar, eyev: Vector| {
+            let m = Material {
+                ambient: 0.0,
+                diffuse: 0.0,
+                specular_model: SpecularModel::Ggx { roughness },
+                ..Default::default()
+            };
+            let light = PointLight::new(Point::new(0, 0, -10), Color::new(1, 1, 1));
+            m.lighting(
+                &light,
+                &Sphere::default(),
+                Point::new(0, 0, 0),
+                eyev,
+                Vector::new(0, 0, -1),
+                WHITE,
+            )
+            .red
+        };
+        let mirror = Vector::new(0, 0, -1);
+        let off = Vector::new(0.0, 0.5, -1.0).normalized();
+        assert!(specular_at(0.2, mirror) > specular_at(0.8, mirror));
+        assert!(specular_at(0.2, off) < specular_at(0.8, off));
+        // no highlight when looking from behind the surface
+        assert_eq!(specular_at(0.5, Vector::new(0, 0, 1)), 0.0);
+    }
+
+    #[test]
+    fn emissive_not_added_per_light() {
+        let m = Material {
+            emissive: Color::new(0.5, 0.0, 0.0),
+            ..Default::default()
+        };
+        let position = Point::new(0, 0, 0);
+
+        let eyev = Vector::new(0, 0, -1);
+        let normalv = Vector::new(0, 0, -1);
+        let light = PointLight::new(Point::new(0, 0, -10), Color::new(1, 1, 1));
+        let lighting = |transmission| {
+            m.lighting(
+                &light,
+                &Sphere::default(),
+                position,
+                eyev,
+                normalv,
+                transmission,
+            )
+        };
+        // added only once per hit, together with the ambient term
+        assert_eq!(lighting(BLACK), BLACK);
+        assert_eq!(lighting(WHITE), Color::new(1.8, 1.8, 1.8));
+    }
+
+    #[test]
+    fn lighting_partially_in_shadow() {
         let m = Material::default();
         let position = Point::new(0, 0, 0);
 
         let eyev = Vector::new(0, 0, -1);
         let normalv = Vector::new(0, 0, -1);
         let light = PointLight::new(Point::new(0, 0, -10), Color::new(1, 1, 1));
-        let in_shadow = true;
+        let transmission = Color::new(0.5, 0.5, 0.0);
         let result = m.lighting(
             &light,
             &Sphere::default(),
             position,
             eyev,
             normalv,
-            in_shadow,
-            true,
+            transmission,
         );
-        assert_eq!(result, Color::new(0.1, 0.1, 0.1));
+        assert_eq!(result, Color::new(0.9, 0.9, 0.0));
+    }
+
+    #[test]
+    fn lighting_without_specular() {
+        let m = Material::default();
+        let position = Point::new(0, 0, 0);
+
+        let eyev = Vector::new(0, 0, -1);
+        let normalv = Vector::new(0, 0, -1);
+        let mut light = PointLight::new(Point::new(0, 0, -10), Color::new(1, 1, 1));
+        light.specular = false;
+        let result = m.lighting(&light, &Sphere::default(), position, eyev, normalv, WHITE);
+        assert_eq!(result, Color::new(0.9, 0.9, 0.9));
+    }
+
+    #[test]
+    fn lighting_without_diffuse() {
+        let m = Material::default();
+        let position = Point::new(0, 0, 0);
+
+        let eyev = Vector::new(0, 0, -1);
+        let normalv = Vector::new(0, 0, -1);
+        let mut light = PointLight::new(Point::new(0, 0, -10), Color::new(1, 1, 1));
+        light.diffuse = false;
+        let result = m.lighting(&light, &Sphere::default(), position, eyev, normalv, WHITE);
+        assert_eq!(result, Color::new(0.9, 0.9, 0.9));
     }
 
     #[test]
@@ -2,6 +2,8 @@ use std::ops::{Index, IndexMut, Mul, MulAssign};
 
 use crate::{
     epsilon::EpsilonEqual,
+    scalar::{IntoScalar, Scalar},
+    transform::Transform,
     tuple::{Point, Vector},
 };
 
@@ -23,7 +25,7 @@ pub const IDENTITY_MATRIX_4: Mat4 = Matrix::new([
 #[derive(Copy, Clone, Debug)]
 /// Matrix type, shorthand versions for dimensions 2-4 available as type [`Mat2`], [`Mat3`] and [`Mat4`].
 pub struct Matrix<const SIZE: usize> {
-    content: [[f64; SIZE]; SIZE],
+    content: [[Scalar; SIZE]; SIZE],
 }
 
 impl<const SIZE: usize> Matrix<{ SIZE }> {
@@ -35,15 +37,20 @@ impl<const SIZE: usize> Matrix<{ SIZE }> {
     }
 
     /// Creates a new matrix from the given rectangular array
-    pub const fn new(arr: [[f64; SIZE]; SIZE]) -> Self {
+    pub const fn new(arr: [[Scalar; SIZE]; SIZE]) -> Self {
         Matrix { content: arr }
     }
 
     /// returns the inner array.
-    pub const fn get(&self, x: usize, y: usize) -> f64 {
+    pub const fn get(&self, x: usize, y: usize) -> Scalar {
         self.content[x][y]
     }
 
+    /// The rows of this matrix, the inverse of [`Self::new`]
+    pub const fn to_array(&self) -> [[Scalar; SIZE]; SIZE] {
+        self.content
+    }
+
     /// transposes a matrix.
     pub fn transpose(&self) -> Self {
         let mut m = Matrix::<SIZE>::new_empty();
@@ -59,7 +66,7 @@ impl<const SIZE: usize> Matrix<{ SIZE }> {
 }
 
 impl<const SIZE: usize> Index<usize> for Matrix<SIZE> {
-    type Output = [f64; SIZE];
+    type Output = [Scalar; SIZE];
 
     fn index(&self, index: usize) -> &Self::Output {
         &self.content[index]
@@ -94,7 +101,7 @@ impl<const SIZE: usize> Mul for Matrix<SIZE> {
 
         for row in 0..SIZE {
             for col in 0..SIZE {
-                let mut val: f64 = 0.;
+                let mut val: Scalar = 0.;
                 for x in 0..SIZE {
                     val += self[row][x] * rhs[x][col];
                 }
@@ -138,6 +145,18 @@ impl Mul<Vector> for Mat4 {
     }
 }
 
+impl<const SIZE: usize> From<[[Scalar; SIZE]; SIZE]> for Matrix<SIZE> {
+    fn from(arr: [[Scalar; SIZE]; SIZE]) -> Self {
+        Self::new(arr)
+    }
+}
+
+impl<const SIZE: usize> From<Matrix<SIZE>> for [[Scalar; SIZE]; SIZE] {
+    fn from(matrix: Matrix<SIZE>) -> Self {
+        matrix.content
+    }
+}
+
 impl Default for Mat4 {
     fn default() -> Self {
         IDENTITY_MATRIX_4
@@ -146,7 +165,7 @@ impl Default for Mat4 {
 
 impl Mat2 {
     /// The determinant of this matrix
-    pub fn determinant(&self) -> f64 {
+    pub fn determinant(&self) -> Scalar {
         self[0][0] * self[1][1] - self[0][1] * self[1][0]
     }
 
@@ -192,12 +211,12 @@ impl Mat3 {
     }
 
     /// Calculates the minor of this matrix by calculating the determinant of its i-j-submatrix.
-    pub fn minor(&self, i: usize, j: usize) -> f64 {
+    pub fn minor(&self, i: usize, j: usize) -> Scalar {
         self.submatrix(i, j).determinant()
     }
 
     /// Calculates the i-j-cofactor of this matrix
-    pub fn cofactor(&self, i: usize, j: usize) -> f64 {
+    pub fn cofactor(&self, i: usize, j: usize) -> Scalar {
         let mut cofactor = self.minor(i, j);
         if (i + j) % 2 == 1 {
             cofactor *= -1.;
@@ -207,8 +226,8 @@ impl Mat3 {
     }
 
     /// Calculates the determinant of this matrix.
-    pub fn determinant(&self) -> f64 {
-        let mut det: f64 = 0.;
+    pub fn determinant(&self) -> Scalar {
+        let mut det: Scalar = 0.;
 
         for column in 0..3 {
             det += self[0][column] * self.cofactor(0, column);
@@ -266,12 +285,12 @@ impl Mat4 {
     }
 
     /// i-j-minor of this matrix
-    pub fn minor(&self, i: usize, j: usize) -> f64 {
+    pub fn minor(&self, i: usize, j: usize) -> Scalar {
         self.submatrix(i, j).determinant()
     }
 
     /// i-j-cofactor of this matrix
-    pub fn cofactor(&self, i: usize, j: usize) -> f64 {
+    pub fn cofactor(&self, i: usize, j: usize) -> Scalar {
         let mut cofactor = self.minor(i, j);
         if (i + j) % 2 == 1 {
             cofactor *= -1.;
@@ -281,8 +300,8 @@ impl Mat4 {
     }
 
     /// Determinant of this matrix
-    pub fn determinant(&self) -> f64 {
-        let mut det: f64 = 0.;
+    pub fn determinant(&self) -> Scalar {
+        let mut det: Scalar = 0.;
         for column in 0..4 {
             det += self[0][column] * self.cofactor(0, column);
         }
@@ -296,52 +315,88 @@ impl Mat4 {
 
     /// Inverts this matrix
     pub fn inverse(&self) -> Self {
-        let mut m1 = Mat4::new_empty();
-        let determinant = self.determinant();
+        let [[a00, a01, a02, a03], [a10, a11, a12, a13], [a20, a21, a22, a23], [a30, a31, a32, a33]] =
+            self.content;
+
+        // the determinants of the 2x2 matrices in the upper two and the lower two rows,
+        // from which all cofactors are built without creating any submatrices
+        let s0 = a00 * a11 - a10 * a01;
+        let s1 = a00 * a12 - a10 * a02;
+        let s2 = a00 * a13 - a10 * a03;
+        let s3 = a01 * a12 - a11 * a02;
+        let s4 = a01 * a13 - a11 * a03;
+        let s5 = a02 * a13 - a12 * a03;
+
+        let c0 = a20 * a31 - a30 * a21;
+        let c1 = a20 * a32 - a30 * a22;
+        let c2 = a20 * a33 - a30 * a23;
+        let c3 = a21 * a32 - a31 * a22;
+        let c4 = a21 * a33 - a31 * a23;
+        let c5 = a22 * a33 - a32 * a23;
+
+        let determinant = s0 * c5 - s1 * c4 + s2 * c3 + s3 * c2 - s4 * c1 + s5 * c0;
 
-        for row in 0..4 {
-            for col in 0..4 {
-                let c = self.cofactor(row, col);
-                m1[col][row] = c / determinant;
-            }
-        }
-
-        m1
+        Mat4::new([
+            [
+                (a11 * c5 - a12 * c4 + a13 * c3) / determinant,
+                (-a01 * c5 + a02 * c4 - a03 * c3) / determinant,
+                (a31 * s5 - a32 * s4 + a33 * s3) / determinant,
+                (-a21 * s5 + a22 * s4 - a23 * s3) / determinant,
+            ],
+            [
+                (-a10 * c5 + a12 * c2 - a13 * c1) / determinant,
+                (a00 * c5 - a02 * c2 + a03 * c1) / determinant,
+                (-a30 * s5 + a32 * s2 - a33 * s1) / determinant,
+                (a20 * s5 - a22 * s2 + a23 * s1) / determinant,
+            ],
+            [
+                (a10 * c4 - a11 * c2 + a13 * c0) / determinant,
+                (-a00 * c4 + a01 * c2 - a03 * c0) / determinant,
+                (a30 * s4 - a31 * s2 + a33 * s0) / determinant,
+                (-a20 * s4 + a21 * s2 - a23 * s0) / determinant,
+            ],
+            [
+                (-a10 * c3 + a11 * c1 - a12 * c0) / determinant,
+                (a00 * c3 - a01 * c1 + a02 * c0) / determinant,
+                (-a30 * s3 + a31 * s1 - a32 * s0) / determinant,
+                (a20 * s3 - a21 * s1 + a22 * s0) / determinant,
+            ],
+        ])
     }
 
     /// Creates a new 4x4-Matrix translated by x, y and z.
-    pub fn new_translation<T: Into<f64>>(x: T, y: T, z: T) -> Self {
+    pub fn new_translation<T: IntoScalar>(x: T, y: T, z: T) -> Self {
         Mat4::new([
-            [1., 0., 0., x.into()],
-            [0., 1., 0., y.into()],
-            [0., 0., 1., z.into()],
+            [1., 0., 0., x.into_scalar()],
+            [0., 1., 0., y.into_scalar()],
+            [0., 0., 1., z.into_scalar()],
             [0., 0., 0., 1.],
         ])
     }
 
     /// Translates this matrix by x, y and z.
-    pub fn translate<T: Into<f64>>(&mut self, x: T, y: T, z: T) {
+    pub fn translate<T: IntoScalar>(&mut self, x: T, y: T, z: T) {
         *self *= Self::new_translation(x, y, z);
     }
 
     /// Creates a new 4x3 matrix scaled by x, y and z.
-    pub fn new_scaling<T: Into<f64>>(x: T, y: T, z: T) -> Self {
+    pub fn new_scaling<T: IntoScalar>(x: T, y: T, z: T) -> Self {
         Mat4::new([
-            [x.into(), 0., 0., 0.],
-            [0., y.into(), 0., 0.],
-            [0., 0., z.into(), 0.],
+            [x.into_scalar(), 0., 0., 0.],
+            [0., y.into_scalar(), 0., 0.],
+            [0., 0., z.into_scalar(), 0.],
             [0., 0., 0., 1.],
         ])
     }
 
     /// Scales this matrix by x, y and z
-    pub fn scale<T: Into<f64>>(&mut self, x: T, y: T, z: T) {
+    pub fn scale<T: IntoScalar>(&mut self, x: T, y: T, z: T) {
         *self *= Self::new_scaling(x, y, z);
     }
 
     /// Creates a new rotation matrix for given x-rotation.
-    pub fn new_rotation_x<T: Into<f64>>(r: T) -> Self {
-        let r: f64 = r.into();
+    pub fn new_rotation_x<T: IntoScalar>(r: T) -> Self {
+        let r: Scalar = r.into_scalar();
         Mat4::new([
             [1., 0., 0., 0.],
             [0., r.cos(), -r.sin(), 0.],
@@ -351,13 +406,13 @@ impl Mat4 {
     }
 
     /// rotates this matrix on the x axis
-    pub fn rotate_x<T: Into<f64>>(&mut self, r: T) {
+    pub fn rotate_x<T: IntoScalar>(&mut self, r: T) {
         *self *= Self::new_rotation_x(r);
     }
 
     /// Creates a new rotation matrix for given y-rotation.
-    pub fn new_rotation_y<T: Into<f64>>(r: T) -> Self {
-        let r: f64 = r.into();
+    pub fn new_rotation_y<T: IntoScalar>(r: T) -> Self {
+        let r: Scalar = r.into_scalar();
         Mat4::new([
             [r.cos(), 0., r.sin(), 0.],
             [0., 1., 0., 0.],
@@ -367,13 +422,13 @@ impl Mat4 {
     }
 
     /// rotates this matrix on the x axis
-    pub fn rotate_y<T: Into<f64>>(&mut self, r: T) {
+    pub fn rotate_y<T: IntoScalar>(&mut self, r: T) {
         *self *= Self::new_rotation_y(r);
     }
 
     /// Creates a new rotation matrix for given z-rotation.
-    pub fn new_rotation_z<T: Into<f64>>(r: T) -> Self {
-        let r: f64 = r.into();
+    pub fn new_rotation_z<T: IntoScalar>(r: T) -> Self {
+        let r: Scalar = r.into_scalar();
         Mat4::new([
             [r.cos(), -r.sin(), 0., 0.],
             [r.sin(), r.cos(), 0., 0.],
@@ -383,24 +438,60 @@ impl Mat4 {
     }
 
     /// rotates this matrix on the x axis
-    pub fn rotate_z<T: Into<f64>>(&mut self, r: T) {
+    pub fn rotate_z<T: IntoScalar>(&mut self, r: T) {
         *self *= Self::new_rotation_z(r);
     }
 
     /// new shearing matrix
-    pub fn new_shearing<T: Into<f64>>(x_y: T, x_z: T, y_x: T, y_z: T, z_x: T, z_y: T) -> Self {
+    pub fn new_shearing<T: IntoScalar>(x_y: T, x_z: T, y_x: T, y_z: T, z_x: T, z_y: T) -> Self {
         Mat4::new([
-            [1., x_y.into(), x_z.into(), 0.],
-            [y_x.into(), 1., y_z.into(), 0.],
-            [z_x.into(), z_y.into(), 1., 0.],
+            [1., x_y.into_scalar(), x_z.into_scalar(), 0.],
+            [y_x.into_scalar(), 1., y_z.into_scalar(), 0.],
+            [z_x.into_scalar(), z_y.into_scalar(), 1., 0.],
             [0., 0., 0., 1.],
         ])
     }
 
     /// applies shearing on this matrix
-    pub fn shear<T: Into<f64>>(&mut self, x_y: T, x_z: T, y_x: T, y_z: T, z_x: T, z_y: T) {
+    pub fn shear<T: IntoScalar>(&mut self, x_y: T, x_z: T, y_x: T, y_z: T, z_x: T, z_y: T) {
         *self *= Self::new_shearing(x_y, x_z, y_x, y_z, z_x, z_y);
     }
+
+    /// Splits this matrix into translation, rotation and scale, so that it equals translating after rotating after scaling.
+    /// A mirroring is returned as a negative x scale. Shearing and projection cannot be recovered.
+    /// Axes scaled to 0 have no rotation; they are left as they are.
+    pub fn decompose(&self) -> (Vector, Mat3, Vector) {
+        let translation = Vector::new(self[0][3], self[1][3], self[2][3]);
+        let mut axes =
+            [0, 1, 2].map(|column| Vector::new(self[0][column], self[1][column], self[2][column]));
+        let mut scale = axes.map(|axis| axis.magnitude());
+        if self.submatrix(3, 3).determinant() < 0.0 {
+            scale[0] = -scale[0];
+        }
+        for (axis, scale) in axes.iter_mut().zip(scale) {
+            if scale != 0.0 {
+                *axis /= scale;
+            }
+        }
+        let rotation = Mat3::new([
+            [axes[0].x, axes[1].x, axes[2].x],
+            [axes[0].y, axes[1].y, axes[2].y],
+            [axes[0].z, axes[1].z, axes[2].z],
+        ]);
+        (
+            translation,
+            rotation,
+            Vector::new(scale[0], scale[1], scale[2]),
+        )
+    }
+}
+
+/// Composes transformations in the order they are applied: ```IDENTITY_MATRIX_4.rotated_x(r).scaled(2, 2, 2)``` first rotates, then scales.
+/// The mutating helpers like [`Mat4::translate`] multiply on the other side and apply their transformation first.
+impl Transform for Mat4 {
+    fn transformed(self, matrix: Mat4) -> Self {
+        matrix * self
+    }
 }
 
 #[cfg(test)]
@@ -541,6 +632,15 @@ mod matrix_tests {
         assert_eq!(m.transpose(), mt);
     }
 
+    #[test]
+    fn array_conversions() {
+        let rows = [[1., 2., 3.], [4., 5., 6.], [7., 8., 9.]];
+        let m = Mat3::from(rows);
+        assert_eq!(m, Mat3::new(rows));
+        assert_eq!(m.to_array(), rows);
+        assert_eq!(<[[Scalar; 3]; 3]>::from(m), rows);
+    }
+
     #[test]
     fn transpose_identity() {
         assert_eq!(IDENTITY_MATRIX_4.transpose(), IDENTITY_MATRIX_4);
@@ -683,6 +783,33 @@ mod matrix_tests {
         assert_eq!(b, reference);
     }
 
+    #[test]
+    fn inverse_matches_cofactors() {
+        let matrices = [
+            Mat4::new([
+                [6., 4., 4., 4.],
+                [5., 5., 7., 6.],
+                [4., -9., 3., -7.],
+                [9., 1., 7., -6.],
+            ]),
+            Mat4::new_translation(1, -2, 3)
+                * Mat4::new_rotation_y(0.7)
+                * Mat4::new_shearing(0.5, 0.0, 0.0, 1.0, 0.25, 0.0)
+                * Mat4::new_scaling(2.0, 0.5, 3.0),
+        ];
+        for m in matrices {
+            let mut reference = Mat4::new_empty();
+            let determinant = m.determinant();
+            for row in 0..4 {
+                for col in 0..4 {
+                    reference[col][row] = m.cofactor(row, col) / determinant;
+                }
+            }
+            assert_eq!(m.inverse(), reference);
+            assert_eq!(m * m.inverse(), IDENTITY_MATRIX_4);
+        }
+    }
+
     #[test]
     fn inverse_2() {
         let a = Mat4::new([
@@ -745,16 +872,17 @@ mod matrix_tests {
 
 #[cfg(test)]
 mod translation_matrix_tests {
-    use std::f64::consts::PI;
-
     use crate::{
+        epsilon::EpsilonEqual,
         matrix::{Mat4, IDENTITY_MATRIX_4},
+        scalar::{consts::PI, Scalar},
+        transform::Transform,
         tuple::{Point, Vector},
     };
 
     #[test]
     fn new_translation() {
-        let translation_matrix = Mat4::new_translation(5.0_f64, -3., 2.);
+        let translation_matrix = Mat4::new_translation(5.0, -3., 2.);
         let p = Point::new(-3., 4., 5.);
         let reference = Point::new(2., 1., 7.);
         assert_eq!(translation_matrix * p, reference);
@@ -827,7 +955,7 @@ mod translation_matrix_tests {
 
     #[test]
     fn rotation_x() {
-        let sq2: f64 = 2.0_f64.sqrt();
+        let sq2: Scalar = Scalar::sqrt(2.0);
         let p = Point::new(0., 1., 0.);
 
         let half_quarter = Mat4::new_rotation_x(PI / 4.);
@@ -849,7 +977,7 @@ mod translation_matrix_tests {
 
     #[test]
     fn rotation_inv_x() {
-        let sq2: f64 = 2.0_f64.sqrt();
+        let sq2: Scalar = Scalar::sqrt(2.0);
         let p = Point::new(0., 1., 0.);
 
         let half_quarter = Mat4::new_rotation_x(PI / 4.);
@@ -862,7 +990,7 @@ mod translation_matrix_tests {
 
     #[test]
     fn rotation_y() {
-        let sq2: f64 = 2.0_f64.sqrt();
+        let sq2: Scalar = Scalar::sqrt(2.0);
         let p = Point::new(0., 0., 1.);
 
         let half_quarter = Mat4::new_rotation_y(PI / 4.);
@@ -884,7 +1012,7 @@ mod translation_matrix_tests {
 
     #[test]
     fn rotation_z() {
-        let sq2: f64 = 2.0_f64.sqrt();
+        let sq2: Scalar = Scalar::sqrt(2.0);
         let p = Point::new(0., 1., 0.);
 
         let half_quarter = Mat4::new_rotation_z(PI / 4.);
@@ -975,4 +1103,52 @@ mod translation_matrix_tests {
         let p4 = c * p3;
         assert_eq!(p4, p4_ref);
     }
+
+    #[test]
+    fn chained_transformations() {
+        let transform = IDENTITY_MATRIX_4
+            .rotated_x(PI / 2.)
+            .scaled(5, 5, 5)
+            .translated(10, 5, 7);
+        assert_eq!(transform * Point::new(1, 0, 1), Point::new(15, 0, 7));
+        assert_eq!(
+            transform,
+            Mat4::new_translation(10, 5, 7)
+                * Mat4::new_scaling(5, 5, 5)
+                * Mat4::new_rotation_x(PI / 2.)
+        );
+    }
+
+    #[test]
+    fn decompose() {
+        let transform = IDENTITY_MATRIX_4
+            .scaled(2, 3, 4)
+            .rotated_y(PI / 3.)
+            .rotated_x(PI / 4.)
+            .translated(1, -2, 3);
+        let (translation, rotation, scale) = transform.decompose();
+        assert_eq!(translation, Vector::new(1, -2, 3));
+        assert_eq!(
+            rotation,
+            (Mat4::new_rotation_x(PI / 4.) * Mat4::new_rotation_y(PI / 3.)).submatrix(3, 3)
+        );
+        assert_eq!(scale, Vector::new(2, 3, 4));
+    }
+
+    #[test]
+    fn decompose_mirrored() {
+        let transform = IDENTITY_MATRIX_4.scaled(1, -2, 1).rotated_z(PI / 2.);
+        let (translation, rotation, scale) = transform.decompose();
+        assert_eq!(translation, Vector::new(0, 0, 0));
+        assert_eq!(scale, Vector::new(-1, 2, 1));
+        assert!(rotation.determinant().e_equals(1.0));
+        let mut rebuilt = Mat4::new_empty();
+        for row in 0..3 {
+            for column in 0..3 {
+                rebuilt[row][column] = rotation[row][column];
+            }
+        }
+        rebuilt[3][3] = 1.0;
+        assert_eq!(rebuilt * Mat4::new_scaling(-1, 2, 1), transform);
+    }
 }
@@ -0,0 +1,94 @@
+//! Conversions between [`Point`], [`Vector`], [`Mat4`] and the points, vectors and matrices of [nalgebra](https://docs.rs/nalgebra),
+//! in single and double precision. Converting to a precision lower than [`crate::scalar::Scalar`] rounds.
+use crate::{
+    matrix::Mat4,
+    scalar::Scalar,
+    tuple::{Point, Vector},
+};
+
+macro_rules! impl_nalgebra_conversions {
+    ($($float:ty),*) => {
+        $(
+            impl From<Point> for nalgebra::Point3<$float> {
+                fn from(p: Point) -> Self {
+                    Self::new(p.x as $float, p.y as $float, p.z as $float)
+                }
+            }
+
+            impl From<nalgebra::Point3<$float>> for Point {
+                fn from(p: nalgebra::Point3<$float>) -> Self {
+                    Point::new(p.x as Scalar, p.y as Scalar, p.z as Scalar)
+                }
+            }
+
+            impl From<Vector> for nalgebra::Vector3<$float> {
+                fn from(v: Vector) -> Self {
+                    Self::new(v.x as $float, v.y as $float, v.z as $float)
+                }
+            }
+
+            impl From<nalgebra::Vector3<$float>> for Vector {
+                fn from(v: nalgebra::Vector3<$float>) -> Self {
+                    Vector::new(v.x as Scalar, v.y as Scalar, v.z as Scalar)
+                }
+            }
+
+            impl From<Mat4> for nalgebra::Matrix4<$float> {
+                fn from(m: Mat4) -> Self {
+                    Self::from_fn(|row, column| m[row][column] as $float)
+                }
+            }
+
+            impl From<nalgebra::Matrix4<$float>> for Mat4 {
+                fn from(m: nalgebra::Matrix4<$float>) -> Self {
+                    Mat4::new(std::array::from_fn(|row| {
+                        std::array::from_fn(|column| m[(row, column)] as Scalar)
+                    }))
+                }
+            }
+        )*
+    };
+}
+
+impl_nalgebra_conversions!(f32, f64);
+
+#[cfg(test)]
+mod nalgebra_interop_tests {
+    use crate::{
+        matrix::Mat4,
+        transform::Transform,
+        tuple::{Point, Vector},
+    };
+
+    #[test]
+    fn tuples() {
+        let p = Point::new(1.0, -2.0, 0.5);
+        assert_eq!(
+            nalgebra::Point3::<f64>::from(p),
+            nalgebra::Point3::new(1.0, -2.0, 0.5)
+        );
+        assert_eq!(Point::from(nalgebra::Point3::new(1.0f32, -2.0, 0.5)), p);
+        let v = Vector::new(0, 3, -4);
+        assert_eq!(
+            nalgebra::Vector3::<f32>::from(v),
+            nalgebra::Vector3::new(0.0, 3.0, -4.0)
+        );
+        assert_eq!(Vector::from(nalgebra::Vector3::new(0.0, 3.0, -4.0)), v);
+    }
+
+    #[test]
+    fn matrices() {
+        let m = Mat4::default()
+            .scaled(2, 3, 4)
+            .rotated_y(0.5)
+            .translated(1, 2, 3);
+        let converted = nalgebra::Matrix4::<f64>::from(m);
+        let p = Point::new(1, -1, 2);
+        assert_eq!(
+            Point::from(converted.transform_point(&nalgebra::Point3::from(p))),
+            m * p
+        );
+        assert_eq!(Mat4::from(converted), m);
+        assert_eq!(Mat4::from(nalgebra::Matrix4::<f32>::from(m)), m);
+    }
+}
@@ -8,11 +8,15 @@ use std::rc::Rc;
 use std::sync::Arc;
 
 use crate::{
-    color::Color,
+    canvas::Canvas,
+    color::{Color, WHITE},
     epsilon::EPSILON,
     matrix::{Mat4, IDENTITY_MATRIX_4},
+    scalar::Scalar,
     shapes::shape::Shape,
-    tuple::Point,
+    transform::Transform,
+    tuple::{Point, Vector},
+    uv::{uv_image, CubeMap, TextureMap, UvMapping},
 };
 
 #[cfg(not(feature = "rayon"))]
@@ -23,25 +27,158 @@ pub type PatternFunction = Rc<dyn Fn(Point) -> Color>;
 /// A function to apply a pattern onto an object. Takes a point (in object space) and returns the color at that point.
 pub type PatternFunction = Arc<dyn Fn(Point) -> Color + Send + Sync>;
 
-#[derive(Clone)]
+#[cfg(not(feature = "rayon"))]
+/// Wraps a closure into a [`PatternFunction`], whichever pointer type it uses with the enabled features.
+pub fn pattern_function(pattern_fn: impl Fn(Point) -> Color + 'static) -> PatternFunction {
+    Rc::new(pattern_fn)
+}
+
+#[cfg(feature = "rayon")]
+/// Wraps a closure into a [`PatternFunction`], whichever pointer type it uses with the enabled features.
+pub fn pattern_function(
+    pattern_fn: impl Fn(Point) -> Color + Send + Sync + 'static,
+) -> PatternFunction {
+    Arc::new(pattern_fn)
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// How [`Pattern::blend`] combines the colors of two patterns
+pub enum BlendMode {
+    /// The mean of both colors
+    Average,
+    /// Both colors multiplied, darkening the result
+    Multiply,
+    /// Both colors inverted, multiplied and inverted again, brightening the result
+    Screen,
+}
+
+impl BlendMode {
+    /// Combines two colors according to this mode
+    pub fn blend(&self, a: Color, b: Color) -> Color {
+        match self {
+            BlendMode::Average => (a + b) * 0.5,
+            BlendMode::Multiply => a * b,
+            BlendMode::Screen => WHITE - (WHITE - a) * (WHITE - b),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// The direction a gradient pattern runs along
+pub enum GradientAxis {
+    /// Along the x axis
+    X,
+    /// Along the y axis, e.g. for skies
+    Y,
+    /// Along the z axis
+    Z,
+    /// Outwards from the y axis, like the ring pattern
+    Radial,
+    /// Along an arbitrary direction. Its length sets the frequency: the gradient repeats every 1 / length units.
+    Direction(Vector),
+}
+
+impl GradientAxis {
+    /// The position of a point along this axis
+    fn position(&self, point: Point) -> Scalar {
+        match self {
+            GradientAxis::X => point.x,
+            GradientAxis::Y => point.y,
+            GradientAxis::Z => point.z,
+            GradientAxis::Radial => (point.x.powi(2) + point.z.powi(2)).sqrt(),
+            GradientAxis::Direction(direction) => {
+                point.x * direction.x + point.y * direction.y + point.z * direction.z
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 /// A pattern to apply to an object.
 pub struct Pattern {
-    /// The [`PatternFunction`] that converts the point into a color
-    pub pattern_fn: PatternFunction,
+    /// What the pattern looks like
+    pub kind: PatternKind,
     transformation_matrix: Mat4,
     inverse_transformation_matrix: Mat4,
 }
 
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// The kinds of patterns. All built-in ones are plain data; [`PatternKind::Custom`] is the escape hatch for anything else.
+/// The "colors" of the built-in patterns are patterns themselves, each evaluated with its own transformation.
+pub enum PatternKind {
+    /// A single color everywhere
+    Solid(Color),
+    /// Alternating stripes along x
+    Stripe(Box<Pattern>, Box<Pattern>),
+    /// A linear gradient along a [`GradientAxis`], going back and forth
+    Gradient(Box<Pattern>, Box<Pattern>, GradientAxis),
+    /// Concentric rings around the y axis
+    Ring(Box<Pattern>, Box<Pattern>),
+    /// 3d checkers
+    Checker(Box<Pattern>, Box<Pattern>),
+    /// Two patterns combined according to a [`BlendMode`]
+    Blend(Box<Pattern>, Box<Pattern>, BlendMode),
+    /// A 2d pattern wrapped around the object, see [`TextureMap`]. Not serialized, see the "serde" feature.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Texture(TextureMap),
+    /// A 2d pattern per cube face, see [`CubeMap`]. Not serialized, see the "serde" feature.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Cube(CubeMap),
+    /// Returns the point as color. x -> red, y -> green, z -> blue
+    Test,
+    /// A user-defined [`PatternFunction`]. Not serialized, see the "serde" feature.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Custom(PatternFunction),
+}
+
+impl PatternKind {
+    /// The color at a point in pattern space
+    pub fn color_at(&self, point: Point) -> Color {
+        match self {
+            PatternKind::Solid(color) => *color,
+            PatternKind::Stripe(a, b) => {
+                stripe_at(a.apply_pattern(point), b.apply_pattern(point), &point)
+            }
+            PatternKind::Gradient(a, b, axis) => gradient_at(
+                a.apply_pattern(point),
+                b.apply_pattern(point),
+                axis.position(point),
+            ),
+            PatternKind::Ring(a, b) => {
+                ring_at(a.apply_pattern(point), b.apply_pattern(point), &point)
+            }
+            PatternKind::Checker(a, b) => {
+                checker_at(a.apply_pattern(point), b.apply_pattern(point), &point)
+            }
+            PatternKind::Blend(a, b, mode) => {
+                mode.blend(a.apply_pattern(point), b.apply_pattern(point))
+            }
+            PatternKind::Texture(texture_map) => texture_map.color_at(point),
+            PatternKind::Cube(cube_map) => cube_map.color_at(point),
+            PatternKind::Test => test_at(&point),
+            PatternKind::Custom(pattern_fn) => pattern_fn(point),
+        }
+    }
+}
+
 impl Pattern {
     /// Creates a new pattern with a user-defined pattern function.
     pub fn new(pattern_fn: PatternFunction, transformation_matrix: Mat4) -> Self {
         Self {
-            pattern_fn,
+            kind: PatternKind::Custom(pattern_fn),
             transformation_matrix,
             inverse_transformation_matrix: transformation_matrix.inverse(),
         }
     }
 
+    /// The transformation of the pattern relative to the object it is applied to
+    pub fn transformation_matrix(&self) -> Mat4 {
+        self.transformation_matrix
+    }
+
     /// Sets this object's transformation matrix which is used to scale, rotate,... the pattern on the object itself
     pub fn set_transformation_matrix(&mut self, matrix: Mat4) {
         self.transformation_matrix = matrix;
@@ -51,80 +188,98 @@ impl Pattern {
     /// Renders pattern but using world space coordinates
     pub fn apply_pattern_world_space(&self, object: &dyn Shape, point: Point) -> Color {
         let point_object_space = object.inverse_transformation_matrix() * point;
-        let point_pattern_space = self.inverse_transformation_matrix * point_object_space;
-        (self.pattern_fn)(point_pattern_space)
+        self.apply_pattern(point_object_space)
+    }
+
+    /// Renders the pattern at a point given in the space the pattern is attached to (e.g. object space), applying only the pattern's own transformation.
+    pub fn apply_pattern(&self, point: Point) -> Color {
+        let point_pattern_space = self.inverse_transformation_matrix * point;
+        self.kind.color_at(point_pattern_space)
     }
 }
 
-impl From<PatternFunction> for Pattern {
-    fn from(pattern_fn: PatternFunction) -> Self {
+impl Transform for Pattern {
+    fn transformed(mut self, matrix: Mat4) -> Self {
+        self.set_transformation_matrix(matrix * self.transformation_matrix);
+        self
+    }
+}
+
+impl From<PatternKind> for Pattern {
+    fn from(kind: PatternKind) -> Self {
         Self {
-            pattern_fn,
+            kind,
             transformation_matrix: IDENTITY_MATRIX_4,
             inverse_transformation_matrix: IDENTITY_MATRIX_4,
         }
     }
 }
 
-/// Built-in patterns
-impl Pattern {
-    /// Creates a new stripe pattern
-    pub fn stripe(color_a: Color, color_b: Color) -> Self {
-        let pattern_fn = move |point| stripe_at(color_a, color_b, &point);
+impl From<PatternFunction> for Pattern {
+    fn from(pattern_fn: PatternFunction) -> Self {
+        PatternKind::Custom(pattern_fn).into()
+    }
+}
 
-        #[cfg(not(feature = "rayon"))]
-        let pattern_fn: PatternFunction = Rc::new(pattern_fn);
-        #[cfg(feature = "rayon")]
-        let pattern_fn: PatternFunction = Arc::new(pattern_fn);
+impl From<Color> for Pattern {
+    fn from(color: Color) -> Self {
+        Pattern::solid(color)
+    }
+}
 
-        pattern_fn.into()
+/// Built-in patterns
+impl Pattern {
+    /// A pattern of a single color, mostly useful as part of other patterns
+    pub fn solid(color: Color) -> Self {
+        PatternKind::Solid(color).into()
     }
 
-    /// Creates a new gradient pattern
-    pub fn gradient(color_a: Color, color_b: Color) -> Self {
-        let pattern_fn = move |point| gradient_at(color_a, color_b, &point);
+    /// Creates a new stripe pattern.
+    /// Like all built-in patterns, it takes either colors or other patterns (e.g. checkers of stripes), which are evaluated with their own transformation.
+    pub fn stripe(a: impl Into<Pattern>, b: impl Into<Pattern>) -> Self {
+        PatternKind::Stripe(Box::new(a.into()), Box::new(b.into())).into()
+    }
 
-        #[cfg(not(feature = "rayon"))]
-        let pattern_fn: PatternFunction = Rc::new(pattern_fn);
-        #[cfg(feature = "rayon")]
-        let pattern_fn: PatternFunction = Arc::new(pattern_fn);
+    /// Creates a new gradient pattern along the x axis
+    pub fn gradient(a: impl Into<Pattern>, b: impl Into<Pattern>) -> Self {
+        Pattern::gradient_along(a, b, GradientAxis::X)
+    }
 
-        pattern_fn.into()
+    /// Creates a new gradient pattern along the given axis, e.g. [`GradientAxis::Y`] for a sky
+    pub fn gradient_along(
+        a: impl Into<Pattern>,
+        b: impl Into<Pattern>,
+        axis: GradientAxis,
+    ) -> Self {
+        PatternKind::Gradient(Box::new(a.into()), Box::new(b.into()), axis).into()
     }
 
     /// Creates a new ring pattern
-    pub fn ring(color_a: Color, color_b: Color) -> Self {
-        let pattern_fn = move |point| ring_at(color_a, color_b, &point);
-
-        #[cfg(not(feature = "rayon"))]
-        let pattern_fn: PatternFunction = Rc::new(pattern_fn);
-        #[cfg(feature = "rayon")]
-        let pattern_fn: PatternFunction = Arc::new(pattern_fn);
+    pub fn ring(a: impl Into<Pattern>, b: impl Into<Pattern>) -> Self {
+        PatternKind::Ring(Box::new(a.into()), Box::new(b.into())).into()
+    }
 
-        pattern_fn.into()
+    /// Creates a new 3d checker pattern.
+    /// On curved objects, prefer [`crate::uv::uv_checkers`] in a [`TextureMap`], which tiles correctly instead of showing acne.
+    pub fn checker(a: impl Into<Pattern>, b: impl Into<Pattern>) -> Self {
+        PatternKind::Checker(Box::new(a.into()), Box::new(b.into())).into()
     }
-    /// Creates a new ring pattern
-    pub fn checker(color_a: Color, color_b: Color) -> Self {
-        let pattern_fn = move |point| checker_at(color_a, color_b, &point);
 
-        #[cfg(not(feature = "rayon"))]
-        let pattern_fn: PatternFunction = Rc::new(pattern_fn);
-        #[cfg(feature = "rayon")]
-        let pattern_fn: PatternFunction = Arc::new(pattern_fn);
+    /// Creates a new pattern showing an image, wrapped around the object using the given mapping.
+    /// Images can be loaded e.g. with [`crate::ppm::read_ppm`].
+    pub fn image(image: Canvas, mapping: UvMapping) -> Self {
+        TextureMap::new(uv_image(image), mapping).into()
+    }
 
-        pattern_fn.into()
+    /// Creates a pattern combining two other patterns, e.g. two stripe patterns at 90° into a woven look.
+    /// Both patterns keep their own transformation, which is applied after this pattern's one.
+    pub fn blend(a: Pattern, b: Pattern, mode: BlendMode) -> Self {
+        PatternKind::Blend(Box::new(a), Box::new(b), mode).into()
     }
 
     /// test pattern that returns the point hit as color. x -> red, y -> green, z -> blue
     pub fn test_pattern() -> Self {
-        let pattern_fn = move |point| test_at(&point);
-
-        #[cfg(not(feature = "rayon"))]
-        let pattern_fn: PatternFunction = Rc::new(pattern_fn);
-        #[cfg(feature = "rayon")]
-        let pattern_fn: PatternFunction = Arc::new(pattern_fn);
-
-        pattern_fn.into()
+        PatternKind::Test.into()
     }
 }
 
@@ -136,11 +291,11 @@ fn stripe_at(color_a: Color, color_b: Color, point: &Point) -> Color {
     }
 }
 
-/// Returns the result of the stripe pattern at a given coordinate in pattern space
-fn gradient_at(color_a: Color, color_b: Color, point: &Point) -> Color {
+/// Returns the result of the gradient pattern at a given position along its axis
+fn gradient_at(color_a: Color, color_b: Color, position: Scalar) -> Color {
     let distance = color_b - color_a;
-    let mut fraction = point.x - point.x.floor();
-    if (point.x.floor() % 2.0).abs() > EPSILON {
+    let mut fraction = position - position.floor();
+    if (position.floor() % 2.0).abs() > EPSILON {
         fraction = 1.0 - fraction;
     }
     color_a + distance * fraction
@@ -173,34 +328,66 @@ fn test_at(point: &Point) -> Color {
     Color::new(point.x, point.y, point.z)
 }
 
-impl Debug for Pattern {
+impl Debug for PatternKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("Pattern")
-            .field("transformation_matrix", &self.transformation_matrix)
-            .field(
-                "inverse_transformation_matrix",
-                &self.inverse_transformation_matrix,
-            )
-            .finish()
+        match self {
+            PatternKind::Solid(color) => f.debug_tuple("Solid").field(color).finish(),
+            PatternKind::Stripe(a, b) => f.debug_tuple("Stripe").field(a).field(b).finish(),
+            PatternKind::Gradient(a, b, axis) => f
+                .debug_tuple("Gradient")
+                .field(a)
+                .field(b)
+                .field(axis)
+                .finish(),
+            PatternKind::Ring(a, b) => f.debug_tuple("Ring").field(a).field(b).finish(),
+            PatternKind::Checker(a, b) => f.debug_tuple("Checker").field(a).field(b).finish(),
+            PatternKind::Blend(a, b, mode) => f
+                .debug_tuple("Blend")
+                .field(a)
+                .field(b)
+                .field(mode)
+                .finish(),
+            PatternKind::Texture(texture_map) => {
+                f.debug_tuple("Texture").field(texture_map).finish()
+            }
+            PatternKind::Cube(cube_map) => f.debug_tuple("Cube").field(cube_map).finish(),
+            PatternKind::Test => f.write_str("Test"),
+            PatternKind::Custom(_) => f.write_str("Custom"),
+        }
     }
 }
 
-impl PartialEq for Pattern {
+impl PartialEq for PatternKind {
+    /// Built-in patterns are compared by value, custom pattern functions only by identity.
     fn eq(&self, other: &Self) -> bool {
-        std::ptr::addr_of!(self.pattern_fn) == std::ptr::addr_of!(other.pattern_fn)
-            && self.transformation_matrix == other.transformation_matrix
-            && self.inverse_transformation_matrix == other.inverse_transformation_matrix
+        match (self, other) {
+            (PatternKind::Solid(a), PatternKind::Solid(b)) => a == b,
+            (PatternKind::Stripe(a1, b1), PatternKind::Stripe(a2, b2))
+            | (PatternKind::Ring(a1, b1), PatternKind::Ring(a2, b2))
+            | (PatternKind::Checker(a1, b1), PatternKind::Checker(a2, b2)) => a1 == a2 && b1 == b2,
+            (PatternKind::Gradient(a1, b1, axis1), PatternKind::Gradient(a2, b2, axis2)) => {
+                a1 == a2 && b1 == b2 && axis1 == axis2
+            }
+            (PatternKind::Blend(a1, b1, mode1), PatternKind::Blend(a2, b2, mode2)) => {
+                a1 == a2 && b1 == b2 && mode1 == mode2
+            }
+            (PatternKind::Texture(a), PatternKind::Texture(b)) => a == b,
+            (PatternKind::Cube(a), PatternKind::Cube(b)) => a == b,
+            (PatternKind::Test, PatternKind::Test) => true,
+            #[cfg(not(feature = "rayon"))]
+            (PatternKind::Custom(a), PatternKind::Custom(b)) => Rc::ptr_eq(a, b),
+            #[cfg(feature = "rayon")]
+            (PatternKind::Custom(a), PatternKind::Custom(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
     }
 }
-
 #[cfg(test)]
 mod pattern_tests {
-    use std::rc::Rc;
-
     use crate::{
         color::{Color, BLACK, WHITE},
         matrix::{Mat4, IDENTITY_MATRIX_4},
-        pattern::Pattern,
+        pattern::{pattern_function, Pattern},
         shapes::shape::Shape,
         shapes::sphere::Sphere,
         tuple::Point,
@@ -239,13 +426,37 @@ mod pattern_tests {
         assert_eq!(p, p);
         let p2 = Pattern::stripe(BLACK, WHITE);
         assert_ne!(p, p2);
-        let p3 = Pattern::new(Rc::new(|_p| WHITE), IDENTITY_MATRIX_4);
+        let p3 = Pattern::new(pattern_function(|_p| WHITE), IDENTITY_MATRIX_4);
         assert_eq!(p3, p3);
         assert_ne!(p, p3);
     }
 
+    #[test]
+    fn built_in_patterns_compare_by_value() {
+        assert_eq!(Pattern::stripe(WHITE, BLACK), Pattern::stripe(WHITE, BLACK));
+        assert_ne!(Pattern::stripe(WHITE, BLACK), Pattern::ring(WHITE, BLACK));
+        let mut transformed = Pattern::stripe(WHITE, BLACK);
+        transformed.set_transformation_matrix(Mat4::new_scaling(2, 2, 2));
+        assert_ne!(Pattern::stripe(WHITE, BLACK), transformed);
+        assert_eq!(
+            Pattern::checker(transformed.clone(), BLACK),
+            Pattern::checker(transformed, BLACK)
+        );
+    }
+
+    #[test]
+    fn debug() {
+        let pattern = Pattern::stripe(WHITE, Pattern::test_pattern());
+        let debug = format!("{:?}", pattern.kind);
+        assert!(debug.starts_with("Stripe(Pattern { kind: Solid("));
+        assert!(debug.contains("kind: Test"));
+    }
+
     fn test_xyz_pattern() -> Pattern {
-        Pattern::new(Rc::new(|p| Color::new(p.x, p.y, p.z)), IDENTITY_MATRIX_4)
+        Pattern::new(
+            pattern_function(|p| Color::new(p.x, p.y, p.z)),
+            IDENTITY_MATRIX_4,
+        )
     }
 
     #[test]
@@ -287,28 +498,28 @@ mod stripe_tests {
     #[test]
     fn stripe_constant_in_y() {
         let pattern = Pattern::stripe(WHITE, BLACK);
-        assert_eq!((pattern.pattern_fn)(Point::new(0, 0, 0)), WHITE);
-        assert_eq!((pattern.pattern_fn)(Point::new(0, 1, 0)), WHITE);
-        assert_eq!((pattern.pattern_fn)(Point::new(0, 2, 0)), WHITE);
+        assert_eq!(pattern.kind.color_at(Point::new(0, 0, 0)), WHITE);
+        assert_eq!(pattern.kind.color_at(Point::new(0, 1, 0)), WHITE);
+        assert_eq!(pattern.kind.color_at(Point::new(0, 2, 0)), WHITE);
     }
 
     #[test]
     fn stripe_constant_in_z() {
         let pattern = Pattern::stripe(WHITE, BLACK);
-        assert_eq!((pattern.pattern_fn)(Point::new(0, 0, 0)), WHITE);
-        assert_eq!((pattern.pattern_fn)(Point::new(0, 0, 1)), WHITE);
-        assert_eq!((pattern.pattern_fn)(Point::new(0, 0, 2)), WHITE);
+        assert_eq!(pattern.kind.color_at(Point::new(0, 0, 0)), WHITE);
+        assert_eq!(pattern.kind.color_at(Point::new(0, 0, 1)), WHITE);
+        assert_eq!(pattern.kind.color_at(Point::new(0, 0, 2)), WHITE);
     }
 
     #[test]
     fn stripe_alternates_in_x() {
         let pattern = Pattern::stripe(WHITE, BLACK);
-        assert_eq!((pattern.pattern_fn)(Point::new(0, 0, 0)), WHITE);
-        assert_eq!((pattern.pattern_fn)(Point::new(0.9, 0, 0)), WHITE);
-        assert_eq!((pattern.pattern_fn)(Point::new(1, 0, 0)), BLACK);
-        assert_eq!((pattern.pattern_fn)(Point::new(-0.1, 0, 0)), BLACK);
-        assert_eq!((pattern.pattern_fn)(Point::new(-1, 0, 0)), BLACK);
-        assert_eq!((pattern.pattern_fn)(Point::new(-1.1, 0, 2)), WHITE);
+        assert_eq!(pattern.kind.color_at(Point::new(0, 0, 0)), WHITE);
+        assert_eq!(pattern.kind.color_at(Point::new(0.9, 0, 0)), WHITE);
+        assert_eq!(pattern.kind.color_at(Point::new(1, 0, 0)), BLACK);
+        assert_eq!(pattern.kind.color_at(Point::new(-0.1, 0, 0)), BLACK);
+        assert_eq!(pattern.kind.color_at(Point::new(-1, 0, 0)), BLACK);
+        assert_eq!(pattern.kind.color_at(Point::new(-1.1, 0, 2)), WHITE);
     }
 }
 
@@ -324,29 +535,29 @@ mod gradient_tests {
 
     #[test]
     fn gradient_function_linear_interpolation() {
-        let color = gradient_at(WHITE, BLACK, &Point::new(0, 0, 0));
+        let color = gradient_at(WHITE, BLACK, 0.0);
         assert_eq!(color, WHITE);
-        let color = gradient_at(WHITE, BLACK, &Point::new(0.25, 0, 0));
+        let color = gradient_at(WHITE, BLACK, 0.25);
         assert_eq!(color, Color::new(0.75, 0.75, 0.75));
-        let color = gradient_at(WHITE, BLACK, &Point::new(0.5, 0, 0));
+        let color = gradient_at(WHITE, BLACK, 0.5);
         assert_eq!(color, Color::new(0.5, 0.5, 0.5));
-        let color = gradient_at(WHITE, BLACK, &Point::new(0.75, 0, 0));
+        let color = gradient_at(WHITE, BLACK, 0.75);
         assert_eq!(color, Color::new(0.25, 0.25, 0.25));
-        let color = gradient_at(WHITE, BLACK, &Point::new(1, 0, 0));
+        let color = gradient_at(WHITE, BLACK, 1.0);
         assert_eq!(color, BLACK);
-        let color = gradient_at(WHITE, BLACK, &Point::new(1.25, 0, 0));
+        let color = gradient_at(WHITE, BLACK, 1.25);
         assert_eq!(color, Color::new(0.25, 0.25, 0.25));
 
-        let color = gradient_at(WHITE, BLACK, &Point::new(1.5, 0, 0));
+        let color = gradient_at(WHITE, BLACK, 1.5);
         assert_eq!(color, Color::new(0.5, 0.5, 0.5));
-        let color = gradient_at(WHITE, BLACK, &Point::new(1.75, 0, 0));
+        let color = gradient_at(WHITE, BLACK, 1.75);
         assert_eq!(color, Color::new(0.75, 0.75, 0.75));
     }
 
     #[test]
     fn gradient_linear_interpolation() {
         let pattern = Pattern::gradient(WHITE, BLACK);
-        assert_eq!((pattern.pattern_fn)(Point::new(0, 0, 0)), WHITE);
+        assert_eq!(pattern.kind.color_at(Point::new(0, 0, 0)), WHITE);
     }
 }
 
@@ -374,9 +585,9 @@ mod ring_tests {
     #[test]
     fn ring() {
         let pattern = Pattern::ring(WHITE, BLACK);
-        let color = (pattern.pattern_fn)(Point::new(0, 0, 0));
+        let color = pattern.kind.color_at(Point::new(0, 0, 0));
         assert_eq!(color, WHITE);
-        let color = (pattern.pattern_fn)(Point::new(1, 0, 0));
+        let color = pattern.kind.color_at(Point::new(1, 0, 0));
         assert_eq!(color, BLACK);
     }
 }
@@ -419,3 +630,124 @@ mod checkers_tests {
         assert_eq!(checker_at(WHITE, BLACK, &Point::new(0, 0, 1.01)), BLACK);
     }
 }
+
+#[cfg(test)]
+mod blend_tests {
+    use crate::{
+        color::{Color, BLACK, WHITE},
+        matrix::Mat4,
+        pattern::{BlendMode, Pattern},
+        tuple::Point,
+    };
+
+    #[test]
+    fn blend_modes() {
+        let a = Color::new(0.5, 1.0, 0.0);
+        let b = Color::new(0.5, 0.0, 0.0);
+        assert_eq!(BlendMode::Average.blend(a, b), Color::new(0.5, 0.5, 0.0));
+        assert_eq!(BlendMode::Multiply.blend(a, b), Color::new(0.25, 0.0, 0.0));
+        assert_eq!(BlendMode::Screen.blend(a, b), Color::new(0.75, 1.0, 0.0));
+    }
+
+    #[test]
+    fn woven_stripes() {
+        let horizontal = Pattern::stripe(WHITE, BLACK);
+        let mut vertical = Pattern::stripe(WHITE, BLACK);
+        vertical.set_transformation_matrix(Mat4::new_rotation_y(crate::scalar::consts::FRAC_PI_2));
+        let pattern = Pattern::blend(horizontal, vertical, BlendMode::Average);
+
+        let gray = Color::new(0.5, 0.5, 0.5);
+        assert_eq!(pattern.apply_pattern(Point::new(0.5, 0.0, -0.5)), WHITE);
+        assert_eq!(pattern.apply_pattern(Point::new(1.5, 0.0, -0.5)), gray);
+        assert_eq!(pattern.apply_pattern(Point::new(0.5, 0.0, 0.5)), gray);
+        assert_eq!(pattern.apply_pattern(Point::new(1.5, 0.0, 0.5)), BLACK);
+    }
+}
+
+#[cfg(test)]
+mod nested_tests {
+    use crate::{
+        color::{Color, BLACK, WHITE},
+        matrix::Mat4,
+        pattern::Pattern,
+        tuple::Point,
+    };
+
+    #[test]
+    fn solid() {
+        let pattern: Pattern = Color::new(0.1, 0.2, 0.3).into();
+        assert_eq!(
+            pattern.apply_pattern(Point::new(5, -3, 2)),
+            Color::new(0.1, 0.2, 0.3)
+        );
+    }
+
+    #[test]
+    fn checkers_of_stripes() {
+        let red = Color::new(1, 0, 0);
+        let mut stripes = Pattern::stripe(red, BLACK);
+        stripes.set_transformation_matrix(Mat4::new_scaling(0.25, 1.0, 1.0));
+        let pattern = Pattern::checker(stripes, WHITE);
+
+        assert_eq!(pattern.apply_pattern(Point::new(0.1, 0.5, 0.5)), red);
+        assert_eq!(pattern.apply_pattern(Point::new(0.3, 0.5, 0.5)), BLACK);
+        assert_eq!(pattern.apply_pattern(Point::new(1.1, 0.5, 0.5)), WHITE);
+        assert_eq!(pattern.apply_pattern(Point::new(1.3, 0.5, 0.5)), WHITE);
+    }
+
+    #[test]
+    fn gradient_between_patterns() {
+        let pattern = Pattern::gradient(Pattern::stripe(WHITE, BLACK), BLACK);
+        assert_eq!(
+            pattern.apply_pattern(Point::new(0.5, 0, 0)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        assert_eq!(pattern.apply_pattern(Point::new(1.5, 0, 0)), BLACK);
+    }
+}
+
+#[cfg(test)]
+mod gradient_axis_tests {
+    use crate::{
+        color::{Color, BLACK, WHITE},
+        pattern::{GradientAxis, Pattern},
+        tuple::{Point, Vector},
+    };
+
+    #[test]
+    fn vertical() {
+        let pattern = Pattern::gradient_along(WHITE, BLACK, GradientAxis::Y);
+        let gray = Color::new(0.75, 0.75, 0.75);
+        assert_eq!(pattern.apply_pattern(Point::new(5.0, 0.25, -3.0)), gray);
+        assert_eq!(pattern.apply_pattern(Point::new(0.25, 0.0, 0.0)), WHITE);
+        assert_eq!(pattern.apply_pattern(Point::new(0, 1, 0)), BLACK);
+    }
+
+    #[test]
+    fn along_z_and_radial() {
+        let z = Pattern::gradient_along(WHITE, BLACK, GradientAxis::Z);
+        assert_eq!(
+            z.apply_pattern(Point::new(0.0, 0.0, 0.5)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        let radial = Pattern::gradient_along(WHITE, BLACK, GradientAxis::Radial);
+        assert_eq!(
+            radial.apply_pattern(Point::new(0.3, 7.0, 0.4)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+    }
+
+    #[test]
+    fn along_direction() {
+        let pattern =
+            Pattern::gradient_along(WHITE, BLACK, GradientAxis::Direction(Vector::new(0, 2, 0)));
+        assert_eq!(
+            pattern.apply_pattern(Point::new(0.0, 0.25, 0.0)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        assert_ne!(
+            pattern,
+            Pattern::gradient_along(WHITE, BLACK, GradientAxis::Y)
+        );
+    }
+}
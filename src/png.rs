@@ -0,0 +1,338 @@
+//! PNG file format logic, only available with the "png" feature. Includes animated PNGs (APNG) of several frames.
+use std::io::{self, Read, Write};
+
+use ::png::{BitDepth, ColorType, Decoder, DecodingError, Encoder, EncodingError, Transformations};
+
+use crate::{
+    canvas::Canvas,
+    color::{Color, WHITE},
+    quantize::{self, Quantization},
+    scalar::Scalar,
+};
+
+/// Reads a PNG image into a canvas. Color values are scaled to be between 0 and 1 and converted from sRGB to linear, alpha is ignored.
+pub fn read_png<R: Read>(reader: R) -> Result<Canvas, DecodingError> {
+    read_png_with_alpha(reader).map(|(canvas, _)| canvas)
+}
+
+/// Reads a PNG image like [`read_png`], additionally returning its alpha channel as a grayscale canvas (white where the image is opaque), e.g. for a [`crate::uv::Decal`].
+/// Images without alpha are opaque everywhere.
+pub fn read_png_with_alpha<R: Read>(reader: R) -> Result<(Canvas, Canvas), DecodingError> {
+    let mut decoder = Decoder::new(reader);
+    decoder.set_transformations(Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info()?;
+
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer)?;
+    let channels = match info.color_type {
+        ColorType::Grayscale => 1,
+        ColorType::GrayscaleAlpha => 2,
+        ColorType::Rgb | ColorType::Indexed => 3,
+        ColorType::Rgba => 4,
+    };
+
+    let width = info.width as usize;
+    let height = info.height as usize;
+    let mut canvas = Canvas::new(width, height);
+    let mut alpha = Canvas::new_with_color(width, height, WHITE);
+    for y in 0..height {
+        let row = &buffer[y * info.line_size..(y + 1) * info.line_size];
+        for x in 0..width {
+            let pixel = &row[x * channels..(x + 1) * channels];
+            let channel = |i: usize| pixel[i] as Scalar / 255.0;
+            let color = if channels < 3 {
+                Color::new(channel(0), channel(0), channel(0))
+            } else {
+                Color::new(channel(0), channel(1), channel(2))
+            };
+            canvas
+                .write_pixel(x, y, color.to_linear())
+                .expect("Canvas WIDTH and HEIGHT volation.");
+            if channels % 2 == 0 {
+                let a = channel(channels - 1);
+                alpha
+                    .write_pixel(x, y, Color::new(a, a, a))
+                    .expect("Canvas WIDTH and HEIGHT volation.");
+            }
+        }
+    }
+
+    Ok((canvas, alpha))
+}
+
+/// Writes the canvas as an 8 bit PNG image. Colors are converted from linear to sRGB, see [`Color::to_rgb8`].
+/// Canvases with transparent pixels (see [`Canvas::alpha_at`]) are written with an alpha channel.
+pub fn write_png<W: Write>(canvas: &Canvas, writer: W) -> Result<(), EncodingError> {
+    write_png_with(canvas, writer, Quantization::default())
+}
+
+/// Writes the canvas as a PNG image like [`write_png`], converting colors to 8 or 16 bits as given by ```quantization```
+pub fn write_png_with<W: Write>(
+    canvas: &Canvas,
+    writer: W,
+    quantization: Quantization,
+) -> Result<(), EncodingError> {
+    let opaque = canvas.is_opaque();
+    let encoder = encoder(writer, canvas, opaque, quantization);
+    // row by row, so the whole image is never held in memory twice
+    let mut writer = encoder.write_header()?;
+    let mut stream = writer.stream_writer()?;
+    let mut row = Vec::new();
+    for y in 0..canvas.height() {
+        row.clear();
+        encode_row(canvas, y, opaque, quantization, &mut row);
+        stream.write_all(&row)?;
+    }
+    stream.finish()
+}
+
+/// Writes the frames (e.g. rendered from [`crate::camera_path::CameraPath::frames`]) as an animated PNG, played in a loop at ```frames_per_second```.
+/// Viewers not supporting animation show the first frame. All frames need to have the same size.
+pub fn write_apng<W: Write>(
+    frames: &[Canvas],
+    writer: W,
+    frames_per_second: u16,
+    quantization: Quantization,
+) -> Result<(), EncodingError> {
+    let invalid = |message: &str| {
+        EncodingError::IoError(io::Error::new(io::ErrorKind::InvalidInput, message))
+    };
+    let first = frames.first().ok_or_else(|| invalid("no frames"))?;
+    if frames
+        .iter()
+        .any(|frame| frame.width() != first.width() || frame.height() != first.height())
+    {
+        return Err(invalid("frames differ in size"));
+    }
+    if frames_per_second == 0 {
+        return Err(invalid("frames per second need to be positive"));
+    }
+    let frame_count = u32::try_from(frames.len()).map_err(|_| invalid("too many frames"))?;
+
+    let opaque = frames.iter().all(Canvas::is_opaque);
+    let mut encoder = encoder(writer, first, opaque, quantization);
+    // 0 plays loop forever
+    encoder.set_animated(frame_count, 0)?;
+    encoder.set_frame_delay(1, frames_per_second)?;
+    let mut writer = encoder.write_header()?;
+    let mut data = Vec::new();
+    for frame in frames {
+        data.clear();
+        for y in 0..frame.height() {
+            encode_row(frame, y, opaque, quantization, &mut data);
+        }
+        writer.write_image_data(&data)?;
+    }
+    writer.finish()
+}
+
+/// An encoder for images of the size of ```canvas```, with alpha unless ```opaque``` and the bit depth of ```quantization```
+fn encoder<W: Write>(
+    writer: W,
+    canvas: &Canvas,
+    opaque: bool,
+    quantization: Quantization,
+) -> Encoder<'static, W> {
+    let mut encoder = Encoder::new(writer, canvas.width() as u32, canvas.height() as u32);
+    encoder.set_color(if opaque {
+        ColorType::Rgb
+    } else {
+        ColorType::Rgba
+    });
+    encoder.set_depth(match quantization.depth {
+        quantize::BitDepth::Eight => BitDepth::Eight,
+        quantize::BitDepth::Sixteen => BitDepth::Sixteen,
+    });
+    encoder
+}
+
+/// Appends row ```y``` of the canvas to ```data```, in the layout of [`encoder`]
+fn encode_row(
+    canvas: &Canvas,
+    y: usize,
+    opaque: bool,
+    quantization: Quantization,
+    data: &mut Vec<u8>,
+) {
+    // 16 bit components are big endian
+    let mut push = |value: u16| match quantization.depth {
+        quantize::BitDepth::Eight => data.push(value as u8),
+        quantize::BitDepth::Sixteen => data.extend_from_slice(&value.to_be_bytes()),
+    };
+    for x in 0..canvas.width() {
+        // PNG colors are not premultiplied
+        let (color, alpha) = canvas.straight_at(x, y);
+        for value in quantization.levels(color, x, y) {
+            push(value);
+        }
+        if !opaque {
+            push((alpha * quantization.depth.max_value() as Scalar).round() as u16);
+        }
+    }
+}
+
+#[cfg(test)]
+mod png_tests {
+    use ::png::{BitDepth, ColorType, Encoder};
+
+    use crate::{
+        canvas::Canvas,
+        color::Color,
+        png::{read_png, read_png_with_alpha, write_apng, write_png, write_png_with},
+        quantize::{self, Quantization},
+    };
+
+    fn encode(width: u32, height: u32, color_type: ColorType, data: &[u8]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut encoder = Encoder::new(&mut bytes, width, height);
+        encoder.set_color(color_type);
+        encoder.set_depth(BitDepth::Eight);
+        encoder
+            .write_header()
+            .unwrap()
+            .write_image_data(data)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn rgb() {
+        let bytes = encode(2, 1, ColorType::Rgb, &[255, 0, 0, 0, 51, 255]);
+        let canvas = read_png(bytes.as_slice()).unwrap();
+        assert_eq!(canvas.width(), 2);
+        assert_eq!(canvas.height(), 1);
+        assert_eq!(canvas.pixel_at(0, 0).unwrap(), Color::new(1, 0, 0));
+        assert_eq!(
+            canvas.pixel_at(1, 0).unwrap(),
+            Color::new(0.0, 0.2, 1.0).to_linear()
+        );
+    }
+
+    #[test]
+    fn grayscale_alpha() {
+        let bytes = encode(1, 2, ColorType::GrayscaleAlpha, &[255, 0, 102, 255]);
+        let canvas = read_png(bytes.as_slice()).unwrap();
+        assert_eq!(canvas.pixel_at(0, 0).unwrap(), Color::new(1, 1, 1));
+        assert_eq!(
+            canvas.pixel_at(0, 1).unwrap(),
+            Color::new(0.4, 0.4, 0.4).to_linear()
+        );
+    }
+
+    #[test]
+    fn alpha() {
+        let bytes = encode(2, 1, ColorType::Rgba, &[255, 0, 0, 255, 0, 0, 255, 51]);
+        let (canvas, alpha) = read_png_with_alpha(bytes.as_slice()).unwrap();
+        assert_eq!(canvas.pixel_at(1, 0).unwrap(), Color::new(0, 0, 1));
+        assert_eq!(alpha.pixel_at(0, 0).unwrap(), Color::new(1, 1, 1));
+        assert_eq!(alpha.pixel_at(1, 0).unwrap(), Color::new(0.2, 0.2, 0.2));
+
+        let bytes = encode(1, 1, ColorType::Rgb, &[0, 0, 0]);
+        let (_, alpha) = read_png_with_alpha(bytes.as_slice()).unwrap();
+        assert_eq!(alpha.pixel_at(0, 0).unwrap(), Color::new(1, 1, 1));
+    }
+
+    #[test]
+    fn write_opaque() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.write_pixel(0, 0, Color::new(1, 0, 0)).unwrap();
+        canvas
+            .write_pixel(1, 0, Color::from_rgb8(0, 51, 255))
+            .unwrap();
+        let mut bytes = Vec::new();
+        write_png(&canvas, &mut bytes).unwrap();
+        let (read, alpha) = read_png_with_alpha(bytes.as_slice()).unwrap();
+        assert_eq!(read.pixel_at(0, 0).unwrap(), Color::new(1, 0, 0));
+        assert_eq!(read.pixel_at(1, 0).unwrap(), Color::from_rgb8(0, 51, 255));
+        assert_eq!(alpha.pixel_at(1, 0).unwrap(), Color::new(1, 1, 1));
+    }
+
+    #[test]
+    fn write_transparent() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas
+            .write_pixel(0, 0, Color::from_rgb8(0, 0, 255) * 0.2)
+            .unwrap();
+        canvas.write_alpha(0, 0, 0.2).unwrap();
+        canvas.write_alpha(1, 0, 0.0).unwrap();
+        let mut bytes = Vec::new();
+        write_png(&canvas, &mut bytes).unwrap();
+        let (read, alpha) = read_png_with_alpha(bytes.as_slice()).unwrap();
+        assert_eq!(read.pixel_at(0, 0).unwrap(), Color::new(0, 0, 1));
+        assert_eq!(alpha.pixel_at(0, 0).unwrap(), Color::new(0.2, 0.2, 0.2));
+        assert_eq!(alpha.pixel_at(1, 0).unwrap(), Color::new(0, 0, 0));
+    }
+
+    #[test]
+    fn write_sixteen_bits() {
+        let mut canvas = Canvas::new_with_color(2, 1, Color::new(1.0, 0.5, 0.0));
+        canvas
+            .write_pixel(1, 0, Color::new(1.0, 0.5, 0.0) * 0.5)
+            .unwrap();
+        canvas.write_alpha(1, 0, 0.5).unwrap();
+        let mut bytes = Vec::new();
+        let quantization = Quantization::default().with_depth(quantize::BitDepth::Sixteen);
+        write_png_with(&canvas, &mut bytes, quantization).unwrap();
+
+        let mut reader = ::png::Decoder::new(bytes.as_slice()).read_info().unwrap();
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer).unwrap();
+        assert_eq!(info.bit_depth, BitDepth::Sixteen);
+        assert_eq!(info.color_type, ColorType::Rgba);
+        assert_eq!(&buffer[0..8], &[255, 255, 188, 64, 0, 0, 255, 255]);
+        // not premultiplied, so the same color as the first pixel
+        assert_eq!(&buffer[8..14], &buffer[0..6]);
+        assert_eq!(&buffer[14..16], &32768u16.to_be_bytes());
+
+        // still readable, as 8 bits
+        let read = read_png(bytes.as_slice()).unwrap();
+        assert_eq!(read.pixel_at(0, 0).unwrap(), Color::from_rgb8(255, 188, 0));
+    }
+
+    #[test]
+    fn animated() {
+        let mut frames = vec![
+            Canvas::new_with_color(2, 1, Color::new(1, 0, 0)),
+            Canvas::new_with_color(2, 1, Color::new(0, 1, 0)),
+            Canvas::new_with_color(2, 1, Color::new(0, 0, 1)),
+        ];
+        frames[2].write_alpha(1, 0, 0.0).unwrap();
+        let mut bytes = Vec::new();
+        write_apng(&frames, &mut bytes, 25, Quantization::default()).unwrap();
+
+        let mut reader = ::png::Decoder::new(bytes.as_slice()).read_info().unwrap();
+        let animation = reader.info().animation_control.unwrap();
+        assert_eq!((animation.num_frames, animation.num_plays), (3, 0));
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let mut pixels = Vec::new();
+        for _ in 0..3 {
+            let info = reader.next_frame(&mut buffer).unwrap();
+            assert_eq!(info.color_type, ColorType::Rgba);
+            let control = reader.info().frame_control.unwrap();
+            assert_eq!((control.delay_num, control.delay_den), (1, 25));
+            pixels.push(buffer[..8].to_vec());
+        }
+        assert_eq!(pixels[0], vec![255, 0, 0, 255, 255, 0, 0, 255]);
+        assert_eq!(pixels[1], vec![0, 255, 0, 255, 0, 255, 0, 255]);
+        assert_eq!(pixels[2], vec![0, 0, 255, 255, 0, 0, 255, 0]);
+
+        // the first frame for viewers without animation
+        let read = read_png(bytes.as_slice()).unwrap();
+        assert_eq!(read.pixel_at(0, 0).unwrap(), Color::new(1, 0, 0));
+    }
+
+    #[test]
+    fn animated_invalid() {
+        let mut bytes = Vec::new();
+        let quantization = Quantization::default();
+        assert!(write_apng(&[], &mut bytes, 25, quantization).is_err());
+        let frames = [Canvas::new(2, 1), Canvas::new(1, 2)];
+        assert!(write_apng(&frames, &mut bytes, 25, quantization).is_err());
+        assert!(write_apng(&frames[..1], &mut bytes, 0, quantization).is_err());
+    }
+
+    #[test]
+    fn not_a_png() {
+        assert!(read_png("P3\n1 1\n255\n0 0 0".as_bytes()).is_err());
+    }
+}
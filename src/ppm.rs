@@ -1,58 +1,121 @@
-use crate::canvas::Canvas;
+use std::io::{self, Write};
+
+use crate::{canvas::Canvas, color::Color, quantize::Quantization, scalar::Scalar};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Errors reading a PPM file may cause
+pub enum PpmError {
+    /// The file does not start with the "P3" magic number of plain PPM files.
+    InvalidMagicNumber,
+    /// Width, height or maximum color value are missing or not valid numbers.
+    InvalidHeader,
+    /// The pixel data is not a valid number, exceeds the maximum color value or is incomplete.
+    InvalidPixelData,
+}
 
 /// Creates a PPM file format string from the canvas that can then be written to a file.
+/// Colors are converted from linear to sRGB, see [`Color::to_srgb`], or use [`write_ppm_with`] for other curves.
+/// For large canvases, prefer [`write_ppm_to`], which does not hold the whole file in memory.
 pub fn write_to_ppm(canvas: Canvas) -> String {
-    let mut header = format!("P3\n{} {}\n255", canvas.width(), canvas.height());
-    let mut body = "\n".to_string();
+    let mut ppm = Vec::new();
+    write_ppm_to(&canvas, &mut ppm).expect("writing to a vector does not fail");
+    String::from_utf8(ppm).expect("PPM files are ASCII")
+}
 
+/// Writes the canvas as a PPM file to ```writer``` row by row, like [`write_to_ppm`].
+/// Wrap files in a [`std::io::BufWriter`], as every row is written separately.
+pub fn write_ppm_to<W: Write>(canvas: &Canvas, writer: W) -> io::Result<()> {
+    write_ppm_with(canvas, writer, Quantization::default())
+}
+
+/// Writes the canvas as a PPM file like [`write_ppm_to`], converting colors to 8 bits as given by ```quantization```,
+/// e.g. with a gamma of 2.2 to match images of other renderers, or dithered against banding.
+/// With [`crate::quantize::BitDepth::Sixteen`], the maximum color value is 65535.
+pub fn write_ppm_with<W: Write>(
+    canvas: &Canvas,
+    mut writer: W,
+    quantization: Quantization,
+) -> io::Result<()> {
+    writeln!(
+        writer,
+        "P3\n{} {}\n{}",
+        canvas.width(),
+        canvas.height(),
+        quantization.depth.max_value()
+    )?;
+
+    let mut row = String::new();
     for y in 0..canvas.height() {
-        let mut row = String::new();
+        row.clear();
         let mut len = 0;
         for x in 0..canvas.width() {
             let color = canvas
                 .pixel_at(x, y)
                 .expect("Canvas WIDTH and HEIGHT volation.");
 
-            let red = format!("{} ", convert_color(color.red));
-            let green = format!("{} ", convert_color(color.green));
-            let blue = format!("{} ", convert_color(color.blue));
-
-            len += red.chars().count();
-            if len > 70 {
-                row.push('\n');
-                len = red.chars().count();
+            for channel in quantization.levels(color, x, y) {
+                let value = format!("{channel} ");
+                len += value.len();
+                if len > 70 {
+                    row.push('\n');
+                    len = value.len();
+                }
+                row.push_str(&value);
             }
-            row.push_str(&red);
-            len += green.chars().count();
-            if len > 70 {
-                row.push('\n');
-                len = green.chars().count();
-            }
-            row.push_str(&green);
-            len += blue.chars().count();
-            if len > 70 {
-                row.push('\n');
-                len = blue.chars().count();
-            }
-            row.push_str(&blue);
         }
         row.push('\n');
-        body.push_str(&row);
+        writer.write_all(row.as_bytes())?;
     }
+    Ok(())
+}
 
-    header.push_str(&body);
+/// Reads a plain (P3) PPM file into a canvas. Color values are scaled to be between 0 and 1 according to the maximum color value of the file,
+/// and converted from sRGB to linear, see [`Color::to_linear`].
+pub fn read_ppm(ppm: &str) -> Result<Canvas, PpmError> {
+    let mut tokens = ppm
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .flat_map(str::split_whitespace);
 
-    header
-}
+    if tokens.next() != Some("P3") {
+        return Err(PpmError::InvalidMagicNumber);
+    }
 
-fn convert_color(color: f64) -> usize {
-    if color > 1. {
-        255
-    } else if color <= 0. {
-        0
-    } else {
-        (color * 255.).round() as usize
+    let mut header = || -> Result<usize, PpmError> {
+        tokens
+            .next()
+            .and_then(|token| token.parse().ok())
+            .ok_or(PpmError::InvalidHeader)
+    };
+    let width = header()?;
+    let height = header()?;
+    let max_value = header()?;
+    if max_value == 0 {
+        return Err(PpmError::InvalidHeader);
     }
+
+    let mut canvas = Canvas::new(width, height);
+    let mut channel = || -> Result<Scalar, PpmError> {
+        let value: usize = tokens
+            .next()
+            .and_then(|token| token.parse().ok())
+            .ok_or(PpmError::InvalidPixelData)?;
+        if value > max_value {
+            return Err(PpmError::InvalidPixelData);
+        }
+        Ok(value as Scalar / max_value as Scalar)
+    };
+
+    for y in 0..height {
+        for x in 0..width {
+            let color = Color::new(channel()?, channel()?, channel()?).to_linear();
+            canvas
+                .write_pixel(x, y, color)
+                .expect("Canvas WIDTH and HEIGHT volation.");
+        }
+    }
+
+    Ok(canvas)
 }
 
 #[cfg(test)]
@@ -60,7 +123,8 @@ mod ppm_tests {
     use crate::{
         canvas::Canvas,
         color::Color,
-        ppm::{convert_color, write_to_ppm},
+        ppm::{read_ppm, write_ppm_to, write_ppm_with, write_to_ppm},
+        quantize::{BitDepth, Gamma, Quantization},
     };
 
     #[test]
@@ -82,7 +146,8 @@ mod ppm_tests {
         c.write_pixel(4, 2, c3).unwrap();
         let ppm: String = write_to_ppm(c);
 
-        let reference = "255 0 0 0 0 0 0 0 0 0 0 0 0 0 0 \n0 0 0 0 0 0 0 128 0 0 0 0 0 0 0 \n0 0 0 0 0 0 0 0 0 0 0 0 0 0 255 ";
+        // 0.5 is converted to sRGB
+        let reference = "255 0 0 0 0 0 0 0 0 0 0 0 0 0 0 \n0 0 0 0 0 0 0 188 0 0 0 0 0 0 0 \n0 0 0 0 0 0 0 0 0 0 0 0 0 0 255 ";
         assert!(ppm.contains(reference));
     }
 
@@ -91,10 +156,31 @@ mod ppm_tests {
         let color: Color = Color::new(1., 0.8, 0.6);
         let c = Canvas::new_with_color(10, 2, color);
         let ppm: String = write_to_ppm(c);
-        let reference = "255 204 153 255 204 153 255 204 153 255 204 153 255 204 153 255 204 \n153 255 204 153 255 204 153 255 204 153 255 204 153 \n255 204 153 255 204 153 255 204 153 255 204 153 255 204 153 255 204 \n153 255 204 153 255 204 153 255 204 153 255 204 153";
+        let reference = "255 231 203 255 231 203 255 231 203 255 231 203 255 231 203 255 231 \n203 255 231 203 255 231 203 255 231 203 255 231 203 \n255 231 203 255 231 203 255 231 203 255 231 203 255 231 203 255 231 \n203 255 231 203 255 231 203 255 231 203 255 231 203";
         assert!(ppm.contains(reference));
     }
 
+    #[test]
+    fn streams_rows() {
+        /// Keeps every write separately
+        struct Writes(Vec<Vec<u8>>);
+        impl std::io::Write for Writes {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.push(buf.to_vec());
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let c = Canvas::new_with_color(10, 3, Color::new(1., 0.8, 0.6));
+        let mut writes = Writes(Vec::new());
+        write_ppm_to(&c, &mut writes).unwrap();
+        assert!(writes.0.len() >= 4);
+        assert_eq!(writes.0.concat(), write_to_ppm(c).into_bytes());
+    }
+
     #[test]
     fn end_is_newline() {
         let color: Color = Color::new(1., 0.8, 0.6);
@@ -104,12 +190,110 @@ mod ppm_tests {
     }
 
     #[test]
-    fn convert_color_to_255() {
-        assert_eq!(convert_color(1.), 255);
-        assert_eq!(convert_color(2.4), 255);
-        assert_eq!(convert_color(0.5), 128);
-        assert_eq!(convert_color(-1.), 0);
-        assert_eq!(convert_color(-0.5), 0);
-        assert_eq!(convert_color(0.), 0);
+    fn gamma() {
+        let c = Canvas::new_with_color(1, 1, Color::new(1.5, 0.5, -0.5));
+        let mut ppm = Vec::new();
+        write_ppm_with(&c, &mut ppm, Quantization::new(Gamma::Linear)).unwrap();
+        assert_eq!(ppm, b"P3\n1 1\n255\n255 128 0 \n");
+        ppm.clear();
+        write_ppm_with(&c, &mut ppm, Quantization::new(Gamma::Power(2.2))).unwrap();
+        assert_eq!(ppm, b"P3\n1 1\n255\n255 186 0 \n");
+    }
+
+    #[test]
+    fn sixteen_bits() {
+        let mut c = Canvas::new(2, 1);
+        c.write_pixel(0, 0, Color::new(1.0, 0.5, 0.2)).unwrap();
+        let mut ppm = Vec::new();
+        let quantization = Quantization::default().with_depth(BitDepth::Sixteen);
+        write_ppm_with(&c, &mut ppm, quantization).unwrap();
+        let ppm = String::from_utf8(ppm).unwrap();
+        assert!(ppm.starts_with("P3\n2 1\n65535\n65535 48192 31754 0 0 0"));
+
+        let read = read_ppm(&ppm).unwrap();
+        let color = read.pixel_at(0, 0).unwrap();
+        assert!((color.green - 0.5).abs() < 0.0001 && (color.blue - 0.2).abs() < 0.0001);
+    }
+}
+
+#[cfg(test)]
+mod read_ppm_tests {
+    use crate::{
+        canvas::Canvas,
+        color::Color,
+        ppm::{read_ppm, write_to_ppm, PpmError},
+    };
+
+    #[test]
+    fn wrong_magic_number() {
+        let ppm = "P32\n1 1\n255\n0 0 0 0 0 0 0 0 0 0 0 0";
+        assert_eq!(read_ppm(ppm).unwrap_err(), PpmError::InvalidMagicNumber);
+    }
+
+    #[test]
+    fn dimensions() {
+        let ppm = "P3\n10 2\n255\n".to_string() + &"0 0 0 ".repeat(20);
+        let canvas = read_ppm(&ppm).unwrap();
+        assert_eq!(canvas.width(), 10);
+        assert_eq!(canvas.height(), 2);
+    }
+
+    #[test]
+    fn pixel_data() {
+        let ppm = "P3\n4 3\n255\n255 127 0  0 127 255  127 255 0  255 255 255\n0 0 0  255 0 0  0 255 0  0 0 255\n255 255 0  0 255 255  255 0 255  127 127 127";
+        let canvas = read_ppm(ppm).unwrap();
+        assert_eq!(
+            canvas.pixel_at(0, 0).unwrap(),
+            Color::new(1.0, 0.49804, 0.0).to_linear()
+        );
+        assert_eq!(
+            canvas.pixel_at(1, 0).unwrap(),
+            Color::new(0.0, 0.49804, 1.0).to_linear()
+        );
+        assert_eq!(canvas.pixel_at(3, 0).unwrap(), Color::new(1, 1, 1));
+        assert_eq!(canvas.pixel_at(1, 1).unwrap(), Color::new(1, 0, 0));
+        assert_eq!(
+            canvas.pixel_at(3, 2).unwrap(),
+            Color::new(0.21223, 0.21223, 0.21223)
+        );
+    }
+
+    #[test]
+    fn comments_and_line_breaks() {
+        let ppm = "P3\n# this is a comment\n2 1\n# this, too\n255\n# another comment\n255 255 255\n# oh, no, comments in the pixel data!\n255 0\n255";
+        let canvas = read_ppm(ppm).unwrap();
+        assert_eq!(canvas.pixel_at(0, 0).unwrap(), Color::new(1, 1, 1));
+        assert_eq!(canvas.pixel_at(1, 0).unwrap(), Color::new(1, 0, 1));
+    }
+
+    #[test]
+    fn scaled_by_maximum_value() {
+        let ppm = "P3\n2 2\n100\n100 100 100  50 50 50\n75 50 25  0 0 0";
+        let canvas = read_ppm(ppm).unwrap();
+        assert_eq!(
+            canvas.pixel_at(0, 1).unwrap(),
+            Color::new(0.75, 0.5, 0.25).to_linear()
+        );
+    }
+
+    #[test]
+    fn incomplete_data() {
+        assert_eq!(
+            read_ppm("P3\n2 2\n255\n0 0 0").unwrap_err(),
+            PpmError::InvalidPixelData
+        );
+        assert_eq!(read_ppm("P3\n2\n").unwrap_err(), PpmError::InvalidHeader);
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut c = Canvas::new(3, 2);
+        c.write_pixel(1, 1, Color::new(1, 0, 1)).unwrap();
+        c.write_pixel(2, 0, Color::new(0.5, 0.2, 0.8)).unwrap();
+        let canvas = read_ppm(&write_to_ppm(c)).unwrap();
+        assert_eq!(canvas.pixel_at(1, 1).unwrap(), Color::new(1, 0, 1));
+        let gray = canvas.pixel_at(2, 0).unwrap();
+        assert!((gray.red - 0.5).abs() < 0.005 && (gray.blue - 0.8).abs() < 0.005);
+        assert_eq!(canvas.pixel_at(0, 0).unwrap(), Color::new(0, 0, 0));
     }
 }
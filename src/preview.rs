@@ -0,0 +1,168 @@
+//! A window showing renders as they progress, only available with the "preview" feature
+use std::time::{Duration, Instant};
+
+use minifb::{Key, Window, WindowOptions};
+
+use crate::{
+    camera::Camera,
+    canvas::{Canvas, CanvasError},
+    color::Color,
+    scalar::Scalar,
+    world::World,
+};
+
+/// How often the window is redrawn while rendering. Redrawing after every row would slow down small renders.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Debug)]
+/// Errors a preview may return
+pub enum PreviewError {
+    /// The window could not be opened or updated
+    Window(minifb::Error),
+    /// Writing to the canvas failed
+    Canvas(CanvasError),
+    /// The window was closed or Escape was pressed before the render finished. Contains the rows rendered so far.
+    Aborted(Canvas),
+}
+
+impl From<minifb::Error> for PreviewError {
+    fn from(error: minifb::Error) -> Self {
+        Self::Window(error)
+    }
+}
+
+impl From<CanvasError> for PreviewError {
+    fn from(error: CanvasError) -> Self {
+        Self::Canvas(error)
+    }
+}
+
+#[derive(Debug)]
+/// A window displaying the rows of a render as soon as they are finished, so long renders can be watched (and aborted) early.
+pub struct PreviewWindow {
+    window: Window,
+    buffer: Vec<u32>,
+    width: usize,
+    height: usize,
+}
+
+impl PreviewWindow {
+    /// Opens a window with the given ```title```, as big as the images ```camera``` renders
+    pub fn new(title: &str, camera: &Camera) -> Result<Self, PreviewError> {
+        let (width, height) = (camera.hsize, camera.vsize);
+        let mut window = Window::new(title, width, height, WindowOptions::default())?;
+        window.set_target_fps(0);
+        Ok(Self {
+            window,
+            buffer: vec![0; width * height],
+            width,
+            height,
+        })
+    }
+
+    /// Renders the world like [`Camera::render`], showing every row in the window once it is finished.
+    /// With the "rayon" feature, rows are rendered in parallel batches.
+    ///
+    /// Closing the window or pressing Escape aborts the render, returning [`PreviewError::Aborted`] with the rows finished so far.
+    pub fn render(&mut self, camera: &Camera, world: &World) -> Result<Canvas, PreviewError> {
+        if (camera.hsize, camera.vsize) != (self.width, self.height) {
+            return Err(CanvasError::InvalidSize.into());
+        }
+        world.clear_irradiance_cache();
+        let mut canvas = Canvas::new(self.width, self.height);
+        let mut last_redraw = Instant::now();
+        let mut y = 0;
+        while y < self.height {
+            if !self.window.is_open() || self.window.is_key_down(Key::Escape) {
+                return Err(PreviewError::Aborted(canvas));
+            }
+            let rows = Self::render_rows(camera, world, y);
+            for row in rows {
+                for (x, (color, alpha)) in row.into_iter().enumerate() {
+                    canvas.write_pixel(x, y, color)?;
+                    if camera.settings.transparent_background {
+                        canvas.write_alpha(x, y, alpha)?;
+                    }
+                    self.buffer[y * self.width + x] = pack(color);
+                }
+                y += 1;
+            }
+            if last_redraw.elapsed() >= REDRAW_INTERVAL {
+                self.redraw()?;
+                last_redraw = Instant::now();
+            }
+        }
+        self.redraw()?;
+        Ok(canvas)
+    }
+
+    /// Shows ```canvas``` in the window, e.g. a render loaded from a file. It must be as big as the window.
+    pub fn show(&mut self, canvas: &Canvas) -> Result<(), PreviewError> {
+        if (canvas.width(), canvas.height()) != (self.width, self.height) {
+            return Err(CanvasError::InvalidSize.into());
+        }
+        for y in 0..self.height {
+            for x in 0..self.width {
+                self.buffer[y * self.width + x] = pack(canvas.pixel_at(x, y)?);
+            }
+        }
+        self.redraw()
+    }
+
+    /// Keeps showing the last image until the window is closed or Escape is pressed
+    pub fn wait_until_closed(&mut self) -> Result<(), PreviewError> {
+        while self.window.is_open() && !self.window.is_key_down(Key::Escape) {
+            self.redraw()?;
+            std::thread::sleep(REDRAW_INTERVAL);
+        }
+        Ok(())
+    }
+
+    fn redraw(&mut self) -> Result<(), PreviewError> {
+        self.window
+            .update_with_buffer(&self.buffer, self.width, self.height)?;
+        Ok(())
+    }
+
+    /// Renders the rows starting at ```y```: a single one, or one per thread with the "rayon" feature
+    #[cfg(not(feature = "rayon"))]
+    fn render_rows(camera: &Camera, world: &World, y: usize) -> Vec<Vec<(Color, Scalar)>> {
+        vec![camera.render_row(world, y)]
+    }
+
+    /// Renders the rows starting at ```y```: a single one, or one per thread with the "rayon" feature
+    #[cfg(feature = "rayon")]
+    fn render_rows(camera: &Camera, world: &World, y: usize) -> Vec<Vec<(Color, Scalar)>> {
+        use rayon::prelude::*;
+
+        let end = (y + rayon::current_num_threads()).min(camera.vsize);
+        let mut rows = Vec::with_capacity(end - y);
+        (y..end)
+            .into_par_iter()
+            .map(|y| camera.render_row(world, y))
+            .collect_into_vec(&mut rows);
+        rows
+    }
+}
+
+/// The color as a pixel of the window buffer: 0RGB, 8 bit sRGB per channel
+pub(crate) fn pack(color: Color) -> u32 {
+    let [red, green, blue] = color.to_rgb8();
+    u32::from_be_bytes([0, red, green, blue])
+}
+
+#[cfg(test)]
+mod preview_tests {
+    use crate::{
+        color::{Color, BLACK, WHITE},
+        preview::pack,
+    };
+
+    #[test]
+    fn pack_colors() {
+        assert_eq!(pack(BLACK), 0);
+        assert_eq!(pack(WHITE), 0x00FF_FFFF);
+        assert_eq!(pack(Color::new(1.0, 0.0, 2.0)), 0x00FF_00FF);
+        assert_eq!(pack(Color::from_rgb8(0x12, 0x34, 0x56)), 0x0012_3456);
+    }
+}
@@ -0,0 +1,232 @@
+//! Conversion of linear colors into the 8 or 16 bit values written to PPM, PNG and BMP images.
+use crate::{
+    color::{linear_to_srgb, Color},
+    scalar::Scalar,
+};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+/// The curve applied to linear colors before they are quantized to 8 bits
+pub enum Gamma {
+    /// The piecewise sRGB curve, see [`Color::to_srgb`]
+    #[default]
+    Srgb,
+    /// A pure power curve, c^(1/gamma). Many other renderers approximate sRGB with a gamma of 2.2.
+    Power(Scalar),
+    /// No curve, the linear values are written as they are
+    Linear,
+}
+
+impl Gamma {
+    /// Applies the curve to a single color component
+    pub fn encode(&self, c: Scalar) -> Scalar {
+        match self {
+            Gamma::Srgb => linear_to_srgb(c),
+            Gamma::Power(gamma) => c.max(0.0).powf(1.0 / gamma),
+            Gamma::Linear => c,
+        }
+    }
+}
+
+/// Thresholds of ordered dithering, each of 0 to 63 appearing once
+const BAYER: [[u8; 8]; 8] = [
+    [0, 32, 8, 40, 2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44, 4, 36, 14, 46, 6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [3, 35, 11, 43, 1, 33, 9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47, 7, 39, 13, 45, 5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+/// Noise added before rounding to 8 bits, trading the banding of smooth gradients (skies, soft shadows) for fine grain
+pub enum Dither {
+    /// Plain rounding
+    #[default]
+    None,
+    /// A repeating 8x8 Bayer pattern
+    Ordered,
+    /// Interleaved gradient noise, which like blue noise has little low frequency content, so it does not clump
+    GradientNoise,
+}
+
+impl Dither {
+    /// The offset added to the pixel at (```x```, ```y```), in 8 bit steps between -0.5 and 0.5
+    pub fn offset(&self, x: usize, y: usize) -> Scalar {
+        match self {
+            Dither::None => 0.0,
+            Dither::Ordered => (BAYER[y % 8][x % 8] as Scalar + 0.5) / 64.0 - 0.5,
+            Dither::GradientNoise => {
+                // always in f64, as the noise comes from the fractions of large products
+                let (x, y) = (x as f64, y as f64);
+                ((52.9829189 * (0.06711056 * x + 0.00583715 * y).fract()).fract() - 0.5) as Scalar
+            }
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+/// The number of bits per color component of written images
+pub enum BitDepth {
+    /// 256 levels, what displays show
+    #[default]
+    Eight,
+    /// 65536 levels, keeping smooth gradients through printing and further editing. Only PPM and PNG images support it.
+    Sixteen,
+}
+
+impl BitDepth {
+    /// The value of a full component, e.g. 255 for 8 bits
+    pub const fn max_value(&self) -> u16 {
+        match self {
+            BitDepth::Eight => u8::MAX as u16,
+            BitDepth::Sixteen => u16::MAX,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+/// How the image writers turn the colors of a canvas into 8 or 16 bit values, see e.g. [`crate::ppm::write_ppm_with`].
+/// The default matches [`Color::to_rgb8`].
+pub struct Quantization {
+    /// The curve applied before quantizing
+    pub gamma: Gamma,
+    /// The noise added before rounding
+    pub dither: Dither,
+    /// The bits per component of images supporting several
+    pub depth: BitDepth,
+}
+
+impl Quantization {
+    /// Quantization with the given curve, without dithering
+    pub fn new(gamma: Gamma) -> Self {
+        Self {
+            gamma,
+            dither: Dither::None,
+            depth: BitDepth::Eight,
+        }
+    }
+
+    /// The same quantization, dithered
+    pub fn with_dither(mut self, dither: Dither) -> Self {
+        self.dither = dither;
+        self
+    }
+
+    /// The same quantization with another bit depth
+    pub fn with_depth(mut self, depth: BitDepth) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// The 8 bit components of ```color``` at the pixel (```x```, ```y```), which only matters for dithering.
+    /// Components outside of 0 to 1 (after the curve) are clamped, so black and white stay as they are.
+    /// Always 8 bits, whatever the depth, see [`Self::levels`].
+    pub fn quantize(&self, color: Color, x: usize, y: usize) -> [u8; 3] {
+        self.scale(color, x, y, BitDepth::Eight).map(|c| c as u8)
+    }
+
+    /// The components of ```color``` at the pixel (```x```, ```y```) like [`Self::quantize`], but between 0 and the maximum value of the depth
+    pub fn levels(&self, color: Color, x: usize, y: usize) -> [u16; 3] {
+        self.scale(color, x, y, self.depth)
+    }
+
+    fn scale(&self, color: Color, x: usize, y: usize, depth: BitDepth) -> [u16; 3] {
+        let max = depth.max_value() as Scalar;
+        let offset = self.dither.offset(x, y);
+        let convert = |c: Scalar| {
+            (self.gamma.encode(c).clamp(0.0, 1.0) * max + offset)
+                .round()
+                .clamp(0.0, max) as u16
+        };
+        [
+            convert(color.red),
+            convert(color.green),
+            convert(color.blue),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod quantize_tests {
+    use crate::{
+        color::Color,
+        quantize::{BitDepth, Dither, Gamma, Quantization},
+        scalar::Scalar,
+    };
+
+    #[test]
+    fn clamps_and_rounds() {
+        let linear = Quantization::new(Gamma::Linear);
+        assert_eq!(
+            linear.quantize(Color::new(1.0, 2.4, 0.5), 0, 0),
+            [255, 255, 128]
+        );
+        assert_eq!(
+            linear.quantize(Color::new(-1.0, -0.5, 0.0), 0, 0),
+            [0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn curves() {
+        let color = Color::new(0.5, 0.2, 0.0);
+        assert_eq!(
+            Quantization::default().quantize(color, 0, 0),
+            color.to_rgb8()
+        );
+        assert_eq!(
+            Quantization::new(Gamma::Power(2.2)).quantize(color, 0, 0),
+            [186, 123, 0]
+        );
+        assert_eq!(Gamma::Power(2.0).encode(0.25), 0.5);
+        assert_eq!(Gamma::Power(2.0).encode(-1.0), 0.0);
+    }
+
+    #[test]
+    fn ordered_dither_keeps_average() {
+        let dithered = Quantization::new(Gamma::Linear).with_dither(Dither::Ordered);
+        let value = 100.25 / 255.0;
+        let mut sum = 0;
+        for y in 0..8 {
+            for x in 0..8 {
+                sum += dithered.quantize(Color::new(value, 0.0, 1.0), x, y)[0] as usize;
+            }
+        }
+        assert_eq!(sum as Scalar / 64.0, 100.25);
+        assert_eq!(
+            dithered.quantize(Color::new(0.0, 0.0, 1.0), 7, 7),
+            [0, 0, 255]
+        );
+    }
+
+    #[test]
+    fn dither_offsets() {
+        for dither in [Dither::Ordered, Dither::GradientNoise] {
+            let offsets: Vec<Scalar> = (0..64).map(|i| dither.offset(i % 8, i / 8)).collect();
+            assert!(offsets.iter().all(|offset| (-0.5..0.5).contains(offset)));
+            let mean = offsets.iter().sum::<Scalar>() / 64.0;
+            assert!(mean.abs() < 0.1, "{dither:?}");
+        }
+        assert_eq!(Dither::None.offset(3, 5), 0.0);
+    }
+
+    #[test]
+    fn sixteen_bits() {
+        let wide = Quantization::new(Gamma::Linear).with_depth(BitDepth::Sixteen);
+        assert_eq!(
+            wide.levels(Color::new(1.0, 0.5, -1.0), 0, 0),
+            [65535, 32768, 0]
+        );
+        // quantize stays 8 bit
+        assert_eq!(
+            wide.quantize(Color::new(1.0, 0.5, -1.0), 0, 0),
+            [255, 128, 0]
+        );
+        assert_eq!(
+            Quantization::default().levels(Color::new(0.5, 0.2, 0.0), 0, 0),
+            Color::new(0.5, 0.2, 0.0).to_rgb8().map(u16::from)
+        );
+    }
+}
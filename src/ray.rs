@@ -1,5 +1,6 @@
 use crate::{
     matrix::Mat4,
+    scalar::{IntoScalar, Scalar},
     tuple::{Point, Vector},
 };
 
@@ -18,8 +19,8 @@ impl Ray {
         Ray { origin, direction }
     }
     /// The position of the [`Ray`]
-    pub fn position<T: Into<f64>>(&self, t: T) -> Point {
-        let t: f64 = t.into();
+    pub fn position<T: IntoScalar>(&self, t: T) -> Point {
+        let t: Scalar = t.into_scalar();
         self.origin + self.direction * t
     }
     #[inline]
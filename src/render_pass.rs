@@ -0,0 +1,363 @@
+//! Auxiliary images of a render (render passes), e.g. for compositing in external tools
+use crate::{
+    canvas::{Canvas, CanvasError},
+    color::{Color, BLACK, WHITE},
+    sampling::Rng,
+    scalar::Scalar,
+    shapes::shape::ObjectId,
+};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+/// An auxiliary image a camera can render in addition to the final image, see [`crate::camera::Camera::passes`].
+///
+/// All passes describe the first object hit by the camera rays. The lighting passes (ambient to refraction) add up to the final image, unless the material has a clear coat.
+pub enum RenderPass {
+    /// The world space surface normal, its x, y and z components stored as red, green and blue (between -1 and 1)
+    Normal,
+    /// The unlit surface color, including patterns and decals
+    Albedo,
+    /// The light the surface emits itself
+    Emission,
+    /// The ambient light, including emission
+    Ambient,
+    /// The diffuse and specular light of all light sources
+    Direct,
+    /// The light reflected from other objects
+    Reflection,
+    /// The light refracted through the surface
+    Refraction,
+}
+
+impl RenderPass {
+    /// All passes
+    pub const ALL: [RenderPass; 7] = [
+        RenderPass::Normal,
+        RenderPass::Albedo,
+        RenderPass::Emission,
+        RenderPass::Ambient,
+        RenderPass::Direct,
+        RenderPass::Reflection,
+        RenderPass::Refraction,
+    ];
+
+    const fn bit(self) -> u8 {
+        1 << self as u8
+    }
+
+    /// Whether this pass holds light, which is exposed like the final image. Normals and albedo are stored as they are.
+    pub const fn is_light(&self) -> bool {
+        !matches!(self, RenderPass::Normal | RenderPass::Albedo)
+    }
+
+    /// The color of this pass for a shaded point
+    pub(crate) fn color(&self, layers: &ShadingLayers) -> Color {
+        match self {
+            RenderPass::Normal => layers.normal,
+            RenderPass::Albedo => layers.albedo,
+            RenderPass::Emission => layers.emission,
+            RenderPass::Ambient => layers.ambient,
+            RenderPass::Direct => layers.direct,
+            RenderPass::Reflection => layers.reflection,
+            RenderPass::Refraction => layers.refraction,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// A set of [`RenderPass`]es
+pub struct RenderPasses(u8);
+
+impl RenderPasses {
+    /// No passes at all
+    pub const NONE: RenderPasses = RenderPasses(0);
+
+    /// This set with ```pass``` added
+    pub const fn with(self, pass: RenderPass) -> Self {
+        Self(self.0 | pass.bit())
+    }
+
+    /// Whether ```pass``` is part of this set
+    pub const fn contains(&self, pass: RenderPass) -> bool {
+        self.0 & pass.bit() != 0
+    }
+
+    /// Whether this set has no passes
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// The passes of this set, in the order of [`RenderPass::ALL`]
+    pub fn iter(&self) -> impl Iterator<Item = RenderPass> + '_ {
+        RenderPass::ALL
+            .into_iter()
+            .filter(|pass| self.contains(*pass))
+    }
+}
+
+impl From<RenderPass> for RenderPasses {
+    fn from(pass: RenderPass) -> Self {
+        RenderPasses::NONE.with(pass)
+    }
+}
+
+impl FromIterator<RenderPass> for RenderPasses {
+    fn from_iter<T: IntoIterator<Item = RenderPass>>(iter: T) -> Self {
+        iter.into_iter()
+            .fold(RenderPasses::NONE, RenderPasses::with)
+    }
+}
+
+#[derive(Clone, Debug)]
+/// The final image of a render together with the requested passes
+pub struct RenderLayers {
+    /// The final image, as [`crate::camera::Camera::render`] would return it
+    pub beauty: Canvas,
+    passes: Vec<(RenderPass, Canvas)>,
+}
+
+impl RenderLayers {
+    pub(crate) fn new(beauty: Canvas, passes: Vec<(RenderPass, Canvas)>) -> Self {
+        Self { beauty, passes }
+    }
+
+    /// The image of ```pass```, None if it was not rendered
+    pub fn get(&self, pass: RenderPass) -> Option<&Canvas> {
+        self.passes
+            .iter()
+            .find(|(rendered, _)| *rendered == pass)
+            .map(|(_, canvas)| canvas)
+    }
+
+    /// All rendered passes with their images
+    pub fn passes(&self) -> &[(RenderPass, Canvas)] {
+        &self.passes
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// Which objects are visible in every pixel, identified by their [`ObjectId`], see [`crate::camera::Camera::render_object_ids`].
+///
+/// Like a cryptomatte, every pixel stores how much of it each object covers, so masks of single objects have smooth edges when rendering several samples per pixel.
+/// Objects without an id and the background are not stored.
+pub struct ObjectIdBuffer {
+    coverage: Vec<Vec<(ObjectId, Scalar)>>,
+    width: usize,
+    height: usize,
+}
+
+impl ObjectIdBuffer {
+    /// A new buffer, no pixel covered by any object
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            coverage: vec![Vec::new(); width * height],
+            width,
+            height,
+        }
+    }
+
+    /// Horizontal size of the buffer
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Vertical size of the buffer
+    pub const fn height(&self) -> usize {
+        self.height
+    }
+
+    fn index(&self, x: usize, y: usize) -> Result<usize, CanvasError> {
+        if x >= self.width || y >= self.height {
+            return Err(CanvasError::InvalidCoordinates);
+        }
+        Ok(y * self.width + x)
+    }
+
+    /// Adds ```coverage``` (between 0 and 1) of the object ```id``` to the pixel at the provided coordinates.
+    /// Returns a [`CanvasError::InvalidCoordinates`] if the provided coordinates are not inside of the buffer dimensions.
+    pub fn add_coverage(
+        &mut self,
+        x: usize,
+        y: usize,
+        id: ObjectId,
+        coverage: Scalar,
+    ) -> Result<(), CanvasError> {
+        let index = self.index(x, y)?;
+        let pixel = &mut self.coverage[index];
+        match pixel.iter_mut().find(|(object, _)| *object == id) {
+            Some((_, existing)) => *existing += coverage,
+            None => pixel.push((id, coverage)),
+        }
+        pixel.sort_by(|a, b| b.1.total_cmp(&a.1));
+        Ok(())
+    }
+
+    /// The objects in the pixel at the provided coordinates with the part of the pixel they cover, the one covering the most first.
+    /// Returns a [`CanvasError::InvalidCoordinates`] if the provided coordinates are not inside of the buffer dimensions.
+    pub fn coverage_at(&self, x: usize, y: usize) -> Result<&[(ObjectId, Scalar)], CanvasError> {
+        Ok(&self.coverage[self.index(x, y)?])
+    }
+
+    /// The object covering most of the pixel at the provided coordinates, if any.
+    /// Returns a [`CanvasError::InvalidCoordinates`] if the provided coordinates are not inside of the buffer dimensions.
+    pub fn id_at(&self, x: usize, y: usize) -> Result<Option<ObjectId>, CanvasError> {
+        Ok(self.coverage_at(x, y)?.first().map(|(id, _)| *id))
+    }
+
+    /// A grayscale mask of the object ```id```: white where it covers the pixel completely, black where it is not visible.
+    pub fn mask(&self, id: ObjectId) -> Canvas {
+        self.to_canvas_with(|object| if object == id { WHITE } else { BLACK })
+    }
+
+    /// An image showing every object in its own color, e.g. for picking ids while compositing
+    pub fn to_canvas(&self) -> Canvas {
+        self.to_canvas_with(|ObjectId(id)| {
+            let hue = Rng::new(id as u64).next_scalar() * 360.0;
+            Color::from_hsv(hue, 0.75, 1.0)
+        })
+    }
+
+    fn to_canvas_with(&self, color: impl Fn(ObjectId) -> Color) -> Canvas {
+        let mut canvas = Canvas::new(self.width, self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let pixel = self.coverage[y * self.width + x]
+                    .iter()
+                    .map(|(id, coverage)| color(*id) * *coverage)
+                    .sum();
+                canvas
+                    .write_pixel(x, y, pixel)
+                    .expect("Canvas WIDTH and HEIGHT volation.");
+            }
+        }
+        canvas
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+/// The parts the color of a shaded point is made of
+pub(crate) struct ShadingLayers {
+    pub(crate) color: Color,
+    pub(crate) normal: Color,
+    pub(crate) albedo: Color,
+    pub(crate) emission: Color,
+    pub(crate) ambient: Color,
+    pub(crate) direct: Color,
+    pub(crate) reflection: Color,
+    pub(crate) refraction: Color,
+}
+
+impl ShadingLayers {
+    /// A ray that did not hit anything: only the final color is set
+    pub(crate) fn background(color: Color) -> Self {
+        Self {
+            color,
+            normal: BLACK,
+            albedo: BLACK,
+            emission: BLACK,
+            ambient: BLACK,
+            direct: BLACK,
+            reflection: BLACK,
+            refraction: BLACK,
+        }
+    }
+}
+
+#[cfg(test)]
+mod render_pass_tests {
+    use crate::render_pass::{RenderPass, RenderPasses};
+
+    #[test]
+    fn passes() {
+        let passes = RenderPasses::NONE
+            .with(RenderPass::Direct)
+            .with(RenderPass::Normal);
+        assert!(passes.contains(RenderPass::Direct));
+        assert!(!passes.contains(RenderPass::Albedo));
+        assert!(!passes.is_empty());
+        assert!(RenderPasses::default().is_empty());
+        assert_eq!(
+            passes.iter().collect::<Vec<_>>(),
+            vec![RenderPass::Normal, RenderPass::Direct]
+        );
+        assert_eq!(
+            [RenderPass::Normal, RenderPass::Direct]
+                .into_iter()
+                .collect::<RenderPasses>(),
+            passes
+        );
+        assert_eq!(
+            RenderPasses::from(RenderPass::Albedo),
+            RenderPasses::NONE.with(RenderPass::Albedo)
+        );
+        assert_eq!(
+            RenderPass::ALL
+                .into_iter()
+                .collect::<RenderPasses>()
+                .iter()
+                .count(),
+            7
+        );
+    }
+
+    #[test]
+    fn is_light() {
+        assert!(!RenderPass::Normal.is_light());
+        assert!(!RenderPass::Albedo.is_light());
+        assert!(RenderPass::Reflection.is_light());
+    }
+}
+
+#[cfg(test)]
+mod object_id_buffer_tests {
+    use crate::{
+        canvas::CanvasError, color::Color, render_pass::ObjectIdBuffer, shapes::shape::ObjectId,
+    };
+
+    #[test]
+    fn coverage() {
+        let mut ids = ObjectIdBuffer::new(2, 1);
+        assert_eq!(ids.id_at(0, 0), Ok(None));
+        ids.add_coverage(0, 0, ObjectId(1), 0.25).unwrap();
+        ids.add_coverage(0, 0, ObjectId(2), 0.5).unwrap();
+        ids.add_coverage(0, 0, ObjectId(1), 0.5).unwrap();
+        assert_eq!(
+            ids.coverage_at(0, 0).unwrap(),
+            &[(ObjectId(1), 0.75), (ObjectId(2), 0.5)]
+        );
+        assert_eq!(ids.id_at(0, 0), Ok(Some(ObjectId(1))));
+        assert_eq!(
+            ids.add_coverage(2, 0, ObjectId(1), 1.0),
+            Err(CanvasError::InvalidCoordinates)
+        );
+    }
+
+    #[test]
+    fn mask() {
+        let mut ids = ObjectIdBuffer::new(2, 1);
+        ids.add_coverage(0, 0, ObjectId(1), 0.5).unwrap();
+        ids.add_coverage(0, 0, ObjectId(2), 0.5).unwrap();
+        ids.add_coverage(1, 0, ObjectId(2), 1.0).unwrap();
+        let mask = ids.mask(ObjectId(2));
+        assert_eq!(mask.pixel_at(0, 0).unwrap(), Color::new(0.5, 0.5, 0.5));
+        assert_eq!(mask.pixel_at(1, 0).unwrap(), Color::new(1, 1, 1));
+        assert_eq!(
+            ids.mask(ObjectId(3)).pixel_at(1, 0).unwrap(),
+            Color::new(0, 0, 0)
+        );
+    }
+
+    #[test]
+    fn to_canvas() {
+        let mut ids = ObjectIdBuffer::new(3, 1);
+        ids.add_coverage(0, 0, ObjectId(1), 1.0).unwrap();
+        ids.add_coverage(1, 0, ObjectId(2), 1.0).unwrap();
+        let canvas = ids.to_canvas();
+        assert_ne!(
+            canvas.pixel_at(0, 0).unwrap(),
+            canvas.pixel_at(1, 0).unwrap()
+        );
+        assert_ne!(canvas.pixel_at(0, 0).unwrap(), Color::new(0, 0, 0));
+        assert_eq!(canvas.pixel_at(2, 0).unwrap(), Color::new(0, 0, 0));
+    }
+}
@@ -0,0 +1,386 @@
+use std::cell::Cell;
+
+use crate::{
+    scalar::{consts::PI, Scalar},
+    tuple::{Point, Vector},
+};
+
+/// A small, fast pseudo random number generator (SplitMix64) for stochastic sampling.
+///
+/// Seeding it from the shaded point (or the pixel) keeps renders deterministic, no matter in which order (or on which thread) pixels are rendered.
+#[derive(Copy, Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// A generator starting from the given seed
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// A generator seeded by the coordinates of a point and an additional salt,
+    /// so that different effects sampled at the same point do not correlate.
+    /// The seed of the current render is mixed in as well, see [`with_seed`].
+    pub(crate) fn from_point(p: Point, salt: u64) -> Self {
+        let render_seed = RENDER_SEED.with(Cell::get);
+        let mut seed = salt ^ render_seed.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        for coordinate in [p.x, p.y, p.z] {
+            // widened, as coordinates are f32 with the "f32" feature
+            #[allow(clippy::unnecessary_cast)]
+            let bits = (coordinate as f64).to_bits();
+            seed = Self::new(seed ^ bits).next_u64();
+        }
+        Self::new(seed)
+    }
+
+    /// The next random number, uniformly distributed over all u64 values
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// The next random number, uniformly distributed in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// The next random number like [`Self::next_f64`], as a [`Scalar`]
+    pub fn next_scalar(&mut self) -> Scalar {
+        let digits = Scalar::MANTISSA_DIGITS;
+        (self.next_u64() >> (64 - digits)) as Scalar / (1u64 << digits) as Scalar
+    }
+
+    /// A random direction in the hemisphere around ```normal``` (which must be normalized), see [`cosine_hemisphere`].
+    pub(crate) fn next_hemisphere_direction(&mut self, normal: Vector) -> Vector {
+        cosine_hemisphere(normal, (self.next_scalar(), self.next_scalar()))
+    }
+}
+
+/// Maps a position in the unit square (e.g. from a [`Sampler`]) to a point in the disk of radius 1 around the origin.
+/// The concentric mapping keeps evenly spread positions evenly spread and neighbouring positions close together.
+pub fn concentric_disk((u, v): (Scalar, Scalar)) -> (Scalar, Scalar) {
+    let (a, b) = (2.0 * u - 1.0, 2.0 * v - 1.0);
+    if a == 0.0 && b == 0.0 {
+        return (0.0, 0.0);
+    }
+    // squares around the center are mapped to circles around it
+    let (r, phi) = if a.abs() > b.abs() {
+        (a, PI / 4.0 * (b / a))
+    } else {
+        (b, PI / 2.0 - PI / 4.0 * (a / b))
+    };
+    (r * phi.cos(), r * phi.sin())
+}
+
+/// Maps a position in the unit square (e.g. from a [`Sampler`]) to a direction, all directions being equally likely.
+pub fn uniform_sphere((u, v): (Scalar, Scalar)) -> Vector {
+    let z = 1.0 - 2.0 * u;
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * PI * v;
+    Vector::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+/// Maps a position in the unit square (e.g. from a [`Sampler`]) to a direction in the hemisphere around ```normal``` (which must be normalized),
+/// all directions being equally likely.
+pub fn uniform_hemisphere(normal: Vector, (u, v): (Scalar, Scalar)) -> Vector {
+    let z = u;
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * PI * v;
+    around(normal, r * phi.cos(), r * phi.sin(), z)
+}
+
+/// Maps a position in the unit square (e.g. from a [`Sampler`]) to a direction in the hemisphere around ```normal``` (which must be normalized).
+/// Directions close to the normal are more likely (cosine weighted), as they contribute more to lighting.
+pub fn cosine_hemisphere(normal: Vector, sample: (Scalar, Scalar)) -> Vector {
+    let (x, y) = concentric_disk(sample);
+    let z = (1.0 - x * x - y * y).max(0.0).sqrt();
+    around(normal, x, y, z)
+}
+
+/// The direction with the coordinates ```x```, ```y``` and ```z``` in a space where ```normal``` is the z axis
+fn around(normal: Vector, x: Scalar, y: Scalar, z: Scalar) -> Vector {
+    let helper = if normal.x.abs() > 0.9 {
+        Vector::new(0, 1, 0)
+    } else {
+        Vector::new(1, 0, 0)
+    };
+    let tangent = normal.cross(helper).normalized();
+    let bitangent = normal.cross(tangent);
+
+    tangent * x + bitangent * y + normal * z
+}
+
+thread_local! {
+    /// The seed of the render running on the current thread, see [`with_seed`]
+    static RENDER_SEED: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Runs ```f``` with ```seed``` as the seed of the current render, so the random numbers drawn at shaded points
+/// (light sampling, ambient occlusion, global illumination) derive from it, see [`crate::camera::Camera::seed`].
+pub(crate) fn with_seed<T>(seed: u64, f: impl FnOnce() -> T) -> T {
+    let previous = RENDER_SEED.with(|render_seed| render_seed.replace(seed));
+    let result = f();
+    RENDER_SEED.with(|render_seed| render_seed.set(previous));
+    result
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// How a [`Sampler`] spreads its samples
+pub enum SamplingStrategy {
+    /// Every sample is placed randomly on its own. Samples may clump, which shows up as noise.
+    Random,
+    /// The samples are spread evenly: each gets its own cell of a grid and is placed randomly within it.
+    /// This gives considerably less noise for the same number of samples.
+    #[default]
+    Stratified,
+}
+
+/// Generates sample positions for stochastic effects like anti-aliasing, using a seedable [`Rng`] and a [`SamplingStrategy`].
+///
+/// The same strategy and seed always generate the same samples, so renders are reproducible.
+#[derive(Copy, Clone, Debug)]
+pub struct Sampler {
+    strategy: SamplingStrategy,
+    rng: Rng,
+}
+
+impl Sampler {
+    /// A sampler using ```strategy```, starting from the given seed
+    pub fn new(strategy: SamplingStrategy, seed: u64) -> Self {
+        Self {
+            strategy,
+            rng: Rng::new(seed),
+        }
+    }
+
+    /// The strategy of this sampler
+    pub fn strategy(&self) -> SamplingStrategy {
+        self.strategy
+    }
+
+    /// ```n``` sample positions in the unit square, each coordinate in [0, 1).
+    ///
+    /// Stratified samples use an evenly divided grid if ```n``` is a square number.
+    /// Otherwise, every row and every column of an n×n grid gets exactly one sample (a latin hypercube), which still spreads them evenly along both axes.
+    pub fn unit_square(&mut self, n: usize) -> Vec<(Scalar, Scalar)> {
+        match self.strategy {
+            SamplingStrategy::Random => (0..n)
+                .map(|_| (self.rng.next_scalar(), self.rng.next_scalar()))
+                .collect(),
+            SamplingStrategy::Stratified => {
+                let side = (n as Scalar).sqrt().round() as usize;
+                if side * side == n {
+                    self.jittered_grid(side)
+                } else {
+                    self.latin_hypercube(n)
+                }
+            }
+        }
+    }
+
+    fn jittered_grid(&mut self, side: usize) -> Vec<(Scalar, Scalar)> {
+        let cell = 1.0 / side as Scalar;
+        let mut samples = Vec::with_capacity(side * side);
+        for y in 0..side {
+            for x in 0..side {
+                let sample_x = (x as Scalar + self.rng.next_scalar()) * cell;
+                let sample_y = (y as Scalar + self.rng.next_scalar()) * cell;
+                samples.push((sample_x, sample_y));
+            }
+        }
+        samples
+    }
+
+    fn latin_hypercube(&mut self, n: usize) -> Vec<(Scalar, Scalar)> {
+        let mut rows: Vec<usize> = (0..n).collect();
+        // Fisher-Yates shuffle
+        for i in (1..n).rev() {
+            let j = (self.rng.next_u64() % (i as u64 + 1)) as usize;
+            rows.swap(i, j);
+        }
+        let cell = 1.0 / n as Scalar;
+        rows.iter()
+            .enumerate()
+            .map(|(column, &row)| {
+                (
+                    (column as Scalar + self.rng.next_scalar()) * cell,
+                    (row as Scalar + self.rng.next_scalar()) * cell,
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod sampler_tests {
+    use crate::sampling::{Sampler, SamplingStrategy};
+
+    #[test]
+    fn reproducible() {
+        for strategy in [SamplingStrategy::Random, SamplingStrategy::Stratified] {
+            let a = Sampler::new(strategy, 3).unit_square(7);
+            let b = Sampler::new(strategy, 3).unit_square(7);
+            assert_eq!(a, b);
+            assert_ne!(a, Sampler::new(strategy, 4).unit_square(7));
+        }
+    }
+
+    #[test]
+    fn stratified_grid() {
+        let samples = Sampler::new(SamplingStrategy::Stratified, 1).unit_square(16);
+        assert_eq!(samples.len(), 16);
+        for (i, (x, y)) in samples.iter().enumerate() {
+            assert_eq!((x * 4.0) as usize, i % 4);
+            assert_eq!((y * 4.0) as usize, i / 4);
+        }
+    }
+
+    #[test]
+    fn stratified_latin_hypercube() {
+        let samples = Sampler::new(SamplingStrategy::Stratified, 1).unit_square(5);
+        assert_eq!(samples.len(), 5);
+        let mut columns: Vec<usize> = samples.iter().map(|(x, _)| (x * 5.0) as usize).collect();
+        let mut rows: Vec<usize> = samples.iter().map(|(_, y)| (y * 5.0) as usize).collect();
+        columns.sort();
+        rows.sort();
+        assert_eq!(columns, vec![0, 1, 2, 3, 4]);
+        assert_eq!(rows, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn random_in_unit_square() {
+        let samples = Sampler::new(SamplingStrategy::Random, 9).unit_square(100);
+        assert_eq!(samples.len(), 100);
+        assert!(samples
+            .iter()
+            .all(|&(x, y)| (0.0..1.0).contains(&x) && (0.0..1.0).contains(&y)));
+    }
+}
+
+#[cfg(test)]
+mod direction_tests {
+    use crate::{
+        epsilon::EpsilonEqual,
+        sampling::{
+            concentric_disk, cosine_hemisphere, uniform_hemisphere, uniform_sphere, Sampler,
+            SamplingStrategy,
+        },
+        scalar::Scalar,
+        tuple::Vector,
+    };
+
+    fn samples() -> Vec<(Scalar, Scalar)> {
+        Sampler::new(SamplingStrategy::Stratified, 5).unit_square(1024)
+    }
+
+    #[test]
+    fn disk() {
+        assert_eq!(concentric_disk((0.5, 0.5)), (0.0, 0.0));
+        let (x, y) = concentric_disk((1.0, 0.5));
+        assert!(x.e_equals(1.0) && y.e_equals(0.0));
+        let (x, y) = concentric_disk((0.5, 0.0));
+        assert!(x.e_equals(0.0) && y.e_equals(-1.0));
+        let points: Vec<(Scalar, Scalar)> = samples().into_iter().map(concentric_disk).collect();
+        assert!(points.iter().all(|(x, y)| x.hypot(*y) <= 1.0 + 1e-6));
+        // evenly spread: as many points in the inner half of the area as in the outer half
+        let inner = points
+            .iter()
+            .filter(|(x, y)| x.hypot(*y) < Scalar::sqrt(0.5))
+            .count();
+        assert!((inner as Scalar / 1024.0 - 0.5).abs() < 0.02);
+    }
+
+    #[test]
+    fn sphere() {
+        let directions: Vec<Vector> = samples().into_iter().map(uniform_sphere).collect();
+        assert!(directions.iter().all(|d| d.magnitude().e_equals(1.0)));
+        let mean: Vector = directions.iter().copied().sum::<Vector>() / 1024.0;
+        assert!(mean.magnitude() < 0.02);
+    }
+
+    #[test]
+    fn hemispheres() {
+        let normal = Vector::new(0, 1, 1).normalized();
+        for (sample, mean_cosine) in [
+            (
+                uniform_hemisphere as fn(Vector, (Scalar, Scalar)) -> Vector,
+                0.5,
+            ),
+            (cosine_hemisphere, 2.0 / 3.0),
+        ] {
+            let cosines: Vec<Scalar> = samples()
+                .into_iter()
+                .map(|s| sample(normal, s))
+                .map(|d| {
+                    assert!(d.magnitude().e_equals(1.0));
+                    d.dot(normal)
+                })
+                .collect();
+            assert!(cosines.iter().all(|&c| c >= -1e-6));
+            let mean = cosines.iter().sum::<Scalar>() / 1024.0;
+            assert!((mean - mean_cosine).abs() < 0.02);
+        }
+    }
+}
+
+#[cfg(test)]
+mod rng_tests {
+    use crate::{
+        sampling::{with_seed, Rng},
+        tuple::{Point, Vector},
+    };
+
+    #[test]
+    fn deterministic() {
+        let mut a = Rng::from_point(Point::new(1, 2, 3), 0);
+        let mut b = Rng::from_point(Point::new(1, 2, 3), 0);
+        for _ in 0..10 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+        let mut c = Rng::from_point(Point::new(1, 2, 3), 1);
+        assert_ne!(
+            Rng::from_point(Point::new(1, 2, 3), 0).next_u64(),
+            c.next_u64()
+        );
+    }
+
+    #[test]
+    fn render_seed() {
+        let p = Point::new(1, 2, 3);
+        let unseeded = Rng::from_point(p, 0).next_u64();
+        let seeded = with_seed(7, || Rng::from_point(p, 0).next_u64());
+        assert_ne!(seeded, unseeded);
+        assert_eq!(with_seed(7, || Rng::from_point(p, 0).next_u64()), seeded);
+        // the previous seed is restored afterwards
+        assert_eq!(Rng::from_point(p, 0).next_u64(), unseeded);
+    }
+
+    #[test]
+    fn floats_in_unit_interval() {
+        let mut rng = Rng::new(42);
+        let mut sum = 0.0;
+        for _ in 0..10_000 {
+            let f = rng.next_scalar();
+            assert!((0.0..1.0).contains(&f));
+            sum += f;
+        }
+        assert!((sum / 10_000.0 - 0.5).abs() < 0.02);
+    }
+
+    #[test]
+    fn hemisphere_directions() {
+        let mut rng = Rng::new(7);
+        let normal = Vector::new(1, 1, 0).normalized();
+        for _ in 0..1000 {
+            let d = rng.next_hemisphere_direction(normal);
+            let tolerance = if cfg!(feature = "f32") { 1e-6 } else { 1e-9 };
+            assert!((d.magnitude() - 1.0).abs() < tolerance);
+            assert!(d.dot(normal) >= 0.0);
+        }
+    }
+}
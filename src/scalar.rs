@@ -0,0 +1,36 @@
+//! The floating point type of all computations: ```f64```, or ```f32``` with the "f32" feature.
+//! f32 halves the memory of large triangle meshes and is what GPUs and SIMD paths work with, at the cost of precision.
+
+#[cfg(not(feature = "f32"))]
+/// The floating point type of all computations
+pub type Scalar = f64;
+#[cfg(feature = "f32")]
+/// The floating point type of all computations
+pub type Scalar = f32;
+
+#[cfg(feature = "f32")]
+pub use std::f32::consts;
+#[cfg(not(feature = "f32"))]
+pub use std::f64::consts;
+
+/// Numbers that can be converted to a [`Scalar`], so constructors like [`crate::tuple::Point::new`] take integers as well.
+/// Converting ```f64``` to ```f32``` loses precision.
+pub trait IntoScalar {
+    /// Converts the number to a [`Scalar`]
+    fn into_scalar(self) -> Scalar;
+}
+
+macro_rules! impl_into_scalar {
+    ($($t:ty),*) => {
+        $(
+            impl IntoScalar for $t {
+                #[inline]
+                fn into_scalar(self) -> Scalar {
+                    self as Scalar
+                }
+            }
+        )*
+    };
+}
+
+impl_into_scalar!(i8, i16, i32, u8, u16, u32, f32, f64);
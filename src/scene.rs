@@ -0,0 +1,211 @@
+//! A scene graph over a [`World`]: named nodes with a transformation, holding objects, lights and other nodes.
+//! Moving a node moves everything below it, e.g. a table node carries its top and legs.
+use crate::{
+    light::Light,
+    matrix::{Mat4, IDENTITY_MATRIX_4},
+    shapes::shape::Shape,
+    world::World,
+};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+/// Identifies a node of a [`Scene`], returned by [`Scene::add_node`]
+pub struct NodeId(usize);
+
+#[derive(Debug)]
+struct Node {
+    name: String,
+    parent: Option<NodeId>,
+    transform: Mat4,
+    /// Indices into the objects of the world, with the transformation of the object relative to the node
+    objects: Vec<(usize, Mat4)>,
+    /// Indices into the lights of the world, with the transformation last applied to the light
+    lights: Vec<(usize, Mat4)>,
+}
+
+#[derive(Debug)]
+/// A world whose objects and lights are organized in a tree of named nodes.
+///
+/// Objects keep their own transformation relative to their node, and the node transformations are applied on top,
+/// from the node up to the root. Changes are applied to the world by [`Self::world`], right before rendering it.
+/// Objects and lights already in the world when the scene is created do not belong to any node and stay where they are.
+pub struct Scene {
+    world: World,
+    nodes: Vec<Node>,
+    changed: bool,
+}
+
+impl Scene {
+    /// A scene without nodes around ```world```, which holds the settings (like the skybox) and objects not belonging to any node
+    pub fn new(world: World) -> Self {
+        Self {
+            world,
+            nodes: Vec::new(),
+            changed: false,
+        }
+    }
+
+    /// Adds an empty node below ```parent```, or at the root if None
+    pub fn add_node(&mut self, name: &str, parent: Option<NodeId>) -> NodeId {
+        self.nodes.push(Node {
+            name: name.to_string(),
+            parent,
+            transform: IDENTITY_MATRIX_4,
+            objects: Vec::new(),
+            lights: Vec::new(),
+        });
+        NodeId(self.nodes.len() - 1)
+    }
+
+    /// The first node added with the given name
+    pub fn node(&self, name: &str) -> Option<NodeId> {
+        self.nodes
+            .iter()
+            .position(|node| node.name == name)
+            .map(NodeId)
+    }
+
+    /// The name of a node
+    pub fn name(&self, node: NodeId) -> &str {
+        &self.nodes[node.0].name
+    }
+
+    /// The nodes directly below ```node```
+    pub fn children(&self, node: NodeId) -> Vec<NodeId> {
+        (0..self.nodes.len())
+            .filter(|&i| self.nodes[i].parent == Some(node))
+            .map(NodeId)
+            .collect()
+    }
+
+    /// Adds an object to a node. Its own transformation is relative to the node.
+    pub fn add_object(&mut self, node: NodeId, object: Box<dyn Shape>) {
+        let local = object.transformation_matrix();
+        self.world.add_object(object);
+        let index = self.world.objects().len() - 1;
+        self.nodes[node.0].objects.push((index, local));
+        self.changed = true;
+    }
+
+    /// Adds a light to a node. Its position is relative to the node.
+    pub fn add_light(&mut self, node: NodeId, light: Box<dyn Light>) {
+        self.world.add_light(light);
+        let index = self.world.lights().len() - 1;
+        self.nodes[node.0].lights.push((index, IDENTITY_MATRIX_4));
+        self.changed = true;
+    }
+
+    /// The transformation of a node relative to its parent
+    pub fn transform(&self, node: NodeId) -> Mat4 {
+        self.nodes[node.0].transform
+    }
+
+    /// Sets the transformation of a node relative to its parent, moving everything below it
+    pub fn set_transform(&mut self, node: NodeId, transform: Mat4) {
+        self.nodes[node.0].transform = transform;
+        self.changed = true;
+    }
+
+    /// The transformation of a node in world space, i.e. including the transformations of all nodes above it
+    pub fn world_transform(&self, node: NodeId) -> Mat4 {
+        let node = &self.nodes[node.0];
+        match node.parent {
+            Some(parent) => self.world_transform(parent) * node.transform,
+            None => node.transform,
+        }
+    }
+
+    /// The world with all objects and lights placed according to their nodes, ready to render
+    pub fn world(&mut self) -> &World {
+        if self.changed {
+            self.flatten();
+            self.changed = false;
+        }
+        &self.world
+    }
+
+    /// Ends the scene graph, returning the world with everything where it currently is
+    pub fn into_world(mut self) -> World {
+        self.world();
+        self.world
+    }
+
+    fn flatten(&mut self) {
+        for node in 0..self.nodes.len() {
+            let transform = self.world_transform(NodeId(node));
+            for &(index, local) in &self.nodes[node].objects {
+                self.world.objects_mut()[index].set_transformation_matrix(transform * local);
+            }
+            for (index, applied) in &mut self.nodes[node].lights {
+                // lights can only be moved relative to where they are, so undo the last placement first
+                self.world.lights_mut()[*index].transform(transform * applied.inverse());
+                *applied = transform;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod scene_tests {
+    use crate::{
+        color::WHITE,
+        light::PointLight,
+        matrix::Mat4,
+        ray::Ray,
+        scalar::Scalar,
+        scene::Scene,
+        shapes::sphere::Sphere,
+        transform::Transform,
+        tuple::{Point, Vector},
+        world::World,
+    };
+
+    fn hits(world: &World, r: &Ray) -> Vec<Scalar> {
+        let mut xs = Vec::new();
+        world.intersect(r, &mut xs);
+        let mut ts: Vec<Scalar> = xs.iter().map(|x| x.t).collect();
+        ts.sort_by(Scalar::total_cmp);
+        ts
+    }
+
+    #[test]
+    fn moving_a_node_moves_its_children() {
+        let mut scene = Scene::new(World::default());
+        let room = scene.add_node("room", None);
+        let table = scene.add_node("table", Some(room));
+        scene.add_object(table, Box::new(Sphere::default().translated(0, 1, 0)));
+        scene.add_object(table, Box::new(Sphere::default().translated(0, -1, 0)));
+        assert_eq!(scene.node("table"), Some(table));
+        assert_eq!(scene.children(room), vec![table]);
+
+        let r = Ray::new(Point::new(5, 1, -5), Vector::new(0, 0, 1));
+        assert!(hits(scene.world(), &r).is_empty());
+
+        scene.set_transform(table, Mat4::new_translation(5, 0, 0));
+        assert_eq!(hits(scene.world(), &r), vec![4.0, 6.0]);
+        assert_eq!(
+            scene.world().objects()[1].transformation_matrix(),
+            Mat4::new_translation(5, -1, 0)
+        );
+
+        // both transformations apply
+        scene.set_transform(room, Mat4::new_translation(0, 0, 2));
+        assert_eq!(hits(scene.world(), &r), vec![6.0, 8.0]);
+        assert_eq!(scene.world_transform(table), Mat4::new_translation(5, 0, 2));
+    }
+
+    #[test]
+    fn lights_follow_their_node() {
+        let mut scene = Scene::new(World::default());
+        let lamp = scene.add_node("lamp", None);
+        scene.add_light(lamp, Box::new(PointLight::new(Point::new(0, 2, 0), WHITE)));
+        scene.set_transform(lamp, Mat4::new_translation(10, 0, 0));
+        scene.world();
+        scene.set_transform(lamp, Mat4::new_translation(0, 0, 3));
+
+        let world = scene.into_world();
+        assert_eq!(
+            world.lights()[0].direction_from(Point::new(0, 0, 3)),
+            Vector::new(0, 1, 0)
+        );
+    }
+}
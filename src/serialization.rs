@@ -0,0 +1,668 @@
+//! Serialize and Deserialize for the types that cannot simply derive them: trait objects, and types caching inverted matrices.
+//!
+//! Shapes and lights are written with a ```type``` field naming the concrete type, so only the shapes and lights of this crate can be serialized.
+//! Closures (custom patterns, textures, cube maps, decals and bump maps) cannot be serialized either, serializing them fails.
+use serde::{
+    de::Error as _, ser::Error as _, ser::SerializeStruct, Deserialize, Deserializer, Serialize,
+    Serializer,
+};
+
+use crate::{
+    camera::{Camera, Integrator},
+    color::Color,
+    lens::DepthOfField,
+    light::{DirectionalLight, Light, LightLinking, PointLight, SpotLight},
+    material::Material,
+    matrix::{Matrix, IDENTITY_MATRIX_4},
+    pattern::{Pattern, PatternKind},
+    render_pass::RenderPasses,
+    sampling::SamplingStrategy,
+    scalar::Scalar,
+    settings::RenderSettings,
+    shapes::{
+        plane::Plane,
+        shape::{ObjectId, Shape},
+        sphere::Sphere,
+        triangle_mesh::TriangleMesh,
+        volume::Volume,
+    },
+    skybox::Skybox,
+    tuple::{Point, Vector},
+    world::{AmbientLight, AmbientOcclusion, Fog, GlobalIllumination, World},
+};
+
+use crate::matrix::Mat4;
+
+/// Fails serializing fields that hold closures
+pub(crate) fn unsupported<T, S: Serializer>(_: &T, _: S) -> Result<S::Ok, S::Error> {
+    Err(S::Error::custom(
+        "closures (custom patterns, textures, decals and bump maps) cannot be serialized",
+    ))
+}
+
+/// Skips infinite numbers, which JSON cannot hold. They must be the default of their field.
+pub(crate) fn is_infinite(value: &Scalar) -> bool {
+    value.is_infinite()
+}
+
+fn identity() -> Mat4 {
+    IDENTITY_MATRIX_4
+}
+
+/// Matrices are written as a list of rows
+impl<const SIZE: usize> Serialize for Matrix<SIZE> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq((0..SIZE).map(|row| self[row].as_slice()))
+    }
+}
+
+impl<'de, const SIZE: usize> Deserialize<'de> for Matrix<SIZE> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let rows = Vec::<Vec<Scalar>>::deserialize(deserializer)?;
+        if rows.len() != SIZE || rows.iter().any(|row| row.len() != SIZE) {
+            return Err(D::Error::custom(format!(
+                "expected a matrix of {SIZE} rows with {SIZE} values each"
+            )));
+        }
+        let mut matrix = Matrix::new_empty();
+        for (row, values) in rows.iter().enumerate() {
+            matrix[row].copy_from_slice(values);
+        }
+        Ok(matrix)
+    }
+}
+
+impl Serialize for Pattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Pattern", 2)?;
+        s.serialize_field("kind", &self.kind)?;
+        s.serialize_field("transformation_matrix", &self.transformation_matrix())?;
+        s.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Pattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct PatternData {
+            kind: PatternKind,
+            #[serde(default = "identity")]
+            transformation_matrix: Mat4,
+        }
+        let data = PatternData::deserialize(deserializer)?;
+        let mut pattern = Pattern::from(data.kind);
+        pattern.set_transformation_matrix(data.transformation_matrix);
+        Ok(pattern)
+    }
+}
+
+impl Serialize for Skybox {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Skybox", 2)?;
+        s.serialize_field("color", self.color())?;
+        s.serialize_field("transformation_matrix", &self.transformation_matrix())?;
+        s.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Skybox {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct SkyboxData {
+            color: crate::material::ColorType,
+            #[serde(default = "identity")]
+            transformation_matrix: Mat4,
+        }
+        let data = SkyboxData::deserialize(deserializer)?;
+        let mut skybox = Skybox::new(data.color);
+        skybox.set_transformation_matrix(data.transformation_matrix);
+        Ok(skybox)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+enum ShapeKind {
+    Sphere,
+    Plane,
+    TriangleMesh,
+    Volume,
+}
+
+/// Shapes are written as one flat struct, the fields not used by their ```type``` left out.
+/// Enums tagged by a field or flattened structs are avoided on purpose, as serde_yaml cannot read enums (e.g. ```!Pattern```) within them.
+#[derive(Serialize, Deserialize)]
+struct ShapeData {
+    #[serde(rename = "type")]
+    kind: ShapeKind,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    vertices: Vec<Point>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    faces: Vec<[usize; 3]>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    normals: Vec<Vector>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    face_normals: Vec<[usize; 3]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    boundary: Option<Box<ShapeData>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    density: Option<Scalar>,
+    #[serde(default = "identity")]
+    transformation_matrix: Mat4,
+    #[serde(default)]
+    material: Material,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    back_material: Option<Material>,
+    #[serde(default)]
+    one_sided: bool,
+    #[serde(default)]
+    normal_flipped: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<ObjectId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    shadow_bias: Option<Scalar>,
+}
+
+impl ShapeData {
+    fn of(shape: &dyn Shape) -> Result<Self, String> {
+        let mut data = ShapeData {
+            kind: ShapeKind::Sphere,
+            vertices: Vec::new(),
+            faces: Vec::new(),
+            normals: Vec::new(),
+            face_normals: Vec::new(),
+            boundary: None,
+            density: None,
+            transformation_matrix: shape.transformation_matrix(),
+            material: shape.material().clone(),
+            back_material: shape.back_material().cloned(),
+            one_sided: shape.one_sided(),
+            normal_flipped: shape.normal_flipped(),
+            id: shape.id(),
+            shadow_bias: shape.shadow_bias(),
+        };
+        let any = shape.as_any();
+        if any.is::<Sphere>() {
+            data.kind = ShapeKind::Sphere;
+        } else if any.is::<Plane>() {
+            data.kind = ShapeKind::Plane;
+        } else if let Some(mesh) = any.downcast_ref::<TriangleMesh>() {
+            data.kind = ShapeKind::TriangleMesh;
+            data.vertices = mesh.vertices().to_vec();
+            data.faces = mesh.faces().to_vec();
+            data.normals = mesh.normals().to_vec();
+            data.face_normals = mesh.face_normals().to_vec();
+        } else if let Some(volume) = any.downcast_ref::<Volume>() {
+            data.kind = ShapeKind::Volume;
+            data.boundary = Some(Box::new(Self::of(volume.boundary())?));
+            data.density = Some(volume.density());
+        } else {
+            return Err(format!(
+                "cannot serialize the shape {shape:?}, only the shapes of this crate can be serialized"
+            ));
+        }
+        Ok(data)
+    }
+
+    fn into_shape(self) -> Result<Box<dyn Shape>, String> {
+        let mut shape: Box<dyn Shape> = match self.kind {
+            ShapeKind::Sphere => Box::new(Sphere::default()),
+            ShapeKind::Plane => Box::new(Plane::default()),
+            ShapeKind::TriangleMesh => {
+                if self
+                    .faces
+                    .iter()
+                    .flatten()
+                    .any(|&i| i >= self.vertices.len())
+                {
+                    return Err("a face of the mesh references a vertex that does not exist".into());
+                }
+                if !self.face_normals.is_empty() && self.face_normals.len() != self.faces.len()
+                    || self
+                        .face_normals
+                        .iter()
+                        .flatten()
+                        .any(|&i| i >= self.normals.len())
+                {
+                    return Err("the normals of the mesh do not match its faces".into());
+                }
+                let mut mesh = TriangleMesh::new(self.vertices, self.faces);
+                mesh.set_normals(self.normals, self.face_normals);
+                Box::new(mesh)
+            }
+            ShapeKind::Volume => {
+                let (Some(boundary), Some(density)) = (self.boundary, self.density) else {
+                    return Err("a volume needs a boundary and a density".into());
+                };
+                Box::new(Volume::new(
+                    boundary.into_shape()?,
+                    density,
+                    Color::new(1, 1, 1),
+                ))
+            }
+        };
+        shape.set_transformation_matrix(self.transformation_matrix);
+        shape.set_material(self.material);
+        shape.set_back_material(self.back_material);
+        shape.set_one_sided(self.one_sided);
+        shape.set_normal_flipped(self.normal_flipped);
+        shape.set_id(self.id);
+        shape.set_shadow_bias(self.shadow_bias);
+        Ok(shape)
+    }
+}
+
+impl Serialize for dyn Shape {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ShapeData::of(self)
+            .map_err(S::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Box<dyn Shape> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        ShapeData::deserialize(deserializer)?
+            .into_shape()
+            .map_err(D::Error::custom)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+// the variants are named after the lights, as the names are written to the type field
+#[allow(clippy::enum_variant_names)]
+enum LightKind {
+    PointLight,
+    SpotLight,
+    DirectionalLight,
+}
+
+fn yes() -> bool {
+    true
+}
+
+/// Lights are written as one flat struct like shapes, see [`ShapeData`]
+#[derive(Serialize, Deserialize)]
+struct LightData {
+    #[serde(rename = "type")]
+    kind: LightKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    position: Option<Point>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    direction: Option<Vector>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    inner_angle: Option<Scalar>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    outer_angle: Option<Scalar>,
+    intensity: Color,
+    #[serde(default)]
+    linking: LightLinking,
+    #[serde(default = "yes")]
+    diffuse: bool,
+    #[serde(default = "yes")]
+    specular: bool,
+    #[serde(default)]
+    inverse_square: bool,
+}
+
+impl Serialize for dyn Light {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let any = self.as_any();
+        let data = if let Some(light) = any.downcast_ref::<PointLight>() {
+            LightData {
+                kind: LightKind::PointLight,
+                position: Some(light.position),
+                direction: None,
+                inner_angle: None,
+                outer_angle: None,
+                intensity: light.intensity,
+                linking: light.linking.clone(),
+                diffuse: light.diffuse,
+                specular: light.specular,
+                inverse_square: light.inverse_square,
+            }
+        } else if let Some(light) = any.downcast_ref::<SpotLight>() {
+            LightData {
+                kind: LightKind::SpotLight,
+                position: Some(light.position),
+                direction: Some(light.direction),
+                inner_angle: Some(light.inner_angle),
+                outer_angle: Some(light.outer_angle),
+                intensity: light.intensity,
+                linking: light.linking.clone(),
+                diffuse: light.diffuse,
+                specular: light.specular,
+                inverse_square: light.inverse_square,
+            }
+        } else if let Some(light) = any.downcast_ref::<DirectionalLight>() {
+            LightData {
+                kind: LightKind::DirectionalLight,
+                position: None,
+                direction: Some(light.direction),
+                inner_angle: None,
+                outer_angle: None,
+                intensity: light.intensity,
+                linking: light.linking.clone(),
+                diffuse: light.diffuse,
+                specular: light.specular,
+                inverse_square: false,
+            }
+        } else {
+            return Err(S::Error::custom(format!(
+                "cannot serialize the light {self:?}, only the lights of this crate can be serialized"
+            )));
+        };
+        data.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Box<dyn Light> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = LightData::deserialize(deserializer)?;
+        let missing = |field: &str| D::Error::custom(format!("the light needs a {field}"));
+        Ok(match data.kind {
+            LightKind::PointLight => Box::new(PointLight {
+                linking: data.linking,
+                diffuse: data.diffuse,
+                specular: data.specular,
+                inverse_square: data.inverse_square,
+                ..PointLight::new(
+                    data.position.ok_or_else(|| missing("position"))?,
+                    data.intensity,
+                )
+            }),
+            LightKind::SpotLight => Box::new(SpotLight {
+                linking: data.linking,
+                diffuse: data.diffuse,
+                specular: data.specular,
+                inverse_square: data.inverse_square,
+                ..SpotLight::new(
+                    data.position.ok_or_else(|| missing("position"))?,
+                    data.direction.ok_or_else(|| missing("direction"))?,
+                    data.inner_angle.ok_or_else(|| missing("inner_angle"))?,
+                    data.outer_angle.ok_or_else(|| missing("outer_angle"))?,
+                    data.intensity,
+                )
+            }),
+            LightKind::DirectionalLight => Box::new(DirectionalLight {
+                linking: data.linking,
+                diffuse: data.diffuse,
+                specular: data.specular,
+                ..DirectionalLight::new(
+                    data.direction.ok_or_else(|| missing("direction"))?,
+                    data.intensity,
+                )
+            }),
+        })
+    }
+}
+
+/// Worlds are written with their objects, lights and settings. Caches are rebuilt after reading.
+impl Serialize for World {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("World", 8)?;
+        s.serialize_field("objects", self.objects())?;
+        s.serialize_field("lights", self.lights())?;
+        s.serialize_field("skybox", &self.skybox())?;
+        s.serialize_field("light_samples", &self.light_samples())?;
+        s.serialize_field("ambient_light", &self.ambient_light())?;
+        s.serialize_field("ambient_occlusion", &self.ambient_occlusion())?;
+        s.serialize_field("global_illumination", &self.global_illumination())?;
+        s.serialize_field("fog", &self.fog())?;
+        s.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for World {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct WorldData {
+            #[serde(default)]
+            objects: Vec<Box<dyn Shape>>,
+            #[serde(default)]
+            lights: Vec<Box<dyn Light>>,
+            #[serde(default)]
+            skybox: Option<Skybox>,
+            #[serde(default)]
+            light_samples: Option<usize>,
+            #[serde(default)]
+            ambient_light: AmbientLight,
+            #[serde(default)]
+            ambient_occlusion: Option<AmbientOcclusion>,
+            #[serde(default)]
+            global_illumination: Option<GlobalIllumination>,
+            #[serde(default)]
+            fog: Option<Fog>,
+        }
+        let mut data = WorldData::deserialize(deserializer)?;
+        let mut world = World::default();
+        world.add_objects(&mut data.objects);
+        world.add_lights(&mut data.lights);
+        if let Some(skybox) = data.skybox {
+            world.set_skybox(skybox);
+        }
+        world.set_light_samples(data.light_samples);
+        world.set_ambient_light(data.ambient_light);
+        world.set_ambient_occlusion(data.ambient_occlusion);
+        world.set_global_illumination(data.global_illumination);
+        world.set_fog(data.fog);
+        Ok(world)
+    }
+}
+
+impl Serialize for Camera {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Camera", 15)?;
+        s.serialize_field("hsize", &self.hsize)?;
+        s.serialize_field("vsize", &self.vsize)?;
+        s.serialize_field("field_of_view", &self.field_of_view)?;
+        s.serialize_field("transform", &self.transform())?;
+        s.serialize_field("exposure_value", &self.exposure_value)?;
+        s.serialize_field("exposure_compensation", &self.exposure_compensation)?;
+        s.serialize_field("white_balance", &self.white_balance)?;
+        s.serialize_field("sampling", &self.sampling)?;
+        s.serialize_field("depth_of_field", &self.depth_of_field)?;
+        s.serialize_field("max_sample_radiance", &self.max_sample_radiance)?;
+        s.serialize_field("seed", &self.seed)?;
+        s.serialize_field("passes", &self.passes)?;
+        s.serialize_field("settings", &self.settings)?;
+        s.serialize_field("integrator", &self.integrator)?;
+        s.end()
+    }
+}
+
+/// Only the size and field of view are required, everything else keeps the defaults of [`Camera::new`] if missing
+impl<'de> Deserialize<'de> for Camera {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct CameraData {
+            hsize: usize,
+            vsize: usize,
+            field_of_view: Scalar,
+            transform: Option<Mat4>,
+            exposure_value: Option<Scalar>,
+            exposure_compensation: Option<Scalar>,
+            white_balance: Option<Color>,
+            sampling: Option<SamplingStrategy>,
+            depth_of_field: Option<DepthOfField>,
+            max_sample_radiance: Option<Scalar>,
+            seed: Option<u64>,
+            passes: Option<RenderPasses>,
+            #[serde(default)]
+            settings: RenderSettings,
+            integrator: Option<Integrator>,
+        }
+        let data = CameraData::deserialize(deserializer)?;
+        let mut camera = Camera::new(data.hsize, data.vsize, data.field_of_view);
+        if let Some(transform) = data.transform {
+            camera.set_transform(transform);
+        }
+        camera.exposure_value = data.exposure_value;
+        camera.depth_of_field = data.depth_of_field;
+        camera.max_sample_radiance = data.max_sample_radiance;
+        camera.exposure_compensation = data
+            .exposure_compensation
+            .unwrap_or(camera.exposure_compensation);
+        camera.white_balance = data.white_balance.unwrap_or(camera.white_balance);
+        camera.sampling = data.sampling.unwrap_or(camera.sampling);
+        camera.seed = data.seed.unwrap_or(camera.seed);
+        camera.passes = data.passes.unwrap_or(camera.passes);
+        camera.settings = data.settings;
+        camera.integrator = data.integrator.unwrap_or(camera.integrator);
+        Ok(camera)
+    }
+}
+
+#[cfg(test)]
+mod serialization_tests {
+    use crate::{
+        camera::{Camera, Integrator},
+        color::{Color, WHITE},
+        light::{PointLight, SpotLight},
+        material::ColorType,
+        matrix::Mat4,
+        pattern::{pattern_function, Pattern},
+        ray::Ray,
+        scalar::consts::PI,
+        settings::RenderSettings,
+        shapes::{
+            plane::Plane, shape::Shape, sphere::Sphere, triangle_mesh::TriangleMesh, volume::Volume,
+        },
+        transform::Transform,
+        tuple::{Point, Vector},
+        world::{AmbientLight, Fog, World},
+    };
+
+    fn round_trip<T: serde::Serialize + serde::de::DeserializeOwned>(value: &T) -> T {
+        serde_json::from_str(&serde_json::to_string(value).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn matrix() {
+        let m = Mat4::new_translation(1, 2, 3) * Mat4::new_rotation_y(0.5);
+        assert_eq!(round_trip(&m), m);
+        assert_eq!(
+            serde_json::to_string(&Mat4::new_scaling(2, 2, 2)).unwrap(),
+            "[[2.0,0.0,0.0,0.0],[0.0,2.0,0.0,0.0],[0.0,0.0,2.0,0.0],[0.0,0.0,0.0,1.0]]"
+        );
+        assert!(serde_json::from_str::<Mat4>("[[1.0]]").is_err());
+    }
+
+    #[test]
+    fn pattern() {
+        let p =
+            Pattern::stripe(WHITE, Pattern::checker(WHITE, Color::new(1, 0, 0))).scaled(2, 2, 2);
+        let read = round_trip(&p);
+        assert_eq!(read.transformation_matrix(), p.transformation_matrix());
+        for point in [Point::new(0.5, 0.0, 0.0), Point::new(2.5, 0.3, 0.7)] {
+            assert_eq!(read.apply_pattern(point), p.apply_pattern(point));
+        }
+    }
+
+    #[test]
+    fn closures_fail() {
+        let mut sphere = Sphere::default();
+        sphere.material_mut().color =
+            ColorType::Pattern(Pattern::from(pattern_function(|_| WHITE)));
+        let w = World::builder().object(sphere).build();
+        let error = serde_json::to_string(&w).unwrap_err();
+        assert!(error.to_string().contains("Custom"), "{error}");
+    }
+
+    #[test]
+    fn world() {
+        let mut mesh = TriangleMesh::new(
+            vec![
+                Point::new(0, 1, 0),
+                Point::new(-1, 0, 0),
+                Point::new(1, 0, 0),
+            ],
+            vec![[0, 1, 2]],
+        );
+        mesh.recompute_smooth_normals(None);
+        let mut sphere = Sphere::default().translated(0, 1, 0);
+        sphere.material_mut().color = ColorType::Pattern(Pattern::ring(WHITE, Color::new(0, 0, 1)));
+        sphere.material_mut().reflective = 0.5;
+        let mut w = World::builder()
+            .object(sphere)
+            .object(Plane::default())
+            .object(mesh)
+            .object(Volume::new(Box::new(Sphere::default()), 0.5, WHITE))
+            .light(PointLight::new(Point::new(-10, 10, -10), WHITE))
+            .light(SpotLight::new(
+                Point::new(0, 5, 0),
+                Vector::new(0, -1, 0),
+                0.3,
+                0.5,
+                WHITE,
+            ))
+            .background(Color::new(0.1, 0.2, 0.3))
+            .fog(Fog::new(WHITE, 0.01))
+            .ambient_light(AmbientLight::Color(Color::new(0.2, 0.2, 0.3)))
+            .build();
+        w.objects_mut()[1].set_one_sided(true);
+        w.objects_mut()[1].set_shadow_bias(Some(0.01));
+
+        let json = serde_json::to_string_pretty(&w).unwrap();
+        let read: World = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string_pretty(&read).unwrap(), json);
+        assert_eq!(read.objects().len(), 4);
+        assert!(read.objects()[1].one_sided());
+        assert_eq!(read.objects()[1].shadow_bias(), Some(0.01));
+        assert_eq!(read.ambient_light(), w.ambient_light());
+        assert_eq!(
+            read.lights()[1].as_any().downcast_ref::<SpotLight>(),
+            w.lights()[1].as_any().downcast_ref::<SpotLight>()
+        );
+
+        let mut camera = Camera::new(8, 6, PI / 3.0);
+        camera.set_transform(Camera::view_transform(
+            Point::new(0, 1, -5),
+            Point::new(0, 1, 0),
+            Vector::new(0, 1, 0),
+        ));
+        let original = camera.render(&w).unwrap();
+        let rendered = camera.render(&read).unwrap();
+        for y in 0..6 {
+            for x in 0..8 {
+                assert_eq!(original.pixel_at(x, y), rendered.pixel_at(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn hand_written_world() {
+        let w: World = serde_json::from_str(
+            r#"{
+                "objects": [{ "type": "Sphere", "material": { "ambient": 1.0, "diffuse": 0.0, "specular": 0.0 } }],
+                "lights": [{ "type": "PointLight", "position": { "x": 0, "y": 5, "z": 0 }, "intensity": { "red": 1, "green": 1, "blue": 1 } }]
+            }"#,
+        )
+        .unwrap();
+        let r = Ray::new(Point::new(0, 0, -5), Vector::new(0, 0, 1));
+        assert_eq!(w.color_at(&r, &mut Vec::new(), 0), WHITE);
+        assert!(serde_json::from_str::<World>(r#"{ "objects": [{ "type": "Torus" }] }"#).is_err());
+        assert!(serde_json::from_str::<World>(
+            r#"{ "objects": [{ "type": "TriangleMesh", "vertices": [], "faces": [[0, 1, 2]] }] }"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn camera() {
+        let mut camera = Camera::new(40, 30, PI / 2.0);
+        camera.set_transform(Mat4::new_translation(0, 0, -5));
+        camera.settings.samples_per_pixel = 4;
+        camera.settings.max_distance = 100.0;
+        camera.integrator = Integrator::PathTracing;
+        let read = round_trip(&camera);
+        assert_eq!(read.transform(), camera.transform());
+        assert_eq!(read.pixel_size, camera.pixel_size);
+        assert_eq!(read.settings, camera.settings);
+        assert_eq!(read.integrator, Integrator::PathTracing);
+
+        let minimal: Camera =
+            serde_json::from_str(r#"{ "hsize": 40, "vsize": 30, "field_of_view": 1.0 }"#).unwrap();
+        assert_eq!(minimal.settings, RenderSettings::default());
+        assert_eq!(minimal.white_balance, WHITE);
+    }
+}
@@ -0,0 +1,140 @@
+//! How a render trades quality for speed and deals with numerical limits, gathered in one place: see [`RenderSettings`].
+use std::cell::Cell;
+
+use crate::epsilon::EPSILON;
+use crate::scalar::Scalar;
+
+/// The default for [`RenderSettings::recursion_limit`]
+pub const DEFAULT_RECURSION_LIMIT: usize = 5;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+/// The settings of a render that do not depend on the scene, see [`crate::camera::Camera::settings`].
+pub struct RenderSettings {
+    /// How many times a ray may bounce off reflective or pass through transparent surfaces, [`DEFAULT_RECURSION_LIMIT`] by default.
+    /// 0 disables reflection and refraction completely.
+    pub recursion_limit: usize,
+    /// The number of rays per pixel, which are averaged to smooth jagged edges (anti-aliasing).
+    /// With 1 (the default), a single ray goes through the center of each pixel.
+    pub samples_per_pixel: usize,
+    /// How far the points rays are cast from (for shadows, reflections and refractions) are moved off the surface, so the rays do not hit the surface they start on.
+    /// Raise it if surfaces of a large scene show dark speckles (shadow acne), lower it if shadows of a tiny scene come loose from their objects. 0.0001 by default.
+    pub shadow_bias: Scalar,
+    /// Objects further along a ray than this are ignored, as if the ray had missed them. Infinite by default.
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "crate::serialization::is_infinite")
+    )]
+    pub max_distance: Scalar,
+    /// The number of threads [`crate::camera::Camera::par_render`] uses, None (the default) for one per core. Only used with the "rayon" feature.
+    pub threads: Option<usize>,
+    /// Makes pixels where rays miss every object transparent (and black) instead of showing the skybox, so renders can be composed over other images.
+    /// Reflections and refractions still show the skybox. See [`crate::canvas::Canvas::alpha_at`].
+    pub transparent_background: bool,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            recursion_limit: DEFAULT_RECURSION_LIMIT,
+            samples_per_pixel: 1,
+            shadow_bias: EPSILON,
+            max_distance: Scalar::INFINITY,
+            threads: None,
+            transparent_background: false,
+        }
+    }
+}
+
+thread_local! {
+    /// The shadow bias and maximum distance of the render running on the current thread, see [`with_settings`]
+    static ACTIVE: Cell<(Scalar, Scalar)> = const { Cell::new((EPSILON, Scalar::INFINITY)) };
+}
+
+/// Runs ```f``` with the settings of the current render, which shading deep down in the world reads through [`shadow_bias`] and [`max_distance`].
+/// Outside of a render, the defaults apply.
+pub(crate) fn with_settings<T>(settings: &RenderSettings, f: impl FnOnce() -> T) -> T {
+    let _restore = RestoreSettings(
+        ACTIVE.with(|active| active.replace((settings.shadow_bias, settings.max_distance))),
+    );
+    f()
+}
+
+/// Puts the previous settings back when dropped, so they are restored even if the render panics
+struct RestoreSettings((Scalar, Scalar));
+
+impl Drop for RestoreSettings {
+    fn drop(&mut self) {
+        ACTIVE.with(|active| active.set(self.0));
+    }
+}
+
+/// See [`RenderSettings::shadow_bias`]
+pub(crate) fn shadow_bias() -> Scalar {
+    ACTIVE.with(|active| active.get().0)
+}
+
+/// See [`RenderSettings::max_distance`]
+pub(crate) fn max_distance() -> Scalar {
+    ACTIVE.with(|active| active.get().1)
+}
+
+#[cfg(test)]
+mod settings_tests {
+    use crate::{
+        epsilon::{EpsilonEqual, EPSILON},
+        intersection::Intersection,
+        ray::Ray,
+        scalar::Scalar,
+        settings::{max_distance, shadow_bias, with_settings, RenderSettings},
+        shapes::sphere::Sphere,
+        tuple::{Point, Vector},
+    };
+
+    #[test]
+    fn default() {
+        let settings = RenderSettings::default();
+        assert_eq!(settings.shadow_bias, EPSILON);
+        assert_eq!(settings.max_distance, Scalar::INFINITY);
+        assert_eq!(shadow_bias(), EPSILON);
+        assert_eq!(max_distance(), Scalar::INFINITY);
+    }
+
+    #[test]
+    fn shadow_bias_moves_over_point() {
+        let r = Ray::new(Point::new(0, 0, -5), Vector::new(0, 0, 1));
+        let shape = Sphere::default();
+        let i = Intersection::new(4, &shape);
+        let settings = RenderSettings {
+            shadow_bias: 0.1,
+            max_distance: 10.0,
+            ..Default::default()
+        };
+        let comps = with_settings(&settings, || {
+            assert_eq!(max_distance(), 10.0);
+            i.prepare_computations(&r, &vec![i])
+        });
+        assert!(comps.over_point.z.e_equals(-1.1));
+        assert!(comps.under_point.z.e_equals(-0.9));
+        // restored afterwards
+        assert_eq!(shadow_bias(), EPSILON);
+        assert_eq!(max_distance(), Scalar::INFINITY);
+    }
+
+    #[test]
+    fn restored_after_panic() {
+        let settings = RenderSettings {
+            shadow_bias: 0.1,
+            ..Default::default()
+        };
+        let result = std::panic::catch_unwind(|| {
+            with_settings(&settings, || panic!("pattern failed"));
+        });
+        assert!(result.is_err());
+        assert_eq!(shadow_bias(), EPSILON);
+    }
+}
@@ -1,4 +1,4 @@
-/// This module includes the Shape trait all shapes (so all objects in the world except lights) must implement as well as all shapes included with the crate.
+//! This module includes the Shape trait all shapes (so all objects in the world except lights) must implement as well as all shapes included with the crate.
 
 /// A plane in the world
 pub mod plane;
@@ -6,3 +6,7 @@ pub mod plane;
 pub mod shape;
 /// A sphere in the world
 pub mod sphere;
+/// A mesh of triangles
+pub mod triangle_mesh;
+/// A volume of fog or smoke
+pub mod volume;
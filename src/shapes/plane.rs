@@ -3,10 +3,11 @@ use crate::{
     intersection::Intersection,
     material::Material,
     matrix::{Mat4, IDENTITY_MATRIX_4},
+    scalar::Scalar,
     tuple::Vector,
 };
 
-use super::shape::{Shape, ShapeBound};
+use super::shape::{ObjectId, Shape, ShapeBound};
 
 const NORMAL: Vector = Vector::const_new(0.0, 1.0, 0.0);
 
@@ -15,7 +16,13 @@ const NORMAL: Vector = Vector::const_new(0.0, 1.0, 0.0);
 pub struct Plane {
     transformation_matrix: Mat4,
     inverted_transformation_matrix: Mat4,
+    transposed_inverted_transformation_matrix: Mat4,
     material: Material,
+    back_material: Option<Material>,
+    one_sided: bool,
+    normal_flipped: bool,
+    id: Option<ObjectId>,
+    shadow_bias: Option<Scalar>,
 }
 
 impl ShapeBound for Plane {}
@@ -25,7 +32,13 @@ impl Default for Plane {
         Self {
             transformation_matrix: IDENTITY_MATRIX_4,
             inverted_transformation_matrix: IDENTITY_MATRIX_4,
+            transposed_inverted_transformation_matrix: IDENTITY_MATRIX_4,
             material: Default::default(),
+            back_material: None,
+            one_sided: false,
+            normal_flipped: false,
+            id: None,
+            shadow_bias: None,
         }
     }
 }
@@ -53,6 +66,9 @@ impl Shape for Plane {
     fn inverse_transformation_matrix(&self) -> Mat4 {
         self.inverted_transformation_matrix
     }
+    fn inverse_of_transpose_of_transformation_matrix(&self) -> Mat4 {
+        self.transposed_inverted_transformation_matrix
+    }
     #[inline]
     fn local_normal_at(&self, _p: crate::tuple::Point) -> crate::tuple::Vector {
         NORMAL
@@ -60,7 +76,7 @@ impl Shape for Plane {
 
     #[mutants::skip]
     fn eq(&self, other: &dyn std::any::Any) -> bool {
-        other.downcast_ref::<Self>().map_or(false, |a| self == a)
+        other.downcast_ref::<Self>() == Some(self)
     }
 
     #[mutants::skip]
@@ -76,12 +92,54 @@ impl Shape for Plane {
     fn set_transformation_matrix(&mut self, matrix: Mat4) {
         self.transformation_matrix = matrix;
         self.inverted_transformation_matrix = matrix.inverse();
+        self.transposed_inverted_transformation_matrix =
+            self.inverted_transformation_matrix.transpose();
     }
 
     fn set_material(&mut self, m: Material) {
         self.material = m;
     }
 
+    fn back_material(&self) -> Option<&Material> {
+        self.back_material.as_ref()
+    }
+
+    fn set_back_material(&mut self, m: Option<Material>) {
+        self.back_material = m;
+    }
+
+    fn one_sided(&self) -> bool {
+        self.one_sided
+    }
+
+    fn set_one_sided(&mut self, one_sided: bool) {
+        self.one_sided = one_sided;
+    }
+
+    fn normal_flipped(&self) -> bool {
+        self.normal_flipped
+    }
+
+    fn set_normal_flipped(&mut self, flipped: bool) {
+        self.normal_flipped = flipped;
+    }
+
+    fn id(&self) -> Option<ObjectId> {
+        self.id
+    }
+
+    fn set_id(&mut self, id: Option<ObjectId>) {
+        self.id = id;
+    }
+
+    fn shadow_bias(&self) -> Option<Scalar> {
+        self.shadow_bias
+    }
+
+    fn set_shadow_bias(&mut self, bias: Option<Scalar>) {
+        self.shadow_bias = bias;
+    }
+
     #[mutants::skip]
     fn as_shape(&self) -> &dyn Shape {
         self
@@ -149,4 +207,49 @@ mod plane_tests {
         assert_eq!(intersections[0].t, 1.0);
         assert_eq!(intersections[0].object, p_ref);
     }
+
+    #[test]
+    fn one_sided_visible_from_above() {
+        let mut p = Plane::default();
+        p.set_one_sided(true);
+        let r = Ray::new(Point::new(0, 1, 0), Vector::new(0, -1, 0));
+        let mut intersections = Vec::new();
+        p.intersect(&r, &mut intersections);
+        assert_eq!(intersections.len(), 1);
+        assert_eq!(intersections[0].t, 1.0);
+    }
+
+    #[test]
+    fn one_sided_invisible_from_below() {
+        let mut p = Plane::default();
+        p.set_one_sided(true);
+        let r = Ray::new(Point::new(0, -1, 0), Vector::new(0, 1, 0));
+        let mut intersections = Vec::new();
+        p.intersect(&r, &mut intersections);
+        assert_eq!(intersections.len(), 0);
+    }
+
+    #[test]
+    fn flipped_one_sided_visible_from_below() {
+        let mut p = Plane::default();
+        p.set_one_sided(true);
+        p.set_normal_flipped(true);
+
+        let mut intersections = Vec::new();
+        let from_below = Ray::new(Point::new(0, -1, 0), Vector::new(0, 1, 0));
+        p.intersect(&from_below, &mut intersections);
+        assert_eq!(intersections.len(), 1);
+
+        intersections.clear();
+        let from_above = Ray::new(Point::new(0, 1, 0), Vector::new(0, -1, 0));
+        p.intersect(&from_above, &mut intersections);
+        assert_eq!(intersections.len(), 0);
+    }
+
+    #[test]
+    fn flipped_normal() {
+        let mut p = Plane::default();
+        p.set_normal_flipped(true);
+        assert_eq!(p.normal_at(Point::new(3, 0, -2)), Vector::new(0, -1, 0));
+    }
 }
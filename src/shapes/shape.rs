@@ -1,10 +1,13 @@
 use crate::{
+    bounds::BoundingBox,
     color::Color,
     intersection::{Intersection, PreparedComputations},
-    light::PointLight,
+    light::Light,
     material::Material,
     matrix::Mat4,
     ray::Ray,
+    scalar::Scalar,
+    transform::Transform,
     tuple::{Point, Vector},
 };
 
@@ -18,18 +21,50 @@ pub trait ShapeBound: Any + Debug + Send + Sync {}
 /// Trait dependencies for Shape - differ depending on rayon being active
 pub trait ShapeBound: Any + Debug {}
 
+/// Identifies an object in the world, e.g. so that lights can be linked to it.
+///
+/// Ids are chosen by the user and set with [`Shape::set_id`], objects have none by default.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ObjectId(pub usize);
+
 /// This trait encapsulates the shared behaviour of all objects in the world (not lights, though!).
 ///
 /// If you want to add your own shape, implement this trait for it.
 /// Most of the default methods take work from you (i.e. converting coordinates to object space).
-/// It is heavily recommended though to override [`Self::inverse_transformation_matrix`] to cache the matrix somehow (maybe when setting the original matrix), as this hugely increases performance.
+/// It is heavily recommended though to override [`Self::inverse_transformation_matrix`] and [`Self::inverse_of_transpose_of_transformation_matrix`] to cache the matrices somehow (maybe when setting the original matrix), as this hugely increases performance.
 pub trait Shape: ShapeBound {
     /// The intersection of a ray with this shape.
     /// This method converts the coordinates of the ray to object space and then calls local_intersect for the concrete impelementation.
     /// You probably don't need to overwrite this.
+    /// If the shape is one-sided (see [`Self::one_sided`]), intersections with its back face are discarded.
     fn intersect<'a>(&'a self, ray: &Ray, intersections: &mut Vec<Intersection<'a>>) {
         let ray = ray.transformed(self.inverse_transformation_matrix());
+        if !self.one_sided() {
+            self.local_intersect(&ray, intersections);
+            return;
+        }
+
+        let first_new = intersections.len();
         self.local_intersect(&ray, intersections);
+
+        // only the new hits are filtered, in one pass
+        let mut new = intersections.split_off(first_new);
+        new.retain(|i| {
+            let local_normal = self.oriented_local_normal_at(ray.position(i.t), i);
+            local_normal.dot(ray.direction) <= 0.0
+        });
+        intersections.append(&mut new);
+    }
+    /// Whether the ray hits this shape at a ```t``` between 0 and ```distance```, e.g. for shadow rays, where it does not matter which hit is the nearest.
+    /// By default all intersections are computed (in ```intersections```, which is left as it was) and searched, override it if your shape can stop at the first hit.
+    fn occludes<'a>(
+        &'a self,
+        ray: &Ray,
+        distance: Scalar,
+        intersections: &mut Vec<Intersection<'a>>,
+    ) -> bool {
+        hit_before(self, ray, distance, intersections)
     }
     /// This method transforms a ray to object space.
     /// You probably don't need to overwrite this.
@@ -38,12 +73,39 @@ pub trait Shape: ShapeBound {
     }
     /// Implement your intersection logic here!
     fn local_intersect<'a>(&'a self, ray: &Ray, intersections: &mut Vec<Intersection<'a>>);
+    /// The box containing the whole shape (in object space), or None if it is infinite like a plane.
+    /// Shapes with bounds can be skipped by rays missing them, see [`Self::bounds`]. None by default, so every ray tests the shape.
+    fn local_bounds(&self) -> Option<BoundingBox> {
+        None
+    }
+    /// The box containing the whole shape in world space, see [`Self::local_bounds`].
+    /// You probably don't need to overwrite this.
+    fn bounds(&self) -> Option<BoundingBox> {
+        self.local_bounds()
+            .map(|bounds| bounds.transform(self.transformation_matrix()))
+    }
     /// Returns the material of this shape.
     fn material(&self) -> &Material;
     /// Returns a mutable handle to the material of this shape.
     fn material_mut(&mut self) -> &mut Material;
     /// Replaces this shape's material with the provided one.
     fn set_material(&mut self, m: Material);
+    /// The material of the back face of this shape, if it differs from [`Self::material`].
+    /// None by default, override it together with [`Self::set_back_material`] to support a second material.
+    fn back_material(&self) -> Option<&Material> {
+        None
+    }
+    /// Gives the back face of this shape its own material, e.g. for a poster that is matte gray on the back. ```None``` uses [`Self::material`] on both sides.
+    /// Does nothing by default.
+    fn set_back_material(&mut self, _m: Option<Material>) {}
+    /// The material of the side a ray hit: the back material when the ray hit the shape from inside / behind and there is one.
+    /// You probably don't need to overwrite this.
+    fn material_facing(&self, inside: bool) -> &Material {
+        match self.back_material() {
+            Some(back_material) if inside => back_material,
+            _ => self.material(),
+        }
+    }
 
     /// Returns the transformation matrix of the shape.
     fn transformation_matrix(&self) -> Mat4;
@@ -57,15 +119,73 @@ pub trait Shape: ShapeBound {
     }
     /// Sets a new transformation matrix for this shape.
     fn set_transformation_matrix(&mut self, matrix: Mat4);
+    /// Whether the shape only exists when seen from the side its normals point to.
+    /// Rays hitting the back face pass through, so the shape is neither visible nor casts shadows from behind.
+    /// False by default, override it together with [`Self::set_one_sided`] to support one-sided shapes.
+    fn one_sided(&self) -> bool {
+        false
+    }
+    /// Makes this shape one- or two-sided, see [`Self::one_sided`]. Does nothing by default.
+    fn set_one_sided(&mut self, _one_sided: bool) {}
+    /// Whether the normals of this shape are inverted, turning its inside into its front side.
+    /// False by default, override it together with [`Self::set_normal_flipped`] to support flipping.
+    fn normal_flipped(&self) -> bool {
+        false
+    }
+    /// Inverts the normals of this shape, see [`Self::normal_flipped`]. Does nothing by default.
+    fn set_normal_flipped(&mut self, _flipped: bool) {}
+    /// The id of this object, if it has one.
+    /// None by default, override it together with [`Self::set_id`] to find the shape by id, e.g. in [`crate::world::World::remove_object`].
+    fn id(&self) -> Option<ObjectId> {
+        None
+    }
+    /// Sets the id of this object, see [`ObjectId`]. Does nothing by default.
+    fn set_id(&mut self, _id: Option<ObjectId>) {}
+    /// How far rays cast from this shape's surface (for shadows, reflections and refractions) start off of it, if it differs from [`crate::settings::RenderSettings::shadow_bias`].
+    /// Raise it for huge shapes like ground planes showing shadow acne, lower it for tiny ones whose shadows come loose.
+    /// None by default, override it together with [`Self::set_shadow_bias`] to support a bias per shape.
+    fn shadow_bias(&self) -> Option<Scalar> {
+        None
+    }
+    /// Overrides the shadow bias of the render for this shape, see [`Self::shadow_bias`]. ```None``` uses the one of the render.
+    /// Does nothing by default.
+    fn set_shadow_bias(&mut self, _bias: Option<Scalar>) {}
     /// The object's normal at a given point (world space).
     fn normal_at(&self, p: Point) -> Vector {
         let local_point = self.inverse_transformation_matrix() * p;
-        let local_normal = self.local_normal_at(local_point);
+        let mut local_normal = self.local_normal_at(local_point);
+        if self.normal_flipped() {
+            local_normal = -local_normal;
+        }
+        let world_normal = self.inverse_of_transpose_of_transformation_matrix() * local_normal;
+        world_normal.normalized()
+    }
+    /// The object's normal at the point a ray hit it (world space).
+    /// Unlike [`Self::normal_at`], this knows which part of the shape was hit (see [`Self::local_normal_at_hit`]).
+    /// You probably don't need to overwrite this.
+    fn normal_at_hit(&self, p: Point, hit: &Intersection) -> Vector {
+        let local_point = self.inverse_transformation_matrix() * p;
+        let local_normal = self.oriented_local_normal_at(local_point, hit);
         let world_normal = self.inverse_of_transpose_of_transformation_matrix() * local_normal;
         world_normal.normalized()
     }
     /// Returns the normal at a given point (in object space)
     fn local_normal_at(&self, p: Point) -> Vector;
+    /// Returns the normal at the point a ray hit the shape (in object space).
+    /// Override this if the normal depends on more than the point, e.g. which face of a mesh was hit.
+    fn local_normal_at_hit(&self, p: Point, _hit: &Intersection) -> Vector {
+        self.local_normal_at(p)
+    }
+    /// Returns the normal at the point a ray hit the shape (in object space), inverted if [`Self::normal_flipped`] is set.
+    /// You probably don't need to overwrite this.
+    fn oriented_local_normal_at(&self, p: Point, hit: &Intersection) -> Vector {
+        let normal = self.local_normal_at_hit(p, hit);
+        if self.normal_flipped() {
+            -normal
+        } else {
+            normal
+        }
+    }
     /// Converts a point to object space.
     fn to_object_space(&self, p: Point) -> Point {
         self.inverse_transformation_matrix() * p
@@ -75,23 +195,21 @@ pub trait Shape: ShapeBound {
         self.inverse_of_transpose_of_transformation_matrix() * p
     }
     /// Renders the color a ray sees at a given position.
-    /// Ambient determines whether to include ambient lighting (not included for every light source)
+    /// Transmission is the part of the light reaching the position, see [`crate::light::Light::transmission`].
     fn render_at(
         &self,
         comps: &PreparedComputations,
-        light: &PointLight,
-        in_shadow: bool,
-        ambient: bool,
+        light: &dyn Light,
+        transmission: Color,
     ) -> Color {
         let shape: &dyn Shape = self.as_shape();
-        self.material().lighting(
+        self.material_facing(comps.inside).lighting(
             light,
             shape,
             comps.over_point,
             comps.eyev,
             comps.normalv,
-            in_shadow,
-            ambient,
+            transmission,
         )
     }
     /// Compares this shape to any other one.
@@ -111,20 +229,45 @@ pub trait Shape: ShapeBound {
     fn as_shape(&self) -> &dyn Shape;
 }
 
+impl<S: Shape> Transform for S {
+    fn transformed(mut self, matrix: Mat4) -> Self {
+        self.set_transformation_matrix(matrix * self.transformation_matrix());
+        self
+    }
+}
+
 impl PartialEq for dyn Shape {
     fn eq(&self, other: &dyn Shape) -> bool {
         self.eq(other.as_any())
     }
 }
 
+/// The default of [`Shape::occludes`]: intersects the shape and searches the intersections
+pub(crate) fn hit_before<'a, S: Shape + ?Sized>(
+    shape: &'a S,
+    ray: &Ray,
+    distance: Scalar,
+    intersections: &mut Vec<Intersection<'a>>,
+) -> bool {
+    let first_new = intersections.len();
+    shape.intersect(ray, intersections);
+    let hit = intersections[first_new..]
+        .iter()
+        .any(|i| (0.0..distance).contains(&i.t));
+    intersections.truncate(first_new);
+    hit
+}
+
 #[cfg(test)]
 mod shape_tests {
-    use std::f64::consts::PI;
-
     use crate::{
         material::Material,
         matrix::{Mat4, IDENTITY_MATRIX_4},
         ray::Ray,
+        scalar::{
+            consts::{FRAC_1_SQRT_2, PI},
+            Scalar,
+        },
         tuple::{Point, Vector},
     };
 
@@ -227,7 +370,7 @@ mod shape_tests {
         let mut s = TestShape::default();
         s.set_transform(Mat4::new_scaling(2, 2, 2));
         let mut intersections = Vec::new();
-        let _xs = s.intersect(&r, &mut intersections);
+        s.intersect(&r, &mut intersections);
         unsafe {
             assert_eq!(SAVED_RAY.unwrap().origin, Point::new(0.0, 0.0, -2.5));
             assert_eq!(SAVED_RAY.unwrap().direction, Vector::new(0., 0., 0.5));
@@ -239,7 +382,7 @@ mod shape_tests {
         let mut s = TestShape::default();
         s.set_transform(Mat4::new_translation(5, 0, 0));
         let mut intersections = Vec::new();
-        let _xs = s.intersect(&r, &mut intersections);
+        s.intersect(&r, &mut intersections);
         unsafe {
             assert_eq!(SAVED_RAY.unwrap().origin, Point::new(-5, 0, -5));
             assert_eq!(SAVED_RAY.unwrap().direction, Vector::new(0, 0, 1));
@@ -250,8 +393,8 @@ mod shape_tests {
     fn test_normal_translated() {
         let mut s = TestShape::default();
         s.set_transform(Mat4::new_translation(0, 1, 0));
-        let n = s.normal_at(Point::new(0.0, 1.70711, -0.70711));
-        assert_eq!(n, Vector::new(0.0, 0.70711, -0.70711));
+        let n = s.normal_at(Point::new(0.0, 1.0 + FRAC_1_SQRT_2, -FRAC_1_SQRT_2));
+        assert_eq!(n, Vector::new(0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2));
     }
     #[test]
     fn test_normal_transformed() {
@@ -260,8 +403,8 @@ mod shape_tests {
         s.set_transform(m);
         let n = s.normal_at(Point::new(
             0.0,
-            2.0_f64.sqrt() / 2.0,
-            -(2.0_f64.sqrt() / 2.0),
+            Scalar::sqrt(2.0) / 2.0,
+            -(Scalar::sqrt(2.0) / 2.0),
         ));
         assert_eq!(n, Vector::new(0.0, 0.97014, -0.24254));
     }
@@ -2,22 +2,30 @@
 use std::any::Any;
 
 use crate::{
+    bounds::BoundingBox,
     intersection::Intersection,
     material::Material,
     matrix::{Mat4, IDENTITY_MATRIX_4},
     ray::Ray,
+    scalar::Scalar,
     shapes::shape::Shape,
     tuple::{Point, Vector},
 };
 
-use super::shape::ShapeBound;
+use super::shape::{ObjectId, ShapeBound};
 
 #[derive(Debug, PartialEq)]
 /// The sphere shape.
 pub struct Sphere {
     transformation_matrix: Mat4,
     inverted_transformation_matrix: Mat4,
+    transposed_inverted_transformation_matrix: Mat4,
     material: Material,
+    back_material: Option<Material>,
+    one_sided: bool,
+    normal_flipped: bool,
+    id: Option<ObjectId>,
+    shadow_bias: Option<Scalar>,
 }
 
 impl ShapeBound for Sphere {}
@@ -52,6 +60,13 @@ impl Shape for Sphere {
         self.transformation_matrix
     }
 
+    fn local_bounds(&self) -> Option<BoundingBox> {
+        Some(BoundingBox::new(
+            Point::new(-1, -1, -1),
+            Point::new(1, 1, 1),
+        ))
+    }
+
     fn local_normal_at(&self, p: Point) -> Vector {
         let res_object_space = (p - Point::new(0, 0, 0)).normalized();
         res_object_space.normalized()
@@ -61,6 +76,10 @@ impl Shape for Sphere {
         self.inverted_transformation_matrix
     }
 
+    fn inverse_of_transpose_of_transformation_matrix(&self) -> Mat4 {
+        self.transposed_inverted_transformation_matrix
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
@@ -82,12 +101,54 @@ impl Shape for Sphere {
     fn set_transformation_matrix(&mut self, matrix: Mat4) {
         self.transformation_matrix = matrix;
         self.inverted_transformation_matrix = matrix.inverse();
+        self.transposed_inverted_transformation_matrix =
+            self.inverted_transformation_matrix.transpose();
     }
 
     fn set_material(&mut self, m: Material) {
         self.material = m;
     }
 
+    fn back_material(&self) -> Option<&Material> {
+        self.back_material.as_ref()
+    }
+
+    fn set_back_material(&mut self, m: Option<Material>) {
+        self.back_material = m;
+    }
+
+    fn one_sided(&self) -> bool {
+        self.one_sided
+    }
+
+    fn set_one_sided(&mut self, one_sided: bool) {
+        self.one_sided = one_sided;
+    }
+
+    fn normal_flipped(&self) -> bool {
+        self.normal_flipped
+    }
+
+    fn set_normal_flipped(&mut self, flipped: bool) {
+        self.normal_flipped = flipped;
+    }
+
+    fn id(&self) -> Option<ObjectId> {
+        self.id
+    }
+
+    fn set_id(&mut self, id: Option<ObjectId>) {
+        self.id = id;
+    }
+
+    fn shadow_bias(&self) -> Option<Scalar> {
+        self.shadow_bias
+    }
+
+    fn set_shadow_bias(&mut self, bias: Option<Scalar>) {
+        self.shadow_bias = bias;
+    }
+
     fn as_shape(&self) -> &dyn Shape {
         self
     }
@@ -98,13 +159,20 @@ impl Default for Sphere {
         Self {
             transformation_matrix: IDENTITY_MATRIX_4,
             inverted_transformation_matrix: IDENTITY_MATRIX_4,
+            transposed_inverted_transformation_matrix: IDENTITY_MATRIX_4,
             material: Default::default(),
+            back_material: None,
+            one_sided: false,
+            normal_flipped: false,
+            id: None,
+            shadow_bias: None,
         }
     }
 }
 
 #[cfg(test)]
 impl Sphere {
+    /// A sphere with a glass material, as used throughout the refraction tests
     pub fn new_glass() -> Self {
         Self {
             material: Material::new_glass(),
@@ -119,8 +187,9 @@ mod sphere_tests {
     use crate::{
         intersection::Intersection,
         material::Material,
-        matrix::IDENTITY_MATRIX_4,
+        matrix::{Mat4, IDENTITY_MATRIX_4},
         ray::Ray,
+        scalar::Scalar,
         shapes::shape::Shape,
         tuple::{Point, Vector},
     };
@@ -182,6 +251,17 @@ mod sphere_tests {
         assert_eq!(s.transformation_matrix, IDENTITY_MATRIX_4);
     }
 
+    #[test]
+    fn caches_inverse_of_transpose() {
+        let mut s = Sphere::default();
+        let matrix = Mat4::new_translation(1, 2, 3) * Mat4::new_scaling(1.0, 0.5, 2.0);
+        s.set_transformation_matrix(matrix);
+        assert_eq!(
+            s.inverse_of_transpose_of_transformation_matrix(),
+            matrix.inverse().transpose()
+        );
+    }
+
     #[test]
     fn normal_at_x() {
         let s = Sphere::default();
@@ -202,25 +282,55 @@ mod sphere_tests {
     }
     #[test]
     fn normal_at_nonaxial() {
-        let c = 3_f64.sqrt() / 3.;
+        let c = Scalar::sqrt(3.0) / 3.;
         let s = Sphere::default();
         let n = s.normal_at(Point::new(c, c, c));
         assert_eq!(n, Vector::new(c, c, c));
     }
     #[test]
     fn normal_at_normalized() {
-        let c = 3_f64.sqrt() / 3.;
+        let c = Scalar::sqrt(3.0) / 3.;
         let s = Sphere::default();
         let n = s.normal_at(Point::new(c, c, c));
         assert_eq!(n, n.normalized());
     }
 
+    #[test]
+    fn normal_flipped_points_inwards() {
+        let mut s = Sphere::default();
+        s.set_normal_flipped(true);
+        let n = s.normal_at(Point::new(1, 0, 0));
+        assert_eq!(n, Vector::new(-1, 0, 0));
+    }
+
+    #[test]
+    fn one_sided_only_front_face() {
+        let r = Ray::new(Point::new(0, 0, -5), Vector::new(0, 0, 1));
+        let mut s = Sphere::default();
+        s.set_one_sided(true);
+        let mut xs = Vec::new();
+        s.intersect(&r, &mut xs);
+        assert_eq!(xs, vec![Intersection::new(4.0, &s)]);
+    }
+
+    #[test]
+    fn one_sided_flipped_seen_from_inside() {
+        let r = Ray::new(Point::new(0, 0, 0), Vector::new(0, 0, 1));
+        let mut s = Sphere::default();
+        s.set_one_sided(true);
+        s.set_normal_flipped(true);
+        let mut xs = Vec::new();
+        s.intersect(&r, &mut xs);
+        assert_eq!(xs, vec![Intersection::new(1.0, &s)]);
+    }
+
     #[test]
     fn instantiate() {
         let mut s = Sphere::default();
-        let mut m = Material::default();
-        m.ambient = 1.0;
-        s.set_material(m);
+        s.set_material(Material {
+            ambient: 1.0,
+            ..Default::default()
+        });
         assert_eq!(s.material().ambient, 1.0);
     }
 }
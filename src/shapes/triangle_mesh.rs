@@ -0,0 +1,889 @@
+use std::{any::Any, collections::HashMap};
+
+use crate::{
+    bounds::BoundingBox,
+    epsilon::EPSILON,
+    intersection::{FaceHit, Intersection},
+    material::Material,
+    matrix::{Mat4, IDENTITY_MATRIX_4},
+    pattern::Pattern,
+    ray::Ray,
+    scalar::Scalar,
+    stats,
+    tuple::{Point, Vector},
+};
+
+use super::shape::{hit_before, ObjectId, Shape, ShapeBound};
+
+#[derive(Debug, PartialEq)]
+/// A mesh of triangles, e.g. imported from a STL or OBJ file.
+///
+/// Each face refers to three indices into the vertex list, in counter-clockwise order when seen from the front.
+/// Without normals, every triangle is rendered flat.
+/// With normals (provided by the source file or computed via [`TriangleMesh::recompute_smooth_normals()`]), the normal of a hit is interpolated between the corners of the triangle, which makes the mesh look smooth.
+pub struct TriangleMesh {
+    vertices: Vec<Point>,
+    faces: Vec<[usize; 3]>,
+    normals: Vec<Vector>,
+    face_normals: Vec<[usize; 3]>,
+    bounds: BoundingBox,
+    transformation_matrix: Mat4,
+    inverted_transformation_matrix: Mat4,
+    transposed_inverted_transformation_matrix: Mat4,
+    material: Material,
+    back_material: Option<Material>,
+    one_sided: bool,
+    normal_flipped: bool,
+    id: Option<ObjectId>,
+    shadow_bias: Option<Scalar>,
+}
+
+impl TriangleMesh {
+    /// Creates a new, flat shaded mesh out of vertices and faces indexing into them.
+    ///
+    /// # Panics
+    /// If a face references a vertex that does not exist.
+    pub fn new(vertices: Vec<Point>, faces: Vec<[usize; 3]>) -> Self {
+        assert!(
+            faces.iter().flatten().all(|&i| i < vertices.len()),
+            "A face of the mesh references a vertex that does not exist"
+        );
+        let bounds = BoundingBox::of_points(&vertices);
+        Self {
+            vertices,
+            faces,
+            normals: Vec::new(),
+            face_normals: Vec::new(),
+            bounds,
+            transformation_matrix: IDENTITY_MATRIX_4,
+            inverted_transformation_matrix: IDENTITY_MATRIX_4,
+            transposed_inverted_transformation_matrix: IDENTITY_MATRIX_4,
+            material: Default::default(),
+            back_material: None,
+            one_sided: false,
+            normal_flipped: false,
+            id: None,
+            shadow_bias: None,
+        }
+    }
+
+    /// The vertices of this mesh (object space)
+    pub fn vertices(&self) -> &[Point] {
+        &self.vertices
+    }
+
+    /// The faces of this mesh, each referencing three vertices
+    pub fn faces(&self) -> &[[usize; 3]] {
+        &self.faces
+    }
+
+    /// The normals used for smooth shading. Empty if the mesh is flat shaded.
+    pub fn normals(&self) -> &[Vector] {
+        &self.normals
+    }
+
+    /// For every face, the indices of the normals (see [`Self::normals()`]) at its three corners. Empty if the mesh is flat shaded.
+    pub fn face_normals(&self) -> &[[usize; 3]] {
+        &self.face_normals
+    }
+
+    /// If this mesh interpolates normals across its faces
+    pub fn is_smooth(&self) -> bool {
+        !self.face_normals.is_empty()
+    }
+
+    /// Sets one normal per vertex, e.g. as provided by the source file.
+    ///
+    /// # Panics
+    /// If the number of normals does not match the number of vertices.
+    pub fn set_vertex_normals(&mut self, normals: Vec<Vector>) {
+        assert_eq!(
+            normals.len(),
+            self.vertices.len(),
+            "Exactly one normal per vertex is required"
+        );
+        self.normals = normals.iter().map(|n| n.normalized()).collect();
+        self.face_normals = self.faces.clone();
+    }
+
+    /// Sets the normals and their indices per face as they are, without checking them
+    #[cfg(feature = "serde")]
+    pub(crate) fn set_normals(&mut self, normals: Vec<Vector>, face_normals: Vec<[usize; 3]>) {
+        self.normals = normals;
+        self.face_normals = face_normals;
+    }
+
+    /// Removes all normals, so every triangle is rendered flat again.
+    pub fn clear_normals(&mut self) {
+        self.normals.clear();
+        self.face_normals.clear();
+    }
+
+    /// Computes smooth normals by averaging the normals of all faces sharing a vertex, weighted by their area.
+    ///
+    /// With a ```crease_angle``` (in radians), faces meeting at a steeper angle than it are not averaged, so hard edges (e.g. of a cube) stay sharp.
+    /// Without it, every vertex gets exactly one normal.
+    pub fn recompute_smooth_normals(&mut self, crease_angle: Option<Scalar>) {
+        // not normalized, so larger faces weigh more
+        let weighted_face_normals: Vec<Vector> = (0..self.faces.len())
+            .map(|face| self.weighted_face_normal(face))
+            .collect();
+
+        let mut faces_of_vertex = vec![Vec::new(); self.vertices.len()];
+        for (face, vertices) in self.faces.iter().enumerate() {
+            for &vertex in vertices {
+                faces_of_vertex[vertex].push(face);
+            }
+        }
+
+        self.normals.clear();
+        self.face_normals.clear();
+
+        match crease_angle {
+            None => {
+                for faces in faces_of_vertex.iter() {
+                    let sum = faces.iter().map(|&f| weighted_face_normals[f]).sum();
+                    self.normals.push(normalized_or_zero(sum));
+                }
+                self.face_normals = self.faces.clone();
+            }
+            Some(angle) => {
+                let min_cos = angle.cos();
+                for (face, vertices) in self.faces.iter().enumerate() {
+                    let own_normal = normalized_or_zero(weighted_face_normals[face]);
+                    let mut corners = [0; 3];
+                    for (corner, &vertex) in vertices.iter().enumerate() {
+                        let sum = faces_of_vertex[vertex]
+                            .iter()
+                            .filter(|&&other| {
+                                let other_normal = normalized_or_zero(weighted_face_normals[other]);
+                                other == face || own_normal.dot(other_normal) >= min_cos - EPSILON
+                            })
+                            .map(|&f| weighted_face_normals[f])
+                            .sum();
+                        corners[corner] = self.normals.len();
+                        self.normals.push(normalized_or_zero(sum));
+                    }
+                    self.face_normals.push(corners);
+                }
+            }
+        }
+    }
+
+    /// Smooths the mesh by applying ```iterations``` steps of Loop subdivision.
+    ///
+    /// Every step splits each triangle into four and moves all vertices towards a weighted average of their neighbours, so a coarse cage converges towards a smooth surface.
+    /// Edges used by only one face are treated as boundaries and stay in place, only being smoothed along the boundary itself.
+    /// If the mesh had normals before, smooth normals (without crease angle) are recomputed afterwards.
+    pub fn subdivide(&mut self, iterations: usize) {
+        let was_smooth = self.is_smooth();
+        for _ in 0..iterations {
+            self.subdivide_once();
+        }
+        self.bounds = BoundingBox::of_points(&self.vertices);
+        if was_smooth {
+            self.recompute_smooth_normals(None);
+        } else {
+            self.clear_normals();
+        }
+    }
+
+    fn subdivide_once(&mut self) {
+        let as_vector = |p: Point| p - Point::new(0, 0, 0);
+        let as_point = |v: Vector| Point::new(0, 0, 0) + v;
+
+        // every edge with the vertices opposite to it in its adjacent faces, in the order the faces reach them,
+        // so the new vertices are numbered (and neighbours summed) the same way on every run
+        let mut edge_indices: HashMap<(usize, usize), usize> = HashMap::new();
+        let mut edges: Vec<((usize, usize), Vec<usize>)> = Vec::new();
+        for &[a, b, c] in &self.faces {
+            for (from, to, opposite) in [(a, b, c), (b, c, a), (c, a, b)] {
+                let key = edge_key(from, to);
+                let index = *edge_indices.entry(key).or_insert_with(|| {
+                    edges.push((key, Vec::new()));
+                    edges.len() - 1
+                });
+                edges[index].1.push(opposite);
+            }
+        }
+
+        let mut neighbours = vec![Vec::new(); self.vertices.len()];
+        let mut boundary_neighbours = vec![Vec::new(); self.vertices.len()];
+        for &((a, b), ref opposite) in &edges {
+            neighbours[a].push(b);
+            neighbours[b].push(a);
+            if opposite.len() == 1 {
+                boundary_neighbours[a].push(b);
+                boundary_neighbours[b].push(a);
+            }
+        }
+
+        // even vertices: the original ones, moved
+        let mut new_vertices: Vec<Point> = Vec::with_capacity(self.vertices.len() + edges.len());
+        for (index, &vertex) in self.vertices.iter().enumerate() {
+            let v = as_vector(vertex);
+            let moved = if boundary_neighbours[index].len() == 2 {
+                let [b0, b1] =
+                    [0, 1].map(|i| as_vector(self.vertices[boundary_neighbours[index][i]]));
+                v * 0.75 + (b0 + b1) * 0.125
+            } else if !boundary_neighbours[index].is_empty() || neighbours[index].is_empty() {
+                // corner of a non-manifold or degenerate boundary
+                v
+            } else {
+                let n = neighbours[index].len() as Scalar;
+                let beta = if neighbours[index].len() == 3 {
+                    3.0 / 16.0
+                } else {
+                    3.0 / (8.0 * n)
+                };
+                let sum: Vector = neighbours[index]
+                    .iter()
+                    .map(|&i| as_vector(self.vertices[i]))
+                    .sum();
+                v * (1.0 - n * beta) + sum * beta
+            };
+            new_vertices.push(as_point(moved));
+        }
+
+        // odd vertices: one per edge, numbered like the edges
+        let first_edge_vertex = new_vertices.len();
+        for &((a, b), ref opposite) in &edges {
+            let ends = as_vector(self.vertices[a]) + as_vector(self.vertices[b]);
+            let position = if opposite.len() == 2 {
+                let sides =
+                    as_vector(self.vertices[opposite[0]]) + as_vector(self.vertices[opposite[1]]);
+                ends * 0.375 + sides * 0.125
+            } else {
+                ends * 0.5
+            };
+            new_vertices.push(as_point(position));
+        }
+
+        let mut new_faces = Vec::with_capacity(self.faces.len() * 4);
+        for &[a, b, c] in &self.faces {
+            let [ab, bc, ca] = [(a, b), (b, c), (c, a)]
+                .map(|(from, to)| first_edge_vertex + edge_indices[&edge_key(from, to)]);
+            new_faces.push([a, ab, ca]);
+            new_faces.push([ab, b, bc]);
+            new_faces.push([ca, bc, c]);
+            new_faces.push([ab, bc, ca]);
+        }
+
+        self.vertices = new_vertices;
+        self.faces = new_faces;
+    }
+
+    /// Displaces the surface of the mesh according to a heightmap, giving it real detail (also in its silhouette and shadows) unlike a [`crate::uv::BumpMap`].
+    ///
+    /// The mesh is first refined by splitting every triangle into four ```subdivisions``` times (without smoothing it, unlike [`Self::subdivide`]),
+    /// then every vertex is moved along its normal by the brightness of ```height``` (the mean of its color components, evaluated in object space) times ```scale```.
+    /// If the mesh had normals before, smooth normals are recomputed afterwards.
+    pub fn displace(&mut self, subdivisions: usize, height: &Pattern, scale: Scalar) {
+        let was_smooth = self.is_smooth();
+        for _ in 0..subdivisions {
+            self.split_faces();
+        }
+
+        self.recompute_smooth_normals(None);
+        for (vertex, normal) in self.vertices.iter_mut().zip(self.normals.iter()) {
+            let color = height.apply_pattern(*vertex);
+            let offset = (color.red + color.green + color.blue) / 3.0 * scale;
+            *vertex += *normal * offset;
+        }
+
+        self.bounds = BoundingBox::of_points(&self.vertices);
+        if was_smooth {
+            self.recompute_smooth_normals(None);
+        } else {
+            self.clear_normals();
+        }
+    }
+
+    /// Splits every face into four at the midpoints of its edges, keeping the shape as it is.
+    fn split_faces(&mut self) {
+        let faces = std::mem::take(&mut self.faces);
+        let mut midpoints = HashMap::new();
+        let mut new_faces = Vec::with_capacity(faces.len() * 4);
+        for &[a, b, c] in &faces {
+            let [ab, bc, ca] = [(a, b), (b, c), (c, a)].map(|(from, to)| {
+                *midpoints.entry(edge_key(from, to)).or_insert_with(|| {
+                    let midpoint =
+                        self.vertices[from] + (self.vertices[to] - self.vertices[from]) * 0.5;
+                    self.vertices.push(midpoint);
+                    self.vertices.len() - 1
+                })
+            });
+            new_faces.push([a, ab, ca]);
+            new_faces.push([ab, b, bc]);
+            new_faces.push([ca, bc, c]);
+            new_faces.push([ab, bc, ca]);
+        }
+        self.faces = new_faces;
+    }
+
+    /// The flat normal of a face (object space)
+    pub fn face_normal(&self, face: usize) -> Vector {
+        normalized_or_zero(self.weighted_face_normal(face))
+    }
+
+    /// The normal of a face with a length of twice the face's area
+    fn weighted_face_normal(&self, face: usize) -> Vector {
+        let [p1, p2, p3] = self.faces[face].map(|i| self.vertices[i]);
+        let e1 = p2 - p1;
+        let e2 = p3 - p1;
+        e2.cross(e1)
+    }
+
+    /// Intersects the ray with a single face using the Möller–Trumbore algorithm.
+    fn intersect_face(&self, ray: &Ray, face: usize) -> Option<(Scalar, FaceHit)> {
+        let [p1, p2, p3] = self.faces[face].map(|i| self.vertices[i]);
+        let e1 = p2 - p1;
+        let e2 = p3 - p1;
+
+        let dir_cross_e2 = ray.direction.cross(e2);
+        let det = e1.dot(dir_cross_e2);
+        if det.abs() < EPSILON * EPSILON {
+            return None;
+        }
+
+        let f = 1.0 / det;
+        let p1_to_origin = ray.origin - p1;
+        let u = f * p1_to_origin.dot(dir_cross_e2);
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let origin_cross_e1 = p1_to_origin.cross(e1);
+        let v = f * ray.direction.dot(origin_cross_e1);
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let t = f * e2.dot(origin_cross_e1);
+        Some((t, FaceHit { face, u, v }))
+    }
+
+    /// Interpolates the smooth normal of a face at the given barycentric coordinates
+    fn interpolated_normal(&self, hit: &FaceHit) -> Vector {
+        let [n1, n2, n3] = self.face_normals[hit.face].map(|i| self.normals[i]);
+        n2 * hit.u + n3 * hit.v + n1 * (1.0 - hit.u - hit.v)
+    }
+
+    /// Finds the face a point (object space) lies on, along with its barycentric coordinates.
+    fn face_containing(&self, p: Point) -> Option<FaceHit> {
+        let mut best: Option<(Scalar, FaceHit)> = None;
+        for face in 0..self.faces.len() {
+            let [p1, p2, p3] = self.faces[face].map(|i| self.vertices[i]);
+            let e1 = p2 - p1;
+            let e2 = p3 - p1;
+            let to_p = p - p1;
+
+            let d11 = e1.dot(e1);
+            let d12 = e1.dot(e2);
+            let d22 = e2.dot(e2);
+            let denominator = d11 * d22 - d12 * d12;
+            if denominator.abs() < EPSILON * EPSILON {
+                continue;
+            }
+            let dp1 = to_p.dot(e1);
+            let dp2 = to_p.dot(e2);
+            let u = (d22 * dp1 - d12 * dp2) / denominator;
+            let v = (d11 * dp2 - d12 * dp1) / denominator;
+            if u < -EPSILON || v < -EPSILON || u + v > 1.0 + EPSILON {
+                continue;
+            }
+
+            let distance = (p1 + e1 * u + e2 * v - p).magnitude();
+            if best.is_none_or(|(best_distance, _)| distance < best_distance) {
+                best = Some((distance, FaceHit { face, u, v }));
+            }
+        }
+        best.map(|(_, hit)| hit)
+    }
+}
+
+/// Key of the undirected edge between two vertices
+fn edge_key(a: usize, b: usize) -> (usize, usize) {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+fn normalized_or_zero(v: Vector) -> Vector {
+    if v.magnitude() < EPSILON * EPSILON {
+        v
+    } else {
+        v.normalized()
+    }
+}
+
+impl ShapeBound for TriangleMesh {}
+
+impl Shape for TriangleMesh {
+    fn local_intersect<'a>(&'a self, ray: &Ray, intersections: &mut Vec<Intersection<'a>>) {
+        stats::record(|stats| stats.bounds_tests += 1);
+        if !self.bounds.intersects(ray) {
+            return;
+        }
+        stats::record(|stats| stats.triangle_tests += self.faces.len() as u64);
+        for face in 0..self.faces.len() {
+            if let Some((t, face_hit)) = self.intersect_face(ray, face) {
+                intersections.push(Intersection::new_with_face(t, self, face_hit));
+            }
+        }
+    }
+
+    fn occludes<'a>(
+        &'a self,
+        ray: &Ray,
+        distance: Scalar,
+        intersections: &mut Vec<Intersection<'a>>,
+    ) -> bool {
+        // one-sided meshes need the normals of their hits, which the default does
+        if self.one_sided {
+            return hit_before(self, ray, distance, intersections);
+        }
+
+        let ray = self.transform_ray_to_object_space(ray);
+        stats::record(|stats| stats.bounds_tests += 1);
+        if !self.bounds.intersects(&ray) {
+            return false;
+        }
+        for face in 0..self.faces.len() {
+            stats::record(|stats| stats.triangle_tests += 1);
+            if self
+                .intersect_face(&ray, face)
+                .is_some_and(|(t, _)| (0.0..distance).contains(&t))
+            {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    fn set_material(&mut self, m: Material) {
+        self.material = m;
+    }
+
+    fn back_material(&self) -> Option<&Material> {
+        self.back_material.as_ref()
+    }
+
+    fn set_back_material(&mut self, m: Option<Material>) {
+        self.back_material = m;
+    }
+
+    fn transformation_matrix(&self) -> Mat4 {
+        self.transformation_matrix
+    }
+
+    fn inverse_transformation_matrix(&self) -> Mat4 {
+        self.inverted_transformation_matrix
+    }
+
+    fn inverse_of_transpose_of_transformation_matrix(&self) -> Mat4 {
+        self.transposed_inverted_transformation_matrix
+    }
+
+    fn set_transformation_matrix(&mut self, matrix: Mat4) {
+        self.transformation_matrix = matrix;
+        self.inverted_transformation_matrix = matrix.inverse();
+        self.transposed_inverted_transformation_matrix =
+            self.inverted_transformation_matrix.transpose();
+    }
+
+    fn one_sided(&self) -> bool {
+        self.one_sided
+    }
+
+    fn set_one_sided(&mut self, one_sided: bool) {
+        self.one_sided = one_sided;
+    }
+
+    fn normal_flipped(&self) -> bool {
+        self.normal_flipped
+    }
+
+    fn set_normal_flipped(&mut self, flipped: bool) {
+        self.normal_flipped = flipped;
+    }
+
+    fn id(&self) -> Option<ObjectId> {
+        self.id
+    }
+
+    fn set_id(&mut self, id: Option<ObjectId>) {
+        self.id = id;
+    }
+
+    fn shadow_bias(&self) -> Option<Scalar> {
+        self.shadow_bias
+    }
+
+    fn set_shadow_bias(&mut self, bias: Option<Scalar>) {
+        self.shadow_bias = bias;
+    }
+
+    fn local_bounds(&self) -> Option<BoundingBox> {
+        Some(self.bounds)
+    }
+
+    /// Without knowing which face was hit, the face has to be searched for, which is slow for large meshes.
+    fn local_normal_at(&self, p: Point) -> Vector {
+        match self.face_containing(p) {
+            Some(hit) => self.local_normal_at_hit(p, &Intersection::new_with_face(0, self, hit)),
+            None => Vector::new(0, 0, 0),
+        }
+    }
+
+    fn local_normal_at_hit(&self, p: Point, hit: &Intersection) -> Vector {
+        match hit.face_hit {
+            Some(face_hit) if self.is_smooth() => self.interpolated_normal(&face_hit),
+            Some(face_hit) => self.face_normal(face_hit.face),
+            None => self.local_normal_at(p),
+        }
+    }
+
+    #[mutants::skip]
+    fn eq(&self, other: &dyn Any) -> bool {
+        other.downcast_ref::<Self>() == Some(self)
+    }
+
+    #[mutants::skip]
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    #[mutants::skip]
+    fn as_shape(&self) -> &dyn Shape {
+        self
+    }
+}
+
+#[cfg(test)]
+mod triangle_mesh_tests {
+    use crate::{
+        color::{Color, BLACK, WHITE},
+        intersection::{FaceHit, Intersection},
+        matrix::Mat4,
+        pattern::Pattern,
+        ray::Ray,
+        scalar::{consts::PI, Scalar},
+        shapes::shape::Shape,
+        stats,
+        tuple::{Point, Vector},
+    };
+
+    use super::TriangleMesh;
+
+    fn triangle() -> TriangleMesh {
+        TriangleMesh::new(
+            vec![
+                Point::new(0, 1, 0),
+                Point::new(-1, 0, 0),
+                Point::new(1, 0, 0),
+            ],
+            vec![[0, 1, 2]],
+        )
+    }
+
+    /// Two triangles forming a roof ridge along the z axis, meeting at 90°
+    fn roof() -> TriangleMesh {
+        TriangleMesh::new(
+            vec![
+                Point::new(-1, 0, -1),
+                Point::new(-1, 0, 1),
+                Point::new(0, 1, -1),
+                Point::new(0, 1, 1),
+                Point::new(1, 0, -1),
+                Point::new(1, 0, 1),
+            ],
+            vec![[0, 2, 1], [1, 2, 3], [2, 4, 5], [2, 5, 3]],
+        )
+    }
+
+    fn tetrahedron() -> TriangleMesh {
+        TriangleMesh::new(
+            vec![
+                Point::new(1, 1, 1),
+                Point::new(1, -1, -1),
+                Point::new(-1, 1, -1),
+                Point::new(-1, -1, 1),
+            ],
+            vec![[0, 1, 2], [0, 3, 1], [0, 2, 3], [1, 3, 2]],
+        )
+    }
+
+    #[test]
+    fn flat_normal() {
+        let t = triangle();
+        assert_eq!(t.face_normal(0), Vector::new(0, 0, -1));
+        assert!(!t.is_smooth());
+    }
+
+    #[test]
+    fn ray_parallel_to_triangle() {
+        let t = triangle();
+        let r = Ray::new(Point::new(0, -1, -2), Vector::new(0, 1, 0));
+        let mut xs = Vec::new();
+        t.local_intersect(&r, &mut xs);
+        assert!(xs.is_empty());
+    }
+
+    #[test]
+    fn ray_misses_edges() {
+        let t = triangle();
+        let mut xs = Vec::new();
+        for origin in [
+            Point::new(1, 1, -2),
+            Point::new(-1, 1, -2),
+            Point::new(0, -1, -2),
+        ] {
+            t.local_intersect(&Ray::new(origin, Vector::new(0, 0, 1)), &mut xs);
+        }
+        assert!(xs.is_empty());
+    }
+
+    #[test]
+    fn ray_strikes_triangle() {
+        let t = triangle();
+        let r = Ray::new(Point::new(0.0, 0.5, -2.0), Vector::new(0, 0, 1));
+        let mut xs = Vec::new();
+        t.local_intersect(&r, &mut xs);
+        assert_eq!(xs.len(), 1);
+        assert_eq!(xs[0].t, 2.0);
+        let face_hit = xs[0].face_hit.unwrap();
+        assert_eq!(face_hit.face, 0);
+        assert!((face_hit.u - 0.25).abs() < 1e-9);
+        assert!((face_hit.v - 0.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn stats_count_triangle_tests_within_bounds() {
+        let t = tetrahedron();
+        let (_, stats) = stats::collect(|| {
+            let mut xs = Vec::new();
+            t.local_intersect(
+                &Ray::new(Point::new(0, 0, -5), Vector::new(0, 0, 1)),
+                &mut xs,
+            );
+            t.local_intersect(
+                &Ray::new(Point::new(5, 5, -5), Vector::new(0, 0, 1)),
+                &mut xs,
+            );
+        });
+        assert_eq!(stats.bounds_tests, 2);
+        assert_eq!(stats.triangle_tests, 4);
+    }
+
+    #[test]
+    fn occludes_stops_at_first_hit() {
+        let t = tetrahedron();
+        let r = Ray::new(Point::new(0, 0, -5), Vector::new(0, 0, 1));
+        let mut xs = Vec::new();
+        let (occludes, stats) = stats::collect(|| t.occludes(&r, 10.0, &mut xs));
+        assert!(occludes);
+        assert!(stats.triangle_tests < 4);
+        assert!(xs.is_empty());
+        // the tetrahedron is more than 4 units away
+        assert!(!t.occludes(&r, 4.0, &mut xs));
+    }
+
+    #[test]
+    fn vertex_normals_are_interpolated() {
+        let mut t = triangle();
+        t.set_vertex_normals(vec![
+            Vector::new(0, 1, 0),
+            Vector::new(-1, 0, 0),
+            Vector::new(1, 0, 0),
+        ]);
+        assert!(t.is_smooth());
+        let hit = FaceHit {
+            face: 0,
+            u: 0.45,
+            v: 0.25,
+        };
+        let i = Intersection::new_with_face(1, &t, hit);
+        let n = t.normal_at_hit(Point::new(0, 0, 0), &i);
+        assert_eq!(n, Vector::new(-0.5547, 0.83205, 0.0));
+    }
+
+    #[test]
+    fn smooth_normals_average_shared_vertices() {
+        let mut roof = roof();
+        roof.recompute_smooth_normals(None);
+        assert_eq!(roof.normals().len(), 6);
+        let c = Scalar::sqrt(2.0) / 2.0;
+        assert_eq!(roof.normals()[0], Vector::new(-c, c, 0.0));
+        assert_eq!(roof.normals()[2], Vector::new(0, 1, 0));
+        assert_eq!(roof.normals()[3], Vector::new(0, 1, 0));
+        assert_eq!(roof.normals()[5], Vector::new(c, c, 0.0));
+    }
+
+    #[test]
+    fn crease_angle_keeps_hard_edges() {
+        let mut roof = roof();
+        roof.recompute_smooth_normals(Some(PI / 4.0));
+        let c = Scalar::sqrt(2.0) / 2.0;
+        for face in 0..roof.faces().len() {
+            for normal in roof.face_normals()[face] {
+                assert_eq!(roof.normals()[normal], roof.face_normal(face));
+            }
+        }
+        assert_eq!(roof.face_normal(0), Vector::new(-c, c, 0.0));
+
+        roof.recompute_smooth_normals(Some(PI / 2.0 + 0.1));
+        let ridge = roof.face_normals()[1][1];
+        assert_eq!(roof.normals()[ridge], Vector::new(0, 1, 0));
+    }
+
+    #[test]
+    fn equal_meshes() {
+        let mut t = tetrahedron();
+        assert!(Shape::eq(&t, tetrahedron().as_any()));
+        assert!(!Shape::eq(&t, triangle().as_any()));
+        t.set_transformation_matrix(Mat4::new_scaling(2.0, 2.0, 2.0));
+        assert!(!Shape::eq(&t, tetrahedron().as_any()));
+    }
+
+    #[test]
+    fn subdivide_closed_mesh() {
+        let mut t = tetrahedron();
+        t.subdivide(1);
+        assert_eq!(t.vertices().len(), 10);
+        assert_eq!(t.faces().len(), 16);
+        // valence 3 vertices of a tetrahedron centered at the origin shrink to a quarter
+        assert_eq!(t.vertices()[0], Point::new(0.25, 0.25, 0.25));
+        // edge vertices end up at a quarter of the sum of their edge's ends
+        let edge_vertex = t.faces()[0][1];
+        assert_eq!(t.vertices()[edge_vertex], Point::new(0.5, 0.0, 0.0));
+        assert!(!t.is_smooth());
+    }
+
+    #[test]
+    fn subdivide_keeps_winding() {
+        let mut t = tetrahedron();
+        let outwards = t.face_normal(0).dot(Vector::new(1, 0, 0)).signum();
+        t.subdivide(2);
+        assert_eq!(t.faces().len(), 64);
+        for face in 0..t.faces().len() {
+            let center = t.faces()[face]
+                .iter()
+                .fold(Vector::new(0, 0, 0), |sum, &i| {
+                    sum + (t.vertices()[i] - Point::new(0, 0, 0))
+                });
+            assert_eq!(t.face_normal(face).dot(center).signum(), outwards);
+        }
+    }
+
+    #[test]
+    fn subdivide_boundary() {
+        let mut t = triangle();
+        t.subdivide(1);
+        assert_eq!(t.vertices().len(), 6);
+        assert_eq!(t.faces().len(), 4);
+        assert_eq!(t.vertices()[0], Point::new(0.0, 0.75, 0.0));
+        assert_eq!(t.vertices()[1], Point::new(-0.625, 0.125, 0.0));
+        let edge_vertex = t.faces()[0][1];
+        assert_eq!(t.vertices()[edge_vertex], Point::new(-0.5, 0.5, 0.0));
+    }
+
+    #[test]
+    fn subdivide_is_deterministic() {
+        let mut first = roof();
+        let mut second = roof();
+        first.subdivide(2);
+        second.subdivide(2);
+        assert_eq!(first.vertices(), second.vertices());
+        assert_eq!(first.faces(), second.faces());
+    }
+
+    #[test]
+    fn subdivide_recomputes_normals() {
+        let mut t = tetrahedron();
+        t.recompute_smooth_normals(None);
+        t.subdivide(1);
+        assert!(t.is_smooth());
+        assert_eq!(t.normals().len(), t.vertices().len());
+    }
+
+    #[test]
+    fn displace_flat() {
+        let mut t = triangle();
+        let normal = t.face_normal(0);
+        t.displace(1, &Pattern::solid(Color::new(0.5, 0.5, 0.5)), 2.0);
+        assert_eq!(t.vertices().len(), 6);
+        assert_eq!(t.faces().len(), 4);
+        assert_eq!(t.vertices()[0], Point::new(0, 1, 0) + normal);
+        // the new vertices are not smoothed
+        let edge_vertex = t.faces()[0][1];
+        assert_eq!(
+            t.vertices()[edge_vertex],
+            Point::new(-0.5, 0.5, 0.0) + normal
+        );
+        assert!(!t.is_smooth());
+    }
+
+    #[test]
+    fn displace_by_pattern() {
+        let mut t = triangle();
+        t.recompute_smooth_normals(None);
+        let normal = t.face_normal(0);
+        // white for 0 <= x < 1, black elsewhere on the triangle
+        let height = Pattern::stripe(WHITE, BLACK);
+        t.displace(2, &height, 0.1);
+        assert_eq!(t.faces().len(), 16);
+        assert!(t.is_smooth());
+        for vertex in t.vertices() {
+            let offset = (*vertex - Point::new(vertex.x, vertex.y, 0.0)).dot(normal);
+            let expected = if (0.0..1.0).contains(&vertex.x) {
+                0.1
+            } else {
+                0.0
+            };
+            assert!((offset - expected).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn clear_normals() {
+        let mut roof = roof();
+        roof.recompute_smooth_normals(None);
+        roof.clear_normals();
+        assert!(!roof.is_smooth());
+        assert!(roof.normals().is_empty());
+    }
+
+    #[test]
+    fn normal_without_hit_searches_face() {
+        let mut roof = roof();
+        roof.recompute_smooth_normals(None);
+        let n = roof.local_normal_at(Point::new(0, 1, 0));
+        assert_eq!(n, Vector::new(0, 1, 0));
+    }
+
+    #[test]
+    fn smooth_mesh_used_by_prepare_computations() {
+        let mut t = triangle();
+        t.set_vertex_normals(vec![
+            Vector::new(0, 1, 0),
+            Vector::new(-1, 0, 0),
+            Vector::new(1, 0, 0),
+        ]);
+        let r = Ray::new(Point::new(-0.2, 0.3, -2.0), Vector::new(0, 0, 1));
+        let mut xs = Vec::new();
+        t.intersect(&r, &mut xs);
+        let comps = xs[0].prepare_computations(&r, &xs);
+        assert_eq!(comps.normalv, Vector::new(-0.5547, 0.83205, 0.0));
+    }
+}
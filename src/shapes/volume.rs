@@ -0,0 +1,343 @@
+/// A volume of fog or smoke
+use std::any::Any;
+
+use crate::{
+    bounds::BoundingBox,
+    color::Color,
+    intersection::{Intersection, PreparedComputations},
+    light::Light,
+    material::{ColorType, Material},
+    matrix::{Mat4, IDENTITY_MATRIX_4},
+    ray::Ray,
+    sampling::Rng,
+    scalar::Scalar,
+    shapes::shape::Shape,
+    tuple::{Point, Vector},
+};
+
+use super::shape::{ObjectId, ShapeBound};
+
+/// Salt for the random distances rays travel into a volume, see [`Rng::from_point`]
+const VOLUME_SALT: u64 = 4;
+
+#[derive(Debug)]
+/// A volume of constant density filling another (convex) shape, like smoke or a fog bank.
+///
+/// Instead of being hit at its surface, a ray travels a random distance into the volume before it is scattered:
+/// the denser the volume, the shorter. Rays that get through without scattering continue unchanged.
+/// The scattering point is lit by every light without regard to direction, so shafts of light (god rays) appear where the volume is not in shadow.
+/// The result is noisy, which more samples per pixel (see [`crate::settings::RenderSettings::samples_per_pixel`]) smooth out.
+///
+/// The color of the material is the color of the scattered light, a specular highlight makes no sense for volumes and is off by default.
+pub struct Volume {
+    boundary: Box<dyn Shape>,
+    density: Scalar,
+    transformation_matrix: Mat4,
+    inverted_transformation_matrix: Mat4,
+    transposed_inverted_transformation_matrix: Mat4,
+    material: Material,
+    back_material: Option<Material>,
+    one_sided: bool,
+    normal_flipped: bool,
+    id: Option<ObjectId>,
+    shadow_bias: Option<Scalar>,
+}
+
+impl Volume {
+    /// A volume filling ```boundary``` with the given ```density``` (the chance of scattering per unit of distance), scattering light of ```color```
+    pub fn new(boundary: Box<dyn Shape>, density: Scalar, color: Color) -> Self {
+        let material = Material {
+            color: ColorType::Color(color),
+            specular: 0.0,
+            ..Default::default()
+        };
+        Self {
+            boundary,
+            density,
+            transformation_matrix: IDENTITY_MATRIX_4,
+            inverted_transformation_matrix: IDENTITY_MATRIX_4,
+            transposed_inverted_transformation_matrix: IDENTITY_MATRIX_4,
+            material,
+            back_material: None,
+            one_sided: false,
+            normal_flipped: false,
+            id: None,
+            shadow_bias: None,
+        }
+    }
+
+    /// The shape the volume fills
+    pub fn boundary(&self) -> &dyn Shape {
+        self.boundary.as_ref()
+    }
+
+    /// The chance of a ray scattering per unit of distance it travels through the volume
+    pub fn density(&self) -> Scalar {
+        self.density
+    }
+}
+
+// by hand, as the derived implementation cannot compare the boxed boundaries
+impl PartialEq for Volume {
+    fn eq(&self, other: &Self) -> bool {
+        *self.boundary == *other.boundary
+            && self.density == other.density
+            && self.transformation_matrix == other.transformation_matrix
+            && self.material == other.material
+            && self.back_material == other.back_material
+            && self.one_sided == other.one_sided
+            && self.normal_flipped == other.normal_flipped
+            && self.id == other.id
+            && self.shadow_bias == other.shadow_bias
+    }
+}
+
+impl ShapeBound for Volume {}
+
+impl Shape for Volume {
+    fn local_intersect<'a>(&'a self, ray: &Ray, intersections: &mut Vec<Intersection<'a>>) {
+        if self.density <= 0.0 {
+            return;
+        }
+        let mut boundary_hits = Vec::new();
+        self.boundary.intersect(ray, &mut boundary_hits);
+        let (Some(entry), Some(exit)) = (
+            boundary_hits.iter().map(|i| i.t).reduce(Scalar::min),
+            boundary_hits.iter().map(|i| i.t).reduce(Scalar::max),
+        ) else {
+            return;
+        };
+        // rays may start within the volume
+        let entry = entry.max(0.0);
+        if exit <= entry {
+            return;
+        }
+
+        let speed = ray.direction.magnitude();
+        let mut rng = Rng::from_point(ray.origin + ray.direction, VOLUME_SALT);
+        let distance = -(1.0 - rng.next_scalar()).ln() / self.density;
+        if distance < (exit - entry) * speed {
+            intersections.push(Intersection::new(entry + distance / speed, self));
+        }
+    }
+
+    fn local_bounds(&self) -> Option<BoundingBox> {
+        self.boundary.bounds()
+    }
+
+    /// Volumes have no surface, this only keeps shading from failing, see [`Self::render_at`]
+    fn local_normal_at(&self, _p: Point) -> Vector {
+        Vector::new(0, 1, 0)
+    }
+
+    fn render_at(
+        &self,
+        comps: &PreparedComputations,
+        light: &dyn Light,
+        transmission: Color,
+    ) -> Color {
+        // scattering the same amount in every direction: shaded as if the surface faced the light
+        let towards_light = light.direction_from(comps.point);
+        self.material.lighting(
+            light,
+            self,
+            comps.point,
+            comps.eyev,
+            towards_light,
+            transmission,
+        )
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn transformation_matrix(&self) -> Mat4 {
+        self.transformation_matrix
+    }
+
+    fn inverse_transformation_matrix(&self) -> Mat4 {
+        self.inverted_transformation_matrix
+    }
+
+    fn inverse_of_transpose_of_transformation_matrix(&self) -> Mat4 {
+        self.transposed_inverted_transformation_matrix
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn eq(&self, other: &dyn Any) -> bool {
+        other.downcast_ref::<Self>() == Some(self)
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    fn set_transformation_matrix(&mut self, matrix: Mat4) {
+        self.transformation_matrix = matrix;
+        self.inverted_transformation_matrix = matrix.inverse();
+        self.transposed_inverted_transformation_matrix =
+            self.inverted_transformation_matrix.transpose();
+    }
+
+    fn set_material(&mut self, m: Material) {
+        self.material = m;
+    }
+
+    fn back_material(&self) -> Option<&Material> {
+        self.back_material.as_ref()
+    }
+
+    fn set_back_material(&mut self, m: Option<Material>) {
+        self.back_material = m;
+    }
+
+    fn one_sided(&self) -> bool {
+        self.one_sided
+    }
+
+    fn set_one_sided(&mut self, one_sided: bool) {
+        self.one_sided = one_sided;
+    }
+
+    fn normal_flipped(&self) -> bool {
+        self.normal_flipped
+    }
+
+    fn set_normal_flipped(&mut self, flipped: bool) {
+        self.normal_flipped = flipped;
+    }
+
+    fn id(&self) -> Option<ObjectId> {
+        self.id
+    }
+
+    fn set_id(&mut self, id: Option<ObjectId>) {
+        self.id = id;
+    }
+
+    fn shadow_bias(&self) -> Option<Scalar> {
+        self.shadow_bias
+    }
+
+    fn set_shadow_bias(&mut self, bias: Option<Scalar>) {
+        self.shadow_bias = bias;
+    }
+
+    fn as_shape(&self) -> &dyn Shape {
+        self
+    }
+}
+
+#[cfg(test)]
+mod volume_tests {
+    use crate::{
+        color::{Color, WHITE},
+        light::PointLight,
+        matrix::Mat4,
+        ray::Ray,
+        scalar::Scalar,
+        shapes::{plane::Plane, shape::Shape, sphere::Sphere, volume::Volume},
+        transform::Transform,
+        tuple::{Point, Vector},
+        world::World,
+    };
+
+    fn smoke(density: Scalar) -> Volume {
+        Volume::new(Box::new(Sphere::default()), density, WHITE)
+    }
+
+    /// The fraction of rays through the center of a unit sphere volume that scatter
+    fn scattered(volume: &Volume, origin_z: Scalar) -> Scalar {
+        let mut scattered = 0;
+        for i in 0..2000 {
+            // a slightly different origin for every ray, so every ray samples its own distance
+            let origin = Point::new(i as Scalar * 1e-9, 0.0, origin_z);
+            let mut xs = Vec::new();
+            volume.intersect(&Ray::new(origin, Vector::new(0, 0, 1)), &mut xs);
+            if let Some(x) = xs.first() {
+                assert!(x.t >= (-1.0 - origin_z).max(0.0) && x.t <= 1.0 - origin_z);
+                scattered += 1;
+            }
+        }
+        scattered as Scalar / 2000.0
+    }
+
+    #[test]
+    fn scatters_with_density() {
+        // a ray crossing 2 units of the volume scatters with a chance of 1 - e^(-2 * density)
+        let expected = 1.0 - Scalar::exp(-2.0 * 0.5);
+        assert!((scattered(&smoke(0.5), -5.0) - expected).abs() < 0.05);
+        assert!(scattered(&smoke(50.0), -5.0) > 0.99);
+        assert_eq!(scattered(&smoke(0.0), -5.0), 0.0);
+        // from the center, only 1 unit is left
+        let expected = 1.0 - Scalar::exp(-0.5);
+        assert!((scattered(&smoke(0.5), 0.0) - expected).abs() < 0.05);
+    }
+
+    #[test]
+    fn misses_outside_boundary() {
+        let volume = smoke(50.0);
+        let mut xs = Vec::new();
+        volume.intersect(
+            &Ray::new(Point::new(2, 0, -5), Vector::new(0, 0, 1)),
+            &mut xs,
+        );
+        assert!(xs.is_empty());
+        assert_eq!(
+            volume.bounds(),
+            Sphere::default().bounds(),
+            "the bounds are the boundary's"
+        );
+    }
+
+    #[test]
+    fn equal_volumes() {
+        let fog = |density| Volume::new(Box::new(Plane::default()), density, WHITE);
+        assert!(Shape::eq(&fog(0.5), fog(0.5).as_any()));
+        assert!(!Shape::eq(&fog(0.5), fog(0.25).as_any()));
+        assert!(!Shape::eq(&fog(0.5), Plane::default().as_any()));
+    }
+
+    #[test]
+    fn transformed_volume() {
+        let volume = smoke(50.0).translated(5, 0, 0);
+        let mut xs = Vec::new();
+        volume.intersect(
+            &Ray::new(Point::new(5, 0, -5), Vector::new(0, 0, 1)),
+            &mut xs,
+        );
+        assert_eq!(xs.len(), 1);
+        assert!(xs[0].t >= 4.0 && xs[0].t < 4.2);
+    }
+
+    #[test]
+    fn lit_regardless_of_direction() {
+        let mut w = World::default();
+        w.add_light(Box::new(PointLight::new(Point::new(0, 10, 0), WHITE)));
+        // a thin layer of smoke, so it barely shadows itself
+        w.add_object(Box::new(Volume::new(
+            Box::new(Sphere::default().scaled(1.0, 0.001, 1.0)),
+            10.0,
+            WHITE,
+        )));
+        let r = Ray::new(Point::new(0, 0, -5), Vector::new(0, 0, 1));
+        let lit = w.color_at(&r, &mut Vec::new(), 0);
+        // the light is above and the ray comes from the side, still the full diffuse light arrives
+        assert_eq!(lit, Color::new(1.0, 1.0, 1.0));
+
+        // a wall between the light and the smoke leaves only the ambient light
+        let mut wall = Sphere::default();
+        wall.set_transformation_matrix(
+            Mat4::new_translation(0, 5, 0) * Mat4::new_scaling(5.0, 0.1, 5.0),
+        );
+        w.add_object(Box::new(wall));
+        assert_eq!(
+            w.color_at(&r, &mut Vec::new(), 0),
+            Color::new(0.1, 0.1, 0.1)
+        );
+    }
+}
@@ -0,0 +1,107 @@
+//! A background for rays that do not hit any object.
+use crate::{
+    color::Color,
+    material::ColorType,
+    matrix::{Mat4, IDENTITY_MATRIX_4},
+    tuple::{Point, Vector},
+};
+
+#[derive(Clone, Debug, PartialEq)]
+/// An infinitely distant sphere around the world.
+/// Every ray that misses all objects (camera rays as well as reflected and refracted ones) takes the skybox's color in the ray's direction.
+///
+/// Patterns are evaluated on the unit sphere: a ray pointing in direction (x, y, z) sees the pattern at the point (x, y, z).
+/// The position of a ray's origin does not matter, as the sky is infinitely far away.
+pub struct Skybox {
+    color: ColorType,
+    transformation_matrix: Mat4,
+    inverted_transformation_matrix: Mat4,
+}
+
+impl Skybox {
+    /// Creates a new skybox with the given plain color or pattern.
+    pub fn new(color: ColorType) -> Self {
+        Self {
+            color,
+            transformation_matrix: IDENTITY_MATRIX_4,
+            inverted_transformation_matrix: IDENTITY_MATRIX_4,
+        }
+    }
+
+    /// Returns the color or pattern of the sky
+    pub fn color(&self) -> &ColorType {
+        &self.color
+    }
+
+    /// Replaces the color or pattern of the sky
+    pub fn set_color(&mut self, color: ColorType) {
+        self.color = color;
+    }
+
+    /// Returns the transformation matrix of the skybox
+    pub fn transformation_matrix(&self) -> Mat4 {
+        self.transformation_matrix
+    }
+
+    /// Sets the transformation matrix of the skybox. Only rotations (and mirroring) are meaningful, as the sky is infinitely far away.
+    pub fn set_transformation_matrix(&mut self, matrix: Mat4) {
+        self.transformation_matrix = matrix;
+        self.inverted_transformation_matrix = matrix.inverse();
+    }
+
+    /// The color of the sky seen when looking into the given direction (world space).
+    pub fn color_at(&self, direction: Vector) -> Color {
+        match &self.color {
+            ColorType::Color(color) => *color,
+            ColorType::Pattern(pattern) => {
+                let direction = (self.inverted_transformation_matrix * direction).normalized();
+                pattern.apply_pattern(Point::new(direction.x, direction.y, direction.z))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod skybox_tests {
+    use crate::{
+        color::{Color, BLACK, WHITE},
+        material::ColorType,
+        matrix::Mat4,
+        pattern::Pattern,
+        scalar::{consts::PI, Scalar},
+        tuple::Vector,
+    };
+
+    use super::Skybox;
+
+    #[test]
+    fn plain_color_everywhere() {
+        let sky = Skybox::new(ColorType::Color(Color::new(0.2, 0.4, 0.8)));
+        assert_eq!(
+            sky.color_at(Vector::new(0, 1, 0)),
+            Color::new(0.2, 0.4, 0.8)
+        );
+        assert_eq!(
+            sky.color_at(Vector::new(1, -1, 3)),
+            Color::new(0.2, 0.4, 0.8)
+        );
+    }
+
+    #[test]
+    fn pattern_on_unit_sphere() {
+        let sky = Skybox::new(ColorType::Pattern(Pattern::test_pattern()));
+        assert_eq!(sky.color_at(Vector::new(0, 2, 0)), Color::new(0, 1, 0));
+        assert_eq!(
+            sky.color_at(Vector::new(1, 1, 0)),
+            Color::new(Scalar::sqrt(2.0) / 2.0, Scalar::sqrt(2.0) / 2.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn transformed() {
+        let mut sky = Skybox::new(ColorType::Pattern(Pattern::stripe(WHITE, BLACK)));
+        assert_eq!(sky.color_at(Vector::new(-1, 0, 1)), BLACK);
+        sky.set_transformation_matrix(Mat4::new_rotation_y(PI));
+        assert_eq!(sky.color_at(Vector::new(-1, 0, 1)), WHITE);
+    }
+}
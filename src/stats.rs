@@ -0,0 +1,160 @@
+//! Counters of the work done while rendering, to measure the effect of optimizations beyond wall-clock time
+use std::cell::Cell;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+/// What a render did, see [`crate::camera::Camera::render_with_stats`]
+pub struct RenderStats {
+    /// Rays cast from the camera
+    pub camera_rays: u64,
+    /// Rays cast towards lights to test for shadows
+    pub shadow_rays: u64,
+    /// Rays cast for reflections
+    pub reflection_rays: u64,
+    /// Rays cast for refractions
+    pub refraction_rays: u64,
+    /// Rays cast to sample ambient occlusion, global illumination or path tracing bounces
+    pub sampling_rays: u64,
+    /// Tests of a ray against an object
+    pub intersection_tests: u64,
+    /// Tests of a ray against a bounding box, of an object or a node of the world's bounding volume hierarchy
+    pub bounds_tests: u64,
+    /// Tests of a ray against a single triangle of a mesh
+    pub triangle_tests: u64,
+    /// The deepest nesting of reflected and refracted rays, 1 meaning only camera rays were traced
+    pub max_depth: u64,
+}
+
+impl RenderStats {
+    /// The number of rays of all kinds
+    pub fn total_rays(&self) -> u64 {
+        self.camera_rays
+            + self.shadow_rays
+            + self.reflection_rays
+            + self.refraction_rays
+            + self.sampling_rays
+    }
+
+    /// Adds the counters of ```other```, e.g. of another thread
+    pub fn merge(&mut self, other: &RenderStats) {
+        self.camera_rays += other.camera_rays;
+        self.shadow_rays += other.shadow_rays;
+        self.reflection_rays += other.reflection_rays;
+        self.refraction_rays += other.refraction_rays;
+        self.sampling_rays += other.sampling_rays;
+        self.intersection_tests += other.intersection_tests;
+        self.bounds_tests += other.bounds_tests;
+        self.triangle_tests += other.triangle_tests;
+        self.max_depth = self.max_depth.max(other.max_depth);
+    }
+}
+
+thread_local! {
+    /// The counters of the current thread, None while no statistics are collected
+    static STATS: Cell<Option<RenderStats>> = const { Cell::new(None) };
+    /// The current nesting of traced rays
+    static DEPTH: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Updates the counters of the current thread, if statistics are being collected
+pub(crate) fn record(update: impl FnOnce(&mut RenderStats)) {
+    STATS.with(|stats| {
+        if let Some(mut current) = stats.get() {
+            update(&mut current);
+            stats.set(Some(current));
+        }
+    });
+}
+
+/// Marks that a ray is traced one level deeper, until the returned guard is dropped
+pub(crate) fn enter_ray() -> DepthGuard {
+    let depth = DEPTH.with(|depth| {
+        depth.set(depth.get() + 1);
+        depth.get()
+    });
+    record(|stats| stats.max_depth = stats.max_depth.max(depth));
+    DepthGuard
+}
+
+/// Leaves the nesting level entered by [`enter_ray`] when dropped
+#[derive(Debug)]
+pub(crate) struct DepthGuard;
+
+impl Drop for DepthGuard {
+    fn drop(&mut self) {
+        DEPTH.with(|depth| depth.set(depth.get().saturating_sub(1)));
+    }
+}
+
+/// Runs ```f``` on the current thread, counting what it does
+pub(crate) fn collect<T>(f: impl FnOnce() -> T) -> (T, RenderStats) {
+    let previous = STATS.with(|stats| stats.replace(Some(RenderStats::default())));
+    let result = f();
+    let collected = STATS
+        .with(|stats| stats.replace(previous))
+        .unwrap_or_default();
+    // nested collections also count towards the outer one
+    record(|stats| stats.merge(&collected));
+    (result, collected)
+}
+
+#[cfg(test)]
+mod stats_tests {
+    use crate::stats::{collect, enter_ray, record, RenderStats};
+
+    #[test]
+    fn only_recorded_while_collecting() {
+        record(|stats| stats.camera_rays += 1);
+        let (value, stats) = collect(|| {
+            record(|stats| stats.camera_rays += 1);
+            record(|stats| stats.shadow_rays += 2);
+            5
+        });
+        assert_eq!(value, 5);
+        assert_eq!(stats.camera_rays, 1);
+        assert_eq!(stats.total_rays(), 3);
+        let (_, stats) = collect(|| ());
+        assert_eq!(stats, RenderStats::default());
+    }
+
+    #[test]
+    fn nested() {
+        let (inner, outer) = collect(|| {
+            record(|stats| stats.sampling_rays += 1);
+            collect(|| record(|stats| stats.sampling_rays += 2)).1
+        });
+        assert_eq!(inner.sampling_rays, 2);
+        assert_eq!(outer.sampling_rays, 3);
+    }
+
+    #[test]
+    fn depth() {
+        let (_, stats) = collect(|| {
+            let _camera = enter_ray();
+            {
+                let _reflection = enter_ray();
+                let _refraction = enter_ray();
+            }
+            let _reflection = enter_ray();
+        });
+        assert_eq!(stats.max_depth, 3);
+    }
+
+    #[test]
+    fn merge() {
+        let mut a = RenderStats {
+            camera_rays: 1,
+            max_depth: 4,
+            ..Default::default()
+        };
+        let b = RenderStats {
+            camera_rays: 2,
+            triangle_tests: 3,
+            max_depth: 2,
+            ..Default::default()
+        };
+        a.merge(&b);
+        assert_eq!(a.camera_rays, 3);
+        assert_eq!(a.triangle_tests, 3);
+        assert_eq!(a.max_depth, 4);
+    }
+}
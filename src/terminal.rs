@@ -0,0 +1,96 @@
+//! Previews of canvases printed to the terminal, e.g. to check a render over SSH before copying the image file.
+use std::fmt::Write;
+
+use crate::{
+    canvas::{Canvas, Resampling},
+    scalar::Scalar,
+};
+
+/// Characters for ASCII previews, from dark to bright
+const RAMP: &[u8] = b" .:-=+*#%@";
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// How a terminal preview shows colors
+pub enum TerminalColors {
+    /// 24 bit ANSI colors, with two pixels per character (the upper one drawn as a half block)
+    TrueColor,
+    /// Characters of increasing brightness, for terminals without colors
+    Ascii,
+}
+
+/// The canvas scaled to ```columns``` characters wide, as text for printing to the terminal.
+/// The height follows the aspect ratio of the canvas, as characters are about twice as high as wide.
+/// Colors are converted to sRGB, see [`crate::color::Color::to_rgb8`], alpha is ignored.
+pub fn terminal_preview(canvas: &Canvas, columns: usize, colors: TerminalColors) -> String {
+    let mut text = String::new();
+    if canvas.width() == 0 || canvas.height() == 0 || columns == 0 {
+        return text;
+    }
+    let lines = (canvas.height() as Scalar * columns as Scalar / canvas.width() as Scalar / 2.0)
+        .round()
+        .max(1.0) as usize;
+
+    match colors {
+        TerminalColors::TrueColor => {
+            let small = canvas.resized(columns, lines * 2, Resampling::Bilinear);
+            let rows: Vec<_> = small.rows().collect();
+            for pair in rows.chunks(2) {
+                for (upper, lower) in pair[0].iter().zip(pair[1]) {
+                    let [r, g, b] = upper.to_rgb8();
+                    let [br, bg, bb] = lower.to_rgb8();
+                    write!(
+                        text,
+                        "\x1b[38;2;{r};{g};{b}m\x1b[48;2;{br};{bg};{bb}m\u{2580}"
+                    )
+                    .expect("writing to a string does not fail");
+                }
+                text.push_str("\x1b[0m\n");
+            }
+        }
+        TerminalColors::Ascii => {
+            let small = canvas.resized(columns, lines, Resampling::Bilinear);
+            for row in small.rows() {
+                for color in row {
+                    let [r, g, b] = color.to_rgb8().map(|c| c as Scalar / 255.0);
+                    let luminance = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+                    let index = (luminance * (RAMP.len() - 1) as Scalar).round() as usize;
+                    text.push(RAMP[index] as char);
+                }
+                text.push('\n');
+            }
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod terminal_tests {
+    use crate::{
+        canvas::Canvas,
+        color::{Color, WHITE},
+        terminal::{terminal_preview, TerminalColors},
+    };
+
+    #[test]
+    fn ascii() {
+        let mut canvas = Canvas::new(4, 4);
+        canvas.write_pixel(0, 0, WHITE).unwrap();
+        canvas.write_pixel(0, 1, WHITE).unwrap();
+        assert_eq!(
+            terminal_preview(&canvas, 4, TerminalColors::Ascii),
+            "@   \n    \n"
+        );
+        assert_eq!(terminal_preview(&canvas, 0, TerminalColors::Ascii), "");
+    }
+
+    #[test]
+    fn true_color() {
+        let mut canvas = Canvas::new(2, 2);
+        canvas.write_pixel(1, 0, Color::new(1, 0, 0)).unwrap();
+        let preview = terminal_preview(&canvas, 2, TerminalColors::TrueColor);
+        assert_eq!(
+            preview,
+            "\x1b[38;2;0;0;0m\x1b[48;2;0;0;0m\u{2580}\x1b[38;2;255;0;0m\x1b[48;2;0;0;0m\u{2580}\x1b[0m\n"
+        );
+    }
+}
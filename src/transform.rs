@@ -0,0 +1,84 @@
+use crate::matrix::Mat4;
+use crate::scalar::IntoScalar;
+
+/// Chainable transformations for anything that has a transformation matrix, i.e. shapes and patterns, and for matrices themselves.
+///
+/// Each call applies its transformation after the ones before, so
+/// ```
+/// use raytracerchallenge::{shapes::sphere::Sphere, transform::Transform};
+/// let sphere = Sphere::default().scaled(2, 2, 2).translated(0, 1, 0);
+/// ```
+/// first scales the sphere and then moves it up.
+pub trait Transform: Sized {
+    /// Applies ```matrix``` after the current transformation
+    fn transformed(self, matrix: Mat4) -> Self;
+
+    /// Moves by x, y and z
+    fn translated<T: IntoScalar>(self, x: T, y: T, z: T) -> Self {
+        self.transformed(Mat4::new_translation(x, y, z))
+    }
+
+    /// Scales by x, y and z
+    fn scaled<T: IntoScalar>(self, x: T, y: T, z: T) -> Self {
+        self.transformed(Mat4::new_scaling(x, y, z))
+    }
+
+    /// Rotates around the x axis by ```r``` radians
+    fn rotated_x<T: IntoScalar>(self, r: T) -> Self {
+        self.transformed(Mat4::new_rotation_x(r))
+    }
+
+    /// Rotates around the y axis by ```r``` radians
+    fn rotated_y<T: IntoScalar>(self, r: T) -> Self {
+        self.transformed(Mat4::new_rotation_y(r))
+    }
+
+    /// Rotates around the z axis by ```r``` radians
+    fn rotated_z<T: IntoScalar>(self, r: T) -> Self {
+        self.transformed(Mat4::new_rotation_z(r))
+    }
+
+    /// Shears, see [`Mat4::new_shearing`]
+    fn sheared<T: IntoScalar>(self, x_y: T, x_z: T, y_x: T, y_z: T, z_x: T, z_y: T) -> Self {
+        self.transformed(Mat4::new_shearing(x_y, x_z, y_x, y_z, z_x, z_y))
+    }
+}
+
+#[cfg(test)]
+mod transform_tests {
+    use crate::{
+        color::{BLACK, WHITE},
+        matrix::Mat4,
+        pattern::Pattern,
+        scalar::consts::FRAC_PI_2,
+        shapes::{shape::Shape, sphere::Sphere},
+        transform::Transform,
+        tuple::Point,
+    };
+
+    #[test]
+    fn chained_shape_transformations() {
+        let sphere = Sphere::default()
+            .scaled(2, 2, 2)
+            .rotated_y(FRAC_PI_2)
+            .translated(0, 1, 0);
+        let expected = Mat4::new_translation(0, 1, 0)
+            * Mat4::new_rotation_y(FRAC_PI_2)
+            * Mat4::new_scaling(2, 2, 2);
+        assert_eq!(sphere.transformation_matrix(), expected);
+        assert_eq!(
+            sphere.transformation_matrix() * Point::new(1, 0, 0),
+            Point::new(0, 1, -2)
+        );
+    }
+
+    #[test]
+    fn chained_pattern_transformations() {
+        let pattern = Pattern::stripe(WHITE, BLACK)
+            .scaled(0.5, 1.0, 1.0)
+            .translated(0.25, 0.0, 0.0);
+        assert_eq!(pattern.apply_pattern(Point::new(0.3, 0.0, 0.0)), WHITE);
+        assert_eq!(pattern.apply_pattern(Point::new(0.8, 0.0, 0.0)), BLACK);
+        assert_eq!(pattern.apply_pattern(Point::new(0.2, 0.0, 0.0)), BLACK);
+    }
+}
@@ -1,41 +1,45 @@
-use std::ops::{Add, Div, Mul, Neg, Sub};
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 
-use crate::epsilon::EpsilonEqual;
+use crate::epsilon::{EpsilonEqual, EPSILON};
+use crate::scalar::{consts::PI, IntoScalar, Scalar};
 
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A point in 3d euclidan space (left handed coordinates)
 pub struct Point {
     /// x coordinate
-    pub x: f64,
+    pub x: Scalar,
     /// y coordinate
-    pub y: f64,
+    pub y: Scalar,
     /// z coordinate
-    pub z: f64,
+    pub z: Scalar,
 }
 
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A vector in 3d euclidean space (left handed coordinates)
 pub struct Vector {
     /// x direction
-    pub x: f64,
+    pub x: Scalar,
     /// y direction
-    pub y: f64,
+    pub y: Scalar,
     /// z direction
-    pub z: f64,
+    pub z: Scalar,
 }
 
 impl Vector {
     /// A new vector
-    pub fn new<T: Into<f64>>(x: T, y: T, z: T) -> Self {
+    pub fn new<T: IntoScalar>(x: T, y: T, z: T) -> Self {
         Self {
-            x: x.into(),
-            y: y.into(),
-            z: z.into(),
+            x: x.into_scalar(),
+            y: y.into_scalar(),
+            z: z.into_scalar(),
         }
     }
 
     /// constant new function, used to make new generic
-    pub const fn const_new(x: f64, y: f64, z: f64) -> Self {
+    pub const fn const_new(x: Scalar, y: Scalar, z: Scalar) -> Self {
         Self { x, y, z }
     }
 
@@ -69,12 +73,12 @@ impl Vector {
     }
 
     /// Computes the dot product of self.other
-    pub fn dot(&self, rhs: Self) -> f64 {
+    pub fn dot(&self, rhs: Self) -> Scalar {
         self.x * rhs.x + self.y * rhs.y + self.z * rhs.z
     }
 
     /// Computes the magnitude of this vector
-    pub fn magnitude(&self) -> f64 {
+    pub fn magnitude(&self) -> Scalar {
         (self.x.powi(2) + self.y.powi(2) + self.z.powi(2)).sqrt()
     }
 
@@ -82,20 +86,56 @@ impl Vector {
     pub fn reflect(&self, p: Vector) -> Vector {
         *self - p * 2.0 * self.dot(p)
     }
+
+    /// Linear interpolation: this vector for ```t``` = 0, ```other``` for ```t``` = 1
+    pub fn lerp(&self, other: Vector, t: Scalar) -> Vector {
+        *self * (1.0 - t) + other * t
+    }
+
+    /// Spherical interpolation between two normalized vectors: turns from this vector (```t``` = 0) to ```other``` (```t``` = 1) at a constant angular speed, staying normalized.
+    /// Opposite vectors are turned around an arbitrary perpendicular axis.
+    pub fn slerp(&self, other: Vector, t: Scalar) -> Vector {
+        let cos_angle = self.dot(other).clamp(-1.0, 1.0);
+        let angle = cos_angle.acos();
+        if angle < EPSILON {
+            return self.lerp(other, t).normalized();
+        }
+        // the direction perpendicular to this vector, in the plane of both, to turn towards
+        let towards = if angle > PI - EPSILON {
+            let axis = if self.x.abs() < 0.9 {
+                Vector::new(1, 0, 0)
+            } else {
+                Vector::new(0, 1, 0)
+            };
+            self.cross(axis).normalized()
+        } else {
+            (other - *self * cos_angle).normalized()
+        };
+        *self * (angle * t).cos() + towards * (angle * t).sin()
+    }
 }
 
 impl Point {
     /// Creates a new point at (x,y,z)
-    pub fn new<R: Into<f64>, S: Into<f64>, T: Into<f64>>(x: R, y: S, z: T) -> Self {
+    pub fn new<R: IntoScalar, S: IntoScalar, T: IntoScalar>(x: R, y: S, z: T) -> Self {
         Self {
-            x: x.into(),
-            y: y.into(),
-            z: z.into(),
+            x: x.into_scalar(),
+            y: y.into_scalar(),
+            z: z.into_scalar(),
         }
     }
 
+    /// Linear interpolation: this point for ```t``` = 0, ```other``` for ```t``` = 1
+    pub fn lerp(&self, other: Point, t: Scalar) -> Point {
+        Point::new(
+            self.x * (1.0 - t) + other.x * t,
+            self.y * (1.0 - t) + other.y * t,
+            self.z * (1.0 - t) + other.z * t,
+        )
+    }
+
     /// A constant function to create a new point at (x,y,z). Used to make new generic.
-    pub fn const_new(x: f64, y: f64, z: f64) -> Self {
+    pub fn const_new(x: Scalar, y: Scalar, z: Scalar) -> Self {
         Self { x, y, z }
     }
 }
@@ -120,6 +160,12 @@ impl Add<Vector> for Point {
     }
 }
 
+impl AddAssign<Vector> for Point {
+    fn add_assign(&mut self, rhs: Vector) {
+        *self = *self + rhs;
+    }
+}
+
 impl Add for Vector {
     type Output = Self;
 
@@ -128,6 +174,18 @@ impl Add for Vector {
     }
 }
 
+impl AddAssign for Vector {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sum for Vector {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Vector::const_new(0.0, 0.0, 0.0), Add::add)
+    }
+}
+
 impl Sub for Point {
     type Output = Vector;
 
@@ -144,6 +202,25 @@ impl Sub<Vector> for Point {
     }
 }
 
+impl SubAssign<Vector> for Point {
+    fn sub_assign(&mut self, rhs: Vector) {
+        *self = *self - rhs;
+    }
+}
+
+impl Neg for Point {
+    type Output = Self;
+
+    /// Mirrors the point at the origin
+    fn neg(self) -> Self::Output {
+        Point {
+            x: -self.x,
+            y: -self.y,
+            z: -self.z,
+        }
+    }
+}
+
 impl Sub for Vector {
     type Output = Self;
 
@@ -152,6 +229,12 @@ impl Sub for Vector {
     }
 }
 
+impl SubAssign for Vector {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
 impl Neg for Vector {
     type Output = Self;
 
@@ -164,10 +247,10 @@ impl Neg for Vector {
     }
 }
 
-impl Mul<f64> for Vector {
+impl Mul<Scalar> for Vector {
     type Output = Self;
 
-    fn mul(self, rhs: f64) -> Self::Output {
+    fn mul(self, rhs: Scalar) -> Self::Output {
         Self {
             x: self.x * rhs,
             y: self.y * rhs,
@@ -176,10 +259,24 @@ impl Mul<f64> for Vector {
     }
 }
 
-impl Div<f64> for Vector {
+impl Mul<Vector> for Scalar {
+    type Output = Vector;
+
+    fn mul(self, rhs: Vector) -> Self::Output {
+        rhs * self
+    }
+}
+
+impl MulAssign<Scalar> for Vector {
+    fn mul_assign(&mut self, rhs: Scalar) {
+        *self = *self * rhs;
+    }
+}
+
+impl Div<Scalar> for Vector {
     type Output = Self;
 
-    fn div(self, rhs: f64) -> Self::Output {
+    fn div(self, rhs: Scalar) -> Self::Output {
         Self {
             x: self.x / rhs,
             y: self.y / rhs,
@@ -188,8 +285,48 @@ impl Div<f64> for Vector {
     }
 }
 
+impl DivAssign<Scalar> for Vector {
+    fn div_assign(&mut self, rhs: Scalar) {
+        *self = *self / rhs;
+    }
+}
+
+macro_rules! impl_array_conversions {
+    ($($t:ty),*) => {
+        $(
+            impl From<[Scalar; 3]> for $t {
+                fn from([x, y, z]: [Scalar; 3]) -> Self {
+                    Self { x, y, z }
+                }
+            }
+
+            impl From<(Scalar, Scalar, Scalar)> for $t {
+                fn from((x, y, z): (Scalar, Scalar, Scalar)) -> Self {
+                    Self { x, y, z }
+                }
+            }
+
+            impl From<$t> for [Scalar; 3] {
+                fn from(value: $t) -> Self {
+                    [value.x, value.y, value.z]
+                }
+            }
+
+            impl From<$t> for (Scalar, Scalar, Scalar) {
+                fn from(value: $t) -> Self {
+                    (value.x, value.y, value.z)
+                }
+            }
+        )*
+    };
+}
+
+impl_array_conversions!(Point, Vector);
+
 #[cfg(test)]
 mod tuple_tests {
+    use crate::epsilon::EpsilonEqual;
+    use crate::scalar::Scalar;
     use crate::tuple::{Point, Vector};
     #[test]
     fn test_new_point() {
@@ -302,6 +439,47 @@ mod tuple_tests {
         assert_eq!(a / 2.0, reference);
     }
 
+    #[test]
+    fn scalar_on_the_left() {
+        let a = Vector::new(1.0, -2.0, 3.0);
+        assert_eq!(3.5 * a, a * 3.5);
+    }
+
+    #[test]
+    fn negate_point() {
+        assert_eq!(-Point::new(1.0, -2.0, 3.0), Point::new(-1.0, 2.0, -3.0));
+    }
+
+    #[test]
+    fn assign_operators() {
+        let mut p = Point::new(1, 2, 3);
+        p += Vector::new(1, 1, 1);
+        assert_eq!(p, Point::new(2, 3, 4));
+        p -= Vector::new(2, 0, 0);
+        assert_eq!(p, Point::new(0, 3, 4));
+
+        let mut v = Vector::new(1, 2, 3);
+        v += Vector::new(1, 1, 1);
+        assert_eq!(v, Vector::new(2, 3, 4));
+        v -= Vector::new(0, 1, 0);
+        assert_eq!(v, Vector::new(2, 2, 4));
+        v *= 2.0;
+        assert_eq!(v, Vector::new(4, 4, 8));
+        v /= 4.0;
+        assert_eq!(v, Vector::new(1, 1, 2));
+    }
+
+    #[test]
+    fn sum_vectors() {
+        let vectors = [
+            Vector::new(1, 0, 0),
+            Vector::new(0, 2, 0),
+            Vector::new(1, 0, 3),
+        ];
+        assert_eq!(vectors.into_iter().sum::<Vector>(), Vector::new(2, 2, 3));
+        assert_eq!(std::iter::empty().sum::<Vector>(), Vector::new(0, 0, 0));
+    }
+
     #[test]
     fn magnitude() {
         let v = Vector::new(1.0, 0.0, 0.0);
@@ -311,9 +489,9 @@ mod tuple_tests {
         let v = Vector::new(0.0, 0.0, 1.0);
         assert_eq!(v.magnitude(), 1.0);
         let v = Vector::new(1.0, 2.0, 3.0);
-        assert_eq!(v.magnitude(), 14.0f64.sqrt());
+        assert_eq!(v.magnitude(), Scalar::sqrt(14.0));
         let v = Vector::new(-1.0, -2.0, -3.0);
-        assert_eq!(v.magnitude(), 14.0f64.sqrt());
+        assert_eq!(v.magnitude(), Scalar::sqrt(14.0));
     }
 
     #[test]
@@ -357,6 +535,51 @@ mod tuple_tests {
         assert_eq!(b.cross(a), cross_b_a);
     }
 
+    #[test]
+    fn lerp() {
+        let a = Point::new(1, 2, 3);
+        let b = Point::new(3, 2, -1);
+        assert_eq!(a.lerp(b, 0.0), a);
+        assert_eq!(a.lerp(b, 0.5), Point::new(2, 2, 1));
+        assert_eq!(a.lerp(b, 1.0), b);
+
+        let v = Vector::new(0, 4, 0);
+        assert_eq!(
+            v.lerp(Vector::new(2, 0, 0), 0.25),
+            Vector::new(0.5, 3.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn slerp() {
+        let a = Vector::new(1, 0, 0);
+        let b = Vector::new(0, 1, 0);
+        assert_eq!(a.slerp(b, 0.0), a);
+        assert_eq!(a.slerp(b, 1.0), b);
+        let half = Scalar::sqrt(2.0) / 2.0;
+        assert_eq!(a.slerp(b, 0.5), Vector::new(half, half, 0.0));
+        // a third of the way is 30 degrees, not the normalized linear interpolation
+        let third = a.slerp(b, 1.0 / 3.0);
+        assert_eq!(third, Vector::new(Scalar::sqrt(3.0) / 2.0, 0.5, 0.0));
+
+        assert_eq!(a.slerp(a, 0.5), a);
+        let turned = a.slerp(-a, 0.5);
+        assert!(turned.magnitude().e_equals(1.0));
+        assert!(turned.dot(a).e_equals(0.0));
+        assert_eq!(a.slerp(-a, 1.0), -a);
+    }
+
+    #[test]
+    fn array_conversions() {
+        let p = Point::from([1.0, 2.0, 3.0]);
+        assert_eq!(p, Point::new(1, 2, 3));
+        assert_eq!(<[Scalar; 3]>::from(p), [1.0, 2.0, 3.0]);
+        let v: Vector = (1.0, -2.0, 0.5).into();
+        assert_eq!(v, Vector::new(1.0, -2.0, 0.5));
+        let (x, y, z) = v.into();
+        assert_eq!((x, y, z), (1.0, -2.0, 0.5));
+    }
+
     #[test]
     fn reflect_45() {
         let v = Vector::new(1, -1, 0);
@@ -368,7 +591,7 @@ mod tuple_tests {
     #[test]
     fn reflect_slanted() {
         let v = Vector::new(0, -1, 0);
-        let n = Vector::new(2.0_f64.sqrt() / 2.0, 2.0_f64.sqrt() / 2.0, 0.0);
+        let n = Vector::new(Scalar::sqrt(2.0) / 2.0, Scalar::sqrt(2.0) / 2.0, 0.0);
         let r = v.reflect(n);
         assert_eq!(r, Vector::new(1, 0, 0));
     }
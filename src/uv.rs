@@ -0,0 +1,1024 @@
+//! Mapping points on objects to 2d texture coordinates (u, v), so that 2d patterns and images can be wrapped around them.
+use std::fmt::Debug;
+
+#[cfg(not(feature = "rayon"))]
+use std::rc::Rc;
+
+#[cfg(feature = "rayon")]
+use std::sync::Arc;
+
+use crate::{
+    canvas::Canvas,
+    color::Color,
+    matrix::Mat4,
+    pattern::{Pattern, PatternKind},
+    scalar::{consts::PI, Scalar},
+    shapes::shape::Shape,
+    tuple::{Point, Vector},
+};
+
+#[cfg(not(feature = "rayon"))]
+/// A 2d pattern. Takes texture coordinates u and v (both usually between 0 and 1) and returns the color at that position.
+pub type UvFunction = Rc<dyn Fn(Scalar, Scalar) -> Color>;
+
+#[cfg(feature = "rayon")]
+/// A 2d pattern. Takes texture coordinates u and v (both usually between 0 and 1) and returns the color at that position.
+pub type UvFunction = Arc<dyn Fn(Scalar, Scalar) -> Color + Send + Sync>;
+
+#[cfg(not(feature = "rayon"))]
+/// Wraps a closure into a [`UvFunction`], whichever pointer type it uses with the enabled features.
+pub fn uv_function(uv_fn: impl Fn(Scalar, Scalar) -> Color + 'static) -> UvFunction {
+    Rc::new(uv_fn)
+}
+
+#[cfg(feature = "rayon")]
+/// Wraps a closure into a [`UvFunction`], whichever pointer type it uses with the enabled features.
+pub fn uv_function(uv_fn: impl Fn(Scalar, Scalar) -> Color + Send + Sync + 'static) -> UvFunction {
+    Arc::new(uv_fn)
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// The ways to map a point (in pattern space) to texture coordinates.
+pub enum UvMapping {
+    /// Wraps the texture around a unit sphere, u going around the equator and v from the south to the north pole
+    Spherical,
+    /// Repeats the texture on the xz plane, once per unit
+    Planar,
+    /// Wraps the texture around a cylinder along the y axis, repeating once per unit of height
+    Cylindrical,
+    /// Puts the whole texture on each face of the cube from (-1, -1, -1) to (1, 1, 1)
+    Cubic,
+}
+
+impl UvMapping {
+    /// The texture coordinates of a point under this mapping
+    pub fn map(&self, p: Point) -> (Scalar, Scalar) {
+        match self {
+            UvMapping::Spherical => spherical_map(p),
+            UvMapping::Planar => planar_map(p),
+            UvMapping::Cylindrical => cylindrical_map(p),
+            UvMapping::Cubic => cubic_map(p),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// The faces of a cube, as seen from inside a left handed coordinate system looking along positive z
+pub enum CubeFace {
+    /// The face at x = -1
+    Left,
+    /// The face at x = 1
+    Right,
+    /// The face at z = 1
+    Front,
+    /// The face at z = -1
+    Back,
+    /// The face at y = 1
+    Up,
+    /// The face at y = -1
+    Down,
+}
+
+impl CubeFace {
+    /// All six faces
+    pub const ALL: [CubeFace; 6] = [
+        CubeFace::Left,
+        CubeFace::Right,
+        CubeFace::Front,
+        CubeFace::Back,
+        CubeFace::Up,
+        CubeFace::Down,
+    ];
+
+    /// The face of the cube a point lies on, or is closest to
+    pub fn of_point(p: Point) -> Self {
+        let coord = p.x.abs().max(p.y.abs()).max(p.z.abs());
+        if coord == p.x {
+            CubeFace::Right
+        } else if coord == -p.x {
+            CubeFace::Left
+        } else if coord == p.y {
+            CubeFace::Up
+        } else if coord == -p.y {
+            CubeFace::Down
+        } else if coord == p.z {
+            CubeFace::Front
+        } else {
+            CubeFace::Back
+        }
+    }
+
+    /// The texture coordinates of a point on this face of the cube
+    pub fn uv(&self, p: Point) -> (Scalar, Scalar) {
+        let (u, v) = match self {
+            CubeFace::Front => (p.x + 1.0, p.y + 1.0),
+            CubeFace::Back => (1.0 - p.x, p.y + 1.0),
+            CubeFace::Left => (p.z + 1.0, p.y + 1.0),
+            CubeFace::Right => (1.0 - p.z, p.y + 1.0),
+            CubeFace::Up => (p.x + 1.0, 1.0 - p.z),
+            CubeFace::Down => (p.x + 1.0, p.z + 1.0),
+        };
+        (u.rem_euclid(2.0) / 2.0, v.rem_euclid(2.0) / 2.0)
+    }
+}
+
+/// Maps a point on a sphere (around the origin) to texture coordinates
+pub fn spherical_map(p: Point) -> (Scalar, Scalar) {
+    let theta = p.x.atan2(p.z);
+    let radius = (p.x * p.x + p.y * p.y + p.z * p.z).sqrt();
+    let phi = (p.y / radius).acos();
+    let raw_u = theta / (2.0 * PI);
+    let u = 1.0 - (raw_u + 0.5);
+    let v = 1.0 - phi / PI;
+    (u, v)
+}
+
+/// Maps a point on the xz plane to texture coordinates, repeating every unit
+pub fn planar_map(p: Point) -> (Scalar, Scalar) {
+    (p.x.rem_euclid(1.0), p.z.rem_euclid(1.0))
+}
+
+/// Maps a point on a cylinder around the y axis to texture coordinates, repeating every unit of height
+pub fn cylindrical_map(p: Point) -> (Scalar, Scalar) {
+    let theta = p.x.atan2(p.z);
+    let raw_u = theta / (2.0 * PI);
+    let u = 1.0 - (raw_u + 0.5);
+    (u, p.y.rem_euclid(1.0))
+}
+
+/// Maps a point on the cube from (-1, -1, -1) to (1, 1, 1) to texture coordinates on the face it lies on
+pub fn cubic_map(p: Point) -> (Scalar, Scalar) {
+    CubeFace::of_point(p).uv(p)
+}
+
+/// A 2d checker pattern with ```width``` squares along u and ```height``` squares along v, alternating between ```color_a``` and ```color_b```.
+/// For a sphere with spherical mapping, choose width = 2 * height to get squares.
+pub fn uv_checkers(width: usize, height: usize, color_a: Color, color_b: Color) -> UvFunction {
+    uv_function(move |u: Scalar, v: Scalar| {
+        let u2 = (u * width as Scalar).floor() as i64;
+        let v2 = (v * height as Scalar).floor() as i64;
+        if (u2 + v2).rem_euclid(2) == 0 {
+            color_a
+        } else {
+            color_b
+        }
+    })
+}
+
+/// A 2d stripe pattern with ```count``` stripes along u, alternating between ```color_a``` and ```color_b```.
+pub fn uv_stripes(count: usize, color_a: Color, color_b: Color) -> UvFunction {
+    uv_function(move |u: Scalar, _v: Scalar| {
+        if ((u * count as Scalar).floor() as i64).rem_euclid(2) == 0 {
+            color_a
+        } else {
+            color_b
+        }
+    })
+}
+
+/// A 2d ring pattern with ```count``` rings around the center of the texture (u = v = 0.5), alternating between ```color_a``` and ```color_b```.
+pub fn uv_rings(count: usize, color_a: Color, color_b: Color) -> UvFunction {
+    uv_function(move |u: Scalar, v: Scalar| {
+        let distance = ((u - 0.5).powi(2) + (v - 0.5).powi(2)).sqrt() * 2.0;
+        if ((distance * count as Scalar).floor() as i64).rem_euclid(2) == 0 {
+            color_a
+        } else {
+            color_b
+        }
+    })
+}
+
+/// A 2d pattern to check the alignment of a mapping: ```main``` everywhere, except for small squares of the given colors in the four corners of the texture.
+pub fn uv_align_check(
+    main: Color,
+    upper_left: Color,
+    upper_right: Color,
+    bottom_left: Color,
+    bottom_right: Color,
+) -> UvFunction {
+    uv_function(move |u: Scalar, v: Scalar| {
+        if v > 0.8 {
+            if u < 0.2 {
+                return upper_left;
+            }
+            if u > 0.8 {
+                return upper_right;
+            }
+        } else if v < 0.2 {
+            if u < 0.2 {
+                return bottom_left;
+            }
+            if u > 0.8 {
+                return bottom_right;
+            }
+        }
+        main
+    })
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// How colors between the pixels of an image texture are determined
+pub enum TextureFilter {
+    /// The color of the closest pixel. Blocky up close, and aliases on receding surfaces.
+    Nearest,
+    /// Interpolates between the four closest pixels
+    Bilinear,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// What happens with texture coordinates outside of the image
+pub enum TextureAddressing {
+    /// Uses the pixels at the edge of the image. u = 0 and u = 1 are the centers of the first and the last pixel.
+    Clamp,
+    /// Tiles the image, so that u = 1 is u = 0 again. Pixels are spread evenly, with u = 0 at the left edge of the first one.
+    Repeat,
+}
+
+impl TextureAddressing {
+    /// The pixel coordinate (not rounded) of a texture coordinate in an image ```size``` pixels wide
+    fn coordinate(&self, t: Scalar, size: usize) -> Scalar {
+        match self {
+            TextureAddressing::Clamp => t.clamp(0.0, 1.0) * (size - 1) as Scalar,
+            TextureAddressing::Repeat => t.rem_euclid(1.0) * size as Scalar - 0.5,
+        }
+    }
+
+    /// The pixel index of a (possibly out of bounds) pixel coordinate
+    fn index(&self, i: i64, size: usize) -> usize {
+        match self {
+            TextureAddressing::Clamp => i.clamp(0, size as i64 - 1) as usize,
+            TextureAddressing::Repeat => i.rem_euclid(size as i64) as usize,
+        }
+    }
+}
+
+/// A 2d pattern showing an image, e.g. loaded with [`crate::ppm::read_ppm`], using the closest pixel and clamping at the edges.
+/// u goes from the left (0) to the right (1) edge of the image, v from the bottom (0) to the top (1).
+pub fn uv_image(image: Canvas) -> UvFunction {
+    uv_image_filtered(image, TextureFilter::Nearest, TextureAddressing::Clamp)
+}
+
+/// A 2d pattern showing an image like [`uv_image`], with the given filtering and addressing.
+pub fn uv_image_filtered(
+    image: Canvas,
+    filter: TextureFilter,
+    addressing: TextureAddressing,
+) -> UvFunction {
+    uv_function(move |u: Scalar, v: Scalar| {
+        let x = addressing.coordinate(u, image.width());
+        let y = addressing.coordinate(1.0 - v, image.height());
+        let pixel = |x: i64, y: i64| {
+            image
+                .pixel_at(
+                    addressing.index(x, image.width()),
+                    addressing.index(y, image.height()),
+                )
+                .expect("Canvas WIDTH and HEIGHT volation.")
+        };
+        match filter {
+            TextureFilter::Nearest => pixel(x.round() as i64, y.round() as i64),
+            TextureFilter::Bilinear => {
+                let (x0, y0) = (x.floor(), y.floor());
+                let (fx, fy) = (x - x0, y - y0);
+                let (x0, y0) = (x0 as i64, y0 as i64);
+                let top = pixel(x0, y0).lerp(pixel(x0 + 1, y0), fx);
+                let bottom = pixel(x0, y0 + 1).lerp(pixel(x0 + 1, y0 + 1), fx);
+                top.lerp(bottom, fy)
+            }
+        }
+    })
+}
+
+#[derive(Clone)]
+/// A 2d pattern wrapped around an object using a [`UvMapping`].
+pub struct TextureMap {
+    /// The 2d pattern
+    pub uv_fn: UvFunction,
+    /// How points are mapped to texture coordinates
+    pub mapping: UvMapping,
+}
+
+impl TextureMap {
+    /// Creates a new texture map from a 2d pattern and a mapping
+    pub fn new(uv_fn: UvFunction, mapping: UvMapping) -> Self {
+        Self { uv_fn, mapping }
+    }
+
+    /// The color of the texture at a point (in pattern space)
+    pub fn color_at(&self, p: Point) -> Color {
+        let (u, v) = self.mapping.map(p);
+        (self.uv_fn)(u, v)
+    }
+}
+
+impl Debug for TextureMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TextureMap")
+            .field("mapping", &self.mapping)
+            .finish()
+    }
+}
+
+#[derive(Clone)]
+/// Textures a cube (or anything around the origin, like a skybox) with a separate 2d pattern or image for each face.
+pub struct CubeMap {
+    /// The pattern of the face at x = -1
+    pub left: UvFunction,
+    /// The pattern of the face at x = 1
+    pub right: UvFunction,
+    /// The pattern of the face at z = 1
+    pub front: UvFunction,
+    /// The pattern of the face at z = -1
+    pub back: UvFunction,
+    /// The pattern of the face at y = 1
+    pub up: UvFunction,
+    /// The pattern of the face at y = -1
+    pub down: UvFunction,
+}
+
+impl CubeMap {
+    /// Creates a new cube map from the patterns of its six faces
+    pub fn new(
+        left: UvFunction,
+        right: UvFunction,
+        front: UvFunction,
+        back: UvFunction,
+        up: UvFunction,
+        down: UvFunction,
+    ) -> Self {
+        Self {
+            left,
+            right,
+            front,
+            back,
+            up,
+            down,
+        }
+    }
+
+    /// The pattern of the given face
+    pub fn face(&self, face: CubeFace) -> &UvFunction {
+        match face {
+            CubeFace::Left => &self.left,
+            CubeFace::Right => &self.right,
+            CubeFace::Front => &self.front,
+            CubeFace::Back => &self.back,
+            CubeFace::Up => &self.up,
+            CubeFace::Down => &self.down,
+        }
+    }
+
+    /// The color at a point (in pattern space). Points not on the cube are projected onto it from the origin.
+    pub fn color_at(&self, p: Point) -> Color {
+        let scale = p.x.abs().max(p.y.abs()).max(p.z.abs());
+        let p = if scale > 0.0 {
+            Point::new(p.x / scale, p.y / scale, p.z / scale)
+        } else {
+            p
+        };
+        let face = CubeFace::of_point(p);
+        let (u, v) = face.uv(p);
+        (self.face(face))(u, v)
+    }
+}
+
+impl Debug for CubeMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CubeMap").finish_non_exhaustive()
+    }
+}
+
+impl From<CubeMap> for Pattern {
+    fn from(cube_map: CubeMap) -> Self {
+        PatternKind::Cube(cube_map).into()
+    }
+}
+
+impl From<TextureMap> for Pattern {
+    fn from(texture_map: TextureMap) -> Self {
+        PatternKind::Texture(texture_map).into()
+    }
+}
+
+#[derive(Clone)]
+/// An image projected onto an object like a slide projector would, e.g. a label or a logo, layered over the object's color.
+///
+/// The projector is a box from -1 to 1 on every axis in decal space, which is placed in object space using the decal's transformation.
+/// The image covers the box's x (u) and y (v) extent and is projected along z, points outside of the box are not affected.
+pub struct Decal {
+    /// The projected image
+    pub image: UvFunction,
+    /// The opacity of the image, from 0 (transparent) to 1 (opaque), as the mean of the color components. ```None``` is opaque everywhere.
+    pub alpha: Option<UvFunction>,
+    transformation_matrix: Mat4,
+    inverse_transformation_matrix: Mat4,
+}
+
+impl Decal {
+    /// Creates a new decal from a 2d pattern, its opacity and the transformation of the projector
+    pub fn new(image: UvFunction, alpha: Option<UvFunction>, transformation_matrix: Mat4) -> Self {
+        Self {
+            image,
+            alpha,
+            transformation_matrix,
+            inverse_transformation_matrix: transformation_matrix.inverse(),
+        }
+    }
+
+    /// Creates a new decal from an image and an optional grayscale alpha mask (e.g. read with ```png::read_png_with_alpha``` of the "png" feature), both filtered bilinearly
+    pub fn from_image(image: Canvas, alpha: Option<Canvas>, transformation_matrix: Mat4) -> Self {
+        let sample =
+            |image| uv_image_filtered(image, TextureFilter::Bilinear, TextureAddressing::Clamp);
+        Self::new(sample(image), alpha.map(sample), transformation_matrix)
+    }
+
+    /// The transformation of the projector
+    pub fn transformation_matrix(&self) -> Mat4 {
+        self.transformation_matrix
+    }
+
+    /// Places the projector, see [`Decal`]
+    pub fn set_transformation_matrix(&mut self, matrix: Mat4) {
+        self.transformation_matrix = matrix;
+        self.inverse_transformation_matrix = matrix.inverse();
+    }
+
+    /// Layers the decal over ```color```, at a point (world space) of ```object```
+    pub fn over(&self, color: Color, object: &dyn Shape, point: Point) -> Color {
+        let p = self.inverse_transformation_matrix * object.to_object_space(point);
+        if p.x.abs() > 1.0 || p.y.abs() > 1.0 || p.z.abs() > 1.0 {
+            return color;
+        }
+        let (u, v) = ((p.x + 1.0) / 2.0, (p.y + 1.0) / 2.0);
+        let alpha = match &self.alpha {
+            Some(alpha) => {
+                let a = alpha(u, v);
+                ((a.red + a.green + a.blue) / 3.0).clamp(0.0, 1.0)
+            }
+            None => 1.0,
+        };
+        color.lerp((self.image)(u, v), alpha)
+    }
+}
+
+impl Debug for Decal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Decal")
+            .field("transformation_matrix", &self.transformation_matrix)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for Decal {
+    /// The image and alpha are compared by identity
+    fn eq(&self, other: &Self) -> bool {
+        let same_alpha = match (&self.alpha, &other.alpha) {
+            (Some(a), Some(b)) => same_uv_fn(a, b),
+            (None, None) => true,
+            _ => false,
+        };
+        same_uv_fn(&self.image, &other.image)
+            && same_alpha
+            && self.transformation_matrix == other.transformation_matrix
+    }
+}
+
+/// The step used to estimate the slope of a [`BumpMap`]
+const BUMP_DELTA: Scalar = 1e-3;
+
+#[derive(Clone, Debug, PartialEq)]
+/// Fakes small bumps and dents on a surface by tilting its normals according to a heightmap, without changing its geometry.
+pub struct BumpMap {
+    /// The heightmap. Its brightness (the mean of the color components) is the height, in object space units.
+    pub height: TextureMap,
+    /// Scales the heights, making the bumps steeper or flatter. Negative values turn bumps into dents.
+    pub strength: Scalar,
+}
+
+impl BumpMap {
+    /// Creates a new bump map from a heightmap
+    pub fn new(height: TextureMap, strength: Scalar) -> Self {
+        Self { height, strength }
+    }
+
+    /// A bump map from a grayscale image (e.g. loaded with [`crate::ppm::read_ppm`]), wrapped around the object using ```mapping```.
+    /// The image is filtered bilinearly, so that the slopes between pixels are smooth.
+    pub fn from_image(image: Canvas, mapping: UvMapping, strength: Scalar) -> Self {
+        let height = uv_image_filtered(image, TextureFilter::Bilinear, TextureAddressing::Clamp);
+        Self::new(TextureMap::new(height, mapping), strength)
+    }
+
+    /// The height at a point (world space) of ```object```
+    fn height_at(&self, object: &dyn Shape, point: Point) -> Scalar {
+        let color = self.height.color_at(object.to_object_space(point));
+        (color.red + color.green + color.blue) / 3.0 * self.strength
+    }
+
+    /// Tilts ```normal``` (world space) at a point of ```object``` away from the slope of the heightmap.
+    /// The slope is estimated with finite differences.
+    pub fn perturb(&self, object: &dyn Shape, point: Point, normal: Vector) -> Vector {
+        let slope = |axis: Vector| {
+            let ahead = self.height_at(object, point + axis * BUMP_DELTA);
+            let behind = self.height_at(object, point - axis * BUMP_DELTA);
+            (ahead - behind) / (2.0 * BUMP_DELTA)
+        };
+        let gradient = Vector::new(
+            slope(Vector::new(1, 0, 0)),
+            slope(Vector::new(0, 1, 0)),
+            slope(Vector::new(0, 0, 1)),
+        );
+        // only the slope along the surface tilts the normal
+        let surface_gradient = gradient - normal * gradient.dot(normal);
+        (normal - surface_gradient).normalized()
+    }
+}
+
+impl PartialEq for TextureMap {
+    /// The 2d patterns are compared by identity
+    fn eq(&self, other: &Self) -> bool {
+        same_uv_fn(&self.uv_fn, &other.uv_fn) && self.mapping == other.mapping
+    }
+}
+
+impl PartialEq for CubeMap {
+    /// The 2d patterns are compared by identity
+    fn eq(&self, other: &Self) -> bool {
+        CubeFace::ALL
+            .iter()
+            .all(|&face| same_uv_fn(self.face(face), other.face(face)))
+    }
+}
+
+fn same_uv_fn(a: &UvFunction, b: &UvFunction) -> bool {
+    #[cfg(not(feature = "rayon"))]
+    return Rc::ptr_eq(a, b);
+    #[cfg(feature = "rayon")]
+    return Arc::ptr_eq(a, b);
+}
+
+#[cfg(test)]
+mod mapping_tests {
+    use crate::{
+        epsilon::EpsilonEqual,
+        scalar::{consts::FRAC_1_SQRT_2, Scalar},
+        tuple::Point,
+        uv::{cylindrical_map, planar_map, spherical_map, CubeFace, UvMapping},
+    };
+
+    fn assert_uv((u, v): (Scalar, Scalar), (expected_u, expected_v): (Scalar, Scalar)) {
+        assert!(u.e_equals(expected_u), "u: {u} != {expected_u}");
+        assert!(v.e_equals(expected_v), "v: {v} != {expected_v}");
+    }
+
+    #[test]
+    fn spherical() {
+        assert_uv(spherical_map(Point::new(0, 0, -1)), (0.0, 0.5));
+        assert_uv(spherical_map(Point::new(1, 0, 0)), (0.25, 0.5));
+        assert_uv(spherical_map(Point::new(0, 0, 1)), (0.5, 0.5));
+        assert_uv(spherical_map(Point::new(-1, 0, 0)), (0.75, 0.5));
+        assert_uv(spherical_map(Point::new(0, 1, 0)), (0.5, 1.0));
+        assert_uv(spherical_map(Point::new(0, -1, 0)), (0.5, 0.0));
+        assert_uv(
+            spherical_map(Point::new(FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0.0)),
+            (0.25, 0.75),
+        );
+    }
+
+    #[test]
+    fn planar() {
+        assert_uv(planar_map(Point::new(0.25, 0, 0.5)), (0.25, 0.5));
+        assert_uv(planar_map(Point::new(0.25, 0, -0.25)), (0.25, 0.75));
+        assert_uv(planar_map(Point::new(0.25, 0.5, -0.25)), (0.25, 0.75));
+        assert_uv(planar_map(Point::new(1.25, 0, 0.5)), (0.25, 0.5));
+        assert_uv(planar_map(Point::new(0.25, 0, -1.75)), (0.25, 0.25));
+        assert_uv(planar_map(Point::new(1, 0, -1)), (0.0, 0.0));
+        assert_uv(planar_map(Point::new(0, 0, 0)), (0.0, 0.0));
+    }
+
+    #[test]
+    fn cylindrical() {
+        assert_uv(cylindrical_map(Point::new(0, 0, -1)), (0.0, 0.0));
+        assert_uv(cylindrical_map(Point::new(0, 0.5, -1)), (0.0, 0.5));
+        assert_uv(cylindrical_map(Point::new(0, 1, -1)), (0.0, 0.0));
+        assert_uv(
+            cylindrical_map(Point::new(FRAC_1_SQRT_2, 0.5, -FRAC_1_SQRT_2)),
+            (0.125, 0.5),
+        );
+        assert_uv(cylindrical_map(Point::new(1, 0.5, 0)), (0.25, 0.5));
+        assert_uv(cylindrical_map(Point::new(-1, 1.25, 0)), (0.75, 0.25));
+    }
+
+    #[test]
+    fn cube_faces() {
+        assert_eq!(
+            CubeFace::of_point(Point::new(-1, 0.5, -0.25)),
+            CubeFace::Left
+        );
+        assert_eq!(
+            CubeFace::of_point(Point::new(1.1, -0.75, 0.8)),
+            CubeFace::Right
+        );
+        assert_eq!(
+            CubeFace::of_point(Point::new(0.1, 0.6, 0.9)),
+            CubeFace::Front
+        );
+        assert_eq!(CubeFace::of_point(Point::new(-0.7, 0, -2)), CubeFace::Back);
+        assert_eq!(CubeFace::of_point(Point::new(0.5, 1, 0.9)), CubeFace::Up);
+        assert_eq!(
+            CubeFace::of_point(Point::new(-0.2, -1.3, 1.1)),
+            CubeFace::Down
+        );
+    }
+
+    #[test]
+    fn cube_uv() {
+        assert_uv(CubeFace::Front.uv(Point::new(-0.5, 0.5, 1)), (0.25, 0.75));
+        assert_uv(CubeFace::Front.uv(Point::new(0.5, -0.5, 1)), (0.75, 0.25));
+        assert_uv(CubeFace::Back.uv(Point::new(0.5, 0.5, -1)), (0.25, 0.75));
+        assert_uv(CubeFace::Left.uv(Point::new(-1, 0.5, -0.5)), (0.25, 0.75));
+        assert_uv(CubeFace::Right.uv(Point::new(1, 0.5, 0.5)), (0.25, 0.75));
+        assert_uv(CubeFace::Up.uv(Point::new(-0.5, 1, -0.5)), (0.25, 0.75));
+        assert_uv(CubeFace::Down.uv(Point::new(-0.5, -1, 0.5)), (0.25, 0.75));
+        assert_uv(
+            UvMapping::Cubic.map(Point::new(-0.5, -1, 0.5)),
+            (0.25, 0.75),
+        );
+    }
+}
+
+#[cfg(test)]
+mod texture_map_tests {
+    use crate::{
+        color::{Color, BLACK, WHITE},
+        pattern::Pattern,
+        tuple::Point,
+        uv::{uv_function, TextureMap, UvMapping},
+    };
+
+    #[test]
+    fn feeds_pattern_through_mapping() {
+        let upper_half = uv_function(|_u, v| if v > 0.5 { WHITE } else { BLACK });
+        let pattern: Pattern = TextureMap::new(upper_half, UvMapping::Spherical).into();
+        assert_eq!(pattern.apply_pattern(Point::new(0.0, 0.8, 0.6)), WHITE);
+        assert_eq!(pattern.apply_pattern(Point::new(0.0, -0.8, 0.6)), BLACK);
+    }
+
+    #[test]
+    fn color_at() {
+        let uv_color = uv_function(|u, v| Color::new(u, v, 0.0));
+        let map = TextureMap::new(uv_color, UvMapping::Planar);
+        assert_eq!(
+            map.color_at(Point::new(1.25, 0.0, 0.5)),
+            Color::new(0.25, 0.5, 0.0)
+        );
+    }
+}
+
+#[cfg(test)]
+mod image_tests {
+    use crate::{
+        canvas::Canvas,
+        color::{Color, BLACK, WHITE},
+        ppm::read_ppm,
+        uv::{uv_image, uv_image_filtered, TextureAddressing, TextureFilter},
+    };
+
+    /// A 2x2 image, black on the left and white on the right
+    fn black_and_white() -> Canvas {
+        let mut image = Canvas::new(2, 2);
+        image.write_pixel(1, 0, WHITE).unwrap();
+        image.write_pixel(1, 1, WHITE).unwrap();
+        image
+    }
+
+    #[test]
+    fn bilinear_clamped() {
+        let image = uv_image_filtered(
+            black_and_white(),
+            TextureFilter::Bilinear,
+            TextureAddressing::Clamp,
+        );
+        assert_eq!(image(0.0, 0.3), BLACK);
+        assert_eq!(image(0.25, 0.7), Color::new(0.25, 0.25, 0.25));
+        assert_eq!(image(0.5, 0.5), Color::new(0.5, 0.5, 0.5));
+        assert_eq!(image(1.0, 0.0), WHITE);
+        assert_eq!(image(1.5, -1.0), WHITE);
+    }
+
+    #[test]
+    fn bilinear_repeated() {
+        let image = uv_image_filtered(
+            black_and_white(),
+            TextureFilter::Bilinear,
+            TextureAddressing::Repeat,
+        );
+        // pixel centers are at u = 0.25 and u = 0.75, between them it wraps around
+        assert_eq!(image(0.25, 0.5), BLACK);
+        assert_eq!(image(0.75, 0.5), WHITE);
+        assert_eq!(image(0.5, 0.5), Color::new(0.5, 0.5, 0.5));
+        assert_eq!(image(0.0, 0.5), Color::new(0.5, 0.5, 0.5));
+        assert_eq!(image(1.125, 0.5), Color::new(0.25, 0.25, 0.25));
+    }
+
+    #[test]
+    fn nearest_repeated() {
+        let image = uv_image_filtered(
+            black_and_white(),
+            TextureFilter::Nearest,
+            TextureAddressing::Repeat,
+        );
+        assert_eq!(image(0.3, 0.5), BLACK);
+        assert_eq!(image(0.7, 0.5), WHITE);
+        assert_eq!(image(-0.2, 0.5), WHITE);
+        assert_eq!(image(1.2, 0.5), BLACK);
+    }
+
+    #[test]
+    fn image_pixels_by_uv() {
+        let ppm = "P3\n10 10\n10\n".to_string()
+            + &(0..100)
+                .map(|i| format!("{0} {0} {0}", (i % 10 + i / 10) % 10))
+                .collect::<Vec<_>>()
+                .join("\n");
+        let image = uv_image(read_ppm(&ppm).unwrap());
+        let expectations = [
+            (0.0, 0.0, 0.9),
+            (0.3, 0.0, 0.2),
+            (0.6, 0.3, 0.1),
+            (1.0, 1.0, 0.9),
+        ];
+        for (u, v, gray) in expectations {
+            assert_eq!(image(u, v), Color::new(gray, gray, gray).to_linear());
+        }
+    }
+}
+
+#[cfg(test)]
+mod uv_pattern_tests {
+    use crate::{
+        color::{BLACK, WHITE},
+        pattern::Pattern,
+        tuple::Point,
+        uv::{uv_checkers, uv_rings, uv_stripes, TextureMap, UvMapping},
+    };
+
+    #[test]
+    fn checkers() {
+        let checkers = uv_checkers(2, 2, BLACK, WHITE);
+        assert_eq!(checkers(0.0, 0.0), BLACK);
+        assert_eq!(checkers(0.5, 0.0), WHITE);
+        assert_eq!(checkers(0.0, 0.5), WHITE);
+        assert_eq!(checkers(0.5, 0.5), BLACK);
+        assert_eq!(checkers(1.0, 1.0), BLACK);
+    }
+
+    #[test]
+    fn checkers_on_sphere() {
+        let pattern: Pattern =
+            TextureMap::new(uv_checkers(16, 8, BLACK, WHITE), UvMapping::Spherical).into();
+        let expectations = [
+            (Point::new(0.4315, 0.4670, 0.7719), WHITE),
+            (Point::new(-0.9654, 0.2552, -0.0534), BLACK),
+            (Point::new(0.1039, 0.7090, 0.6975), WHITE),
+            (Point::new(-0.4986, -0.7856, -0.3663), BLACK),
+            (Point::new(-0.0317, -0.9395, 0.3411), BLACK),
+            (Point::new(0.4809, -0.7721, 0.4154), BLACK),
+            (Point::new(0.0285, -0.9612, -0.2745), BLACK),
+            (Point::new(-0.5734, -0.2162, -0.7903), WHITE),
+            (Point::new(0.7688, -0.1470, 0.6223), BLACK),
+            (Point::new(-0.7652, 0.2175, 0.6060), BLACK),
+        ];
+        for (point, color) in expectations {
+            assert_eq!(pattern.apply_pattern(point), color);
+        }
+    }
+
+    #[test]
+    fn stripes() {
+        let stripes = uv_stripes(4, BLACK, WHITE);
+        assert_eq!(stripes(0.1, 0.9), BLACK);
+        assert_eq!(stripes(0.3, 0.1), WHITE);
+        assert_eq!(stripes(0.6, 0.5), BLACK);
+    }
+
+    #[test]
+    fn rings() {
+        let rings = uv_rings(2, BLACK, WHITE);
+        assert_eq!(rings(0.5, 0.5), BLACK);
+        assert_eq!(rings(0.8, 0.5), WHITE);
+        assert_eq!(rings(0.5, 0.0), BLACK);
+    }
+}
+
+#[cfg(test)]
+mod cube_map_tests {
+    use crate::{
+        color::Color,
+        pattern::Pattern,
+        scalar::Scalar,
+        tuple::Point,
+        uv::{uv_align_check, CubeMap},
+    };
+
+    const RED: (Scalar, Scalar, Scalar) = (1.0, 0.0, 0.0);
+    const YELLOW: (Scalar, Scalar, Scalar) = (1.0, 1.0, 0.0);
+    const BROWN: (Scalar, Scalar, Scalar) = (1.0, 0.5, 0.0);
+    const GREEN: (Scalar, Scalar, Scalar) = (0.0, 1.0, 0.0);
+    const CYAN: (Scalar, Scalar, Scalar) = (0.0, 1.0, 1.0);
+    const BLUE: (Scalar, Scalar, Scalar) = (0.0, 0.0, 1.0);
+    const PURPLE: (Scalar, Scalar, Scalar) = (1.0, 0.0, 1.0);
+    const WHITE: (Scalar, Scalar, Scalar) = (1.0, 1.0, 1.0);
+
+    fn c(rgb: (Scalar, Scalar, Scalar)) -> Color {
+        Color::new(rgb.0, rgb.1, rgb.2)
+    }
+
+    fn cube() -> Pattern {
+        CubeMap::new(
+            uv_align_check(c(YELLOW), c(CYAN), c(RED), c(BLUE), c(BROWN)),
+            uv_align_check(c(RED), c(YELLOW), c(PURPLE), c(BROWN), c(GREEN)),
+            uv_align_check(c(CYAN), c(RED), c(YELLOW), c(BROWN), c(GREEN)),
+            uv_align_check(c(GREEN), c(PURPLE), c(CYAN), c(WHITE), c(BLUE)),
+            uv_align_check(c(BROWN), c(CYAN), c(PURPLE), c(RED), c(YELLOW)),
+            uv_align_check(c(PURPLE), c(BROWN), c(GREEN), c(BLUE), c(WHITE)),
+        )
+        .into()
+    }
+
+    #[test]
+    fn align_check() {
+        let pattern = uv_align_check(c(WHITE), c(RED), c(YELLOW), c(GREEN), c(CYAN));
+        assert_eq!(pattern(0.5, 0.5), c(WHITE));
+        assert_eq!(pattern(0.1, 0.9), c(RED));
+        assert_eq!(pattern(0.9, 0.9), c(YELLOW));
+        assert_eq!(pattern(0.1, 0.1), c(GREEN));
+        assert_eq!(pattern(0.9, 0.1), c(CYAN));
+    }
+
+    #[test]
+    fn faces() {
+        let cube = cube();
+        let expectations = [
+            // left
+            (Point::new(-1, 0, 0), c(YELLOW)),
+            (Point::new(-1.0, 0.9, -0.9), c(CYAN)),
+            (Point::new(-1.0, 0.9, 0.9), c(RED)),
+            (Point::new(-1.0, -0.9, -0.9), c(BLUE)),
+            (Point::new(-1.0, -0.9, 0.9), c(BROWN)),
+            // front
+            (Point::new(0, 0, 1), c(CYAN)),
+            (Point::new(-0.9, 0.9, 1.0), c(RED)),
+            (Point::new(0.9, -0.9, 1.0), c(GREEN)),
+            // right
+            (Point::new(1, 0, 0), c(RED)),
+            (Point::new(1.0, 0.9, 0.9), c(YELLOW)),
+            (Point::new(1.0, -0.9, -0.9), c(GREEN)),
+            // back
+            (Point::new(0, 0, -1), c(GREEN)),
+            (Point::new(0.9, 0.9, -1.0), c(PURPLE)),
+            (Point::new(-0.9, -0.9, -1.0), c(BLUE)),
+            // up
+            (Point::new(0, 1, 0), c(BROWN)),
+            (Point::new(-0.9, 1.0, -0.9), c(CYAN)),
+            (Point::new(0.9, 1.0, 0.9), c(YELLOW)),
+            // down
+            (Point::new(0, -1, 0), c(PURPLE)),
+            (Point::new(-0.9, -1.0, 0.9), c(BROWN)),
+            (Point::new(0.9, -1.0, -0.9), c(WHITE)),
+        ];
+        for (point, color) in expectations {
+            assert_eq!(cube.apply_pattern(point), color, "at {point:?}");
+        }
+    }
+
+    #[test]
+    fn projects_onto_cube() {
+        let cube = cube();
+        assert_eq!(cube.apply_pattern(Point::new(0.0, 0.0, 0.3)), c(CYAN));
+        assert_eq!(cube.apply_pattern(Point::new(-5.0, 4.5, 4.5)), c(RED));
+    }
+}
+
+#[cfg(test)]
+mod bump_map_tests {
+    use crate::{
+        canvas::Canvas,
+        color::Color,
+        intersection::Intersection,
+        ray::Ray,
+        shapes::{plane::Plane, shape::Shape},
+        tuple::{Point, Vector},
+        uv::{BumpMap, UvMapping},
+    };
+
+    /// A heightmap rising from left (black) to right (white)
+    fn ramp() -> Canvas {
+        let mut image = Canvas::new(3, 1);
+        image.write_pixel(1, 0, Color::new(0.5, 0.5, 0.5)).unwrap();
+        image.write_pixel(2, 0, Color::new(1, 1, 1)).unwrap();
+        image
+    }
+
+    #[test]
+    fn tilts_normal_away_from_slope() {
+        let plane = Plane::default();
+        let bump_map = BumpMap::from_image(ramp(), UvMapping::Planar, 0.5);
+        let normal = bump_map.perturb(&plane, Point::new(0.3, 0.0, 0.5), Vector::new(0, 1, 0));
+        // the height rises by 1 (scaled to 0.5) over one unit of x
+        assert_eq!(normal, Vector::new(-0.5, 1.0, 0.0).normalized());
+    }
+
+    #[test]
+    fn flat_heightmap_keeps_normal() {
+        let plane = Plane::default();
+        let image = Canvas::new_with_color(2, 2, Color::new(0.7, 0.7, 0.7));
+        let bump_map = BumpMap::from_image(image, UvMapping::Planar, 2.0);
+        let normal = Vector::new(0, 1, 0);
+        assert_eq!(
+            bump_map.perturb(&plane, Point::new(0.4, 0.0, 0.6), normal),
+            normal
+        );
+    }
+
+    #[test]
+    fn used_for_shading() {
+        let mut plane = Plane::default();
+        plane.material_mut().bump_map = Some(BumpMap::from_image(ramp(), UvMapping::Planar, -0.5));
+        let r = Ray::new(Point::new(0.3, 1.0, 0.5), Vector::new(0, -1, 0));
+        let i = Intersection::new(1, &plane);
+        let comps = i.prepare_computations(&r, &vec![i]);
+        assert_eq!(comps.normalv, Vector::new(0.5, 1.0, 0.0).normalized());
+        assert_eq!(comps.over_point.x, 0.3);
+        assert!(comps.over_point.y > 0.0);
+    }
+}
+
+#[cfg(test)]
+mod decal_tests {
+    use crate::{
+        canvas::Canvas,
+        color::{Color, BLACK, WHITE},
+        material::ColorType,
+        matrix::Mat4,
+        shapes::{plane::Plane, shape::Shape},
+        tuple::Point,
+        uv::Decal,
+    };
+
+    /// A red decal lying flat on a plane, covering x and z from -1 to 1
+    fn label(alpha: Option<Canvas>) -> Decal {
+        let image = Canvas::new_with_color(2, 2, Color::new(1, 0, 0));
+        Decal::from_image(
+            image,
+            alpha,
+            Mat4::new_rotation_x(crate::scalar::consts::FRAC_PI_2),
+        )
+    }
+
+    #[test]
+    fn projected_inside_the_box() {
+        let plane = Plane::default();
+        let decal = label(None);
+        assert_eq!(
+            decal.over(WHITE, &plane, Point::new(0.5, 0.0, -0.5)),
+            Color::new(1, 0, 0)
+        );
+        assert_eq!(decal.over(WHITE, &plane, Point::new(1.5, 0.0, 0.0)), WHITE);
+    }
+
+    #[test]
+    fn alpha_blends() {
+        let plane = Plane::default();
+        let mut alpha = Canvas::new(2, 2);
+        alpha.write_pixel(1, 0, WHITE).unwrap();
+        alpha.write_pixel(1, 1, WHITE).unwrap();
+        let decal = label(Some(alpha));
+        // transparent on the left, opaque on the right, half transparent in between
+        assert_eq!(decal.over(BLACK, &plane, Point::new(-1.0, 0.0, 0.0)), BLACK);
+        assert_eq!(
+            decal.over(BLACK, &plane, Point::new(1.0, 0.0, 0.0)),
+            Color::new(1, 0, 0)
+        );
+        assert_eq!(
+            decal.over(BLACK, &plane, Point::new(0.0, 0.0, 0.3)),
+            Color::new(0.5, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn layered_over_material_color() {
+        let mut plane = Plane::default();
+        plane.material_mut().color = ColorType::Color(Color::new(0, 0, 1));
+        plane.material_mut().decals.push(label(None));
+        plane.set_transformation_matrix(Mat4::new_translation(10, 0, 0));
+        let material = plane.material();
+        assert_eq!(
+            material.color_at(&plane, Point::new(10.5, 0.0, 0.5)),
+            Color::new(1, 0, 0)
+        );
+        assert_eq!(
+            material.color_at(&plane, Point::new(0.5, 0.0, 0.5)),
+            Color::new(0, 0, 1)
+        );
+    }
+}
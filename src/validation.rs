@@ -0,0 +1,195 @@
+//! Checks for mistakes in scenes, see [`crate::world::World::validate`] and [`crate::camera::Camera::validate`].
+//!
+//! Bad input does not stop a render: it silently shows up as black frames, missing objects or NaN colors. Validating first explains why.
+use std::fmt::Display;
+
+use crate::{
+    color::Color,
+    material::{ColorType, Material},
+    matrix::Mat4,
+    scalar::Scalar,
+};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+/// How bad a [`Diagnostic`] is
+pub enum Severity {
+    /// Renders, but probably not as intended
+    Warning,
+    /// Renders wrong (black, NaN colors) or not at all
+    Error,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// A problem found by validating a scene
+pub struct Diagnostic {
+    /// How bad the problem is
+    pub severity: Severity,
+    /// What is wrong and where, e.g. "object 3: the transformation is not invertible"
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub(crate) fn error(message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn warning(message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            message: message.into(),
+        }
+    }
+
+    /// If this is an [`Severity::Error`]
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.severity {
+            Severity::Warning => write!(f, "warning: {}", self.message),
+            Severity::Error => write!(f, "error: {}", self.message),
+        }
+    }
+}
+
+/// Everything that can be wrong with a transformation, which is inverted to intersect rays
+pub(crate) fn check_transform(subject: &str, matrix: Mat4, diagnostics: &mut Vec<Diagnostic>) {
+    if (0..4).any(|row| matrix[row].iter().any(|v| !v.is_finite())) {
+        diagnostics.push(Diagnostic::error(format!(
+            "{subject}: the transformation contains NaN or infinite values"
+        )));
+    } else if !matrix.invertible() {
+        diagnostics.push(Diagnostic::error(format!(
+            "{subject}: the transformation is not invertible, e.g. it scales by 0"
+        )));
+    }
+}
+
+fn is_finite(color: Color) -> bool {
+    color.red.is_finite() && color.green.is_finite() && color.blue.is_finite()
+}
+
+pub(crate) fn check_color(subject: &str, color: Color, diagnostics: &mut Vec<Diagnostic>) {
+    if !is_finite(color) {
+        diagnostics.push(Diagnostic::error(format!(
+            "{subject}: the color contains NaN or infinite values"
+        )));
+    } else if color.red < 0.0 || color.green < 0.0 || color.blue < 0.0 {
+        diagnostics.push(Diagnostic::warning(format!(
+            "{subject}: the color is negative"
+        )));
+    }
+}
+
+pub(crate) fn check_material(
+    subject: &str,
+    material: &Material,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    if let ColorType::Color(color) = material.color {
+        check_color(subject, color, diagnostics);
+    }
+    let factors = [
+        ("ambient", material.ambient),
+        ("diffuse", material.diffuse),
+        ("specular", material.specular),
+        ("reflective", material.reflective),
+        ("transparency", material.transparency),
+    ];
+    for (name, value) in factors {
+        if !value.is_finite() {
+            diagnostics.push(Diagnostic::error(format!("{subject}: {name} is {value}")));
+        } else if value < 0.0 {
+            diagnostics.push(Diagnostic::warning(format!(
+                "{subject}: {name} is negative"
+            )));
+        }
+    }
+    if !material.refractive_index.is_finite() || material.refractive_index <= 0.0 {
+        diagnostics.push(Diagnostic::error(format!(
+            "{subject}: the refractive index is {}, it must be positive",
+            material.refractive_index
+        )));
+    } else if material.transparency > 0.0 && material.refractive_index == 1.0 {
+        diagnostics.push(Diagnostic::warning(format!(
+            "{subject}: the material is transparent, but its refractive index is 1 (like air), so it does not bend light"
+        )));
+    }
+    if material.reflective + material.transparency > 1.0 {
+        diagnostics.push(Diagnostic::warning(format!(
+            "{subject}: reflective and transparency add up to more than 1, so the surface gives off more light than it receives"
+        )));
+    }
+}
+
+/// Negative biases move the starting points of rays into the surface, so everything shadows itself
+pub(crate) fn check_shadow_bias(subject: &str, bias: Scalar, diagnostics: &mut Vec<Diagnostic>) {
+    if !bias.is_finite() || bias < 0.0 {
+        diagnostics.push(Diagnostic::error(format!(
+            "{subject}: the shadow bias is {bias}, it must not be negative"
+        )));
+    }
+}
+
+#[cfg(test)]
+mod validation_tests {
+    use crate::{
+        material::Material,
+        matrix::Mat4,
+        scalar::Scalar,
+        validation::{check_material, check_transform, Diagnostic, Severity},
+    };
+
+    #[test]
+    fn transforms() {
+        let mut diagnostics = Vec::new();
+        check_transform("a", Mat4::new_scaling(1.0, 2.0, 3.0), &mut diagnostics);
+        assert!(diagnostics.is_empty());
+        check_transform("b", Mat4::new_scaling(1.0, 0.0, 1.0), &mut diagnostics);
+        check_transform(
+            "c",
+            Mat4::new_translation(Scalar::NAN, 0.0, 0.0),
+            &mut diagnostics,
+        );
+        assert_eq!(diagnostics.len(), 2);
+        assert!(diagnostics.iter().all(Diagnostic::is_error));
+        assert!(diagnostics[0].message.starts_with("b: "));
+        assert!(diagnostics[1].to_string().starts_with("error: c: "));
+    }
+
+    #[test]
+    fn materials() {
+        let mut diagnostics = Vec::new();
+        let glass = Material {
+            transparency: 1.0,
+            refractive_index: 1.5,
+            ..Default::default()
+        };
+        check_material("glass", &glass, &mut diagnostics);
+        assert!(diagnostics.is_empty());
+
+        let unset = Material {
+            transparency: 0.9,
+            ..Default::default()
+        };
+        check_material("unset", &unset, &mut diagnostics);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+
+        let broken = Material {
+            diffuse: Scalar::NAN,
+            refractive_index: 0.0,
+            ..Default::default()
+        };
+        diagnostics.clear();
+        check_material("broken", &broken, &mut diagnostics);
+        assert_eq!(diagnostics.len(), 2);
+        assert!(diagnostics.iter().all(Diagnostic::is_error));
+    }
+}
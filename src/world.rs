@@ -1,26 +1,273 @@
 //! The world containing objects and lights
 
+use std::sync::OnceLock;
+
 use crate::{
-    color::{Color, BLACK},
-    epsilon::EpsilonEqual,
-    intersection::{consuming_hit, hit, Intersection, PreparedComputations},
-    light::PointLight,
-    material::{ColorType, Material, Shininess},
-    matrix::Mat4,
+    bvh::Bvh,
+    color::{Color, BLACK, WHITE},
+    deep::DeepSample,
+    epsilon::{EpsilonEqual, EPSILON},
+    intersection::{hit, Intersection, PreparedComputations},
+    irradiance_cache::IrradianceCache,
+    light::{Light, PointLight},
+    material::{ggx_factor, ClearCoat, ColorType, Material, Shininess},
+    matrix::{Mat4, IDENTITY_MATRIX_4},
+    pattern::{Pattern, PatternFunction},
     ray::Ray,
-    shapes::shape::Shape,
+    render_pass::ShadingLayers,
+    sampling::Rng,
+    scalar::Scalar,
+    settings,
+    shapes::shape::{ObjectId, Shape},
     shapes::sphere::Sphere,
-    tuple::Point,
+    skybox::Skybox,
+    stats,
+    tuple::{Point, Vector},
+    validation::{self, Diagnostic},
 };
 
+/// Salt for the random numbers used to pick lights, see [`Rng::from_point`]
+const LIGHT_SAMPLING_SALT: u64 = 1;
+
+/// Salt for the random numbers used for ambient occlusion, see [`Rng::from_point`]
+const AMBIENT_OCCLUSION_SALT: u64 = 2;
+/// Salt for the random numbers used for global illumination, see [`Rng::from_point`]
+const GLOBAL_ILLUMINATION_SALT: u64 = 3;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Where the ambient light comes from, which every surface receives regardless of shadows (scaled by [`Material::ambient`]), see [`World::set_ambient_light`].
+///
+/// Ambient light is added once per shaded point, no matter how many lights there are, and reaches every object regardless of [`Light::linking`].
+pub enum AmbientLight {
+    /// The intensity of the first light added to the world, as in the book. The default.
+    #[default]
+    FirstLight,
+    /// The average intensity of all lights, so it does not depend on the order the lights were added in.
+    AverageOfLights,
+    /// The same color everywhere, independent of the lights
+    Color(Color),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Darkens the ambient light in corners and crevices, by casting rays into the hemisphere around a shaded point and counting how many hit something nearby.
+pub struct AmbientOcclusion {
+    /// The number of rays cast per shaded point. More rays mean less noise.
+    pub samples: usize,
+    /// Objects further away than this do not occlude the point.
+    pub radius: Scalar,
+}
+
+impl AmbientOcclusion {
+    /// Ambient occlusion with the given number of ```samples``` and ```radius```
+    pub fn new(samples: usize, radius: Scalar) -> Self {
+        Self { samples, radius }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Adds light bouncing diffusely between objects, so that e.g. a red wall tints the floor next to it (color bleeding).
+///
+/// The indirect light is gathered by casting rays into the hemisphere around a shaded point, and cached so that nearby points can reuse it.
+/// This is a lot cheaper than path tracing, at the cost of only a single bounce and blurred indirect light.
+/// With the "rayon" feature, the cache is filled in whichever order threads shade points, so parallel renders may differ slightly.
+pub struct GlobalIllumination {
+    /// The number of rays cast to gather the indirect light at a point. More rays mean less blotchy indirect light.
+    pub samples: usize,
+    /// The distance within which cached indirect light is reused. Smaller values give more detail, but need more gathering.
+    pub spacing: Scalar,
+}
+
+impl GlobalIllumination {
+    /// Global illumination with the given number of ```samples``` per gathered point, reused within ```spacing```
+    pub fn new(samples: usize, spacing: Scalar) -> Self {
+        Self { samples, spacing }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Fog filling the whole world: the further away a surface is, the more its color fades into the fog color, giving large scenes a sense of depth (aerial perspective).
+/// Rays missing every object fade into the fog completely, unless the fog thins out with height (see ```falloff```) and the ray escapes upwards.
+pub struct Fog {
+    /// The color surfaces fade into
+    pub color: Color,
+    /// How thick the fog is, i.e. how much of the light it absorbs per unit of distance (at height 0)
+    pub density: Scalar,
+    /// How quickly the fog thins out with height: its density drops by a factor of e every ```1 / falloff``` units upwards. 0 means the fog is equally thick everywhere.
+    pub falloff: Scalar,
+}
+
+impl Fog {
+    /// Fog with the given ```color``` and ```density```, equally thick everywhere
+    pub fn new(color: Color, density: Scalar) -> Self {
+        Self {
+            color,
+            density,
+            falloff: 0.0,
+        }
+    }
+
+    /// The same fog, thinning out with height, see [`Self::falloff`]
+    pub fn with_falloff(mut self, falloff: Scalar) -> Self {
+        self.falloff = falloff;
+        self
+    }
+
+    /// How much of the fog color is seen instead of a surface ```distance``` units along the ray (with a normalized direction), between 0 and 1.
+    /// ```distance``` may be infinite for rays not hitting anything.
+    pub fn amount(&self, r: &Ray, distance: Scalar) -> Scalar {
+        if self.density <= 0.0 {
+            return 0.0;
+        }
+        // the density integrated along the ray, which rises or sinks through the layers of fog
+        let density_at_origin = self.density * (-self.falloff * r.origin.y).exp();
+        let climb = self.falloff * r.direction.y;
+        let optical_depth = if climb.abs() < EPSILON {
+            density_at_origin * distance
+        } else {
+            density_at_origin * (1.0 - (-climb * distance).exp()) / climb
+        };
+        1.0 - (-optical_depth).exp()
+    }
+
+    /// ```color``` seen through the fog ```distance``` units along the ray, see [`Self::amount`]
+    pub fn apply(&self, color: Color, r: &Ray, distance: Scalar) -> Color {
+        let amount = self.amount(r, distance);
+        color.lerp(self.color, amount)
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+/// Where a ray first hit an object, see [`World::cast_ray`]
+pub struct HitInfo<'a> {
+    /// The point hit, in world space
+    pub point: Point,
+    /// The normal of the surface at the point, facing the ray (i.e. flipped when the ray hit the back side) and bent by bump maps
+    pub normal: Vector,
+    /// The distance from the origin of the ray to the point
+    pub distance: Scalar,
+    /// The object hit
+    pub object: &'a dyn Shape,
+    /// The id of the object hit, see [`Shape::id`]
+    pub id: Option<ObjectId>,
+    /// The material of the side of the object that was hit, see [`Shape::material_facing`]
+    pub material: &'a Material,
+    /// Whether the ray hit the object from inside or behind
+    pub inside: bool,
+    /// The index of the face hit, for shapes made of several faces like triangle meshes
+    pub face: Option<usize>,
+}
+
 #[derive(Debug, Default)]
 /// The world to render
 pub struct World {
     objects: Vec<Box<dyn Shape>>,
-    lights: Vec<PointLight>,
+    lights: Vec<Box<dyn Light>>,
+    skybox: Option<Skybox>,
+    light_samples: Option<usize>,
+    ambient_light: AmbientLight,
+    ambient_occlusion: Option<AmbientOcclusion>,
+    global_illumination: Option<GlobalIllumination>,
+    fog: Option<Fog>,
+    irradiance_cache: IrradianceCache,
+    bvh: OnceLock<Bvh>,
+}
+
+#[derive(Debug, Default)]
+/// Assembles a [`World`] step by step, see [`World::builder`]
+pub struct WorldBuilder {
+    world: World,
+}
+
+impl WorldBuilder {
+    /// Adds an object
+    pub fn object(mut self, object: impl Shape) -> Self {
+        self.world.objects.push(Box::new(object));
+        self
+    }
+
+    /// Adds an already boxed object, e.g. one of a list of different shapes
+    pub fn boxed_object(mut self, object: Box<dyn Shape>) -> Self {
+        self.world.objects.push(object);
+        self
+    }
+
+    /// Adds a light
+    pub fn light(mut self, light: impl Light) -> Self {
+        self.world.lights.push(Box::new(light));
+        self
+    }
+
+    /// Sets the skybox, see [`World::set_skybox`]
+    pub fn skybox(mut self, skybox: Skybox) -> Self {
+        self.world.set_skybox(skybox);
+        self
+    }
+
+    /// Sets a uniform background color, see [`World::set_background`]
+    pub fn background(mut self, color: Color) -> Self {
+        self.world.set_background(color);
+        self
+    }
+
+    /// Shades with only ```samples``` randomly picked lights, see [`World::set_light_samples`]
+    pub fn light_samples(mut self, samples: usize) -> Self {
+        self.world.set_light_samples(Some(samples));
+        self
+    }
+
+    /// Sets where the ambient light comes from, see [`World::set_ambient_light`]
+    pub fn ambient_light(mut self, ambient_light: AmbientLight) -> Self {
+        self.world.set_ambient_light(ambient_light);
+        self
+    }
+
+    /// Enables ambient occlusion, see [`World::set_ambient_occlusion`]
+    pub fn ambient_occlusion(mut self, ambient_occlusion: AmbientOcclusion) -> Self {
+        self.world.set_ambient_occlusion(Some(ambient_occlusion));
+        self
+    }
+
+    /// Enables global illumination, see [`World::set_global_illumination`]
+    pub fn global_illumination(mut self, global_illumination: GlobalIllumination) -> Self {
+        self.world
+            .set_global_illumination(Some(global_illumination));
+        self
+    }
+
+    /// Fills the world with fog, see [`World::set_fog`]
+    pub fn fog(mut self, fog: Fog) -> Self {
+        self.world.set_fog(Some(fog));
+        self
+    }
+
+    /// The finished world
+    pub fn build(self) -> World {
+        self.world
+    }
 }
 
 impl World {
+    /// Starts building a world, adding objects and lights in a single expression:
+    /// ```
+    /// use raytracerchallenge::{
+    ///     color::WHITE, light::PointLight, shapes::sphere::Sphere, transform::Transform,
+    ///     tuple::Point, world::World,
+    /// };
+    ///
+    /// let world = World::builder()
+    ///     .light(PointLight::new(Point::new(-10, 10, -10), WHITE))
+    ///     .object(Sphere::default().translated(0, 1, 0))
+    ///     .build();
+    /// assert_eq!(world.objects().len(), 1);
+    /// ```
+    pub fn builder() -> WorldBuilder {
+        WorldBuilder::default()
+    }
+
     /// Returns a test world with to spheres and a lights
     pub fn test_world() -> Self {
         let color_s1 = Color::new(0.8, 1.0, 0.6);
@@ -46,22 +293,39 @@ impl World {
 
         let objects: Vec<Box<dyn Shape>> = vec![Box::new(s1), Box::new(s2)];
 
-        let lights = vec![PointLight::new(
+        let lights: Vec<Box<dyn Light>> = vec![Box::new(PointLight::new(
             Point::new(-10, 10, -10),
             Color::new(1.0, 1.0, 1.0),
-        )];
-
-        Self { objects, lights }
+        ))];
+
+        Self {
+            objects,
+            lights,
+            skybox: None,
+            light_samples: None,
+            ambient_light: AmbientLight::default(),
+            ambient_occlusion: None,
+            global_illumination: None,
+            fog: None,
+            irradiance_cache: IrradianceCache::default(),
+            bvh: OnceLock::new(),
+        }
     }
 
     /// Tries to intersect the ray with all objects in the world.
     /// Results are written to the provided "intersections" vector, which can be re-used later to save on allocations.
+    /// Objects are found through a bounding volume hierarchy, which is built on the first call after the objects changed.
     pub(crate) fn intersect<'a>(&'a self, r: &Ray, intersections: &mut Vec<Intersection<'a>>) {
-        for object in &self.objects {
-            object.intersect(r, intersections);
+        self.bvh().intersect(&self.objects, r, intersections);
+
+        // NaN (from broken transformations, see [`Self::validate`]) sorts last instead of panicking
+        intersections.sort_by(|a, b| a.t.total_cmp(&b.t));
+        let max_distance = settings::max_distance();
+        if max_distance < Scalar::INFINITY {
+            // t is relative to the length of the direction
+            let max_t = max_distance / r.direction.magnitude();
+            intersections.truncate(intersections.partition_point(|i| i.t <= max_t));
         }
-
-        intersections.sort_by(|a, b| a.t.partial_cmp(&b.t).unwrap())
     }
 
     /// Given the prepared computations of the point a ray hit, this function determines the color at this point by first determining the lighting conditions and then rendering the point by accessing its material's render method.
@@ -72,23 +336,318 @@ impl World {
         intersections: &mut Vec<Intersection<'a>>,
         remaining_recursion: usize,
     ) -> Color {
-        let mut ambient = true;
-        let mut surface = BLACK;
+        self.shade_layers(comps, intersections, remaining_recursion)
+            .color
+    }
 
-        for light in self.lights.iter() {
-            let in_shadow = self.in_shadow(light, &comps.over_point, intersections);
-            surface = surface + comps.object.render_at(comps, light, in_shadow, ambient);
-            ambient = false;
+    /// Shades the hit point like [`Self::shade_hit`], keeping the ambient, direct, reflected and refracted light apart.
+    fn shade_layers<'a>(
+        &'a self,
+        comps: &PreparedComputations,
+        intersections: &mut Vec<Intersection<'a>>,
+        remaining_recursion: usize,
+    ) -> ShadingLayers {
+        self.shade_layers_with(comps, intersections, remaining_recursion, true)
+    }
+
+    /// Shades the hit point, adding the indirect light of [`GlobalIllumination`] to the ambient light only if ```indirect``` is set.
+    /// It is not set when gathering the indirect light, which only follows a single bounce.
+    fn shade_layers_with<'a>(
+        &'a self,
+        comps: &PreparedComputations,
+        intersections: &mut Vec<Intersection<'a>>,
+        remaining_recursion: usize,
+        indirect: bool,
+    ) -> ShadingLayers {
+        let mut ambient = self.ambient_at(comps);
+        if let Some(ambient_occlusion) = &self.ambient_occlusion {
+            // the emissive color is part of the ambient term, but glows regardless of occlusion
+            let emissive = comps.material().emissive;
+            let unoccluded = self.unoccluded_at(comps, ambient_occlusion, intersections);
+            ambient = emissive + (ambient - emissive) * unoccluded;
+        }
+        if let (true, Some(global_illumination)) = (indirect, &self.global_illumination) {
+            ambient += self.indirect_light_at(comps, global_illumination, intersections);
         }
 
+        let direct = self.direct_light_at(comps, intersections);
+        let surface = ambient + direct;
+
         let reflected = self.reflected_color_at(comps, remaining_recursion);
         let refracted = self.refracted_color_at(comps, remaining_recursion);
 
-        surface + reflected + refracted
+        let base = surface + reflected + refracted;
+        let mut color = match &comps.material().clear_coat {
+            Some(clear_coat) => {
+                self.clear_coat_at(comps, clear_coat, base, intersections, remaining_recursion)
+            }
+            None => base,
+        };
+        if let Some(fog) = &self.fog {
+            // the ray came from the direction of the eye vector, which is as long as the ray's direction
+            let length = comps.eyev.magnitude();
+            let r = Ray::new(comps.point + comps.eyev * comps.t, -comps.eyev / length);
+            color = fog.apply(color, &r, comps.t * length);
+        }
+        ShadingLayers {
+            color,
+            ambient,
+            direct,
+            reflection: reflected,
+            refraction: refracted,
+            ..ShadingLayers::background(color)
+        }
+    }
+
+    /// Puts a clear coat on top of the color of the base material:
+    /// the coat reflects the surroundings and the highlights of the lights, the base shows through where it does not.
+    fn clear_coat_at<'a>(
+        &'a self,
+        comps: &PreparedComputations,
+        clear_coat: &ClearCoat,
+        base: Color,
+        intersections: &mut Vec<Intersection<'a>>,
+        remaining_recursion: usize,
+    ) -> Color {
+        let reflectance = clear_coat.reflectance(comps.eyev.dot(comps.normalv));
+
+        let mut coat = BLACK;
+        for light in self.lights.iter().filter(|light| light.specular()) {
+            let transmission = self.linked_transmission(comps, light.as_ref(), intersections);
+            if transmission == BLACK {
+                continue;
+            }
+            let lightv = light.direction_from(comps.over_point);
+            let factor = ggx_factor(clear_coat.roughness, lightv, comps.eyev, comps.normalv);
+            coat += light.intensity_at(comps.over_point) * transmission * factor;
+        }
+
+        if remaining_recursion > 0 {
+            let reflect_ray = Ray::new(comps.over_point, comps.reflectv);
+            stats::record(|stats| stats.reflection_rays += 1);
+            coat += self.color_at(&reflect_ray, &mut Vec::new(), remaining_recursion - 1);
+        }
+
+        base.lerp(coat, reflectance)
+    }
+
+    /// The ambient light at the hit point, including the emissive color of the material, see [`AmbientLight`].
+    fn ambient_at(&self, comps: &PreparedComputations) -> Color {
+        let material = comps.material();
+        let intensity = self.ambient_intensity();
+        if intensity == BLACK {
+            return material.emissive;
+        }
+        material.color_at(comps.object, comps.over_point) * intensity * material.ambient
+            + material.emissive
+    }
+
+    /// The intensity of the ambient light, the same for every point
+    fn ambient_intensity(&self) -> Color {
+        match self.ambient_light {
+            AmbientLight::FirstLight => {
+                self.lights.first().map_or(BLACK, |light| light.intensity())
+            }
+            AmbientLight::AverageOfLights if self.lights.is_empty() => BLACK,
+            AmbientLight::AverageOfLights => {
+                let sum: Color = self.lights.iter().map(|light| light.intensity()).sum();
+                sum / self.lights.len() as Scalar
+            }
+            AmbientLight::Color(color) => color,
+        }
+    }
+
+    /// The light arriving indirectly (after one diffuse bounce) at the hit point and reflected diffusely, see [`GlobalIllumination`].
+    fn indirect_light_at<'a>(
+        &'a self,
+        comps: &PreparedComputations,
+        global_illumination: &GlobalIllumination,
+        intersections: &mut Vec<Intersection<'a>>,
+    ) -> Color {
+        let material = comps.material();
+        if material.diffuse <= 0.0 || global_illumination.samples == 0 {
+            return BLACK;
+        }
+
+        let (point, normal) = (comps.over_point, comps.normalv);
+        let spacing = global_illumination.spacing;
+        let irradiance = match self.irradiance_cache.lookup(point, normal, spacing) {
+            Some(irradiance) => irradiance,
+            None => {
+                let irradiance = self.gather_irradiance(comps, global_illumination, intersections);
+                self.irradiance_cache
+                    .insert(point, normal, spacing, irradiance);
+                irradiance
+            }
+        };
+        irradiance * material.color_at(comps.object, comps.point) * material.diffuse
+    }
+
+    /// The average light arriving at the hit point, sampled with cosine weighted rays.
+    fn gather_irradiance<'a>(
+        &'a self,
+        comps: &PreparedComputations,
+        global_illumination: &GlobalIllumination,
+        intersections: &mut Vec<Intersection<'a>>,
+    ) -> Color {
+        let mut rng = Rng::from_point(comps.over_point, GLOBAL_ILLUMINATION_SALT);
+        let mut sum = BLACK;
+        for _ in 0..global_illumination.samples {
+            let r = Ray::new(
+                comps.over_point,
+                rng.next_hemisphere_direction(comps.normalv),
+            );
+            stats::record(|stats| stats.sampling_rays += 1);
+            self.intersect(&r, intersections);
+            let Some(h) = hit(intersections) else {
+                intersections.clear();
+                sum += self.sky_color_at(&r);
+                continue;
+            };
+            let bounce = h.prepare_computations(&r, intersections);
+            intersections.clear();
+            sum += self
+                .shade_layers_with(&bounce, intersections, 0, false)
+                .color;
+        }
+        sum * (1.0 / global_illumination.samples as Scalar)
+    }
+
+    /// The fraction of rays leaving the hit point that do not hit anything within the radius of ```ambient_occlusion```.
+    fn unoccluded_at<'a>(
+        &'a self,
+        comps: &PreparedComputations,
+        ambient_occlusion: &AmbientOcclusion,
+        intersections: &mut Vec<Intersection<'a>>,
+    ) -> Scalar {
+        if ambient_occlusion.samples == 0 {
+            return 1.0;
+        }
+
+        let mut rng = Rng::from_point(comps.over_point, AMBIENT_OCCLUSION_SALT);
+        let mut occluded = 0;
+        for _ in 0..ambient_occlusion.samples {
+            let direction = rng.next_hemisphere_direction(comps.normalv);
+            stats::record(|stats| stats.sampling_rays += 1);
+            if self.is_occluded(
+                comps.over_point,
+                direction,
+                ambient_occlusion.radius,
+                intersections,
+            ) {
+                occluded += 1;
+            }
+        }
+
+        1.0 - occluded as Scalar / ambient_occlusion.samples as Scalar
+    }
+
+    /// Shades the hit point with the lights in the world (without ambient light), sampling them if [`Self::set_light_samples`] asks for it.
+    fn direct_light_at<'a>(
+        &'a self,
+        comps: &PreparedComputations,
+        intersections: &mut Vec<Intersection<'a>>,
+    ) -> Color {
+        match self.light_samples {
+            Some(samples) if samples < self.lights.len() => {
+                self.sampled_lights_at(comps, intersections, samples)
+            }
+            _ => self.all_lights_at(comps, intersections),
+        }
+    }
+
+    /// Shades the hit point with every light in the world (without ambient light).
+    fn all_lights_at<'a>(
+        &'a self,
+        comps: &PreparedComputations,
+        intersections: &mut Vec<Intersection<'a>>,
+    ) -> Color {
+        let mut surface = BLACK;
+
+        for light in self.lights.iter() {
+            let transmission = self.linked_transmission(comps, light.as_ref(), intersections);
+            surface += comps.object.render_at(comps, light.as_ref(), transmission);
+        }
+
+        surface
+    }
+
+    /// Shades the hit point with ```samples``` randomly picked lights (without ambient light).
+    /// Lights are picked with a probability proportional to their (unshadowed) intensity divided by the squared distance, and their contribution is scaled back up accordingly, so that on average the result equals shading with all lights.
+    fn sampled_lights_at<'a>(
+        &'a self,
+        comps: &PreparedComputations,
+        intersections: &mut Vec<Intersection<'a>>,
+        samples: usize,
+    ) -> Color {
+        let mut surface = BLACK;
+
+        let weights: Vec<Scalar> = self
+            .lights
+            .iter()
+            .map(|light| self.light_weight(comps, light.as_ref()))
+            .collect();
+        // running sums of the weights, so that each sample finds its light by binary search
+        let cumulative_weights: Vec<Scalar> = weights
+            .iter()
+            .scan(0.0, |sum, &weight| {
+                *sum += weight;
+                Some(*sum)
+            })
+            .collect();
+        let total_weight = cumulative_weights.last().copied().unwrap_or(0.0);
+        if total_weight <= 0.0 || samples == 0 {
+            return surface;
+        }
+
+        let mut rng = Rng::from_point(comps.over_point, LIGHT_SAMPLING_SALT);
+        for _ in 0..samples {
+            let picked = rng.next_scalar() * total_weight;
+            let index = cumulative_weights.partition_point(|&sum| sum <= picked);
+            let Some(light) = self.lights.get(index) else {
+                // rounded up to the total weight
+                continue;
+            };
+            let transmission = self.linked_transmission(comps, light.as_ref(), intersections);
+            let contribution = comps.object.render_at(comps, light.as_ref(), transmission);
+            let probability = weights[index] / total_weight;
+            surface += contribution * (1.0 / (samples as Scalar * probability));
+        }
+
+        surface
+    }
+
+    /// How strongly a light is expected to contribute to the hit point, used to pick lights when sampling.
+    fn light_weight(&self, comps: &PreparedComputations, light: &dyn Light) -> Scalar {
+        if !light.linking().illuminates(comps.object.id()) {
+            return 0.0;
+        }
+        let intensity = light.intensity_at(comps.over_point);
+        let brightness = (intensity.red + intensity.green + intensity.blue) / 3.0;
+        let distance = light.distance_from(comps.over_point);
+        if distance.is_finite() && distance > 1.0 {
+            brightness / (distance * distance)
+        } else {
+            brightness
+        }
+    }
+
+    /// The transmission of ```light``` at the hit point, BLACK if the light is not linked to the hit object.
+    fn linked_transmission<'a>(
+        &'a self,
+        comps: &PreparedComputations,
+        light: &dyn Light,
+        intersections: &mut Vec<Intersection<'a>>,
+    ) -> Color {
+        if light.linking().illuminates(comps.object.id()) {
+            self.shadow_transmission(light, &comps.over_point, intersections)
+        } else {
+            BLACK
+        }
     }
 
     /// Determines the color a ray produces.
-    /// If it does not hit, returns BLACK.
+    /// If it does not hit, returns the color of the skybox in the ray's direction, or BLACK if there is no skybox.
     /// If it hits, returns the result of the rendered point.
     /// The intersections argument is only for saving on allocations - if in doubt, pass a new vector.
     pub(crate) fn color_at<'a>(
@@ -97,6 +656,7 @@ impl World {
         intersections: &mut Vec<Intersection<'a>>,
         remaining_recursion: usize,
     ) -> Color {
+        let _depth = stats::enter_ray();
         self.intersect(r, intersections);
 
         let hit = hit(intersections);
@@ -106,11 +666,143 @@ impl World {
                 intersections.clear();
                 self.shade_hit(&comps, intersections, remaining_recursion)
             }
-            None => BLACK,
+            None => self.sky_color_at(r),
         };
         color
     }
 
+    /// Determines the color a ray produces like [`Self::color_at`], together with the parts it is made of for render passes.
+    pub(crate) fn layers_at<'a>(
+        &'a self,
+        r: &Ray,
+        intersections: &mut Vec<Intersection<'a>>,
+        remaining_recursion: usize,
+    ) -> ShadingLayers {
+        let _depth = stats::enter_ray();
+        self.intersect(r, intersections);
+        let Some(h) = hit(intersections) else {
+            intersections.clear();
+            return ShadingLayers::background(self.sky_color_at(r));
+        };
+        let comps = h.prepare_computations(r, intersections);
+        intersections.clear();
+        let material = comps.material();
+        let normal = comps.normalv;
+        ShadingLayers {
+            normal: Color::new(normal.x, normal.y, normal.z),
+            albedo: material.color_at(comps.object, comps.point),
+            emission: material.emissive,
+            ..self.shade_layers(&comps, intersections, remaining_recursion)
+        }
+    }
+
+    /// The fraction of ambient light reaching the first object the ray hits, see [`AmbientOcclusion`]. None if the ray does not hit anything.
+    /// The intersections argument is only for saving on allocations - if in doubt, pass a new vector.
+    pub(crate) fn occlusion_at<'a>(
+        &'a self,
+        r: &Ray,
+        intersections: &mut Vec<Intersection<'a>>,
+        ambient_occlusion: &AmbientOcclusion,
+    ) -> Option<Scalar> {
+        self.intersect(r, intersections);
+        let Some(h) = hit(intersections) else {
+            intersections.clear();
+            return None;
+        };
+        let comps = h.prepare_computations(r, intersections);
+        intersections.clear();
+        Some(self.unoccluded_at(&comps, ambient_occlusion, intersections))
+    }
+
+    /// The first object the ray hits, if any.
+    /// The intersections argument is only for saving on allocations - if in doubt, pass a new vector.
+    pub(crate) fn first_hit<'a>(
+        &'a self,
+        r: &Ray,
+        intersections: &mut Vec<Intersection<'a>>,
+    ) -> Option<Intersection<'a>> {
+        self.intersect(r, intersections);
+        let first = hit(intersections);
+        intersections.clear();
+        first
+    }
+
+    /// The distance along the (normalized) ray to the first object it hits, [`Scalar::INFINITY`] if it does not hit anything.
+    /// The intersections argument is only for saving on allocations - if in doubt, pass a new vector.
+    pub(crate) fn depth_at<'a>(
+        &'a self,
+        r: &Ray,
+        intersections: &mut Vec<Intersection<'a>>,
+    ) -> Scalar {
+        self.first_hit(r, intersections)
+            .map_or(Scalar::INFINITY, |h| h.t)
+    }
+
+    /// Every surface along a ray, nearest first, until an opaque one: its distance, the light it adds (without the light refracted through it)
+    /// and its opacity, see [`DeepSample`]. The ray is followed straight through transparent surfaces, as deep compositing expects.
+    pub(crate) fn deep_samples_at<'a>(
+        &'a self,
+        r: &Ray,
+        intersections: &mut Vec<Intersection<'a>>,
+        remaining_recursion: usize,
+    ) -> Vec<DeepSample> {
+        let _depth = stats::enter_ray();
+        self.intersect(r, intersections);
+        let hits = std::mem::take(intersections);
+        let mut samples = Vec::new();
+        for h in hits.iter().filter(|h| h.t >= 0.0) {
+            let comps = h.prepare_computations(r, &hits);
+            let layers = self.shade_layers(&comps, intersections, remaining_recursion);
+            let alpha = 1.0 - comps.material().transparency.clamp(0.0, 1.0);
+            samples.push(DeepSample {
+                depth: h.t,
+                color: layers.color - layers.refraction,
+                alpha,
+            });
+            if alpha >= 1.0 {
+                break;
+            }
+        }
+        samples
+    }
+
+    /// Determines the color a ray produces like [`Self::color_at`], together with its alpha:
+    /// rays that do not hit anything are transparent (alpha 0 and BLACK) instead of showing the skybox.
+    pub(crate) fn color_with_alpha_at<'a>(
+        &'a self,
+        r: &Ray,
+        intersections: &mut Vec<Intersection<'a>>,
+        remaining_recursion: usize,
+    ) -> (Color, Scalar) {
+        let _depth = stats::enter_ray();
+        self.intersect(r, intersections);
+        let Some(h) = hit(intersections) else {
+            intersections.clear();
+            return (BLACK, 0.0);
+        };
+        let comps = h.prepare_computations(r, intersections);
+        intersections.clear();
+        (
+            self.shade_hit(&comps, intersections, remaining_recursion),
+            1.0,
+        )
+    }
+
+    /// The color of the skybox in the direction of the ray, BLACK if the world has no skybox. Fog (see [`Fog`]) covers it as if it was infinitely far away.
+    fn sky_color_at(&self, r: &Ray) -> Color {
+        let sky = match &self.skybox {
+            Some(skybox) => skybox.color_at(r.direction),
+            None => BLACK,
+        };
+        match &self.fog {
+            Some(fog) => {
+                let r = Ray::new(r.origin, r.direction.normalized());
+                fog.apply(sky, &r, Scalar::INFINITY)
+            }
+            None => sky,
+        }
+    }
+
     /// Returns the reflected color at the object
     /// Returns black if either
     /// 1. the reflective index is epsilon_equal 0
@@ -124,16 +816,17 @@ impl World {
             return Color::new(0.0, 0.0, 0.0);
         }
 
-        if comps.object.material().reflective.e_equals(0.0) {
+        if comps.material().reflective.e_equals(0.0) {
             return Color::new(0, 0, 0);
         }
 
         let reflect_ray = Ray::new(comps.over_point, comps.reflectv);
+        stats::record(|stats| stats.reflection_rays += 1);
 
         let mut intersections = Vec::new();
 
         let color = self.color_at(&reflect_ray, &mut intersections, remaining_recursion - 1);
-        color * comps.object.material().reflective
+        color * comps.material().reflective
     }
 
     /// Returns the refracted color at the object
@@ -147,107 +840,570 @@ impl World {
             return BLACK;
         }
 
-        if computations.object.material().transparency == 0.0 {
+        if computations.material().transparency == 0.0 {
             return BLACK;
         }
 
         // total internal reflection
-        let n_ratio = computations.n1 / computations.n2;
-
-        let cos_i = computations.eyev.dot(computations.normalv);
-
-        let sin2_t = n_ratio.powi(2) * (1.0 - cos_i.powi(2));
-
-        if sin2_t > 1.0 {
+        let Some(direction) = refraction_direction(computations) else {
             return BLACK;
-        }
-
-        // compute
-        // find cos(theta_t)
-        let cos_t = (1.0 - sin2_t).sqrt();
-
-        // Compute the direction of the refracted ray
-        let direction =
-            computations.normalv * (n_ratio * cos_i - cos_t) - computations.eyev * n_ratio;
+        };
 
         // Create the refracted ray
         let refract_ray = Ray::new(computations.under_point, direction);
+        stats::record(|stats| stats.refraction_rays += 1);
 
-        return self.color_at(&refract_ray, &mut Vec::new(), remaining_recursion - 1)
-            * computations.object.material().transparency;
+        self.color_at(&refract_ray, &mut Vec::new(), remaining_recursion - 1)
+            * computations.material().transparency
+    }
+
+    /// Determines the color a ray produces with Monte Carlo path tracing instead of the Whitted model of [`Self::color_at`], see [`crate::camera::Integrator::PathTracing`].
+    ///
+    /// At every hit, the emitted light and the direct light of all light sources are added. The path then continues in a single random direction:
+    /// a diffuse bounce, a mirror reflection or a refraction, picked in proportion to the material's ```diffuse```, ```reflective``` and ```transparency```.
+    /// Ambient light is not used: it is replaced by the light bouncing between objects. Paths end after ```max_bounces``` bounces or when they leave the scene.
+    pub(crate) fn path_traced_color_at<'a>(
+        &'a self,
+        r: &Ray,
+        intersections: &mut Vec<Intersection<'a>>,
+        rng: &mut Rng,
+        max_bounces: usize,
+    ) -> Color {
+        let mut color = BLACK;
+        let mut throughput = WHITE;
+        let mut ray = *r;
+        let _depth = stats::enter_ray();
+        for bounce in 0..=max_bounces {
+            self.intersect(&ray, intersections);
+            let Some(h) = hit(intersections) else {
+                intersections.clear();
+                return color + throughput * self.sky_color_at(&ray);
+            };
+            let comps = h.prepare_computations(&ray, intersections);
+            intersections.clear();
+
+            let material = comps.material();
+            let emitted = material.emissive + self.direct_light_at(&comps, intersections);
+            color += throughput * emitted;
+
+            let total = material.diffuse + material.reflective + material.transparency;
+            if bounce == max_bounces || total <= 0.0 {
+                break;
+            }
+            // picking each kind of bounce in proportion to its weight keeps the estimate unbiased
+            let choice = rng.next_scalar() * total;
+            ray = if choice < material.diffuse {
+                throughput *= material.color_at(comps.object, comps.point);
+                Ray::new(
+                    comps.over_point,
+                    rng.next_hemisphere_direction(comps.normalv),
+                )
+            } else if choice < material.diffuse + material.reflective {
+                Ray::new(comps.over_point, comps.reflectv)
+            } else {
+                match refraction_direction(&comps) {
+                    Some(direction) => Ray::new(comps.under_point, direction),
+                    None => Ray::new(comps.over_point, comps.reflectv),
+                }
+            };
+            throughput *= total;
+            stats::record(|stats| {
+                stats.sampling_rays += 1;
+                stats.max_depth = stats.max_depth.max(bounce as u64 + 2);
+            });
+        }
+        color
     }
 
     /// Adds an object to the world
     pub fn add_object(&mut self, object: Box<dyn Shape>) {
         self.objects.push(object);
+        self.objects_changed();
     }
     /// Moves objects out of the given vector into the scene
     pub fn add_objects(&mut self, objects: &mut Vec<Box<dyn Shape>>) {
         self.objects.append(objects);
+        self.objects_changed();
     }
 
     /// Adds a light to the world
-    pub fn add_light(&mut self, light: PointLight) {
+    pub fn add_light(&mut self, light: Box<dyn Light>) {
         self.lights.push(light);
+        self.irradiance_cache.clear();
     }
     /// Moves lights out of the given vector into the scene
-    pub fn add_lights(&mut self, lights: &mut Vec<PointLight>) {
+    pub fn add_lights(&mut self, lights: &mut Vec<Box<dyn Light>>) {
         self.lights.append(lights);
+        self.irradiance_cache.clear();
+    }
+
+    /// Removes the object with the given id (see [`ObjectId`]) from the world and returns it, or None if there is none.
+    /// If several objects share the id, the first one added is removed.
+    pub fn remove_object(&mut self, id: ObjectId) -> Option<Box<dyn Shape>> {
+        let index = self.objects.iter().position(|o| o.id() == Some(id))?;
+        let object = self.objects.remove(index);
+        self.objects_changed();
+        Some(object)
+    }
+
+    /// Puts ```object``` in place of the object with the given id and returns the old one, or None (leaving the world as it is) if there is no such object.
+    /// The new object takes over the id, so it can be replaced again, e.g. in every frame of an animation.
+    pub fn replace_object(
+        &mut self,
+        id: ObjectId,
+        mut object: Box<dyn Shape>,
+    ) -> Option<Box<dyn Shape>> {
+        let index = self.objects.iter().position(|o| o.id() == Some(id))?;
+        object.set_id(Some(id));
+        let old = std::mem::replace(&mut self.objects[index], object);
+        self.objects_changed();
+        Some(old)
+    }
+
+    /// Removes the light at ```index``` (in the order they were added, see [`Self::lights`]) and returns it, or None if there is no such light.
+    pub fn remove_light(&mut self, index: usize) -> Option<Box<dyn Light>> {
+        if index >= self.lights.len() {
+            return None;
+        }
+        let light = self.lights.remove(index);
+        self.irradiance_cache.clear();
+        Some(light)
+    }
+
+    /// Removes all objects and lights. Settings like the skybox or sampling stay as they are.
+    pub fn clear(&mut self) {
+        self.objects.clear();
+        self.lights.clear();
+        self.objects_changed();
     }
 
-    /// Returns a reference to a vector of all objects
-    pub fn objects(&self) -> &Vec<Box<dyn Shape>> {
+    /// Moves all objects and lights of ```other``` into this world. Its other settings (like the skybox) are dropped.
+    pub fn merge(&mut self, other: World) {
+        self.merge_transformed(other, IDENTITY_MATRIX_4);
+    }
+
+    /// Like [`Self::merge`], but places everything of ```other``` by applying ```matrix``` after its own transformations first.
+    /// This allows building large scenes from reusable pieces ("prefabs"): write a function returning the piece as a world, and merge it in once per placement.
+    pub fn merge_transformed(&mut self, other: World, matrix: Mat4) {
+        for mut object in other.objects {
+            object.set_transformation_matrix(matrix * object.transformation_matrix());
+            self.objects.push(object);
+        }
+        for mut light in other.lights {
+            light.transform(matrix);
+            self.lights.push(light);
+        }
+        self.objects_changed();
+    }
+
+    /// All objects, in the order they were added
+    pub fn objects(&self) -> &[Box<dyn Shape>] {
         &self.objects
     }
 
-    /// Returns a reference to a vector of all objects
-    pub fn objects_mut(&mut self) -> &mut Vec<Box<dyn Shape>> {
+    /// All objects, in the order they were added, for changing them. Use [`Self::add_object`] and [`Self::remove_object`] to add and remove objects.
+    pub fn objects_mut(&mut self) -> &mut [Box<dyn Shape>] {
+        self.objects_changed();
         &mut self.objects
     }
 
-    /// Returns a reference to a vector of all lights
-    pub fn lights(&self) -> &Vec<PointLight> {
+    /// Iterates over all objects, in the order they were added
+    pub fn iter_objects(&self) -> impl Iterator<Item = &dyn Shape> + '_ {
+        self.objects.iter().map(|object| object.as_ref())
+    }
+
+    /// Iterates over all objects for changing them, see [`Self::objects_mut`]
+    pub fn iter_objects_mut(&mut self) -> impl Iterator<Item = &mut dyn Shape> + '_ {
+        self.objects_changed();
+        self.objects
+            .iter_mut()
+            .map(|object| object.as_mut() as &mut dyn Shape)
+    }
+
+    /// Iterates over the objects of type ```T```, e.g. ```world.objects_of_type::<Sphere>()```
+    pub fn objects_of_type<T: Shape>(&self) -> impl Iterator<Item = &T> + '_ {
+        self.objects
+            .iter()
+            .filter_map(|object| object.as_any().downcast_ref::<T>())
+    }
+
+    /// Iterates over the objects ```predicate``` is true for
+    pub fn objects_where<'a>(
+        &'a self,
+        predicate: impl Fn(&dyn Shape) -> bool + 'a,
+    ) -> impl Iterator<Item = &'a dyn Shape> + 'a {
+        self.iter_objects().filter(move |object| predicate(*object))
+    }
+
+    /// Iterates over the objects whose (front) material ```predicate``` is true for, e.g. all transparent objects with ```|m| m.transparency > 0.0```
+    pub fn objects_with_material<'a>(
+        &'a self,
+        predicate: impl Fn(&Material) -> bool + 'a,
+    ) -> impl Iterator<Item = &'a dyn Shape> + 'a {
+        self.objects_where(move |object| predicate(object.material()))
+    }
+
+    /// The object with the given id (see [`ObjectId`]), or None if there is none.
+    /// If several objects share the id, the first one added is returned.
+    pub fn object(&self, id: ObjectId) -> Option<&dyn Shape> {
+        self.iter_objects().find(|object| object.id() == Some(id))
+    }
+
+    /// The object with the given id for changing it, see [`Self::object`]
+    pub fn object_mut(&mut self, id: ObjectId) -> Option<&mut dyn Shape> {
+        self.iter_objects_mut()
+            .find(|object| object.id() == Some(id))
+    }
+
+    fn bvh(&self) -> &Bvh {
+        self.bvh.get_or_init(|| Bvh::build(&self.objects))
+    }
+
+    /// Drops everything derived from the objects, so it is rebuilt for the changed scene
+    fn objects_changed(&mut self) {
+        self.irradiance_cache.clear();
+        self.bvh = OnceLock::new();
+    }
+
+    /// All lights, in the order they were added
+    pub fn lights(&self) -> &[Box<dyn Light>] {
         &self.lights
     }
 
-    pub(crate) fn in_shadow<'a>(
+    /// Iterates over all lights, in the order they were added
+    pub fn iter_lights(&self) -> impl Iterator<Item = &dyn Light> + '_ {
+        self.lights.iter().map(|light| light.as_ref())
+    }
+
+    /// Iterates over the lights of type ```T```, e.g. ```world.lights_of_type::<PointLight>()```
+    pub fn lights_of_type<T: Light>(&self) -> impl Iterator<Item = &T> + '_ {
+        self.lights
+            .iter()
+            .filter_map(|light| light.as_any().downcast_ref::<T>())
+    }
+
+    pub(crate) fn lights_mut(&mut self) -> &mut Vec<Box<dyn Light>> {
+        self.irradiance_cache.clear();
+        &mut self.lights
+    }
+
+    /// Shades every point with only ```samples``` randomly picked lights instead of all of them, which makes worlds with many lights a lot faster to render at the cost of noise.
+    /// None (the default) uses all lights.
+    pub fn set_light_samples(&mut self, samples: Option<usize>) {
+        self.light_samples = samples;
+    }
+
+    /// The number of lights sampled per shaded point, see [`Self::set_light_samples`]
+    pub fn light_samples(&self) -> Option<usize> {
+        self.light_samples
+    }
+
+    /// Enables (or with None, disables) ambient occlusion, which darkens the ambient light where objects are close to each other.
+    pub fn set_ambient_occlusion(&mut self, ambient_occlusion: Option<AmbientOcclusion>) {
+        self.ambient_occlusion = ambient_occlusion;
+    }
+
+    /// The ambient occlusion settings, see [`Self::set_ambient_occlusion`]
+    pub fn ambient_occlusion(&self) -> Option<AmbientOcclusion> {
+        self.ambient_occlusion
+    }
+
+    /// Enables (or with None disables) indirect light bouncing between objects, see [`GlobalIllumination`].
+    pub fn set_global_illumination(&mut self, global_illumination: Option<GlobalIllumination>) {
+        self.global_illumination = global_illumination;
+        self.irradiance_cache.clear();
+    }
+
+    /// Forgets the indirect light cached by previous renders, as it depends on their seed and the order points were shaded in.
+    /// Called at the start of every render, so renders with the same seed are identical.
+    pub(crate) fn clear_irradiance_cache(&self) {
+        self.irradiance_cache.clear();
+    }
+
+    /// The global illumination settings, see [`Self::set_global_illumination`]
+    pub fn global_illumination(&self) -> Option<GlobalIllumination> {
+        self.global_illumination
+    }
+
+    /// Sets where the ambient light comes from, see [`AmbientLight`]
+    pub fn set_ambient_light(&mut self, ambient_light: AmbientLight) {
+        self.ambient_light = ambient_light;
+        self.irradiance_cache.clear();
+    }
+
+    /// Where the ambient light comes from, see [`Self::set_ambient_light`]
+    pub fn ambient_light(&self) -> AmbientLight {
+        self.ambient_light
+    }
+
+    /// Fills the world with fog (or with None clears it), see [`Fog`]
+    pub fn set_fog(&mut self, fog: Option<Fog>) {
+        self.fog = fog;
+        self.irradiance_cache.clear();
+    }
+
+    /// The fog filling the world, see [`Self::set_fog`]
+    pub fn fog(&self) -> Option<Fog> {
+        self.fog
+    }
+
+    /// Sets the skybox that is seen by all rays missing every object
+    pub fn set_skybox(&mut self, skybox: Skybox) {
+        self.skybox = Some(skybox);
+    }
+
+    /// Sets a plain background color for all rays missing every object (camera rays as well as reflected and refracted ones), instead of BLACK.
+    /// This replaces the skybox, see [`Self::set_skybox`].
+    pub fn set_background(&mut self, color: Color) {
+        self.set_skybox(Skybox::new(ColorType::Color(color)));
+    }
+
+    /// Computes the background from the direction of the rays missing every object, e.g. for gradients or environment maps.
+    /// ```shader``` is called with the normalized direction as a point. This replaces the skybox, see [`Self::set_skybox`].
+    pub fn set_background_shader(&mut self, shader: PatternFunction) {
+        self.set_skybox(Skybox::new(ColorType::Pattern(Pattern::new(
+            shader,
+            IDENTITY_MATRIX_4,
+        ))));
+    }
+
+    /// Removes the skybox, so that rays missing every object are BLACK again
+    pub fn remove_skybox(&mut self) {
+        self.skybox = None;
+    }
+
+    /// Returns the skybox of this world, if there is one
+    pub fn skybox(&self) -> Option<&Skybox> {
+        self.skybox.as_ref()
+    }
+
+    /// Checks the world for mistakes that would otherwise show up as black frames or odd colors, like transformations that cannot be inverted,
+    /// broken materials or lights hidden inside objects. Nothing found if empty. See also [`crate::camera::Camera::validate`].
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        if self.objects.is_empty() {
+            diagnostics.push(Diagnostic::warning("the world has no objects"));
+        }
+        if self.lights.is_empty() {
+            diagnostics.push(Diagnostic::warning(
+                "the world has no lights, so all objects not glowing themselves are black",
+            ));
+        }
+
+        for (i, object) in self.objects.iter().enumerate() {
+            let subject = match object.id() {
+                Some(id) => format!("object {i} (id {})", id.0),
+                None => format!("object {i}"),
+            };
+            validation::check_transform(&subject, object.transformation_matrix(), &mut diagnostics);
+            validation::check_material(&subject, object.material(), &mut diagnostics);
+            if let Some(back) = object.back_material() {
+                validation::check_material(&format!("{subject}, back"), back, &mut diagnostics);
+            }
+            if let Some(bias) = object.shadow_bias() {
+                validation::check_shadow_bias(&subject, bias, &mut diagnostics);
+            }
+        }
+
+        for (i, light) in self.lights.iter().enumerate() {
+            let subject = format!("light {i}");
+            let intensity = light.intensity();
+            validation::check_color(&subject, intensity, &mut diagnostics);
+            if intensity == BLACK {
+                diagnostics.push(Diagnostic::warning(format!(
+                    "{subject}: the intensity is black, the light does not shine"
+                )));
+            }
+            // lights infinitely far away have no position
+            let origin = Point::new(0, 0, 0);
+            let distance = light.distance_from(origin);
+            if !distance.is_finite() {
+                continue;
+            }
+            let position = if distance < EPSILON {
+                origin
+            } else {
+                origin + light.direction_from(origin) * distance
+            };
+            for (j, object) in self.objects.iter().enumerate() {
+                if object.material().transparency == 0.0 && encloses(object.as_ref(), position) {
+                    diagnostics.push(Diagnostic::warning(format!(
+                        "{subject} is inside of object {j}, which blocks all of its light"
+                    )));
+                }
+            }
+        }
+        diagnostics
+    }
+
+    /// How much of ```light``` reaches ```point```, see [`Light::transmission`].
+    pub(crate) fn shadow_transmission<'a>(
         &'a self,
-        light: &PointLight,
+        light: &dyn Light,
         point: &Point,
         intersections: &mut Vec<Intersection<'a>>,
-    ) -> bool {
-        let v = light.position - *point;
-        let distance = v.magnitude();
-        let direction = v.normalized();
+    ) -> Color {
+        light.transmission(self, *point, intersections)
+    }
 
-        let r = Ray::new(*point, direction);
-        self.intersect(&r, intersections);
+    /// The first object ```r``` hits in front of its origin, or None if it does not hit anything.
+    /// Allows e.g. picking objects under the mouse (see [`crate::camera::Camera::ray_for_pixel`]) or probing for collisions.
+    /// Called outside of a render, it ignores the camera's settings and uses the default [`crate::settings::RenderSettings::shadow_bias`] and [`crate::settings::RenderSettings::max_distance`].
+    pub fn cast_ray(&self, r: &Ray) -> Option<HitInfo<'_>> {
+        let mut intersections = Vec::new();
+        self.intersect(r, &mut intersections);
+        let hit = hit(&intersections)?;
+        let comps = hit.prepare_computations(r, &intersections);
+        Some(HitInfo {
+            point: comps.point,
+            normal: comps.normalv,
+            distance: hit.t * r.direction.magnitude(),
+            object: hit.object,
+            id: hit.object.id(),
+            material: hit.object.material_facing(comps.inside),
+            inside: comps.inside,
+            face: hit.face_hit.map(|face_hit| face_hit.face),
+        })
+    }
 
-        let h = consuming_hit(intersections);
+    /// Whether nothing (transparent or not) lies between ```a``` and ```b```, e.g. for line of sight checks.
+    /// Surfaces ```a``` or ```b``` lie on do not count, so points on objects can see each other.
+    /// Like [`Self::cast_ray`], this uses the default shadow bias and maximum distance, not those of a camera.
+    pub fn is_visible(&self, a: Point, b: Point) -> bool {
+        let (origin, direction, distance) = segment(a, b);
+        !self.is_occluded(origin, direction, distance, &mut Vec::new())
+    }
 
-        match h {
-            Some(intersection) => intersection.t < distance,
-            None => false,
+    /// How much light makes it from ```a``` to ```b```: WHITE if nothing is in the way, BLACK if an opaque object is.
+    /// Transparent objects in between tint it, like the shadows of colored glass, see [`Self::transmission_along`].
+    /// Surfaces ```a``` or ```b``` lie on do not count, and the default shadow bias and maximum distance apply, as for [`Self::is_visible`].
+    pub fn transmission_between(&self, a: Point, b: Point) -> Color {
+        let (origin, direction, distance) = segment(a, b);
+        self.transmission_along(origin, direction, distance, &mut Vec::new())
+    }
+
+    /// The fraction of ```light``` reaching ```point```: 1.0 if nothing is in the way, 0.0 if the point is completely in shadow.
+    ///
+    /// This is the average of the colored [`Light::transmission`] that shading uses, so lights sampling several shadow rays and translucent objects in between show up here, too.
+    pub fn intensity_at(&self, light: &dyn Light, point: Point) -> Scalar {
+        let transmission = light.transmission(self, point, &mut Vec::new());
+        (transmission.red + transmission.green + transmission.blue) / 3.0
+    }
+
+    /// How much light makes it from ```point``` to ```distance``` units along ```direction```, as a color.
+    /// Every transparent surface in between tints the light with its color and dims it by its transparency, an opaque one blocks it completely (BLACK).
+    /// Used by lights to test for shadows. The intersections vector is only provided to save on allocations.
+    pub fn transmission_along<'a>(
+        &'a self,
+        point: Point,
+        direction: Vector,
+        distance: Scalar,
+        intersections: &mut Vec<Intersection<'a>>,
+    ) -> Color {
+        let r = Ray::new(point, direction);
+        stats::record(|stats| stats.shadow_rays += 1);
+
+        // the tints multiply, so the surfaces need not be sorted, and the first opaque one ends the search
+        let mut transmission = WHITE;
+        let blocked = self.bvh().any(&self.objects, &r, |object| {
+            let material = object.material();
+            if material.transparency == 0.0 {
+                return object.occludes(&r, distance, intersections);
+            }
+            object.intersect(&r, intersections);
+            for intersection in intersections.iter() {
+                if (0.0..distance).contains(&intersection.t) {
+                    let color = material.color_at(object, r.position(intersection.t));
+                    transmission *= color * material.transparency;
+                }
+            }
+            intersections.clear();
+            false
+        });
+
+        if blocked {
+            BLACK
+        } else {
+            transmission
         }
     }
+
+    /// Whether any object (transparent or not) lies less than ```distance``` units from ```point``` along ```direction```.
+    /// Stops at the first object found, which makes it a lot cheaper than finding the nearest hit.
+    pub fn is_occluded<'a>(
+        &'a self,
+        point: Point,
+        direction: Vector,
+        distance: Scalar,
+        intersections: &mut Vec<Intersection<'a>>,
+    ) -> bool {
+        let r = Ray::new(point, direction);
+        self.bvh().any(&self.objects, &r, |object| {
+            object.occludes(&r, distance, intersections)
+        })
+    }
+}
+
+/// The ray from ```a``` to ```b``` and its length, both ends moved inwards by the shadow bias so surfaces the points lie on are not hit
+fn segment(a: Point, b: Point) -> (Point, Vector, Scalar) {
+    let bias = settings::shadow_bias();
+    let length = (b - a).magnitude();
+    if length <= 2.0 * bias {
+        return (a, Vector::new(0, 0, 1), 0.0);
+    }
+    let direction = (b - a).normalized();
+    (a + direction * bias, direction, length - 2.0 * bias)
+}
+
+/// The direction of the ray refracted at the hit point (Snell's law), None on total internal reflection.
+fn refraction_direction(computations: &PreparedComputations) -> Option<Vector> {
+    let n_ratio = computations.n1 / computations.n2;
+    let cos_i = computations.eyev.dot(computations.normalv);
+    let sin2_t = n_ratio.powi(2) * (1.0 - cos_i.powi(2));
+    if sin2_t > 1.0 {
+        return None;
+    }
+
+    let cos_t = (1.0 - sin2_t).sqrt();
+    Some(computations.normalv * (n_ratio * cos_i - cos_t) - computations.eyev * n_ratio)
+}
+
+/// Whether ```point``` is inside of the closed ```object```: rays from it in opposite directions both cross the surface an odd number of times
+fn encloses(object: &dyn Shape, point: Point) -> bool {
+    // not along an axis, so rays do not graze the edges of boxes and meshes
+    let direction = Vector::new(0.31, 0.87, 0.38);
+    [direction, -direction].iter().all(|&direction| {
+        let mut xs = Vec::new();
+        object.intersect(&Ray::new(point, direction), &mut xs);
+        xs.iter().filter(|x| x.t > 0.0).count() % 2 == 1
+    })
 }
 
 #[cfg(test)]
 mod world_tests {
-    use std::thread;
+    use std::{collections::HashSet, thread};
 
     use crate::{
         color::{Color, BLACK, WHITE},
         epsilon::EpsilonEqual,
         intersection::Intersection,
-        light::PointLight,
-        material::{ColorType, Material},
+        light::{Light, LightLinking, PointLight, SpotLight},
+        material::{ClearCoat, ColorType, Material},
         matrix::Mat4,
-        pattern::Pattern,
+        pattern::{pattern_function, Pattern},
         ray::Ray,
-        shapes::{plane::Plane, shape::Shape, sphere::Sphere},
+        sampling::Rng,
+        scalar::Scalar,
+        shapes::{
+            plane::Plane,
+            shape::{ObjectId, Shape},
+            sphere::Sphere,
+        },
+        skybox::Skybox,
+        stats,
+        transform::Transform,
         tuple::{Point, Vector},
-        world::World,
+        world::{AmbientLight, AmbientOcclusion, Fog, GlobalIllumination, World},
     };
 
     #[test]
@@ -258,87 +1414,328 @@ mod world_tests {
     }
 
     #[test]
-    fn new_test_default() {
-        let w = World::test_world();
+    fn new_test_default() {
+        let w = World::test_world();
+
+        let light = PointLight::new(Point::new(-10, 10, -10), Color::new(1, 1, 1));
+        let mut s = Sphere::default();
+        s.set_material(Material {
+            color: ColorType::Color(Color::new(0.8, 1.0, 0.6)),
+            diffuse: 0.7,
+            specular: 0.2,
+            ..Default::default()
+        });
+        let mut s2 = Sphere::default();
+        let transf = Mat4::new_scaling(0.5, 0.5, 0.5);
+        s2.set_transformation_matrix(transf);
+
+        assert_eq!(w.lights, vec!(Box::new(light) as Box<dyn Light>));
+        let ws1 = w.objects[0].as_any().downcast_ref::<Sphere>().unwrap();
+        let ws2 = w.objects[1].as_any().downcast_ref::<Sphere>().unwrap();
+        assert_eq!(ws1, &s);
+        assert_eq!(ws2, &s2);
+    }
+
+    #[test]
+    fn intersect_with_ray() {
+        let w = World::test_world();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0, 0, 1));
+        let mut intersections = Vec::new();
+        w.intersect(&r, &mut intersections);
+        assert_eq!(intersections.len(), 4);
+        assert!(intersections[0].t.e_equals(4.));
+        assert!(intersections[1].t.e_equals(4.5));
+        assert!(intersections[2].t.e_equals(5.5));
+        assert!(intersections[3].t.e_equals(6.));
+    }
+
+    #[test]
+    fn test_shade_intersection() {
+        let w = World::test_world();
+        let r = Ray::new(Point::new(0, 0, -5), Vector::new(0, 0, 1));
+        let shape = w.objects.first().unwrap();
+        let s = &**shape;
+        let i = Intersection::new(4.0, s);
+        let comps = i.prepare_computations(&r, &vec![i]);
+        let mut intersections = Vec::new();
+        let c = w.shade_hit(&comps, &mut intersections, 0);
+        assert_eq!(c, Color::new(0.38066, 0.47583, 0.2855));
+    }
+
+    #[test]
+    fn test_shade_intersection_inside() {
+        let mut w = World::test_world();
+        w.lights = vec![Box::new(PointLight::new(
+            Point::new(0.0, 0.25, 0.0),
+            Color::new(1, 1, 1),
+        ))];
+        let r = Ray::new(Point::new(0, 0, 0), Vector::new(0, 0, 1));
+        let s = &*w.objects[1];
+
+        let i = Intersection::new(0.5, s);
+        let mut intersections = Vec::new();
+        let comps = i.prepare_computations(&r, &vec![i]);
+        let c = w.shade_hit(&comps, &mut intersections, 0);
+        assert_eq!(c, Color::new(0.90498, 0.90498, 0.90498));
+    }
+
+    #[test]
+    fn ray_misses() {
+        let w = World::test_world();
+        let r = Ray::new(Point::new(0, 0, -5), Vector::new(0, 1, 0));
+        let mut intersections = Vec::new();
+        let c = w.color_at(&r, &mut intersections, 0);
+        assert_eq!(c, BLACK);
+    }
+
+    #[test]
+    fn ray_misses_skybox() {
+        let mut w = World::test_world();
+        w.set_skybox(Skybox::new(ColorType::Pattern(Pattern::test_pattern())));
+        let r = Ray::new(Point::new(0, 0, -5), Vector::new(0, 1, 0));
+        let mut intersections = Vec::new();
+        let c = w.color_at(&r, &mut intersections, 0);
+        assert_eq!(c, Color::new(0, 1, 0));
+
+        w.remove_skybox();
+        let c = w.color_at(&r, &mut Vec::new(), 0);
+        assert_eq!(c, BLACK);
+    }
+
+    #[test]
+    fn bvh_rebuilt_after_objects_changed() {
+        let mut w = World::test_world();
+        let r = Ray::new(Point::new(5, 0, -5), Vector::new(0, 0, 1));
+        let hits = |w: &World| {
+            let mut xs = Vec::new();
+            w.intersect(&r, &mut xs);
+            xs.iter().map(|x| x.t).collect::<Vec<Scalar>>()
+        };
+        assert!(hits(&w).is_empty());
+
+        w.objects_mut()[1].set_transformation_matrix(Mat4::new_translation(5, 0, 0));
+        assert_eq!(hits(&w), vec![4.0, 6.0]);
+
+        let mut far = Sphere::default();
+        far.set_transformation_matrix(Mat4::new_translation(5, 0, 10));
+        w.add_object(Box::new(far));
+        assert_eq!(hits(&w), vec![4.0, 6.0, 14.0, 16.0]);
+    }
+
+    #[test]
+    fn background() {
+        let mut w = World::test_world();
+        w.set_background(Color::new(0.1, 0.2, 0.3));
+        let r = Ray::new(Point::new(0, 0, -5), Vector::new(0, 1, 0));
+        assert_eq!(
+            w.color_at(&r, &mut Vec::new(), 0),
+            Color::new(0.1, 0.2, 0.3)
+        );
+
+        // a gradient from the horizon up
+        w.set_background_shader(pattern_function(|direction| {
+            Color::new(0.0, 0.0, direction.y)
+        }));
+        let up = Ray::new(Point::new(0, 0, -5), Vector::new(0, 1, 0));
+        assert_eq!(w.color_at(&up, &mut Vec::new(), 0), Color::new(0, 0, 1));
+        let diagonal = Ray::new(Point::new(0, 0, -5), Vector::new(0, 1, -1).normalized());
+        assert_eq!(
+            w.color_at(&diagonal, &mut Vec::new(), 0),
+            Color::new(0.0, 0.0, Scalar::sqrt(0.5))
+        );
+    }
+
+    #[test]
+    fn skybox_seen_in_reflection() {
+        let mut w = World::default();
+        w.set_skybox(Skybox::new(ColorType::Color(Color::new(0.2, 0.4, 0.6))));
+
+        let mut mirror = Plane::default();
+        mirror.material_mut().reflective = 1.0;
+        mirror.material_mut().ambient = 0.0;
+        mirror.set_transformation_matrix(Mat4::new_translation(0, -1, 0));
+        w.add_object(Box::new(mirror));
+
+        let r = Ray::new(
+            Point::new(0, 0, -3),
+            Vector::new(0.0, -(Scalar::sqrt(2.0)) / 2.0, Scalar::sqrt(2.0) / 2.0),
+        );
+        let mut intersections = Vec::new();
+        let c = w.color_at(&r, &mut intersections, 1);
+        assert_eq!(c, Color::new(0.2, 0.4, 0.6));
+    }
+
+    #[test]
+    fn ray_hits() {
+        let w = World::test_world();
+        let r = Ray::new(Point::new(0, 0, -5), Vector::new(0, 0, 1));
+        let mut intersections = Vec::new();
+        let c = w.color_at(&r, &mut intersections, 0);
+        assert_eq!(c, Color::new(0.38066, 0.47583, 0.2855));
+    }
+    #[test]
+    fn layers() {
+        let w = World::test_world();
+        let r = Ray::new(Point::new(0, 0, -5), Vector::new(0, 0, 1));
+        let layers = w.layers_at(&r, &mut Vec::new(), 1);
+        assert_eq!(layers.color, w.color_at(&r, &mut Vec::new(), 1));
+        assert_eq!(
+            layers.color,
+            layers.ambient + layers.direct + layers.reflection + layers.refraction
+        );
+        assert_eq!(layers.normal, Color::new(0, 0, -1));
+        assert_eq!(layers.albedo, Color::new(0.8, 1.0, 0.6));
+        assert_eq!(layers.emission, Color::new(0, 0, 0));
+        assert_eq!(layers.ambient, Color::new(0.08, 0.1, 0.06));
+
+        let r = Ray::new(Point::new(0, 0, -5), Vector::new(0, 1, 0));
+        let layers = w.layers_at(&r, &mut Vec::new(), 1);
+        assert_eq!(layers.color, Color::new(0, 0, 0));
+        assert_eq!(layers.albedo, Color::new(0, 0, 0));
+    }
+
+    #[test]
+    fn color_with_alpha() {
+        let mut w = World::test_world();
+        w.set_skybox(Skybox::new(ColorType::Color(Color::new(0.2, 0.4, 0.6))));
+        let r = Ray::new(Point::new(0, 0, -5), Vector::new(0, 0, 1));
+        assert_eq!(
+            w.color_with_alpha_at(&r, &mut Vec::new(), 1),
+            (w.color_at(&r, &mut Vec::new(), 1), 1.0)
+        );
+        let r = Ray::new(Point::new(0, 0, -5), Vector::new(0, 1, 0));
+        assert_eq!(w.color_with_alpha_at(&r, &mut Vec::new(), 1), (BLACK, 0.0));
+    }
 
-        let light = PointLight::new(Point::new(-10, 10, -10), Color::new(1, 1, 1));
-        let mut s = Sphere::default();
-        let mut mat = Material::default();
-        mat.color = ColorType::Color(Color::new(0.8, 1.0, 0.6));
-        mat.diffuse = 0.7;
-        mat.specular = 0.2;
-        s.set_material(mat);
-        let mut s2 = Sphere::default();
-        let transf = Mat4::new_scaling(0.5, 0.5, 0.5);
-        s2.set_transformation_matrix(transf);
+    #[test]
+    fn occlusion() {
+        let mut w = World::default();
+        let mut ball = Sphere::default();
+        ball.set_transformation_matrix(Mat4::new_translation(0, 1, 0));
+        w.add_object(Box::new(ball));
+        w.add_object(Box::new(Plane::default()));
+        let ambient_occlusion = AmbientOcclusion::new(64, 10.0);
 
-        assert_eq!(w.lights, vec!(light));
-        let ws1 = w.objects[0].as_any().downcast_ref::<Sphere>().unwrap();
-        let ws2 = w.objects[1].as_any().downcast_ref::<Sphere>().unwrap();
-        assert_eq!(ws1, &s);
-        assert_eq!(ws2, &s2);
+        let down = Vector::new(0, -1, 0);
+        let near = Ray::new(Point::new(1.2, 0.5, 0.0), down);
+        let occlusion = w
+            .occlusion_at(&near, &mut Vec::new(), &ambient_occlusion)
+            .unwrap();
+        assert!(occlusion > 0.5 && occlusion < 1.0);
+        let far = Ray::new(Point::new(20.0, 0.5, 0.0), down);
+        assert_eq!(
+            w.occlusion_at(&far, &mut Vec::new(), &ambient_occlusion),
+            Some(1.0)
+        );
+        let up = Ray::new(Point::new(20.0, 0.5, 0.0), Vector::new(0, 1, 0));
+        assert_eq!(
+            w.occlusion_at(&up, &mut Vec::new(), &ambient_occlusion),
+            None
+        );
     }
 
     #[test]
-    fn intersect_with_ray() {
-        let w = World::test_world();
-        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0, 0, 1));
-        let mut intersections = Vec::new();
-        w.intersect(&r, &mut intersections);
-        assert_eq!(intersections.len(), 4);
-        assert!(intersections[0].t.e_equals(4.));
-        assert!(intersections[1].t.e_equals(4.5));
-        assert!(intersections[2].t.e_equals(5.5));
-        assert!(intersections[3].t.e_equals(6.));
+    fn path_tracing_bounces_emitted_light() {
+        let mut w = World::default();
+        let mut lamp = Sphere::default();
+        lamp.set_transformation_matrix(Mat4::new_translation(0, 2, 0));
+        lamp.material_mut().emissive = Color::new(4, 4, 4);
+        w.add_object(Box::new(lamp));
+        w.add_object(Box::new(Plane::default()));
+
+        // the floor below the lamp is only lit by light bouncing off the lamp
+        let r = Ray::new(
+            Point::new(0.0, 0.5, -2.0),
+            Vector::new(0, -1, 1).normalized(),
+        );
+        assert_eq!(w.color_at(&r, &mut Vec::new(), 5), BLACK);
+        let traced = |seed: u64| {
+            let mut rng = Rng::new(seed);
+            (0..64).fold(BLACK, |sum, _| {
+                sum + w.path_traced_color_at(&r, &mut Vec::new(), &mut rng, 5)
+            })
+        };
+        assert!(traced(1).red > 0.0);
+        assert_eq!(traced(1), traced(1));
+        let mut rng = Rng::new(1);
+        // without bounces, only the direct light counts
+        assert_eq!(
+            w.path_traced_color_at(&r, &mut Vec::new(), &mut rng, 0),
+            BLACK
+        );
+
+        // the lamp itself glows
+        let r = Ray::new(Point::new(0, 2, -5), Vector::new(0, 0, 1));
+        let lamp = w.path_traced_color_at(&r, &mut Vec::new(), &mut rng, 0);
+        assert_eq!(lamp, Color::new(4, 4, 4));
     }
 
     #[test]
-    fn test_shade_intersection() {
+    fn path_tracing_direct_light() {
         let w = World::test_world();
         let r = Ray::new(Point::new(0, 0, -5), Vector::new(0, 0, 1));
-        let shape = w.objects.first().unwrap();
-        let s = &**shape;
-        let i = Intersection::new(4.0, s);
+        let i = Intersection::new(4.0, w.objects[0].as_shape());
         let comps = i.prepare_computations(&r, &vec![i]);
-        let mut intersections = Vec::new();
-        let c = w.shade_hit(&comps, &mut intersections, 0);
-        assert_eq!(c, Color::new(0.38066, 0.47583, 0.2855));
+        let direct = w.all_lights_at(&comps, &mut Vec::new());
+        assert_eq!(
+            w.path_traced_color_at(&r, &mut Vec::new(), &mut Rng::new(1), 0),
+            direct
+        );
     }
 
     #[test]
-    fn test_shade_intersection_inside() {
-        let mut w = World::test_world();
-        w.lights = vec![PointLight::new(
-            Point::new(0.0, 0.25, 0.0),
+    fn global_illumination_bleeds_color() {
+        let mut w = World::default();
+        w.add_light(Box::new(PointLight::new(
+            Point::new(0, 5, 0),
             Color::new(1, 1, 1),
-        )];
-        let r = Ray::new(Point::new(0, 0, 0), Vector::new(0, 0, 1));
-        let s = &*w.objects[1];
+        )));
+        w.add_object(Box::new(Plane::default()));
+        let mut wall = Plane::default();
+        wall.set_transformation_matrix(
+            Mat4::new_translation(1, 0, 0) * Mat4::new_rotation_z(crate::scalar::consts::FRAC_PI_2),
+        );
+        wall.material_mut().color = ColorType::Color(Color::new(1, 0, 0));
+        w.add_object(Box::new(wall));
 
-        let i = Intersection::new(0.5, s);
-        let mut intersections = Vec::new();
-        let comps = i.prepare_computations(&r, &vec![i]);
-        let c = w.shade_hit(&comps, &mut intersections, 0);
-        assert_eq!(c, Color::new(0.90498, 0.90498, 0.90498));
-    }
+        let r = Ray::new(
+            Point::new(0.5, 1.0, -1.0),
+            Vector::new(0, -1, 1).normalized(),
+        );
+        let plain = w.color_at(&r, &mut Vec::new(), 0);
+        w.set_global_illumination(Some(GlobalIllumination::new(64, 0.5)));
+        assert_eq!(w.irradiance_cache.len(), 0);
+        let bleeding = w.color_at(&r, &mut Vec::new(), 0);
+        assert_eq!(w.irradiance_cache.len(), 1);
+        // the red wall tints the floor
+        assert!(bleeding.red - plain.red > bleeding.green - plain.green);
+        assert!(bleeding.green >= plain.green);
+
+        // nearby points reuse the cached light
+        let r = Ray::new(
+            Point::new(0.55, 1.0, -1.0),
+            Vector::new(0, -1, 1).normalized(),
+        );
+        w.color_at(&r, &mut Vec::new(), 0);
+        assert_eq!(w.irradiance_cache.len(), 1);
 
-    #[test]
-    fn ray_misses() {
-        let w = World::test_world();
-        let r = Ray::new(Point::new(0, 0, -5), Vector::new(0, 1, 0));
-        let mut intersections = Vec::new();
-        let c = w.color_at(&r, &mut intersections, 0);
-        assert_eq!(c, BLACK);
+        w.set_global_illumination(None);
+        assert_eq!(w.irradiance_cache.len(), 0);
+        assert_eq!(w.global_illumination(), None);
     }
 
     #[test]
-    fn ray_hits() {
+    fn depth() {
         let w = World::test_world();
         let r = Ray::new(Point::new(0, 0, -5), Vector::new(0, 0, 1));
-        let mut intersections = Vec::new();
-        let c = w.color_at(&r, &mut intersections, 0);
-        assert_eq!(c, Color::new(0.38066, 0.47583, 0.2855));
+        assert_eq!(w.depth_at(&r, &mut Vec::new()), 4.0);
+        let r = Ray::new(Point::new(0, 0, -5), Vector::new(0, 1, 0));
+        assert_eq!(w.depth_at(&r, &mut Vec::new()), Scalar::INFINITY);
+        // inside the outer sphere, the inner one is hit first
+        let r = Ray::new(Point::new(0, 0, 0.75), Vector::new(0, 0, -1));
+        assert_eq!(w.depth_at(&r, &mut Vec::new()), 0.25);
     }
+
     #[test]
     fn intersection_behind_ray() {
         let mut w = World::test_world();
@@ -376,6 +1773,248 @@ mod world_tests {
         assert_eq!(first, &Sphere::default());
     }
 
+    #[test]
+    fn fog_amount() {
+        let fog = Fog::new(WHITE, 0.5);
+        let r = Ray::new(Point::new(0, 0, 0), Vector::new(0, 0, 1));
+        assert_eq!(fog.amount(&r, 0.0), 0.0);
+        assert!(fog.amount(&r, 2.0).e_equals(1.0 - Scalar::exp(-1.0)));
+        assert_eq!(fog.amount(&r, Scalar::INFINITY), 1.0);
+        assert_eq!(Fog::new(WHITE, 0.0).amount(&r, Scalar::INFINITY), 0.0);
+
+        // thinning out upwards, rays going up escape the fog, rays going down sink into it
+        let fog = fog.with_falloff(1.0);
+        let up = Ray::new(Point::new(0, 0, 0), Vector::new(0, 1, 0));
+        assert!(fog
+            .amount(&up, Scalar::INFINITY)
+            .e_equals(1.0 - Scalar::exp(-0.5)));
+        let down = Ray::new(Point::new(0, 0, 0), Vector::new(0, -1, 0));
+        assert_eq!(fog.amount(&down, Scalar::INFINITY), 1.0);
+        // at the same distance, there is more fog below
+        let side = Ray::new(Point::new(0, -1, 0), Vector::new(1, 0, 0));
+        assert!(fog.amount(&side, 1.0) > fog.amount(&r, 1.0));
+    }
+
+    #[test]
+    fn fog_fades_distant_surfaces() {
+        let mut w = World::test_world();
+        let r = Ray::new(Point::new(0, 0, -5), Vector::new(0, 0, 1));
+        let clear = w.color_at(&r, &mut Vec::new(), 0);
+        // the same distance with a longer direction
+        let long = Ray::new(Point::new(0, 0, -5), Vector::new(0, 0, 2));
+        let clear_long = w.color_at(&long, &mut Vec::new(), 0);
+        let fog_color = Color::new(0.5, 0.5, 0.5);
+        w.set_fog(Some(Fog::new(fog_color, 0.1)));
+        assert_eq!(w.fog(), Some(Fog::new(fog_color, 0.1)));
+
+        // the sphere is hit 4 units away
+        let amount = 1.0 - Scalar::exp(-0.4);
+        assert_eq!(
+            w.color_at(&r, &mut Vec::new(), 0),
+            clear * (1.0 - amount) + fog_color * amount
+        );
+        assert_eq!(
+            w.color_at(&long, &mut Vec::new(), 0),
+            clear_long * (1.0 - amount) + fog_color * amount
+        );
+        let miss = Ray::new(Point::new(0, 0, -5), Vector::new(0, 1, 0));
+        assert_eq!(w.color_at(&miss, &mut Vec::new(), 0), fog_color);
+    }
+
+    #[test]
+    fn builder() {
+        let w = World::builder()
+            .light(PointLight::new(Point::new(-10, 10, -10), WHITE))
+            .object(Sphere::default())
+            .boxed_object(Box::new(Plane::default()))
+            .background(Color::new(0.1, 0.2, 0.3))
+            .light_samples(1)
+            .ambient_occlusion(AmbientOcclusion::new(4, 1.0))
+            .build();
+        assert_eq!(w.objects().len(), 2);
+        assert_eq!(w.lights().len(), 1);
+        assert_eq!(w.light_samples(), Some(1));
+        assert_eq!(w.ambient_occlusion(), Some(AmbientOcclusion::new(4, 1.0)));
+        assert!(w.global_illumination().is_none());
+        let r = Ray::new(Point::new(0, 5, 0), Vector::new(0, 1, 0));
+        assert_eq!(
+            w.color_at(&r, &mut Vec::new(), 0),
+            Color::new(0.1, 0.2, 0.3)
+        );
+    }
+
+    #[test]
+    fn merge() {
+        let lamp = || {
+            World::builder()
+                .object(Sphere::default())
+                .light(PointLight::new(Point::new(0, 2, 0), WHITE))
+                .build()
+        };
+        let mut w = World::default();
+        w.merge(lamp());
+        w.merge_transformed(lamp(), Mat4::new_translation(10, 0, 0));
+        assert_eq!(w.objects().len(), 2);
+        assert_eq!(w.lights().len(), 2);
+        assert_eq!(
+            w.lights()[1].direction_from(Point::new(10, 0, 0)),
+            Vector::new(0, 1, 0)
+        );
+
+        let r = Ray::new(Point::new(10, 0, -5), Vector::new(0, 0, 1));
+        let mut xs = Vec::new();
+        w.intersect(&r, &mut xs);
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t, 4.0);
+    }
+
+    #[test]
+    fn typed_queries() {
+        let mut w = World::test_world();
+        w.add_object(Box::new(Plane::default()));
+        w.add_light(Box::new(SpotLight::new(
+            Point::new(0, 5, 0),
+            Vector::new(0, -1, 0),
+            0.3,
+            0.5,
+            WHITE,
+        )));
+        assert_eq!(w.iter_objects().count(), 3);
+        assert_eq!(w.iter_lights().count(), 2);
+        assert_eq!(w.objects_of_type::<Sphere>().count(), 2);
+        assert_eq!(w.objects_of_type::<Plane>().count(), 1);
+        assert_eq!(w.lights_of_type::<PointLight>().count(), 1);
+        assert_eq!(w.lights_of_type::<SpotLight>().count(), 1);
+
+        // only the outer sphere of the test world has a color other than white
+        let colored: Vec<&dyn Shape> = w
+            .objects_with_material(|m| m.color != ColorType::Color(WHITE))
+            .collect();
+        assert_eq!(colored.len(), 1);
+        assert!(colored[0].eq(w.objects()[0].as_any()));
+        assert_eq!(w.objects_where(|o| o.bounds().is_none()).count(), 1);
+
+        assert!(w.object(ObjectId(3)).is_none());
+        w.objects_mut()[1].set_id(Some(ObjectId(3)));
+        w.object_mut(ObjectId(3))
+            .unwrap()
+            .set_transformation_matrix(Mat4::new_scaling(2, 2, 2));
+        assert_eq!(
+            w.object(ObjectId(3)).unwrap().transformation_matrix(),
+            Mat4::new_scaling(2, 2, 2)
+        );
+    }
+
+    #[test]
+    fn cast_ray() {
+        let mut w = World::test_world();
+        w.objects_mut()[0].set_id(Some(ObjectId(1)));
+        let hit = w
+            .cast_ray(&Ray::new(Point::new(0, 0, -5), Vector::new(0, 0, 2)))
+            .unwrap();
+        assert_eq!(hit.point, Point::new(0, 0, -1));
+        assert_eq!(hit.normal, Vector::new(0, 0, -1));
+        assert_eq!(hit.distance, 4.0);
+        assert_eq!(hit.id, Some(ObjectId(1)));
+        assert!(hit.object.eq(w.objects()[0].as_any()));
+        assert_eq!(hit.material, w.objects()[0].material());
+        assert!(!hit.inside);
+        assert_eq!(hit.face, None);
+
+        // from the center, the inner sphere is hit from inside
+        let hit = w
+            .cast_ray(&Ray::new(Point::new(0, 0, 0), Vector::new(0, 1, 0)))
+            .unwrap();
+        assert_eq!(hit.point, Point::new(0, 0.5, 0));
+        assert_eq!(hit.normal, Vector::new(0, -1, 0));
+        assert!(hit.inside);
+
+        assert!(w
+            .cast_ray(&Ray::new(Point::new(0, 0, -5), Vector::new(0, 1, 0)))
+            .is_none());
+    }
+
+    #[test]
+    fn is_visible() {
+        let w = World::test_world();
+        assert!(!w.is_visible(Point::new(0, 0, -5), Point::new(0, 0, 5)));
+        assert!(w.is_visible(Point::new(0, 0, -5), Point::new(0, 5, -5)));
+        assert!(w.is_visible(Point::new(0, 0, -5), Point::new(0, 0, -5)));
+        // on the surface of the outer sphere, facing away from it
+        assert!(w.is_visible(Point::new(0, 0, -1), Point::new(0, 0, -5)));
+        assert!(w.is_visible(Point::new(0, 0, -5), Point::new(0, 0, -1)));
+        assert!(!w.is_visible(Point::new(0, 0, -1), Point::new(0, 0, 1)));
+    }
+
+    #[test]
+    fn transmission_between() {
+        let mut w = World::default();
+        w.add_object(Box::new(Sphere::default()));
+        w.objects_mut()[0].material_mut().transparency = 0.5;
+        w.objects_mut()[0].material_mut().color = ColorType::Color(Color::new(1, 0, 0));
+        let a = Point::new(0, 0, -5);
+        assert_eq!(w.transmission_between(a, Point::new(0, 5, -5)), WHITE);
+        assert_eq!(
+            w.transmission_between(a, Point::new(0, 0, 5)),
+            Color::new(0.25, 0.0, 0.0)
+        );
+        // ends within the sphere, so only passes one surface
+        assert_eq!(
+            w.transmission_between(a, Point::new(0, 0, 0)),
+            Color::new(0.5, 0.0, 0.0)
+        );
+        assert!(!w.is_visible(a, Point::new(0, 0, 0)));
+
+        w.objects_mut()[0].material_mut().transparency = 0.0;
+        assert_eq!(w.transmission_between(a, Point::new(0, 0, 5)), BLACK);
+    }
+
+    #[test]
+    fn remove_and_replace_objects() {
+        let mut w = World::test_world();
+        w.objects_mut()[1].set_id(Some(ObjectId(7)));
+        let r = Ray::new(Point::new(0, 0, -5), Vector::new(0, 0, 1));
+        let hits = |w: &World| {
+            let mut xs = Vec::new();
+            w.intersect(&r, &mut xs);
+            let mut ts = xs.iter().map(|x| x.t).collect::<Vec<Scalar>>();
+            ts.sort_by(Scalar::total_cmp);
+            ts
+        };
+        assert_eq!(hits(&w), vec![4.0, 4.5, 5.5, 6.0]);
+
+        let mut bigger = Sphere::default();
+        bigger.set_transformation_matrix(Mat4::new_scaling(2, 2, 2));
+        let old = w.replace_object(ObjectId(7), Box::new(bigger)).unwrap();
+        assert_eq!(old.id(), Some(ObjectId(7)));
+        assert_eq!(w.objects()[1].id(), Some(ObjectId(7)));
+        assert_eq!(hits(&w), vec![3.0, 4.0, 6.0, 7.0]);
+
+        assert!(w.remove_object(ObjectId(7)).is_some());
+        assert_eq!(w.objects().len(), 1);
+        assert_eq!(hits(&w), vec![4.0, 6.0]);
+        assert!(w.remove_object(ObjectId(7)).is_none());
+        assert!(w
+            .replace_object(ObjectId(7), Box::new(Sphere::default()))
+            .is_none());
+        assert_eq!(w.objects().len(), 1);
+    }
+
+    #[test]
+    fn remove_lights_and_clear() {
+        let mut w = World::test_world();
+        assert!(w.remove_light(1).is_none());
+        assert!(w.remove_light(0).is_some());
+        assert!(w.lights().is_empty());
+
+        w.clear();
+        assert!(w.objects().is_empty());
+        let r = Ray::new(Point::new(0, 0, -5), Vector::new(0, 0, 1));
+        let mut xs = Vec::new();
+        w.intersect(&r, &mut xs);
+        assert!(xs.is_empty());
+    }
+
     #[test]
     fn add_objects() {
         let mut w = World::default();
@@ -393,7 +2032,7 @@ mod world_tests {
         assert_eq!(w.lights.len(), 0);
 
         let l = PointLight::new(Point::new(0, 0, 0), WHITE);
-        w.add_light(l);
+        w.add_light(Box::new(l));
         assert_eq!(w.lights.len(), 1);
     }
 
@@ -405,7 +2044,7 @@ mod world_tests {
         let l = PointLight::new(Point::new(0, 0, 0), WHITE);
         let l2 = PointLight::new(Point::new(0, 0, 0), WHITE);
 
-        w.add_lights(&mut vec![l, l2]);
+        w.add_lights(&mut vec![Box::new(l), Box::new(l2)]);
         assert_eq!(w.lights.len(), 2);
     }
 
@@ -414,11 +2053,11 @@ mod world_tests {
         let w = World::test_world();
         let p = Point::new(0, 10, 0);
         let mut intersections = Vec::new();
-        let shadowed = {
-            let light = w.lights()[0];
-            w.in_shadow(&light, &p, &mut intersections)
+        let transmission = {
+            let light = w.lights()[0].as_ref();
+            w.shadow_transmission(light, &p, &mut intersections)
         };
-        assert_eq!(shadowed, false);
+        assert_eq!(transmission, WHITE);
     }
 
     #[test]
@@ -426,11 +2065,11 @@ mod world_tests {
         let w = World::test_world();
         let p = Point::new(10, -10, 10);
         let mut intersections = Vec::new();
-        let shadowed = {
-            let light = w.lights()[0];
-            w.in_shadow(&light, &p, &mut intersections)
+        let transmission = {
+            let light = w.lights()[0].as_ref();
+            w.shadow_transmission(light, &p, &mut intersections)
         };
-        assert_eq!(shadowed, true);
+        assert_eq!(transmission, BLACK);
     }
 
     #[test]
@@ -438,11 +2077,11 @@ mod world_tests {
         let w = World::test_world();
         let p = Point::new(-20, 20, -20);
         let mut intersections = Vec::new();
-        let shadowed = {
-            let light = w.lights()[0];
-            w.in_shadow(&light, &p, &mut intersections)
+        let transmission = {
+            let light = w.lights()[0].as_ref();
+            w.shadow_transmission(light, &p, &mut intersections)
         };
-        assert_eq!(shadowed, false);
+        assert_eq!(transmission, WHITE);
     }
 
     #[test]
@@ -450,17 +2089,369 @@ mod world_tests {
         let w = World::test_world();
         let p = Point::new(-2, 2, -2);
         let mut intersections = Vec::new();
-        let shadowed = {
-            let light = w.lights()[0];
-            w.in_shadow(&light, &p, &mut intersections)
+        let transmission = {
+            let light = w.lights()[0].as_ref();
+            w.shadow_transmission(light, &p, &mut intersections)
+        };
+        assert_eq!(transmission, WHITE);
+    }
+
+    #[test]
+    fn transparent_object_casts_colored_shadow() {
+        let mut w = World::default();
+        w.add_light(Box::new(PointLight::new(Point::new(0, 10, 0), WHITE)));
+
+        let mut glass = Sphere::default();
+        glass.material_mut().transparency = 0.5;
+        glass.material_mut().color = ColorType::Color(Color::new(1.0, 0.5, 0.0));
+        w.add_object(Box::new(glass));
+
+        let p = Point::new(0, -5, 0);
+        let mut intersections = Vec::new();
+        let transmission = {
+            let light = w.lights()[0].as_ref();
+            w.shadow_transmission(light, &p, &mut intersections)
+        };
+        // the light passes through two surfaces of the sphere
+        assert_eq!(transmission, Color::new(0.25, 0.0625, 0.0));
+    }
+
+    #[test]
+    fn shadow_stops_at_first_opaque_object() {
+        let mut w = World::default();
+        w.add_light(Box::new(PointLight::new(Point::new(0, 0, -20), WHITE)));
+        for z in 0..8 {
+            let mut s = Sphere::default();
+            s.set_transformation_matrix(Mat4::new_translation(0, 0, 3 * z));
+            w.add_object(Box::new(s));
+        }
+
+        let p = Point::new(0, 0, 30);
+        let mut intersections = Vec::new();
+        let (transmission, stats) = stats::collect(|| {
+            let light = w.lights()[0].as_ref();
+            w.shadow_transmission(light, &p, &mut intersections)
+        });
+        assert_eq!(transmission, BLACK);
+        assert_eq!(stats.shadow_rays, 1);
+        assert!(stats.intersection_tests < 8);
+        assert!(intersections.is_empty());
+    }
+
+    #[test]
+    fn validate() {
+        assert!(World::test_world().validate().is_empty());
+        assert_eq!(
+            World::default().validate().len(),
+            2,
+            "no objects and no lights"
+        );
+
+        let mut w = World::test_world();
+        let mut flat = Sphere::default().scaled(1.0, 0.0, 1.0);
+        flat.set_id(Some(ObjectId(7)));
+        w.add_object(Box::new(flat));
+        w.add_object(Box::new(Sphere::default().translated(
+            Scalar::NAN,
+            0.0,
+            0.0,
+        )));
+        let mut glass = Sphere::default();
+        glass.material_mut().transparency = 1.0;
+        glass.set_shadow_bias(Some(-1.0));
+        w.add_object(Box::new(glass));
+        // inside a big opaque sphere
+        w.add_light(Box::new(PointLight::new(Point::new(0, 0, 0), WHITE)));
+        let messages: Vec<String> = w.validate().iter().map(|d| d.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "error: object 2 (id 7): the transformation is not invertible, e.g. it scales by 0",
+                "error: object 3: the transformation contains NaN or infinite values",
+                "warning: object 4: the material is transparent, but its refractive index is 1 (like air), so it does not bend light",
+                "error: object 4: the shadow bias is -1, it must not be negative",
+                "warning: light 1 is inside of object 0, which blocks all of its light",
+                "warning: light 1 is inside of object 1, which blocks all of its light",
+            ]
+        );
+    }
+
+    #[test]
+    fn nan_intersections_do_not_panic() {
+        let mut w = World::default();
+        w.add_object(Box::new(Sphere::default()));
+        w.add_object(Box::new(Sphere::default().translated(
+            Scalar::NAN,
+            0.0,
+            0.0,
+        )));
+        let mut xs = Vec::new();
+        w.intersect(
+            &Ray::new(Point::new(0, 0, -5), Vector::new(0, 0, 1)),
+            &mut xs,
+        );
+    }
+
+    #[test]
+    fn is_occluded() {
+        let w = World::test_world();
+        let mut xs = Vec::new();
+        let p = Point::new(0, 0, -5);
+        let towards = Vector::new(0, 0, 1);
+        assert!(w.is_occluded(p, towards, 10.0, &mut xs));
+        // the outer sphere starts 4 units away
+        assert!(!w.is_occluded(p, towards, 4.0, &mut xs));
+        assert!(!w.is_occluded(p, Vector::new(0, 1, 0), 10.0, &mut xs));
+        assert!(xs.is_empty());
+    }
+
+    #[test]
+    fn fractional_intensity() {
+        let mut w = World::default();
+        let light = PointLight::new(Point::new(0, 10, 0), WHITE);
+
+        let mut glass = Sphere::default();
+        glass.material_mut().transparency = 0.5;
+        w.add_object(Box::new(glass));
+
+        let mut wall = Sphere::default();
+        wall.set_transformation_matrix(Mat4::new_translation(5, 0, 0));
+        w.add_object(Box::new(wall));
+
+        assert_eq!(w.intensity_at(&light, Point::new(-5, 0, 0)), 1.0);
+        assert_eq!(w.intensity_at(&light, Point::new(10, -10, 0)), 0.0);
+        assert!(w.intensity_at(&light, Point::new(0, -5, 0)).e_equals(0.25));
+    }
+
+    #[test]
+    fn one_sided_plane_casts_no_shadow_from_behind() {
+        let mut w = World::default();
+        w.add_light(Box::new(PointLight::new(Point::new(0, 10, 0), WHITE)));
+
+        let mut wall = Plane::default();
+        wall.set_one_sided(true);
+        w.add_object(Box::new(wall));
+
+        let p = Point::new(0, -5, 0);
+        let mut intersections = Vec::new();
+        let transmission = {
+            let light = w.lights()[0].as_ref();
+            w.shadow_transmission(light, &p, &mut intersections)
+        };
+        assert_eq!(transmission, WHITE);
+    }
+
+    #[test]
+    fn ambient_light() {
+        let ambient = |lights: Vec<Color>, ambient_light: AmbientLight| {
+            let mut w = World::test_world();
+            w.lights = lights
+                .into_iter()
+                .map(|color| {
+                    Box::new(PointLight::new(Point::new(-10, 10, -10), color)) as Box<dyn Light>
+                })
+                .collect();
+            w.set_ambient_light(ambient_light);
+            let r = Ray::new(Point::new(0, 0, -5), Vector::new(0, 0, 1));
+            let i = Intersection::new(4.0, w.objects[0].as_ref());
+            let comps = i.prepare_computations(&r, &vec![i]);
+            w.ambient_at(&comps)
+        };
+        let dim = Color::new(0.5, 0.5, 0.5);
+        let full = Color::new(0.08, 0.1, 0.06);
+
+        assert_eq!(ambient(vec![WHITE, BLACK], AmbientLight::FirstLight), full);
+        assert_eq!(ambient(vec![BLACK, WHITE], AmbientLight::FirstLight), BLACK);
+        assert_eq!(
+            ambient(vec![WHITE, BLACK], AmbientLight::AverageOfLights),
+            ambient(vec![BLACK, WHITE], AmbientLight::AverageOfLights)
+        );
+        assert_eq!(
+            ambient(vec![WHITE, BLACK], AmbientLight::AverageOfLights),
+            ambient(vec![dim], AmbientLight::FirstLight)
+        );
+        assert_eq!(ambient(vec![], AmbientLight::AverageOfLights), BLACK);
+        assert_eq!(ambient(vec![], AmbientLight::Color(WHITE)), full);
+    }
+
+    #[test]
+    fn unlinked_light_only_adds_ambient() {
+        let mut w = World::test_world();
+        w.objects_mut()[0].set_id(Some(ObjectId(1)));
+        let mut light = PointLight::new(Point::new(-10, 10, -10), Color::new(1, 1, 1));
+        light.set_linking(LightLinking::AllExcept(HashSet::from([ObjectId(1)])));
+        w.lights = vec![Box::new(light)];
+
+        let r = Ray::new(Point::new(0, 0, -5), Vector::new(0, 0, 1));
+        let i = Intersection::new(4.0, w.objects[0].as_ref());
+        let comps = i.prepare_computations(&r, &vec![i]);
+        let c = w.shade_hit(&comps, &mut Vec::new(), 0);
+        assert_eq!(c, Color::new(0.08, 0.1, 0.06));
+    }
+
+    #[test]
+    fn sampled_lights_converge_to_all_lights() {
+        fn shade(w: &World, samples: Option<usize>) -> Color {
+            let r = Ray::new(Point::new(0, 0, -5), Vector::new(0, 0, 1));
+            let i = Intersection::new(4.0, w.objects[0].as_ref());
+            let comps = i.prepare_computations(&r, &vec![i]);
+            match samples {
+                Some(samples) => w.sampled_lights_at(&comps, &mut Vec::new(), samples),
+                None => w.all_lights_at(&comps, &mut Vec::new()),
+            }
+        }
+
+        let mut w = World::test_world();
+        w.add_light(Box::new(PointLight::new(
+            Point::new(10, 10, -10),
+            Color::new(0.5, 0.5, 0.5),
+        )));
+        w.add_light(Box::new(PointLight::new(
+            Point::new(0, -10, -10),
+            Color::new(0.2, 0.2, 0.2),
+        )));
+
+        let all = shade(&w, None);
+        assert_ne!(shade(&w, Some(1)), all);
+
+        let many = shade(&w, Some(20_000));
+        assert!((many.red - all.red).abs() < 0.01);
+        assert!((many.green - all.green).abs() < 0.01);
+        assert!((many.blue - all.blue).abs() < 0.01);
+    }
+
+    #[test]
+    fn ambient_occlusion_darkens_corners() {
+        fn unoccluded(w: &World, x: Scalar) -> Scalar {
+            let r = Ray::new(Point::new(x, 1.0, 0.0), Vector::new(0, -1, 0));
+            let i = Intersection::new(1.0, w.objects[0].as_ref());
+            let comps = i.prepare_computations(&r, &vec![i]);
+            w.unoccluded_at(&comps, &AmbientOcclusion::new(200, 2.0), &mut Vec::new())
+        }
+
+        let mut w = World::default();
+        w.add_object(Box::new(Plane::default()));
+        let mut ball = Sphere::default();
+        ball.set_transformation_matrix(Mat4::new_translation(0, 1, 0));
+        w.add_object(Box::new(ball));
+
+        assert_eq!(unoccluded(&w, 10.0), 1.0);
+        assert!(unoccluded(&w, 0.5) < 0.6);
+        assert!(unoccluded(&w, 0.5) < unoccluded(&w, 1.5));
+    }
+
+    #[test]
+    fn ambient_occlusion_only_changes_ambient() {
+        let mut w = World::default();
+        w.add_light(Box::new(PointLight::new(Point::new(-10, 10, -10), WHITE)));
+        w.add_object(Box::new(Plane::default()));
+        let mut ball = Sphere::default();
+        ball.set_transformation_matrix(Mat4::new_translation(0, 1, 0));
+        w.add_object(Box::new(ball));
+
+        let r = Ray::new(
+            Point::new(0.5, 1.0, -5.0),
+            Vector::new(0, -1, 5).normalized(),
+        );
+        let without = w.color_at(&r, &mut Vec::new(), 0);
+        w.set_ambient_occlusion(Some(AmbientOcclusion::new(16, 2.0)));
+        assert_eq!(w.ambient_occlusion(), Some(AmbientOcclusion::new(16, 2.0)));
+        let with = w.color_at(&r, &mut Vec::new(), 0);
+        // at most the ambient part (0.1 * WHITE) can be lost
+        assert!(with.red < without.red && with.red >= without.red - 0.1);
+    }
+
+    #[test]
+    fn clear_coat_reflects_more_at_grazing_angles() {
+        let mut w = World::default();
+        w.set_skybox(Skybox::new(ColorType::Color(WHITE)));
+        let mut floor = Plane::default();
+        floor.material_mut().color = ColorType::Color(BLACK);
+        let clear_coat = ClearCoat::new(1.0, 0.1, 1.5);
+        floor.material_mut().clear_coat = Some(clear_coat);
+        w.add_object(Box::new(floor));
+
+        let look_at_floor = |w: &World, direction: Vector| {
+            let r = Ray::new(Point::new(0, 1, 0), direction.normalized());
+            w.color_at(&r, &mut Vec::new(), 1)
+        };
+        let steep = look_at_floor(&w, Vector::new(0, -1, 0));
+        assert_eq!(steep, Color::new(0.04, 0.04, 0.04));
+        let grazing = look_at_floor(&w, Vector::new(0.0, -0.2, 1.0));
+        let cos = Vector::new(0.0, 0.2, -1.0).normalized().y;
+        let expected = clear_coat.reflectance(cos);
+        assert_eq!(grazing, Color::new(expected, expected, expected));
+        assert!(grazing.red > 0.3);
+    }
+
+    #[test]
+    fn clear_coat_highlight() {
+        let mut w = World::default();
+        w.add_light(Box::new(PointLight::new(Point::new(0, 10, 0), WHITE)));
+        let mut floor = Plane::default();
+        floor.material_mut().specular = 0.0;
+        w.add_object(Box::new(floor.clone()));
+        let r = Ray::new(Point::new(0, 1, 0), Vector::new(0, -1, 0));
+        let without = w.color_at(&r, &mut Vec::new(), 0);
+
+        floor.material_mut().clear_coat = Some(ClearCoat::new(1.0, 0.3, 1.5));
+        let mut coated = World::default();
+        coated.add_light(Box::new(PointLight::new(Point::new(0, 10, 0), WHITE)));
+        coated.add_object(Box::new(floor));
+        let with = coated.color_at(&r, &mut Vec::new(), 0);
+        // the base is dimmed a little, but the coat's highlight more than makes up for it
+        assert!(with.red > without.red);
+    }
+
+    #[test]
+    fn back_material() {
+        let mut w = World::default();
+        let mut poster = Plane::default();
+        poster.material_mut().emissive = Color::new(1, 0, 0);
+        let back = Material {
+            emissive: Color::new(0.5, 0.5, 0.5),
+            ..Default::default()
         };
-        assert_eq!(shadowed, false);
+        poster.set_back_material(Some(back.clone()));
+        assert_eq!(poster.back_material(), Some(&back));
+        w.add_object(Box::new(poster));
+
+        let front = Ray::new(Point::new(0, 1, 0), Vector::new(0, -1, 0));
+        assert_eq!(w.color_at(&front, &mut Vec::new(), 0), Color::new(1, 0, 0));
+        let behind = Ray::new(Point::new(0, -1, 0), Vector::new(0, 1, 0));
+        assert_eq!(
+            w.color_at(&behind, &mut Vec::new(), 0),
+            Color::new(0.5, 0.5, 0.5)
+        );
+    }
+
+    #[test]
+    fn emissive_glows_without_lights_and_occlusion() {
+        let mut w = World::default();
+        w.add_object(Box::new(Plane::default()));
+        let mut ball = Sphere::default();
+        ball.set_transformation_matrix(Mat4::new_translation(0, 1, 0));
+        ball.material_mut().emissive = Color::new(0.2, 0.8, 0.2);
+        w.add_object(Box::new(ball));
+        w.set_ambient_occlusion(Some(AmbientOcclusion::new(16, 2.0)));
+
+        let r = Ray::new(Point::new(0.0, 0.3, -5.0), Vector::new(0, 0, 1));
+        let c = w.color_at(&r, &mut Vec::new(), 0);
+        assert_eq!(c, Color::new(0.2, 0.8, 0.2));
+    }
+
+    #[test]
+    fn light_samples_setting() {
+        let mut w = World::test_world();
+        assert_eq!(w.light_samples(), None);
+        w.set_light_samples(Some(4));
+        assert_eq!(w.light_samples(), Some(4));
     }
 
     #[test]
     fn test_shade_hit_shadowed() {
         let mut w = World::default();
-        w.add_light(PointLight::new(Point::new(0, 0, -10), WHITE));
+        w.add_light(Box::new(PointLight::new(Point::new(0, 0, -10), WHITE)));
 
         let s1 = Sphere::default();
         w.add_object(Box::new(s1));
@@ -508,10 +2499,10 @@ mod world_tests {
 
         let r = Ray::new(
             Point::new(0, 0, -3),
-            Vector::new(0.0, -(2.0_f64.sqrt()) / 2.0_f64, 2.0_f64.sqrt() / 2.0_f64),
+            Vector::new(0.0, -(Scalar::sqrt(2.0)) / 2.0, Scalar::sqrt(2.0) / 2.0),
         );
         let shape = w.objects().get(2).unwrap();
-        let i = Intersection::new(2.0_f64.sqrt(), shape.as_shape());
+        let i = Intersection::new(Scalar::sqrt(2.0), shape.as_shape());
         let comps = i.prepare_computations(&r, &vec![i]);
         let color = w.reflected_color_at(&comps, 1);
         assert_eq!(color, Color::new(0.19032, 0.2379, 0.14274));
@@ -528,11 +2519,11 @@ mod world_tests {
 
         let r = Ray::new(
             Point::new(0, 0, -3),
-            Vector::new(0.0, -(2.0_f64.sqrt()) / 2.0_f64, 2.0_f64.sqrt() / 2.0_f64),
+            Vector::new(0.0, -(Scalar::sqrt(2.0)) / 2.0, Scalar::sqrt(2.0) / 2.0),
         );
 
         let shape = w.objects().get(2).unwrap();
-        let intersection = Intersection::new(2.0_f64.sqrt(), shape.as_shape());
+        let intersection = Intersection::new(Scalar::sqrt(2.0), shape.as_shape());
         let comps = intersection.prepare_computations(&r, &vec![intersection]);
 
         let mut intersections = Vec::new();
@@ -546,10 +2537,10 @@ mod world_tests {
             .stack_size(1024 * 1024)
             .spawn(move || {
                 let mut w = World::default();
-                w.add_light(PointLight::new(
+                w.add_light(Box::new(PointLight::new(
                     Point::const_new(0.0, 0.0, 0.0),
                     Color::new(1, 1, 1),
-                ));
+                )));
 
                 let mut lower = Plane::default();
                 lower.material_mut().reflective = 1.0;
@@ -584,14 +2575,8 @@ mod world_tests {
         let r = Ray::new(Point::new(0, 0, -5), Vector::new(0, 0, 1));
 
         let xs = vec![
-            Intersection {
-                t: 4.0,
-                object: shape.as_ref(),
-            },
-            Intersection {
-                t: 6.0,
-                object: shape.as_ref(),
-            },
+            Intersection::new(4.0, shape.as_ref()),
+            Intersection::new(6.0, shape.as_ref()),
         ];
 
         let comps = xs[0].prepare_computations(&r, &xs);
@@ -615,14 +2600,8 @@ mod world_tests {
         let r = Ray::new(Point::new(0, 0, -5), Vector::new(0, 0, 1));
 
         let xs = vec![
-            Intersection {
-                t: 4.0,
-                object: shape.as_ref(),
-            },
-            Intersection {
-                t: 6.0,
-                object: shape.as_ref(),
-            },
+            Intersection::new(4.0, shape.as_ref()),
+            Intersection::new(6.0, shape.as_ref()),
         ];
 
         let comps = xs[0].prepare_computations(&r, &xs);
@@ -643,17 +2622,14 @@ mod world_tests {
 
         let shape = w.objects().first().unwrap();
 
-        let r = Ray::new(Point::new(0, 0, 2.0f64.sqrt() / 2.0), Vector::new(0, 1, 0));
+        let r = Ray::new(
+            Point::new(0, 0, Scalar::sqrt(2.0) / 2.0),
+            Vector::new(0, 1, 0),
+        );
 
         let xs = vec![
-            Intersection {
-                t: -(2.0f64.sqrt()) / 2.0,
-                object: shape.as_ref(),
-            },
-            Intersection {
-                t: 2.0f64.sqrt() / 2.0,
-                object: shape.as_ref(),
-            },
+            Intersection::new(-(Scalar::sqrt(2.0)) / 2.0, shape.as_ref()),
+            Intersection::new(Scalar::sqrt(2.0) / 2.0, shape.as_ref()),
         ];
 
         let comps = xs[1].prepare_computations(&r, &xs);
@@ -721,14 +2697,14 @@ mod world_tests {
 
         let ray = Ray::new(
             Point::new(0, 0, -3),
-            Vector::new(0.0, -(2.0f64.sqrt()) / 2.0, (2.0f64.sqrt()) / 2.0),
+            Vector::new(0.0, -(Scalar::sqrt(2.0)) / 2.0, (Scalar::sqrt(2.0)) / 2.0),
         );
 
         let flöör = w.objects().get(2).unwrap();
 
         assert_eq!(floor.transformation_matrix(), flöör.transformation_matrix());
 
-        let xs = vec![Intersection::new(2.0f64.sqrt(), flöör.as_ref())];
+        let xs = vec![Intersection::new(Scalar::sqrt(2.0), flöör.as_ref())];
 
         let xs = dbg!(xs);
 
@@ -739,6 +2715,7 @@ mod world_tests {
 
         let color = w.shade_hit(&comps, &mut Vec::new(), 5);
 
-        assert_eq!(color, Color::new(0.93642, 0.68642, 0.68642));
+        // the ball is lit through the half-transparent floor, so it is brighter than a ball in hard shadow (0.93642, 0.68642, 0.68642)
+        assert_eq!(color, Color::new(1.12546, 0.68642, 0.68642));
     }
 }